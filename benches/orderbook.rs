@@ -0,0 +1,167 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use orderbook::{Command, FlowConfig, FlowGenerator, NewOrder, OrderBook, Side, Units};
+
+/// Reproducible synthetic order flow: the same seed always yields the same commands.
+struct Flow {
+    state: u64,
+    next_id: u64,
+    mid: Units,
+}
+
+impl Flow {
+    fn new(seed: u64) -> Self {
+        // xorshift64 gets stuck at zero
+        Self { state: seed | 1, next_id: 0, mid: 10_000 }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn side(&mut self) -> Side {
+        if self.next() & 1 == 0 {
+            Side::Buy
+        } else {
+            Side::Sell
+        }
+    }
+
+    fn quantity(&mut self) -> Units {
+        Units::from(self.next() % 100 + 1)
+    }
+
+    /// Limit order resting `1..=levels` ticks away from the mid, never crossing.
+    fn passive(&mut self, levels: u64) -> NewOrder {
+        let side = self.side();
+        let offset = Units::from(self.next() % levels + 1);
+        let price = match side {
+            Side::Buy => self.mid - offset,
+            Side::Sell => self.mid + offset,
+        };
+        NewOrder::limit(side, price, self.quantity(), self.id())
+    }
+
+    /// Limit order priced up to `levels` ticks through the mid, so it usually trades.
+    fn aggressive(&mut self, levels: u64) -> NewOrder {
+        let side = self.side();
+        let offset = Units::from(self.next() % levels);
+        let price = match side {
+            Side::Buy => self.mid + offset,
+            Side::Sell => self.mid - offset,
+        };
+        NewOrder::limit(side, price, self.quantity(), self.id())
+    }
+
+    fn passive_orders(&mut self, count: usize, levels: u64) -> Vec<NewOrder> {
+        (0..count).map(|_| self.passive(levels)).collect()
+    }
+}
+
+fn book_with(orders: &[NewOrder]) -> OrderBook {
+    let mut ob = OrderBook::new();
+    for order in orders {
+        ob.submit(order.clone()).unwrap();
+    }
+    ob
+}
+
+fn insert_only(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_only");
+    for count in [1_000, 10_000] {
+        let orders = Flow::new(1).passive_orders(count, 500);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &orders, |b, orders| {
+            b.iter_batched(|| orders.clone(), |orders| book_with(&orders), BatchSize::LargeInput)
+        });
+    }
+    group.finish();
+}
+
+fn heavy_matching(c: &mut Criterion) {
+    let mut flow = Flow::new(2);
+    let resting = flow.passive_orders(10_000, 50);
+    // Every other order crosses into the resting liquidity
+    let stream: Vec<NewOrder> =
+        (0..2_000).map(|i| if i % 2 == 0 { flow.aggressive(5) } else { flow.passive(50) }).collect();
+
+    let mut group = c.benchmark_group("heavy_matching");
+    group.throughput(Throughput::Elements(stream.len() as u64));
+    group.bench_function("mixed", |b| {
+        b.iter_batched(
+            || (book_with(&resting), stream.clone()),
+            |(mut ob, stream)| {
+                for order in stream {
+                    ob.submit(order).unwrap();
+                }
+                ob
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn cancel_churn(c: &mut Criterion) {
+    let mut flow = Flow::new(3);
+    let resting = flow.passive_orders(10_000, 100);
+    // A market maker pulling and re-posting its quotes: each new quote replaces the previous one
+    let mut commands = Vec::new();
+    let mut last = None;
+    for _ in 0..2_000 {
+        let quote = flow.passive(10);
+        if let Some(id) = last.replace(quote.id) {
+            commands.push(Command::Cancel { id });
+        }
+        commands.push(Command::Place(quote));
+    }
+
+    let mut group = c.benchmark_group("cancel_churn");
+    group.throughput(Throughput::Elements(commands.len() as u64));
+    group.bench_function("quote_replace", |b| {
+        b.iter_batched(|| book_with(&resting), |mut ob| ob.apply_batch(&commands), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+fn deep_sweep(c: &mut Criterion) {
+    let mut group = c.benchmark_group("deep_sweep");
+    for levels in [100, 1_000] {
+        let mut flow = Flow::new(4);
+        let resting: Vec<NewOrder> = (0..levels * 4)
+            .map(|i| NewOrder::limit(Side::Sell, 10_001 + i % levels, flow.quantity(), flow.id()))
+            .collect();
+        let total: Units = resting.iter().map(|order| order.quantity).sum();
+        group.throughput(Throughput::Elements(resting.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(levels), &resting, |b, resting| {
+            b.iter_batched(
+                || book_with(resting),
+                |mut ob| ob.submit(NewOrder::market(Side::Buy, total, 0)).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn generated_flow(c: &mut Criterion) {
+    let commands: Vec<Command> =
+        FlowGenerator::new(5, FlowConfig::default()).take(20_000).map(|(_, command)| command).collect();
+
+    let mut group = c.benchmark_group("generated_flow");
+    group.throughput(Throughput::Elements(commands.len() as u64));
+    group.bench_function("default_config", |b| {
+        b.iter_batched(OrderBook::new, |mut ob| ob.apply_batch(&commands), BatchSize::LargeInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, insert_only, heavy_matching, cancel_churn, deep_sweep, generated_flow);
+criterion_main!(benches);
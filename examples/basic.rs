@@ -0,0 +1,15 @@
+use orderbook::{OrderBook, Side};
+
+fn main() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 9, 200, 2).unwrap();
+    ob.place_order(Side::Sell, 12, 150, 3).unwrap();
+
+    for trade in ob.place_order(Side::Sell, 9, 150, 4).unwrap().trades {
+        println!("trade {} @ {} (maker {}, taker {})", trade.quantity, trade.price, trade.maker_id, trade.taker_id);
+    }
+
+    println!("best buy {:?}, best sell {:?}", ob.best_buy(), ob.best_sell());
+}
@@ -0,0 +1,44 @@
+/* Drives the C interface end to end. Build and run from the repository root:
+ *   cargo rustc --lib --release --features ffi --crate-type staticlib
+ *   cc -Iinclude examples/ffi.c target/release/liborderbook.a -lpthread -ldl -lm -o target/ffi && target/ffi
+ */
+#include <stdio.h>
+
+#include "orderbook.h"
+
+int main(void) {
+    ObBook *book = ob_book_new();
+    ObReport report;
+    ob_place_order(book, OB_SELL, 101, 10, 1, &report);
+    ob_place_order(book, OB_SELL, 102, 10, 2, &report);
+    if (ob_place_order(book, OB_BUY, 102, 15, 3, &report) != OB_OK || report.status != OB_STATUS_FILLED) {
+        fprintf(stderr, "order 3 didn't fill\n");
+        return 1;
+    }
+
+    ObTrade trades[8];
+    size_t trade_count = ob_poll_trades(book, trades, 8);
+    for (size_t i = 0; i < trade_count; i++) {
+        printf("trade %llu: %llu @ %llu, maker %llu taker %llu\n", (unsigned long long)trades[i].seq,
+               (unsigned long long)trades[i].quantity, (unsigned long long)trades[i].price,
+               (unsigned long long)trades[i].maker_id, (unsigned long long)trades[i].taker_id);
+    }
+
+    ObEvent events[4];
+    size_t event_count, total = 0;
+    while ((event_count = ob_poll_events(book, events, 4)) > 0) {
+        total += event_count;
+    }
+    printf("%zu events\n", total);
+
+    uint64_t price, quantity, remaining;
+    if (ob_best_price(book, OB_SELL, &price, &quantity) == OB_OK) {
+        printf("best ask: %llu @ %llu\n", (unsigned long long)quantity, (unsigned long long)price);
+    }
+    if (ob_cancel(book, 2, &remaining) == OB_OK) {
+        printf("cancelled order 2 with %llu left\n", (unsigned long long)remaining);
+    }
+    int status = ob_cancel(book, 2, &remaining) == OB_ERR_UNKNOWN_ORDER ? 0 : 1;
+    ob_book_free(book);
+    return status;
+}
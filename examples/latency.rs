@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use orderbook::{Command, NewOrder, OrderBook, Pipeline, Side, Units, WaitStrategy};
+
+// Small deterministic generator so runs are comparable
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn commands(count: u64) -> Vec<Command> {
+    let mut state = 0x2545_f491_4f6c_dd1d;
+    (0..count)
+        .map(|id| {
+            let r = next(&mut state);
+            let side = if r & 1 == 0 { Side::Buy } else { Side::Sell };
+            let offset = Units::from((r >> 1) % 500);
+            let price = match side {
+                Side::Buy => 10_000 - offset + 2,
+                Side::Sell => 10_000 + offset - 2,
+            };
+            Command::Place(NewOrder::limit(side, price, Units::from((r >> 20) % 100 + 1), id))
+        })
+        .collect()
+}
+
+fn report(name: &str, mut samples: Vec<Duration>) {
+    samples.sort_unstable();
+    let at = |q: f64| samples[((samples.len() - 1) as f64 * q) as usize].as_nanos();
+    println!("{:<20} p50 {:>7} ns  p99 {:>7} ns  p99.9 {:>8} ns", name, at(0.5), at(0.99), at(0.999));
+}
+
+// Round-trip latency of each command, sent one at a time
+fn pipeline(commands: &[Command], wait: WaitStrategy) -> Vec<Duration> {
+    let mut pipeline = Pipeline::spawn(OrderBook::new(), 1024, wait);
+    let samples = commands
+        .iter()
+        .map(|command| {
+            let start = Instant::now();
+            pipeline.execute(command.clone()).unwrap();
+            start.elapsed()
+        })
+        .collect();
+    pipeline.shutdown().unwrap();
+    samples
+}
+
+// The naive alternative: a shared book behind a mutex, with another thread polling it
+fn mutex(commands: &[Command]) -> Vec<Duration> {
+    let book = Arc::new(Mutex::new(OrderBook::new()));
+    let done = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (book, done) = (Arc::clone(&book), Arc::clone(&done));
+        thread::spawn(move || {
+            while !done.load(Ordering::Relaxed) {
+                let _ = book.lock().unwrap().depth(10);
+            }
+        })
+    };
+    let samples = commands
+        .iter()
+        .map(|command| {
+            let start = Instant::now();
+            book.lock().unwrap().apply_batch(std::slice::from_ref(command));
+            start.elapsed()
+        })
+        .collect();
+    done.store(true, Ordering::Relaxed);
+    reader.join().unwrap();
+    samples
+}
+
+fn main() {
+    // The rings only pay off with a core each for the caller and the matching thread; on a
+    // single core every handoff waits for the scheduler
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    if cores < 2 {
+        println!("only {} core available, expect the pipeline to trail the mutex", cores);
+    }
+    let commands = commands(200_000);
+    report("spsc busy-spin", pipeline(&commands, WaitStrategy::BusySpin));
+    report("spsc park", pipeline(&commands, WaitStrategy::Park));
+    report("mutex + reader", mutex(&commands));
+}
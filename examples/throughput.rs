@@ -0,0 +1,37 @@
+use std::time::Instant;
+
+use orderbook::{OrderBook, Side, Units};
+
+// Small deterministic generator so runs are comparable
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+fn main() {
+    let orders: u64 = 200_000;
+    let mut state = 0x2545_f491_4f6c_dd1d;
+
+    let mut ob = OrderBook::new();
+    let start = Instant::now();
+    for id in 0..orders {
+        let r = next(&mut state);
+        let side = if r & 1 == 0 { Side::Buy } else { Side::Sell };
+        // Prices around 10_000 with a few hundred distinct levels per side
+        let offset = Units::from((r >> 1) % 500);
+        let price = match side {
+            Side::Buy => 10_000 - offset + 2,
+            Side::Sell => 10_000 + offset - 2,
+        };
+        ob.place_order(side, price, Units::from((r >> 20) % 100 + 1), id).unwrap();
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{} orders in {:?} ({:.0} ns/order)",
+        orders,
+        elapsed,
+        elapsed.as_nanos() as f64 / orders as f64
+    );
+}
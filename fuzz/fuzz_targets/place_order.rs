@@ -0,0 +1,29 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use orderbook::{OrderBook, Side};
+
+// Each 4-byte chunk is one call: an op byte, a price byte and a two-byte quantity. Prices sit in
+// a narrow band so orders keep crossing.
+fuzz_target!(|data: &[u8]| {
+    let mut ob = OrderBook::new();
+    for (id, chunk) in (0u64..).zip(data.chunks_exact(4)) {
+        let side = if chunk[0] & 1 == 0 { Side::Buy } else { Side::Sell };
+        let price = 100 + u64::from(chunk[1] % 32);
+        let quantity = u64::from(u16::from_le_bytes([chunk[2], chunk[3]]));
+        match (chunk[0] >> 1) & 3 {
+            0 | 1 => {
+                let _ = ob.place_order(side, price, quantity, id);
+            }
+            2 => {
+                let _ = ob.place_market_order(side, quantity, id);
+            }
+            _ => {
+                let _ = ob.cancel_order(id.saturating_sub(u64::from(chunk[1])));
+            }
+        }
+        if let (Some((bid, _)), Some((ask, _))) = (ob.best_buy(), ob.best_sell()) {
+            assert!(bid < ask, "crossed book: {} >= {}", bid, ask);
+        }
+    }
+});
@@ -0,0 +1,189 @@
+/* C interface to the orderbook engine; build the library with `cargo rustc --lib --release --features ffi --crate-type staticlib` (or cdylib). */
+
+#ifndef ORDERBOOK_H
+#define ORDERBOOK_H
+
+/* Generated by cbindgen from src/ffi.rs; don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define OB_OK 0
+
+// A required pointer argument was null
+#define OB_ERR_NULL -1
+
+// `side` is neither `OB_BUY` nor `OB_SELL`
+#define OB_ERR_SIDE -2
+
+// A resting or held stop order already has the id
+#define OB_ERR_DUPLICATE_ID -3
+
+// No resting order has the id
+#define OB_ERR_UNKNOWN_ORDER -4
+
+// The book detected a bug in itself and needs rebuilding
+#define OB_ERR_INTERNAL -5
+
+// The side has no orders
+#define OB_ERR_EMPTY -6
+
+#define OB_BUY 0
+
+#define OB_SELL 1
+
+#define OB_STATUS_FILLED 1
+
+#define OB_STATUS_RESTED 2
+
+#define OB_STATUS_CANCELLED 3
+
+#define OB_STATUS_PENDING 4
+
+#define OB_STATUS_REJECTED 5
+
+#define OB_EVENT_ACCEPTED 1
+
+#define OB_EVENT_RESTED 2
+
+#define OB_EVENT_PARTIALLY_FILLED 3
+
+#define OB_EVENT_FILLED 4
+
+#define OB_EVENT_CANCELLED 5
+
+#define OB_EVENT_REJECTED 6
+
+#define OB_EVENT_TRIGGERED 7
+
+#define OB_EVENT_EXPIRED 8
+
+#define OB_EVENT_REPRICED 9
+
+#define OB_EVENT_LEVEL_ADDED 10
+
+#define OB_EVENT_LEVEL_REMOVED 11
+
+#define OB_EVENT_BEST_PRICE_CHANGED 12
+
+#define OB_EVENT_SESSION_PHASE_CHANGED 13
+
+#define OB_EVENT_TRADE_BUSTED 14
+
+#define OB_EVENT_TRADE_CORRECTED 15
+
+// Opaque handle to a book and the events and trades not yet polled from it, for embedding the
+// engine in C and C++ programs through include/orderbook.h (generated with `cbindgen --config
+// cbindgen.toml --output include/orderbook.h`).
+//
+// Functions take the handle from `ob_book_new` and return an `OB_*` status code, writing
+// results to caller-provided out-parameters and buffers. Events and trades queue up in the
+// handle until polled. A handle may move between threads but not be shared by them.
+typedef struct ObBook ObBook;
+
+// What `ob_place_order` and `ob_place_market_order` did with an order. `reject_reason` is
+// set with `OB_STATUS_REJECTED`: the `RejectReason` variant's position in its declaration,
+// counting from 1. The call's trades are queued for `ob_poll_trades`.
+typedef struct ObReport {
+  uint64_t order_id;
+  uint32_t status;
+  uint32_t reject_reason;
+  uint64_t filled_quantity;
+  uint64_t remaining_quantity;
+  uint64_t trade_count;
+} ObReport;
+
+// One book event, `kind` being an `OB_EVENT_*` code. Fields an event doesn't carry are zero:
+// order events set `id` and whichever of `price`, `quantity` and `remaining` apply; level and
+// best-price events set `side` and `price`, with `has_price` false when a side emptied;
+// trade busts and corrections set `id` to the trade's sequence number and `price` and
+// `quantity` to what it was busted at or corrected to; `code` is the reject reason (as in
+// `ObReport`) or the session phase, counting from 1.
+typedef struct ObEvent {
+  uint32_t kind;
+  uint32_t code;
+  uint64_t id;
+  uint8_t side;
+  bool has_price;
+  uint64_t price;
+  uint64_t quantity;
+  uint64_t remaining;
+} ObEvent;
+
+typedef struct ObTrade {
+  uint64_t price;
+  uint64_t quantity;
+  uint64_t maker_id;
+  uint64_t taker_id;
+  uint64_t seq;
+  uint64_t timestamp;
+} ObTrade;
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// A new empty book; free it with `ob_book_free`.
+struct ObBook *ob_book_new(void);
+
+// Free a book; null is ignored.
+//
+// # Safety
+// `book` is null or a handle from `ob_book_new` that hasn't been freed.
+void ob_book_free(struct ObBook *book);
+
+// Enter a limit order, writing what became of it to `report` unless that is null.
+//
+// # Safety
+// `book` is a live handle from `ob_book_new`; `report` is null or valid for writes.
+int32_t ob_place_order(struct ObBook *book,
+                       uint8_t side,
+                       uint64_t price,
+                       uint64_t quantity,
+                       uint64_t id,
+                       struct ObReport *report);
+
+// Enter a market order, writing what became of it to `report` unless that is null.
+//
+// # Safety
+// `book` is a live handle from `ob_book_new`; `report` is null or valid for writes.
+int32_t ob_place_market_order(struct ObBook *book,
+                              uint8_t side,
+                              uint64_t quantity,
+                              uint64_t id,
+                              struct ObReport *report);
+
+// Cancel a resting order, writing its unfilled quantity to `remaining` unless that is null.
+//
+// # Safety
+// `book` is a live handle from `ob_book_new`; `remaining` is null or valid for writes.
+int32_t ob_cancel(struct ObBook *book, uint64_t id, uint64_t *remaining);
+
+// Best price and visible quantity on `side`, or `OB_ERR_EMPTY`.
+//
+// # Safety
+// `book` is a live handle from `ob_book_new`; `price` and `quantity` are valid for writes.
+int32_t ob_best_price(const struct ObBook *book, uint8_t side, uint64_t *price, uint64_t *quantity);
+
+// Move up to `capacity` of the oldest unpolled events into `events`, returning how many.
+//
+// # Safety
+// `book` is a live handle from `ob_book_new`; `events` is valid for `capacity` writes.
+size_t ob_poll_events(struct ObBook *book, struct ObEvent *events, size_t capacity);
+
+// Move up to `capacity` of the oldest unpolled trades into `trades`, returning how many.
+//
+// # Safety
+// `book` is a live handle from `ob_book_new`; `trades` is valid for `capacity` writes.
+size_t ob_poll_trades(struct ObBook *book, struct ObTrade *trades, size_t capacity);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ORDERBOOK_H */
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::book::OrderBook;
+use crate::order::Order;
+use crate::types::{Side, Trade};
+use crate::units::{widen, Units};
+
+/// What an owner has traded on a book. Quantities and notionals are in the book's raw units.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// Bought minus sold; negative when short
+    pub net_quantity: i128,
+    /// Entry cost of the open position: price times quantity of the fills it was built from
+    pub open_notional: u128,
+    /// Profit of the quantity closed out so far, against the average entry price
+    pub realized_pnl: i128,
+    pub bought_quantity: u128,
+    pub sold_quantity: u128,
+    pub trades: u64,
+}
+
+impl Position {
+    /// Average entry price of the open position, `None` when flat.
+    pub fn average_price(&self) -> Option<f64> {
+        (self.net_quantity != 0).then(|| self.open_notional as f64 / self.net_quantity.unsigned_abs() as f64)
+    }
+
+    /// Quantity bought and sold.
+    pub fn filled_volume(&self) -> u128 {
+        self.bought_quantity + self.sold_quantity
+    }
+
+    /// Profit the open position would realize if closed at `price`.
+    pub fn unrealized_pnl(&self, price: Units) -> i128 {
+        let value = (price as i128).saturating_mul(self.net_quantity);
+        value - self.open_notional as i128 * self.net_quantity.signum()
+    }
+
+    fn apply(&mut self, side: Side, price: Units, quantity: Units) {
+        match side {
+            Side::Buy => self.bought_quantity += widen(quantity),
+            Side::Sell => self.sold_quantity += widen(quantity),
+        }
+        self.trades += 1;
+        self.trade(sign(side), widen(price), quantity as i128);
+    }
+
+    // Take back a fill. Quantity still held on the fill's side comes off the position at the
+    // fill's price, which undoes the fill exactly if nothing since has closed against it; the
+    // rest is traded back the other way at the same price. Either way realized plus unrealized
+    // profit comes out as if the fill had never happened, if not always split the same way.
+    fn reverse(&mut self, side: Side, price: Units, quantity: Units) {
+        match side {
+            Side::Buy => self.bought_quantity = self.bought_quantity.saturating_sub(widen(quantity)),
+            Side::Sell => self.sold_quantity = self.sold_quantity.saturating_sub(widen(quantity)),
+        }
+        self.trades = self.trades.saturating_sub(1);
+
+        let (sign, price, mut quantity) = (sign(side), widen(price), quantity as i128);
+        if self.net_quantity.signum() == sign {
+            let held = self.net_quantity.unsigned_abs();
+            let removed = (quantity as u128).min(held);
+            self.open_notional = if removed == held { 0 } else { self.open_notional.saturating_sub(price * removed) };
+            self.net_quantity -= removed as i128 * sign;
+            quantity -= removed as i128;
+        }
+        if quantity > 0 {
+            self.trade(-sign, price, quantity);
+        }
+    }
+
+    // Move the position by `quantity` bought (`sign` 1) or sold (-1) at `price`
+    fn trade(&mut self, sign: i128, price: u128, mut quantity: i128) {
+        // Close out against the open position first, at its average entry price
+        if self.net_quantity.signum() == -sign {
+            let open = self.net_quantity.unsigned_abs();
+            let closed = (quantity as u128).min(open);
+            let cost = self.open_notional * closed / open;
+            let proceeds = price * closed;
+            // A long is closed by selling, a short by buying back
+            self.realized_pnl += (proceeds as i128 - cost as i128) * -sign;
+            self.open_notional -= cost;
+            self.net_quantity += closed as i128 * sign;
+            quantity -= closed as i128;
+        }
+        // Whatever is left opens or adds to a position on this side
+        if quantity > 0 {
+            self.open_notional += price * quantity as u128;
+            self.net_quantity += quantity * sign;
+        }
+    }
+}
+
+fn sign(side: Side) -> i128 {
+    match side {
+        Side::Buy => 1,
+        Side::Sell => -1,
+    }
+}
+
+/// Positions of every owner that has traded on a book, kept up to date from its trades.
+/// Orders without an owner aren't tracked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Accounts {
+    pub(crate) positions: HashMap<u64, Position>,
+}
+
+impl Accounts {
+    /// Position of `owner`, flat if it hasn't traded.
+    pub fn position(&self, owner: u64) -> Position {
+        self.positions.get(&owner).copied().unwrap_or_default()
+    }
+
+    /// Every owner that has traded, with its position, in no particular order.
+    pub fn positions(&self) -> impl Iterator<Item = (u64, &Position)> {
+        self.positions.iter().map(|(&owner, position)| (owner, position))
+    }
+
+    // Book a new trade to the owners of both orders
+    pub(crate) fn record(&mut self, trade: &Trade) {
+        for (owner, side) in Self::sides(trade) {
+            self.positions.entry(owner).or_default().apply(side, trade.price, trade.quantity);
+        }
+    }
+
+    // Take a busted or corrected trade back out of the owners' positions
+    pub(crate) fn reverse(&mut self, trade: &Trade) {
+        for (owner, side) in Self::sides(trade) {
+            self.positions.entry(owner).or_default().reverse(side, trade.price, trade.quantity);
+        }
+    }
+
+    // The owners of a trade's orders with the side each traded on
+    fn sides(trade: &Trade) -> impl Iterator<Item = (u64, Side)> {
+        let maker_side = match trade.taker_side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        [(trade.maker_owner, maker_side), (trade.taker_owner, trade.taker_side)]
+            .into_iter()
+            .filter_map(|(owner, side)| Some((owner?, side)))
+    }
+}
+
+impl OrderBook {
+    pub fn accounts(&self) -> &Accounts {
+        &self.accounts
+    }
+
+    /// Shorthand for `accounts().position(owner)`.
+    pub fn position(&self, owner: u64) -> Position {
+        self.accounts.position(owner)
+    }
+
+    /// Resting orders of `owner`, bids then asks, each in price-time priority. Costs a scan of
+    /// the whole book.
+    pub fn open_orders(&self, owner: u64) -> impl Iterator<Item = &Order> {
+        self.iter_bids().chain(self.iter_asks()).filter(move |order| order.owner == Some(owner))
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+
+use crate::feed::{BookMirror, MarketData, SequenceGap};
+use crate::types::{Side, Trade};
+use crate::units::{widen, Units};
+
+/// Liquidity metrics of a book, maintained from its L2 feed and trades.
+///
+/// Each feed message only touches the level it names, so per-side totals stay current
+/// without rescanning the book; queries over the top levels read just those levels.
+///
+/// The realized spread of a trade is twice its signed distance from the mid price `horizon`
+/// clock units later, positive when the resting side came out ahead. Trades are signed by
+/// comparing them with the mid price before they printed, so pass each operation's trades to
+/// `on_trade` before the feed messages it produced; trades at the mid aren't counted.
+#[derive(Debug, Clone)]
+pub struct BookAnalytics {
+    mirror: BookMirror,
+    // Visible quantity and order count, bids then asks
+    totals: [(u128, u64); 2],
+    horizon: u64,
+    // (time due, trade price, 1 for buyer-initiated or -1) of trades awaiting their realized spread
+    pending: VecDeque<(u64, Units, i8)>,
+    realized_sum: f64,
+    realized_count: u64,
+}
+
+impl BookAnalytics {
+    /// Analytics of an empty book at feed sequence 0, matching a new `L2Publisher`.
+    pub fn new(horizon: u64) -> Self {
+        Self {
+            mirror: BookMirror::new(),
+            totals: [(0, 0); 2],
+            horizon,
+            pending: VecDeque::new(),
+            realized_sum: 0.0,
+            realized_count: 0,
+        }
+    }
+
+    /// Apply one feed message, with the sequencing rules of `BookMirror::apply`.
+    pub fn apply(&mut self, message: &MarketData) -> Result<(), SequenceGap> {
+        match message {
+            MarketData::Snapshot(_) => {
+                self.mirror.apply(message)?;
+                for side in [Side::Buy, Side::Sell] {
+                    let levels = self.mirror.levels(side).values();
+                    self.totals[side as usize] =
+                        levels.fold((0, 0), |(quantity, orders), &(q, n)| (quantity + widen(q), orders + n as u64));
+                }
+            }
+            MarketData::Update(update) => {
+                let level = |mirror: &BookMirror| mirror.levels(update.side).get(&update.price).copied().unwrap_or((0, 0));
+                let before = level(&self.mirror);
+                self.mirror.apply(message)?;
+                let after = level(&self.mirror);
+                let (quantity, orders) = &mut self.totals[update.side as usize];
+                *quantity = *quantity + widen(after.0) - widen(before.0);
+                *orders = *orders + after.1 as u64 - before.1 as u64;
+            }
+        }
+        Ok(())
+    }
+
+    /// Record a trade for the realized spread, settling those whose horizon has passed by its
+    /// time.
+    pub fn on_trade(&mut self, trade: &Trade) {
+        self.settle(trade.timestamp);
+        let Some(mid) = self.mid_price() else {
+            return;
+        };
+        let sign = match (trade.price as f64).partial_cmp(&mid) {
+            Some(std::cmp::Ordering::Greater) => 1,
+            Some(std::cmp::Ordering::Less) => -1,
+            _ => return,
+        };
+        self.pending.push_back((trade.timestamp.saturating_add(self.horizon), trade.price, sign));
+    }
+
+    /// Measure the realized spread of the trades whose horizon ended by `now` against the
+    /// current mid price; without one they're dropped.
+    pub fn settle(&mut self, now: u64) {
+        let mid = self.mid_price();
+        while let Some(&(due, price, sign)) = self.pending.front() {
+            if due > now {
+                break;
+            }
+            self.pending.pop_front();
+            if let Some(mid) = mid {
+                self.realized_sum += 2.0 * f64::from(sign) * (price as f64 - mid);
+                self.realized_count += 1;
+            }
+        }
+    }
+
+    /// Average realized spread of the trades settled so far.
+    pub fn realized_spread(&self) -> Option<f64> {
+        (self.realized_count > 0).then(|| self.realized_sum / self.realized_count as f64)
+    }
+
+    /// The book as the feed has described it.
+    pub fn mirror(&self) -> &BookMirror {
+        &self.mirror
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        let (bid, ask) = (self.mirror.best_buy()?.0, self.mirror.best_sell()?.0);
+        Some((bid as f64 + ask as f64) / 2.0)
+    }
+
+    /// `(bid - ask) / (bid + ask)` of the visible quantity in the best `levels` levels per
+    /// side, from -1 (all asks) to 1 (all bids); `None` if both are empty.
+    pub fn imbalance(&self, levels: usize) -> Option<f64> {
+        let depth = |side| self.depth_curve(side, levels).last().map_or(0, |&(_, cumulative)| cumulative);
+        let (bid, ask) = (depth(Side::Buy) as f64, depth(Side::Sell) as f64);
+        (bid + ask > 0.0).then(|| (bid - ask) / (bid + ask))
+    }
+
+    /// Visible quantity available up to and including each of the best `levels` prices of
+    /// `side`, best first.
+    pub fn depth_curve(&self, side: Side, levels: usize) -> Vec<(Units, u128)> {
+        let book = self.mirror.levels(side);
+        let prices: Box<dyn Iterator<Item = (&Units, &(Units, usize))>> = match side {
+            Side::Buy => Box::new(book.iter().rev()),
+            Side::Sell => Box::new(book.iter()),
+        };
+        let mut cumulative = 0u128;
+        prices
+            .take(levels)
+            .map(|(&price, &(quantity, _))| {
+                cumulative += widen(quantity);
+                (price, cumulative)
+            })
+            .collect()
+    }
+
+    /// Visible quantity on `side` over its number of orders; `None` if it has none.
+    pub fn average_order_size(&self, side: Side) -> Option<f64> {
+        let (quantity, orders) = self.totals[side as usize];
+        (orders > 0).then(|| quantity as f64 / orders as f64)
+    }
+
+    /// Visible quantity resting on `side`.
+    pub fn total_quantity(&self, side: Side) -> u128 {
+        self.totals[side as usize].0
+    }
+}
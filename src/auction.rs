@@ -0,0 +1,203 @@
+use std::collections::BTreeSet;
+
+use crate::book::{OrderBook, PriceLevel};
+use crate::error::{InvariantViolation, OrInvariant, OrderBookError};
+use crate::events::{trace_trade, BookEvent, OrderEvent};
+use crate::types::{Side, Trade};
+use crate::units::Units;
+use crate::wal::LogEntry;
+
+/// Outcome of `run_auction`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuctionResult {
+    /// Single price every auction trade printed at, `None` if the book didn't cross
+    pub price: Option<Units>,
+    pub volume: Units,
+    /// Auction trades, followed by those of any stop orders triggered afterwards and of
+    /// strategy follow-ups
+    pub trades: Vec<Trade>,
+}
+
+impl OrderBook {
+    /// Switch to auction mode: limit orders rest without matching, even when they cross, until
+    /// `run_auction`. Market, IOC and FOK orders are rejected and stops don't trigger meanwhile.
+    pub fn start_auction(&mut self) {
+        self.log_command(LogEntry::StartAuction);
+        self.auction = true;
+    }
+
+    pub fn in_auction(&self) -> bool {
+        self.auction
+    }
+
+    /// Price and volume the book would uncross at if the auction ran now; `None` if nothing
+    /// would trade.
+    ///
+    /// The price is the one executing the most volume; ties go to the smallest imbalance
+    /// between buy and sell interest, then to the price nearest the last trade, then to the
+    /// lowest price. Hidden iceberg quantity takes part, and minimum quantities are ignored.
+    pub fn indicative_auction_price(&self) -> Option<(Units, Units)> {
+        let open = |level: &PriceLevel| level.open_quantity();
+        let prices: BTreeSet<Units> = self.buy_map.keys().chain(self.sell_map.keys()).copied().collect();
+        let prices: Vec<Units> = prices.into_iter().collect();
+
+        // Buy interest at or above each candidate price, sell interest at or below it
+        let mut demand: Vec<Units> = vec![0; prices.len()];
+        let mut bids = self.buy_map.iter().rev().peekable();
+        let mut total: Units = 0;
+        for (i, &price) in prices.iter().enumerate().rev() {
+            while let Some((_, level)) = bids.next_if(|(&bid, _)| bid >= price) {
+                total = total.saturating_add(open(level));
+            }
+            demand[i] = total;
+        }
+        let mut supply: Vec<Units> = vec![0; prices.len()];
+        let mut asks = self.sell_map.iter().peekable();
+        let mut total: Units = 0;
+        for (i, &price) in prices.iter().enumerate() {
+            while let Some((_, level)) = asks.next_if(|(&ask, _)| ask <= price) {
+                total = total.saturating_add(open(level));
+            }
+            supply[i] = total;
+        }
+
+        let reference = self.last_trade_price;
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &price)| (price, demand[i].min(supply[i]), demand[i].abs_diff(supply[i])))
+            .filter(|&(_, volume, _)| volume > 0)
+            .min_by_key(|&(price, volume, imbalance)| {
+                (std::cmp::Reverse(volume), imbalance, reference.map_or(0, |r| r.abs_diff(price)), price)
+            })
+            .map(|(price, volume, _)| (price, volume))
+    }
+
+    /// Uncross the book at the indicative auction price and return to continuous trading.
+    ///
+    /// Orders trade in price-time priority on both sides, every trade at the auction price;
+    /// the older order of each pair is reported as the maker. Self-trade prevention doesn't
+    /// apply to the uncross. Stops are checked once it completes.
+    pub fn run_auction(&mut self) -> Result<AuctionResult, OrderBookError> {
+        self.audited(Self::uncross_auction)
+    }
+
+    fn uncross_auction(&mut self) -> Result<AuctionResult, OrderBookError> {
+        self.log_command(LogEntry::RunAuction);
+        self.trade_buffer.clear();
+        self.triggered_stops.clear();
+        let best_before = self.best_prices();
+        let uncross = self.indicative_auction_price();
+        self.auction = false;
+
+        if let Some((price, volume)) = uncross {
+            self.cross_at(price, volume)?;
+            let timestamp = self.now();
+            for trade in &mut self.trade_buffer {
+                self.trade_seq += 1;
+                trade.seq = self.trade_seq;
+                trade.timestamp = timestamp;
+                trace_trade(trade);
+            }
+            if let Some(audit) = &mut self.audit {
+                audit.trades(&self.trade_buffer);
+            }
+            self.count_trades(0);
+            self.apply_oco_fills(0)?;
+            self.last_trade_price = Some(price);
+        }
+        self.activate_stops()?;
+        self.reprice_pegs()?;
+        self.settle_oco()?;
+        self.record_history();
+        self.dispatch_events(best_before);
+
+        let mut trades = self.trade_buffer.clone();
+        let follow_up_trades = self.run_strategies(&trades);
+        trades.extend(follow_up_trades);
+        Ok(AuctionResult { price: uncross.map(|(price, _)| price), volume: uncross.map_or(0, |(_, volume)| volume), trades })
+    }
+
+    // Trade `volume` between the best bids and asks, all at `price`
+    fn cross_at(&mut self, price: Units, volume: Units) -> Result<(), InvariantViolation> {
+        let mut left = volume;
+        while left > 0 {
+            let mut bid_level = self.buy_map.last_entry().or_invariant("auction volume exceeds buy interest")?;
+            let mut ask_level = self.sell_map.first_entry().or_invariant("auction volume exceeds sell interest")?;
+            let (bid_price, ask_price) = (*bid_level.key(), *ask_level.key());
+            self.changes.touch(Side::Buy, bid_price);
+            self.changes.touch(Side::Sell, ask_price);
+            let (bid, ask) = (bid_level.get_mut(), ask_level.get_mut());
+            let buy_key = bid.front().or_invariant("empty buy level")?;
+            let sell_key = ask.front().or_invariant("empty sell level")?;
+            let (buy, sell) = (self.slab.order(buy_key)?, self.slab.order(sell_key)?);
+
+            let quantity = buy.quantity.min(sell.quantity).min(left);
+            // The later of the two orders counts as the taker
+            let (maker, taker, taker_side) =
+                if buy.seq <= sell.seq { (buy, sell, Side::Sell) } else { (sell, buy, Side::Buy) };
+            let mut trade = Trade {
+                price,
+                quantity,
+                maker_id: maker.id,
+                taker_id: taker.id,
+                taker_side,
+                seq: 0,
+                timestamp: 0,
+                maker_fee: 0,
+                taker_fee: 0,
+                maker_client_order_id: maker.client_order_id.clone(),
+                taker_client_order_id: taker.client_order_id.clone(),
+                maker_user_data: maker.user_data,
+                taker_user_data: taker.user_data,
+                maker_owner: maker.owner,
+                taker_owner: taker.owner,
+            };
+            self.fees.charge(&mut trade);
+            self.accounts.record(&trade);
+            self.trade_buffer.push(trade);
+            left -= quantity;
+
+            let mut fills = [(0, 0); 2];
+            for (fill, key) in fills.iter_mut().zip([buy_key, sell_key]) {
+                let order = self.slab.order_mut(key)?;
+                order.quantity -= quantity;
+                order.filled_quantity += quantity;
+                *fill = (order.id, order.remaining_quantity());
+            }
+            bid.totals.remove(quantity, 0)?;
+            ask.totals.remove(quantity, 0)?;
+            for level in [&mut *bid, &mut *ask] {
+                if level.front().is_some_and(|key| self.slab.get(key).is_some_and(|node| node.order.quantity == 0)) {
+                    Self::replenish_front(level, &mut self.slab, &mut self.order_index, &mut self.last_order_seq, None)?;
+                }
+                level.debug_assert_totals(&self.slab);
+            }
+            let bid_emptied = bid.is_empty();
+            let ask_emptied = ask.is_empty();
+            if bid_emptied {
+                bid_level.remove();
+            }
+            if ask_emptied {
+                ask_level.remove();
+            }
+
+            for (id, remaining) in fills {
+                let event = if remaining == 0 {
+                    OrderEvent::Filled { id, price, quantity }
+                } else {
+                    OrderEvent::PartiallyFilled { id, price, quantity, remaining }
+                };
+                self.emit(event);
+            }
+            if bid_emptied {
+                self.emit(BookEvent::LevelRemoved { side: Side::Buy, price: bid_price });
+            }
+            if ask_emptied {
+                self.emit(BookEvent::LevelRemoved { side: Side::Sell, price: ask_price });
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::mpsc::Sender;
+
+use crate::book::OrderBook;
+use crate::clock::Clock;
+use crate::events::Event;
+use crate::types::Trade;
+use crate::units::Units;
+use crate::wal::LogEntry;
+
+/// What one call on a book did: the commands it logged, with the events and trades they led
+/// to and the best prices around them. Enough to reconstruct why an order traded where it did
+/// without replaying the book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuditRecord {
+    /// Per-book record number, starting at 1
+    pub seq: u64,
+    /// Time the call started, by the audit's clock
+    pub timestamp: u64,
+    /// The entries the call added to the event log; one except for bulk cancels, which log a
+    /// cancel per order
+    pub commands: Vec<LogEntry>,
+    /// Events in the order they were emitted, including any best-price changes
+    pub events: Vec<Event>,
+    pub trades: Vec<Trade>,
+    /// Best bid and ask before and after the call
+    pub best_before: (Option<Units>, Option<Units>),
+    pub best_after: (Option<Units>, Option<Units>),
+}
+
+// Format: `<seq> <timestamp> <bid|->/<ask|-> -> <bid|->/<ask|-> | <command>; ... | <event>, ... |
+// <quantity>@<price> maker <id> taker <id>, ...`, with commands as in the event log and events
+// in their debug form
+impl fmt::Display for AuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn price(price: Option<Units>) -> String {
+            price.map_or_else(|| "-".to_string(), |price| price.to_string())
+        }
+
+        write!(
+            f,
+            "{} {} {}/{} -> {}/{} |",
+            self.seq,
+            self.timestamp,
+            price(self.best_before.0),
+            price(self.best_before.1),
+            price(self.best_after.0),
+            price(self.best_after.1)
+        )?;
+        for (n, command) in self.commands.iter().enumerate() {
+            write!(f, "{} {}", if n == 0 { "" } else { ";" }, command)?;
+        }
+        write!(f, " |")?;
+        for (n, event) in self.events.iter().enumerate() {
+            write!(f, "{} {:?}", if n == 0 { "" } else { "," }, event)?;
+        }
+        write!(f, " |")?;
+        for (n, trade) in self.trades.iter().enumerate() {
+            write!(
+                f,
+                "{} {}@{} maker {} taker {}",
+                if n == 0 { "" } else { "," },
+                trade.quantity,
+                trade.price,
+                trade.maker_id,
+                trade.taker_id
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the audit records of a book, one per call, once the call has finished.
+pub trait AuditSink: Send {
+    fn record(&mut self, record: &AuditRecord) -> io::Result<()>;
+}
+
+impl<F: FnMut(&AuditRecord) + Send> AuditSink for F {
+    fn record(&mut self, record: &AuditRecord) -> io::Result<()> {
+        self(record);
+        Ok(())
+    }
+}
+
+/// Forwards records to a channel, e.g. a drop-copy session; a disconnected receiver is ignored.
+impl AuditSink for Sender<AuditRecord> {
+    fn record(&mut self, record: &AuditRecord) -> io::Result<()> {
+        let _ = self.send(record.clone());
+        Ok(())
+    }
+}
+
+/// Writes each record as a line, flushing after every one so a file holds every call that
+/// finished before a crash.
+#[derive(Debug)]
+pub struct AuditWriter<W> {
+    writer: W,
+}
+
+impl<W: Write + Send> AuditWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> AuditSink for AuditWriter<W> {
+    fn record(&mut self, record: &AuditRecord) -> io::Result<()> {
+        writeln!(self.writer, "{}", record)?;
+        self.writer.flush()
+    }
+}
+
+pub(crate) struct Audit {
+    sink: Box<dyn AuditSink>,
+    clock: Box<dyn Clock>,
+    seq: u64,
+    // Nesting of the audited calls in progress; calls made by another call (a modify's
+    // cancel/replace, the orders a resume submits) belong to the outer call's record
+    depth: u32,
+    record: Option<AuditRecord>,
+    error: Option<io::Error>,
+}
+
+impl Audit {
+    fn open(&mut self, best_before: (Option<Units>, Option<Units>)) {
+        self.record = Some(AuditRecord {
+            seq: 0,
+            timestamp: self.clock.now(),
+            commands: Vec::new(),
+            events: Vec::new(),
+            trades: Vec::new(),
+            best_before,
+            best_after: best_before,
+        });
+    }
+
+    // Sends the open record, if the call logged anything
+    fn close(&mut self, best_after: (Option<Units>, Option<Units>)) {
+        let Some(mut record) = self.record.take() else {
+            return;
+        };
+        if record.commands.is_empty() {
+            return;
+        }
+        self.seq += 1;
+        record.seq = self.seq;
+        record.best_after = best_after;
+        if let Err(err) = self.sink.record(&record) {
+            self.error.get_or_insert(err);
+        }
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub(crate) fn event(&mut self, event: Event) {
+        if let Some(record) = &mut self.record {
+            record.events.push(event);
+        }
+    }
+
+    pub(crate) fn trades(&mut self, trades: &[Trade]) {
+        if let Some(record) = &mut self.record {
+            record.trades.extend_from_slice(trades);
+        }
+    }
+}
+
+impl OrderBook {
+    /// Send a record of every call that changes the book to `sink` from now on: the entries it
+    /// adds to the event log, stamped with `clock`, with the events, trades and best prices that
+    /// came of them. Replaces any previous sink. Listeners aren't needed for the events to be
+    /// recorded; transitions made by `update_session` are recorded as the calls it makes.
+    pub fn enable_audit(&mut self, sink: impl AuditSink + 'static, clock: Box<dyn Clock>) {
+        self.audit = Some(Audit { sink: Box::new(sink), clock, seq: 0, depth: 0, record: None, error: None });
+    }
+
+    pub fn disable_audit(&mut self) {
+        self.audit = None;
+    }
+
+    /// First error the audit sink returned, if any. Records keep being sent after an error.
+    pub fn audit_error(&self) -> Option<&io::Error> {
+        self.audit.as_ref().and_then(|audit| audit.error.as_ref())
+    }
+
+    // Runs `call` as one audited call: a record opens before it and is sent after it, unless
+    // it's nested in another audited call
+    pub(crate) fn audited<T>(&mut self, call: impl FnOnce(&mut Self) -> T) -> T {
+        if self.audit.is_none() {
+            return call(self);
+        }
+        let best_before = self.best_prices();
+        if let Some(audit) = &mut self.audit {
+            if audit.depth == 0 {
+                audit.open(best_before);
+            }
+            audit.depth += 1;
+        }
+        let result = call(self);
+        let best_after = self.best_prices();
+        if let Some(audit) = &mut self.audit {
+            audit.depth -= 1;
+            if audit.depth == 0 {
+                audit.close(best_after);
+            }
+        }
+        result
+    }
+
+    // Adds `entry` to the log, if kept, and to the audit record of the call making it. A call
+    // that isn't audited as a whole (one that emits nothing) gets a record of its own.
+    pub(crate) fn log_command(&mut self, entry: LogEntry) {
+        if self.audit.as_ref().is_some_and(|audit| audit.depth == 0) {
+            return self.audited(|ob| ob.log_command(entry));
+        }
+        if let Some(audit) = &mut self.audit {
+            if let (1, Some(record)) = (audit.depth, &mut audit.record) {
+                record.commands.push(entry.clone());
+            }
+        }
+        if let Some(log) = &mut self.log {
+            log.append(entry);
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! HTTP front end to an `Exchange`.
+//!
+//! Usage: `rest_server [ADDRESS] [SYMBOL...]`, e.g. `rest_server 127.0.0.1:8080 ACME INITECH`.
+
+use orderbook::{serve_http, Exchange, RestConfig};
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:8080".to_string());
+    let mut symbols: Vec<String> = args.collect();
+    if symbols.is_empty() {
+        symbols.push("ACME".to_string());
+    }
+
+    let mut exchange = Exchange::new();
+    for symbol in &symbols {
+        if let Err(err) = exchange.add_symbol(symbol) {
+            eprintln!("{}", err);
+        }
+    }
+    let listener = TcpListener::bind(&address).await?;
+    println!("serving {} on http://{}", symbols.join(", "), listener.local_addr()?);
+    serve_http(listener, exchange, RestConfig::default()).await
+}
@@ -0,0 +1,81 @@
+//! Terminal ladder view of a single book, with orders entered from the keyboard in the REPL's
+//! syntax (`buy 10@100`, `sell 5`, `cancel 3`).
+//!
+//! Usage: `tui [--demo [SEED]]`. With `--demo`, synthetic order flow keeps the book moving.
+
+use std::io;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use orderbook::{Command, FlowConfig, FlowGenerator, LadderView};
+use ratatui::crossterm::event::{self, Event};
+use ratatui::DefaultTerminal;
+
+// Time between commands of the demo flow
+const DEMO_TICK: Duration = Duration::from_millis(150);
+
+// Added to the demo flow's ids so they stay clear of the ids the book hands out to typed orders
+const DEMO_IDS: u64 = 1 << 40;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let seed = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => None,
+        ["--demo"] => Some(Ok(1)),
+        ["--demo", seed] => Some(seed.parse().map_err(|_| format!("invalid seed {}", seed))),
+        _ => Some(Err("usage: tui [--demo [SEED]]".to_string())),
+    };
+    let flow = match seed.transpose() {
+        Ok(seed) => seed.map(|seed| FlowGenerator::new(seed, FlowConfig::default())),
+        Err(message) => {
+            eprintln!("{}", message);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, LadderView::new(), flow);
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, mut view: LadderView, mut flow: Option<FlowGenerator>) -> io::Result<()> {
+    let mut next_tick = Instant::now() + DEMO_TICK;
+    loop {
+        terminal.draw(|frame| view.render(frame))?;
+        let timeout = match flow {
+            Some(_) => next_tick.saturating_duration_since(Instant::now()),
+            None => Duration::from_secs(60),
+        };
+        if event::poll(timeout)? {
+            if let Event::Key(key) = event::read()? {
+                if !view.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+        if let Some(flow) = flow.as_mut().filter(|_| Instant::now() >= next_tick) {
+            next_tick += DEMO_TICK;
+            if let Some((_, command)) = flow.next() {
+                view.apply(&demo_ids(command));
+            }
+        }
+    }
+}
+
+fn demo_ids(command: Command) -> Command {
+    match command {
+        Command::Place(mut order) => {
+            order.id += DEMO_IDS;
+            Command::Place(order)
+        }
+        Command::Cancel { id } => Command::Cancel { id: id + DEMO_IDS },
+        Command::Modify { id, price, quantity } => Command::Modify { id: id + DEMO_IDS, price, quantity },
+    }
+}
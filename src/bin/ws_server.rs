@@ -0,0 +1,26 @@
+//! WebSocket front end to an `Exchange`.
+//!
+//! Usage: `ws_server [ADDRESS] [SYMBOL...]`, e.g. `ws_server 127.0.0.1:9001 ACME INITECH`.
+
+use orderbook::{serve, Exchange, WsConfig};
+use tokio::net::TcpListener;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:9001".to_string());
+    let mut symbols: Vec<String> = args.collect();
+    if symbols.is_empty() {
+        symbols.push("ACME".to_string());
+    }
+
+    let mut exchange = Exchange::new();
+    for symbol in &symbols {
+        if let Err(err) = exchange.add_symbol(symbol) {
+            eprintln!("{}", err);
+        }
+    }
+    let listener = TcpListener::bind(&address).await?;
+    println!("serving {} on ws://{}", symbols.join(", "), listener.local_addr()?);
+    serve(listener, exchange, WsConfig::default()).await
+}
@@ -0,0 +1,1085 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::accounts::Accounts;
+use crate::audit::Audit;
+use crate::changes::LevelChanges;
+use crate::clock::{Clock, TestClock};
+use crate::error::{InvariantViolation, OrInvariant, OrderBookError};
+use crate::events::{BookEvent, Event, EventListener, OrderEvent};
+use crate::fees::Fees;
+use crate::halt::Halt;
+#[cfg(feature = "latency")]
+use crate::latency::LatencyReport;
+use crate::latency::{Operation, Stopwatch};
+use crate::order::{NewOrder, Order, OrderStatus, QueuePosition};
+use crate::match_policy::{Fifo, MatchPolicy};
+use crate::oco::OcoLinks;
+use crate::session::Session;
+use crate::peg::Pegs;
+use crate::priority::PriorityPolicy;
+use crate::risk::PreTradeCheck;
+use crate::slab::OrderSlab;
+use crate::strategy::TradeStrategy;
+use crate::seed::LoadError;
+use crate::stats::Counters;
+use crate::units::{notional, OverflowError, Units};
+use crate::types::{
+    CancelError, ExecutionReport, MarketRemainder, OrderOutcome, PlaceError, PostOnlyPolicy, PriceConfig, PriceConfigError,
+    ProtectionBand, ReduceOnlyPolicy, RejectReason, SelfTradePolicy, Side, StopTrigger, Trade, TrailingOffset,
+};
+use crate::wal::{EventLog, LogEntry};
+
+// The orders at one price, in priority order, as a queue linked through the book's order slab
+#[derive(Debug)]
+pub struct PriceLevel {
+    // Slab keys of the oldest and newest orders
+    head: Option<usize>,
+    tail: Option<usize>,
+    len: usize,
+    pub(crate) totals: LevelTotals,
+}
+
+// Running sums of the visible and hidden quantities of a level's orders, kept in step on every
+// insert, fill, amend and cancel so level queries don't walk the queue. Their sum always fits
+// in `Units`: `add` refuses anything that would overflow it. Kept apart from the orders so the
+// matching loops can update it while holding an order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct LevelTotals {
+    visible: Units,
+    hidden: Units,
+}
+
+impl LevelTotals {
+    // Count quantity added to the level's orders
+    pub(crate) fn add(&mut self, visible: Units, hidden: Units) -> Result<(), OverflowError> {
+        let new_visible = self.visible.checked_add(visible).ok_or(OverflowError)?;
+        let new_hidden = self.hidden.checked_add(hidden).ok_or(OverflowError)?;
+        new_visible.checked_add(new_hidden).ok_or(OverflowError)?;
+        (self.visible, self.hidden) = (new_visible, new_hidden);
+        Ok(())
+    }
+
+    // Whether `add` would take the quantity
+    pub(crate) fn fits(self, visible: Units, hidden: Units) -> bool {
+        let mut totals = self;
+        totals.add(visible, hidden).is_ok()
+    }
+
+    // Count quantity taken off the level's orders; more than the level holds is a bug
+    pub(crate) fn remove(&mut self, visible: Units, hidden: Units) -> Result<(), InvariantViolation> {
+        let new_visible = self.visible.checked_sub(visible).or_invariant("level visible quantity underflow")?;
+        let new_hidden = self.hidden.checked_sub(hidden).or_invariant("level hidden quantity underflow")?;
+        (self.visible, self.hidden) = (new_visible, new_hidden);
+        Ok(())
+    }
+
+    // Move an iceberg slice from hidden to visible
+    pub(crate) fn reveal(&mut self, slice: Units) -> Result<(), InvariantViolation> {
+        self.remove(0, slice)?;
+        self.add(slice, 0).or_invariant("revealing a slice keeps the level total")
+    }
+}
+
+impl PriceLevel {
+    pub(crate) fn new() -> Self {
+        Self { head: None, tail: None, len: 0, totals: LevelTotals::default() }
+    }
+
+    /// Visible quantity of the level.
+    pub(crate) fn total_quantity(&self) -> Units {
+        self.totals.visible
+    }
+
+    pub(crate) fn hidden_quantity(&self) -> Units {
+        self.totals.hidden
+    }
+
+    /// Visible plus hidden quantity.
+    pub(crate) fn open_quantity(&self) -> Units {
+        self.total_quantity() + self.hidden_quantity()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Slab key of the order first in line
+    pub(crate) fn front(&self) -> Option<usize> {
+        self.head
+    }
+
+    // Queue an order at the back, returning its slab key
+    pub(crate) fn push_back(&mut self, slab: &mut OrderSlab, order: Order) -> Result<usize, InvariantViolation> {
+        self.insert(slab, order, |_, _| false)
+    }
+
+    // Queue an order ahead of the orders at the back it goes `ahead` of, returning its slab key.
+    // Whoever rests it has checked the level total has room for it.
+    pub(crate) fn insert(
+        &mut self,
+        slab: &mut OrderSlab,
+        order: Order,
+        ahead: impl Fn(&Order, &Order) -> bool,
+    ) -> Result<usize, InvariantViolation> {
+        self.totals.add(order.quantity, order.hidden_quantity).or_invariant("level overflow is rejected before an order rests")?;
+        let key = slab.insert(order)?;
+        let after = self.place(slab, key, ahead)?;
+        self.link_after(slab, key, after)?;
+        self.debug_assert_totals(slab);
+        Ok(key)
+    }
+
+    // Queue an order right behind the order keyed `after`, or at the front for `None`,
+    // returning its slab key
+    pub(crate) fn insert_after(&mut self, slab: &mut OrderSlab, order: Order, after: Option<usize>) -> Result<usize, InvariantViolation> {
+        // Only ever an order `take_resting` took off this level
+        self.totals.add(order.quantity, order.hidden_quantity).or_invariant("restored order overflows its level")?;
+        let key = slab.insert(order)?;
+        self.link_after(slab, key, after)?;
+        self.debug_assert_totals(slab);
+        Ok(key)
+    }
+
+    // Take an order out of the queue and the slab; the totals are the caller's to adjust
+    pub(crate) fn remove(&mut self, slab: &mut OrderSlab, key: usize) -> Result<Order, InvariantViolation> {
+        self.unlink(slab, key)?;
+        Ok(slab.remove(key)?.order)
+    }
+
+    // Send an order behind every order of its tier or above, keeping its key
+    pub(crate) fn move_to_back(&mut self, slab: &mut OrderSlab, key: usize) -> Result<(), InvariantViolation> {
+        if self.tail != Some(key) {
+            self.unlink(slab, key)?;
+            let after = self.place(slab, key, |order, other| order.tier > other.tier)?;
+            self.link_after(slab, key, after)?;
+        }
+        Ok(())
+    }
+
+    // Queue every order again by tier, then in sequence order, each going `ahead` of the
+    // orders at the back as it would on arrival
+    pub(crate) fn requeue(&mut self, slab: &mut OrderSlab, ahead: impl Fn(&Order, &Order) -> bool) -> Result<(), InvariantViolation> {
+        let mut keys = Vec::with_capacity(self.len);
+        for key in self.keys(slab) {
+            let order = slab.order(key)?;
+            keys.push((std::cmp::Reverse(order.tier), order.seq, key));
+        }
+        keys.sort_unstable();
+        (self.head, self.tail, self.len) = (None, None, 0);
+        for (_, _, key) in keys {
+            let after = self.place(slab, key, &ahead)?;
+            self.link_after(slab, key, after)?;
+        }
+        Ok(())
+    }
+
+    /// Orders in priority order, first in line first.
+    pub(crate) fn iter<'a>(&self, slab: &'a OrderSlab) -> impl Iterator<Item = &'a Order> {
+        self.keys(slab).filter_map(|key| Some(&slab.get(key)?.order))
+    }
+
+    // Keys in priority order; a broken link ends the walk early, for `validate` to report
+    pub(crate) fn keys<'a>(&self, slab: &'a OrderSlab) -> impl Iterator<Item = usize> + 'a {
+        std::iter::successors(self.head, |&key| slab.get(key)?.next)
+    }
+
+    // Count and visible quantity of the orders queued ahead of `key`, walking back from it
+    pub(crate) fn ahead_of(&self, slab: &OrderSlab, key: usize) -> (usize, Units) {
+        std::iter::successors(slab.get(key).and_then(|node| node.prev), |&key| slab.get(key)?.prev)
+            .filter_map(|key| slab.get(key))
+            .fold((0, 0), |(count, quantity), node| (count + 1, quantity + node.order.quantity))
+    }
+
+    // Keys in time priority, following the links without trusting them; for `validate`
+    pub(crate) fn checked_keys(&self, slab: &OrderSlab) -> Result<Vec<usize>, InvariantViolation> {
+        let mut keys = Vec::with_capacity(self.len);
+        let mut prev = None;
+        let mut next = self.head;
+        while let Some(key) = next {
+            if keys.len() == self.len {
+                return Err(InvariantViolation("level queue is longer than its count"));
+            }
+            let node = slab.get(key).ok_or(InvariantViolation("level queue links to a vacant slot"))?;
+            if node.prev != prev {
+                return Err(InvariantViolation("level queue back link is broken"));
+            }
+            keys.push(key);
+            prev = Some(key);
+            next = node.next;
+        }
+        if keys.len() != self.len {
+            return Err(InvariantViolation("level queue is shorter than its count"));
+        }
+        if self.tail != prev {
+            return Err(InvariantViolation("level queue tail isn't its last order"));
+        }
+        Ok(keys)
+    }
+
+    // The queued order that unqueued `key` goes right behind, walking forward from the back
+    // past the orders it goes `ahead` of; `None` for the front
+    fn place(
+        &self,
+        slab: &OrderSlab,
+        key: usize,
+        ahead: impl Fn(&Order, &Order) -> bool,
+    ) -> Result<Option<usize>, InvariantViolation> {
+        let order = slab.order(key)?;
+        let mut after = self.tail;
+        while let Some(other) = after {
+            let node = slab.node(other)?;
+            if !ahead(order, &node.order) {
+                break;
+            }
+            after = node.prev;
+        }
+        Ok(after)
+    }
+
+    fn link_after(&mut self, slab: &mut OrderSlab, key: usize, after: Option<usize>) -> Result<(), InvariantViolation> {
+        let next = match after {
+            Some(after) => slab.node(after)?.next,
+            None => self.head,
+        };
+        let node = slab.node_mut(key)?;
+        node.prev = after;
+        node.next = next;
+        match after {
+            Some(after) => slab.node_mut(after)?.next = Some(key),
+            None => self.head = Some(key),
+        }
+        match next {
+            Some(next) => slab.node_mut(next)?.prev = Some(key),
+            None => self.tail = Some(key),
+        }
+        self.len += 1;
+        Ok(())
+    }
+
+    fn unlink(&mut self, slab: &mut OrderSlab, key: usize) -> Result<(), InvariantViolation> {
+        let node = slab.node(key)?;
+        let (prev, next) = (node.prev, node.next);
+        match prev {
+            Some(prev) => slab.node_mut(prev)?.next = next,
+            None => self.head = next,
+        }
+        match next {
+            Some(next) => slab.node_mut(next)?.prev = prev,
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+        Ok(())
+    }
+
+    // Check the running sums against the orders after a change; compiled out of release builds
+    #[inline]
+    pub(crate) fn debug_assert_totals(&self, slab: &OrderSlab) {
+        debug_assert_eq!(
+            Some(self.totals.visible),
+            self.iter(slab).try_fold(0, |sum: Units, o| sum.checked_add(o.quantity)),
+            "cached visible quantity out of step"
+        );
+        debug_assert_eq!(
+            Some(self.totals.hidden),
+            self.iter(slab).try_fold(0, |sum: Units, o| sum.checked_add(o.hidden_quantity)),
+            "cached hidden quantity out of step"
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepthLevel {
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub price: Units,
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub quantity: Units,
+    pub order_count: usize,
+}
+
+/// Top of the book per side, best price first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Depth {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+}
+
+/// What sweeping the book for a given quantity would cost, from `cost_to_buy` / `cost_to_sell`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepCost {
+    /// Quantity the visible book can fill, at most the quantity asked for
+    pub filled_quantity: Units,
+    /// Sum of price * quantity over the fills
+    pub notional: u128,
+    /// Price of the last level reached, `None` if the side is empty
+    pub worst_price: Option<Units>,
+    /// Whether the whole quantity could be filled
+    pub fully_filled: bool,
+}
+
+impl SweepCost {
+    /// Volume-weighted average fill price, `None` if nothing would fill.
+    pub fn vwap(&self) -> Option<f64> {
+        (self.filled_quantity > 0).then(|| self.notional as f64 / self.filled_quantity as f64)
+    }
+}
+
+/// Owned copy of the book's state: every resting order, held stop orders and the last trade.
+/// Used to hand a book over to another instance, persist it across restarts or reconcile
+/// against an external venue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookSnapshot {
+    pub buys: Vec<Order>,
+    pub sells: Vec<Order>,
+    /// Held stop orders as `(stop_price, order to submit once triggered)`, in trigger order
+    pub stops: Vec<(Units, NewOrder)>,
+    pub last_trade_price: Option<Units>,
+    pub last_trade_seq: u64,
+}
+
+pub struct OrderBook {
+    // Price levels in price order: the best bid is the last buy entry, the best ask the first
+    // sell entry
+    pub(crate) buy_map: BTreeMap<Units, PriceLevel>,
+    pub(crate) sell_map: BTreeMap<Units, PriceLevel>,
+    pub(crate) trade_buffer: Vec<Trade>,
+    pub(crate) trade_history: VecDeque<Trade>,
+    pub(crate) trade_history_capacity: usize,
+    // Sequence number of the last trade
+    pub(crate) trade_seq: u64,
+    pub(crate) last_trade_price: Option<Units>,
+    pub(crate) price_band: Option<Units>,
+    pub(crate) protection_band: Option<ProtectionBand>,
+    pub(crate) price_config: PriceConfig,
+    // Every resting order, queued by its price level
+    pub(crate) slab: OrderSlab,
+    // Resting order id -> (side, price) of its level and its slab key
+    pub(crate) order_index: HashMap<u64, (Side, Units, usize)>,
+    pub(crate) market_remainder: MarketRemainder,
+    pub(crate) post_only_policy: PostOnlyPolicy,
+    pub(crate) reduce_only_policy: ReduceOnlyPolicy,
+    pub(crate) last_outcome: Option<OrderOutcome>,
+    // Held stop orders keyed by stop price, in arrival order within a price
+    pub(crate) buy_stops: BTreeMap<Units, Vec<NewOrder>>,
+    pub(crate) sell_stops: BTreeMap<Units, Vec<NewOrder>>,
+    pub(crate) stop_index: HashMap<u64, (Side, Units)>,
+    // Offsets of the held trailing stops, by id
+    pub(crate) trailing_stops: BTreeMap<u64, TrailingOffset>,
+    pub(crate) stop_trigger: StopTrigger,
+    pub(crate) triggered_stops: Vec<u64>,
+    pub(crate) self_trade_policy: SelfTradePolicy,
+    pub(crate) listeners: Vec<Box<dyn EventListener>>,
+    // Events raised during the current call, dispatched once it completes
+    pub(crate) events: Vec<Event>,
+    // Sequence number given to the most recent order
+    pub(crate) last_order_seq: u64,
+    pub(crate) clock: Box<dyn Clock>,
+    // Operations recorded for replay, when enabled
+    pub(crate) log: Option<EventLog>,
+    // Clock readings taken ahead of the calls they're for, handed out before the clock's: those
+    // the log recorded with the entry being replayed, or one `peek_time` read
+    pub(crate) pending_times: VecDeque<u64>,
+    // Latest clock reading taken, which a restored checkpoint's clock carries on from
+    pub(crate) last_time: u64,
+    // Last id handed out by next_order_id
+    pub(crate) next_order_id: u64,
+    // Collecting orders for an auction instead of matching them
+    pub(crate) auction: bool,
+    // (expires_at, id) of good-till orders, resting or held. Entries of orders that left the
+    // book some other way are dropped when they come due.
+    pub(crate) expiry_index: BTreeSet<(u64, u64)>,
+    pub(crate) counters: Counters,
+    pub(crate) fees: Fees,
+    pub(crate) accounts: Accounts,
+    pub(crate) pre_trade_checks: Vec<Box<dyn PreTradeCheck>>,
+    pub(crate) halt: Halt,
+    pub(crate) strategies: Vec<Box<dyn TradeStrategy>>,
+    pub(crate) oco: OcoLinks,
+    pub(crate) pegs: Pegs,
+    pub(crate) match_policy: Box<dyn MatchPolicy>,
+    pub(crate) priority_policy: PriorityPolicy,
+    pub(crate) changes: LevelChanges,
+    pub(crate) session: Option<Session>,
+    pub(crate) audit: Option<Audit>,
+    #[cfg(feature = "latency")]
+    pub(crate) latency: LatencyReport,
+}
+
+impl OrderBook {
+    fn get_quantity_at_price(price_map: &BTreeMap<Units, PriceLevel>,  price: Units) -> Option<(Units, Units)> {
+        price_map.get(&price).map(|level| (price, level.total_quantity()))
+    }
+
+    pub fn buy_at(&self, price: Units) -> Option<(Units, Units)> {
+        OrderBook::get_quantity_at_price(&self.buy_map, price)
+    }
+
+    pub fn sell_at(&self, price: Units) -> Option<(Units, Units)> {
+        OrderBook::get_quantity_at_price(&self.sell_map, price)
+    }
+
+    /// Resting orders at `price` in priority order, first in line first.
+    pub fn orders_at(&self, side: Side, price: Units) -> Option<impl Iterator<Item = &Order>> {
+        self.levels(side).get(&price).map(|level| level.iter(&self.slab))
+    }
+
+    pub(crate) fn levels(&self, side: Side) -> &BTreeMap<Units, PriceLevel> {
+        match side {
+            Side::Buy => &self.buy_map,
+            Side::Sell => &self.sell_map,
+        }
+    }
+
+    pub(crate) fn levels_mut(&mut self, side: Side) -> &mut BTreeMap<Units, PriceLevel> {
+        match side {
+            Side::Buy => &mut self.buy_map,
+            Side::Sell => &mut self.sell_map,
+        }
+    }
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderBook {
+    /// A book timestamping orders and trades with `clock`, e.g. a `MonotonicClock` for real
+    /// time. `new` uses a deterministic `TestClock`.
+    pub fn with_clock(clock: Box<dyn Clock>) -> Self {
+        Self { clock, ..Self::new() }
+    }
+
+    /// Swap the clock, e.g. for a book an `Exchange` created. Timestamps already taken stay.
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn new() -> Self {
+        Self {
+            buy_map: BTreeMap::new(),
+            sell_map: BTreeMap::new(),
+            trade_buffer: Vec::with_capacity(128),
+            trade_history: VecDeque::new(),
+            trade_history_capacity: 0,
+            trade_seq: 0,
+            last_trade_price: None,
+            price_band: None,
+            protection_band: None,
+            price_config: PriceConfig::default(),
+            slab: OrderSlab::with_capacity(1024),
+            order_index: HashMap::with_capacity(1024),
+            market_remainder: MarketRemainder::Cancel,
+            post_only_policy: PostOnlyPolicy::Reject,
+            reduce_only_policy: ReduceOnlyPolicy::default(),
+            last_outcome: None,
+            buy_stops: BTreeMap::new(),
+            sell_stops: BTreeMap::new(),
+            stop_index: HashMap::new(),
+            trailing_stops: BTreeMap::new(),
+            stop_trigger: StopTrigger::LastTrade,
+            triggered_stops: Vec::new(),
+            self_trade_policy: SelfTradePolicy::CancelTaker,
+            listeners: Vec::new(),
+            events: Vec::new(),
+            last_order_seq: 0,
+            clock: Box::new(TestClock::default()),
+            log: None,
+            pending_times: VecDeque::new(),
+            last_time: 0,
+            next_order_id: 0,
+            auction: false,
+            expiry_index: BTreeSet::new(),
+            counters: Counters::default(),
+            fees: Fees::default(),
+            accounts: Accounts::default(),
+            pre_trade_checks: Vec::new(),
+            halt: Halt::default(),
+            strategies: Vec::new(),
+            oco: OcoLinks::default(),
+            pegs: Pegs::default(),
+            match_policy: Box::new(Fifo),
+            priority_policy: PriorityPolicy::default(),
+            changes: LevelChanges::default(),
+            session: None,
+            audit: None,
+            #[cfg(feature = "latency")]
+            latency: LatencyReport::default(),
+        }
+    }
+
+    /// Status of a resting order; `None` once it has left the book. The queue position costs
+    /// a walk over the orders ahead of it.
+    pub fn order_status(&self, id: u64) -> Option<OrderStatus> {
+        let &(side, price, key) = self.order_index.get(&id)?;
+        let (queue_position, _) = self.levels(side).get(&price)?.ahead_of(&self.slab, key);
+        let order = self.slab.order(key).ok()?;
+        Some(OrderStatus {
+            side,
+            price,
+            original_quantity: order.original_quantity,
+            remaining_quantity: order.remaining_quantity(),
+            filled_quantity: order.filled_quantity,
+            queue_position,
+        })
+    }
+
+    /// How many orders, and how much visible quantity, stand ahead of a resting order at its
+    /// price level; `None` once it has left the book. Walks back from the order to the front of
+    /// its level, so it costs the number of orders ahead.
+    pub fn queue_position(&self, id: u64) -> Option<QueuePosition> {
+        let &(side, price, key) = self.order_index.get(&id)?;
+        let (orders_ahead, quantity_ahead) = self.levels(side).get(&price)?.ahead_of(&self.slab, key);
+        Some(QueuePosition { side, price, orders_ahead, quantity_ahead })
+    }
+
+    /// Number of orders resting on the book (held stop orders not included).
+    pub fn order_count(&self) -> usize {
+        self.order_index.len()
+    }
+
+    pub fn best_buy(&self) -> Option<(Units, Units)> {
+        self.buy_map.last_key_value().map(|(&price, level)| (price, level.total_quantity()))
+    }
+
+    pub fn best_sell(&self) -> Option<(Units, Units)> {
+        self.sell_map.first_key_value().map(|(&price, level)| (price, level.total_quantity()))
+    }
+
+    /// The `n` best bid prices with their visible quantity, best first. Walks the level map in
+    /// place, so it costs O(log levels + n) and copies nothing.
+    pub fn best_n_buys(&self, n: usize) -> impl Iterator<Item = (Units, Units)> + '_ {
+        self.buy_map.iter().rev().take(n).map(|(&price, level)| (price, level.total_quantity()))
+    }
+
+    /// The `n` best ask prices with their visible quantity, best first; see `best_n_buys`.
+    pub fn best_n_sells(&self, n: usize) -> impl Iterator<Item = (Units, Units)> + '_ {
+        self.sell_map.iter().take(n).map(|(&price, level)| (price, level.total_quantity()))
+    }
+
+    /// Midpoint of the best bid and ask, `None` unless both sides have orders.
+    pub fn mid_price(&self) -> Option<f64> {
+        let ((bid, _), (ask, _)) = self.best_buy().zip(self.best_sell())?;
+        Some((bid as f64 + ask as f64) / 2.0)
+    }
+
+    /// Best ask minus best bid, `None` unless both sides have orders. Also `None` while an
+    /// auction has the book crossed.
+    pub fn spread(&self) -> Option<Units> {
+        let ((bid, _), (ask, _)) = self.best_buy().zip(self.best_sell())?;
+        ask.checked_sub(bid)
+    }
+
+    /// Mid price weighted by the visible size at the touch: leans towards the ask when the bid
+    /// is heavier and vice versa. `None` unless both sides have orders.
+    pub fn microprice(&self) -> Option<f64> {
+        let ((bid, bid_quantity), (ask, ask_quantity)) = self.best_buy().zip(self.best_sell())?;
+        let (bid_quantity, ask_quantity) = (bid_quantity as f64, ask_quantity as f64);
+        let total = bid_quantity + ask_quantity;
+        if total == 0.0 {
+            return self.mid_price();
+        }
+        Some((bid as f64 * ask_quantity + ask as f64 * bid_quantity) / total)
+    }
+
+    /// Remove a resting order from the book, returning it with its unfilled (visible and hidden)
+    /// quantity.
+    pub fn cancel_order(&mut self, id: u64) -> Result<Order, CancelError> {
+        let stopwatch = Stopwatch::start();
+        let result = self.audited(|ob| ob.cancel_resting(id));
+        self.record_latency(Operation::Cancel, stopwatch);
+        result
+    }
+
+    fn cancel_resting(&mut self, id: u64) -> Result<Order, CancelError> {
+        let best_before = self.best_prices();
+        let (order, emptied) = self.take_resting(id)?.ok_or(CancelError::UnknownOrder(id))?;
+        self.log_command(LogEntry::Cancel { id });
+        self.counters.cancels += 1;
+        self.emit(OrderEvent::Cancelled { id, remaining: order.remaining_quantity() });
+        if let Some((side, price)) = emptied {
+            self.emit(BookEvent::LevelRemoved { side, price });
+        }
+        self.reprice_pegs()?;
+        self.settle_oco()?;
+        self.dispatch_events(best_before);
+        Ok(order)
+    }
+
+    /// Take every good-till order with an expiry at or before `now` off the book, including
+    /// held stop orders, and return their ids in expiry order.
+    pub fn expire(&mut self, now: u64) -> Result<Vec<u64>, OrderBookError> {
+        self.audited(|ob| ob.expire_orders(now))
+    }
+
+    fn expire_orders(&mut self, now: u64) -> Result<Vec<u64>, OrderBookError> {
+        self.log_command(LogEntry::Expire { now });
+        let best_before = self.best_prices();
+        let mut expired = Vec::new();
+        while let Some(&(expires_at, id)) = self.expiry_index.first() {
+            if expires_at > now {
+                break;
+            }
+            self.expiry_index.pop_first();
+            let due = |order_expiry: Option<u64>| order_expiry == Some(expires_at);
+            let resting = self.order_index.get(&id).and_then(|&(_, _, key)| self.slab.get(key)?.order.expires_at);
+            if due(resting) {
+                let (order, emptied) = self.take_resting(id)?.or_invariant("expiring order isn't resting")?;
+                self.emit(OrderEvent::Expired { id, remaining: order.remaining_quantity() });
+                if let Some((side, price)) = emptied {
+                    self.emit(BookEvent::LevelRemoved { side, price });
+                }
+                expired.push(id);
+            } else if let Some(&(side, stop_price)) = self.stop_index.get(&id) {
+                let stops = match side {
+                    Side::Buy => &self.buy_stops,
+                    Side::Sell => &self.sell_stops,
+                };
+                let held = stops.get(&stop_price).or_invariant("indexed stop has no trigger level")?;
+                let held = held.iter().find(|o| o.id == id).and_then(|o| o.expires_at);
+                if due(held) {
+                    let order = self.take_stop(id)?.or_invariant("expiring stop isn't held")?;
+                    self.emit(OrderEvent::Expired { id, remaining: order.quantity });
+                    expired.push(id);
+                }
+            }
+        }
+        self.reprice_pegs()?;
+        self.settle_oco()?;
+        self.dispatch_events(best_before);
+        Ok(expired)
+    }
+
+    // Remove a resting order, returning it and the level it emptied, if any
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn take_resting(&mut self, id: u64) -> Result<Option<(Order, Option<(Side, Units)>)>, InvariantViolation> {
+        let Some((side, price, key)) = self.order_index.remove(&id) else {
+            return Ok(None);
+        };
+        let price_map = match side {
+            Side::Buy => &mut self.buy_map,
+            Side::Sell => &mut self.sell_map,
+        };
+        let level = price_map.get_mut(&price).or_invariant("indexed order has no price level")?;
+        self.changes.touch(side, price);
+        let order = level.remove(&mut self.slab, key)?;
+        level.totals.remove(order.quantity, order.hidden_quantity)?;
+        level.debug_assert_totals(&self.slab);
+
+        let emptied = level.is_empty();
+        if emptied {
+            price_map.remove(&price);
+        }
+        Ok(Some((order, emptied.then_some((side, price)))))
+    }
+
+    // Put an order `take_resting` took out back on `side` right behind the order keyed `after`,
+    // as if it had never left
+    fn restore_resting(&mut self, side: Side, order: Order, after: Option<usize>) -> Result<(), InvariantViolation> {
+        let (id, price) = (order.id, order.price);
+        self.changes.touch(side, price);
+        let price_map = match side {
+            Side::Buy => &mut self.buy_map,
+            Side::Sell => &mut self.sell_map,
+        };
+        let level = price_map.entry(price).or_insert_with(PriceLevel::new);
+        let key = level.insert_after(&mut self.slab, order, after)?;
+        self.order_index.insert(id, (side, price, key));
+        Ok(())
+    }
+
+    /// Cancel every resting order, bids first, each side in price-time priority. Held stop
+    /// orders are left alone.
+    pub fn cancel_all(&mut self) -> Result<Vec<Order>, OrderBookError> {
+        self.cancel_matching(&[Side::Buy, Side::Sell], |_| true)
+    }
+
+    /// Cancel every resting order on `side`.
+    pub fn cancel_side(&mut self, side: Side) -> Result<Vec<Order>, OrderBookError> {
+        self.cancel_matching(&[side], |_| true)
+    }
+
+    /// Cancel every resting order of `owner`.
+    pub fn cancel_by_owner(&mut self, owner: u64) -> Result<Vec<Order>, OrderBookError> {
+        self.cancel_where(|order| order.owner == Some(owner))
+    }
+
+    /// Cancel every resting order `predicate` returns true for; it sees each order once, in the
+    /// same order as `cancel_all`.
+    pub fn cancel_where(&mut self, predicate: impl FnMut(&Order) -> bool) -> Result<Vec<Order>, OrderBookError> {
+        self.cancel_matching(&[Side::Buy, Side::Sell], predicate)
+    }
+
+    fn cancel_matching(&mut self, sides: &[Side], predicate: impl FnMut(&Order) -> bool) -> Result<Vec<Order>, OrderBookError> {
+        self.audited(|ob| ob.remove_matching(sides, predicate))
+    }
+
+    fn remove_matching(
+        &mut self,
+        sides: &[Side],
+        mut predicate: impl FnMut(&Order) -> bool,
+    ) -> Result<Vec<Order>, OrderBookError> {
+        let best_before = self.best_prices();
+        let mut cancelled = Vec::new();
+        let mut emptied = Vec::new();
+        for &side in sides {
+            let levels: Box<dyn Iterator<Item = (&Units, &mut PriceLevel)>> = match side {
+                Side::Buy => Box::new(self.buy_map.iter_mut().rev()),
+                Side::Sell => Box::new(self.sell_map.iter_mut()),
+            };
+            for (&price, level) in levels {
+                let mut next = level.front();
+                while let Some(key) = next {
+                    let node = self.slab.node(key)?;
+                    next = node.next;
+                    if predicate(&node.order) {
+                        let order = level.remove(&mut self.slab, key)?;
+                        level.totals.remove(order.quantity, order.hidden_quantity)?;
+                        self.order_index.remove(&order.id);
+                        self.changes.touch(side, price);
+                        cancelled.push(order);
+                    }
+                }
+                level.debug_assert_totals(&self.slab);
+                if level.is_empty() {
+                    emptied.push((side, price));
+                }
+            }
+        }
+
+        for &(side, price) in &emptied {
+            self.levels_mut(side).remove(&price);
+        }
+        self.counters.cancels += cancelled.len() as u64;
+        for order in &cancelled {
+            self.log_command(LogEntry::Cancel { id: order.id });
+            self.emit(OrderEvent::Cancelled { id: order.id, remaining: order.remaining_quantity() });
+        }
+        for (side, price) in emptied {
+            self.emit(BookEvent::LevelRemoved { side, price });
+        }
+        self.reprice_pegs()?;
+        self.settle_oco()?;
+        self.dispatch_events(best_before);
+        Ok(cancelled)
+    }
+
+    /// Amend a resting order to `new_price` / `new_quantity` (the new open quantity, visible
+    /// plus hidden).
+    ///
+    /// Reducing the quantity at the same price keeps the order's place in the queue; an iceberg
+    /// gives up its reserve first. Any other change is a cancel/replace: the order is re-queued
+    /// with a fresh sequence number and may trade immediately if the new price crosses. A
+    /// `new_quantity` of zero cancels the order. A replacement the book would reject, say for a
+    /// price off tick or outside the band, or while the book is halted, rejects the modify and
+    /// leaves the order as it was; pre-trade checks see it then and again as it's submitted.
+    /// The replacement keeps the order's post-only and reduce-only flags. A pegged order can
+    /// only be reduced in place: anything else is rejected with `RejectReason::Pegged`.
+    pub fn modify_order(&mut self, id: u64, new_price: Units, new_quantity: Units) -> Result<ExecutionReport, CancelError> {
+        self.audited(|ob| ob.modify_resting(id, new_price, new_quantity))
+    }
+
+    fn modify_resting(&mut self, id: u64, new_price: Units, new_quantity: Units) -> Result<ExecutionReport, CancelError> {
+        let tags = self.order_index.get(&id).and_then(|&(_, _, key)| {
+            let order = &self.slab.get(key)?.order;
+            Some((order.client_order_id.clone(), order.user_data))
+        });
+        // Logged as one entry rather than as the cancel and submit it may be carried out with
+        let (result, times) = self.unlogged(|ob| ob.amend_order(id, new_price, new_quantity));
+        let mut report = result?;
+        if let Some((client_order_id, user_data)) = tags {
+            report.client_order_id = client_order_id;
+            report.user_data = user_data;
+        }
+        self.log_command(LogEntry::Modify { id, price: new_price, quantity: new_quantity });
+        self.log_times(times);
+        Ok(report)
+    }
+
+    fn amend_order(&mut self, id: u64, new_price: Units, new_quantity: Units) -> Result<ExecutionReport, CancelError> {
+        let &(side, price, key) = self.order_index.get(&id).ok_or(CancelError::UnknownOrder(id))?;
+        self.trade_buffer.clear();
+
+        if new_quantity == 0 {
+            let cancelled = self.cancel_order(id)?;
+            return Ok(self.execution_report(id, cancelled.remaining_quantity(), OrderOutcome::Cancelled));
+        }
+
+        if new_price == price {
+            let level = match side {
+                Side::Buy => self.buy_map.get_mut(&price),
+                Side::Sell => self.sell_map.get_mut(&price),
+            };
+            let level = level.or_invariant("indexed order has no price level")?;
+            let order = self.slab.order_mut(key)?;
+            if new_quantity <= order.quantity + order.hidden_quantity {
+                self.changes.touch(side, price);
+                let hidden = order.hidden_quantity.min(new_quantity.saturating_sub(order.quantity));
+                let visible = new_quantity - hidden;
+                level.totals.remove(order.quantity - visible, order.hidden_quantity - hidden)?;
+                order.hidden_quantity = hidden;
+                order.quantity = visible;
+                level.debug_assert_totals(&self.slab);
+                return Ok(self.execution_report(id, new_quantity, OrderOutcome::Rested));
+            }
+        }
+
+        if self.pegs.orders.contains_key(&id) {
+            self.counters.rejects += 1;
+            return Ok(ExecutionReport::rejected(id, new_quantity, RejectReason::Pegged));
+        }
+        let order = self.slab.order(key)?;
+        let mut replacement = NewOrder::limit(side, new_price, new_quantity, id);
+        replacement.post_only = order.post_only;
+        replacement.reduce_only = order.reduce_only;
+        replacement.display_quantity = order.display_quantity;
+        replacement.owner = order.owner;
+        replacement.expires_at = order.expires_at;
+        replacement.client_order_id = order.client_order_id.clone();
+        replacement.user_data = order.user_data;
+        replacement.min_quantity = order.min_quantity;
+
+        // Try the replacement against the book without the order before giving the order up,
+        // so a rejected one leaves it in its place
+        let after = self.slab.node(key)?.prev;
+        let (order, _) = self.take_resting(id)?.or_invariant("indexed order isn't resting")?;
+        let admitted = self.admit(&mut replacement.clone());
+        self.restore_resting(side, order, after)?;
+        if let Err(reason) = admitted {
+            self.counters.rejects += 1;
+            return Ok(ExecutionReport::rejected(id, new_quantity, reason));
+        }
+
+        // The replacement keeps the order's link, so cancelling it mustn't settle the pair
+        let link = self.oco.unlink(id);
+        self.cancel_order(id)?;
+        if let Some((partner, mode)) = link {
+            self.oco.link(id, partner, mode);
+        }
+        self.submit(replacement).map_err(|err| match err {
+            PlaceError::DuplicateId(_) => CancelError::Invariant(InvariantViolation::new("id freed by the cancel is in use")),
+            PlaceError::Invariant(err) => CancelError::Invariant(err),
+        })
+    }
+
+    /// Reject orders priced more than `max_deviation` away from the last trade price.
+    /// Has no effect until the book has traded at least once.
+    pub fn set_price_band(&mut self, max_deviation: Units) {
+        self.price_band = Some(max_deviation);
+    }
+
+    /// Limit how far past the opposite best price an aggressive order may sweep; `None` turns
+    /// the band off. It doesn't apply to the auction uncross.
+    pub fn set_protection_band(&mut self, band: Option<ProtectionBand>) {
+        self.protection_band = band;
+    }
+
+    pub fn protection_band(&self) -> Option<ProtectionBand> {
+        self.protection_band
+    }
+
+    /// Tick and lot sizes incoming orders are checked against. Orders already on the book are
+    /// left as they are. A grid that fails `PriceConfig::validate` is refused, leaving the
+    /// current one.
+    pub fn set_price_config(&mut self, config: PriceConfig) -> Result<(), PriceConfigError> {
+        config.validate()?;
+        self.price_config = config;
+        Ok(())
+    }
+
+    pub fn price_config(&self) -> PriceConfig {
+        self.price_config
+    }
+
+    pub fn last_trade_price(&self) -> Option<Units> {
+        self.last_trade_price
+    }
+
+    /// Start keeping the last `capacity` trades, oldest evicted first.
+    pub fn enable_trade_history(&mut self, capacity: usize) {
+        self.trade_history_capacity = capacity;
+        while self.trade_history.len() > capacity {
+            self.trade_history.pop_front();
+        }
+        self.trade_history.reserve(capacity.saturating_sub(self.trade_history.len()));
+    }
+
+    /// Trades retained since history was enabled, oldest first.
+    pub fn recent_trades(&self) -> &VecDeque<Trade> {
+        &self.trade_history
+    }
+
+    /// Retained trades with a sequence number greater than `seq`, oldest first.
+    pub fn trades_since(&self, seq: u64) -> impl Iterator<Item = &Trade> {
+        let start = self.trade_history.partition_point(|t| t.seq <= seq);
+        self.trade_history.range(start..)
+    }
+
+    /// The last `n` retained trades, oldest first.
+    pub fn last_n_trades(&self, n: usize) -> impl Iterator<Item = &Trade> {
+        let start = self.trade_history.len().saturating_sub(n);
+        self.trade_history.range(start..)
+    }
+
+    /// Sequence number of the most recent trade, 0 before the first.
+    pub fn last_trade_seq(&self) -> u64 {
+        self.trade_seq
+    }
+
+    /// Top `n` price buckets, best first. Buy prices round down and sell prices round up to a
+    /// multiple of `bucket_size`, so a bucket never looks better than the orders in it.
+    pub fn aggregated_depth(&self, side: Side, bucket_size: Units, n: usize) -> Vec<(Units, Units)> {
+        let bucket_size = bucket_size.max(1);
+        let mut buckets: BTreeMap<Units, Units> = BTreeMap::new();
+        for (&price, level) in self.levels(side) {
+            let bucket = match side {
+                Side::Buy => price / bucket_size * bucket_size,
+                Side::Sell => price.div_ceil(bucket_size).saturating_mul(bucket_size),
+            };
+            let total = buckets.entry(bucket).or_insert(0);
+            *total = total.saturating_add(level.total_quantity());
+        }
+        match side {
+            Side::Buy => buckets.into_iter().rev().take(n).collect(),
+            Side::Sell => buckets.into_iter().take(n).collect(),
+        }
+    }
+
+    /// Best `levels` price levels on each side, with visible quantity and order count.
+    pub fn depth(&self, levels: usize) -> Depth {
+        let depth_level = |(&price, level): (&Units, &PriceLevel)| DepthLevel {
+            price,
+            quantity: level.total_quantity(),
+            order_count: level.len(),
+        };
+        Depth {
+            bids: self.buy_map.iter().rev().take(levels).map(depth_level).collect(),
+            asks: self.sell_map.iter().take(levels).map(depth_level).collect(),
+        }
+    }
+
+    /// Cost of a market buy for `quantity` against the visible asks, leaving the book untouched.
+    /// Hidden iceberg reserves aren't counted, nor is self-trade prevention.
+    pub fn cost_to_buy(&self, quantity: Units) -> SweepCost {
+        Self::sweep_cost(self.sell_map.iter(), quantity)
+    }
+
+    /// Cost of a market sell for `quantity` against the visible bids; see `cost_to_buy`.
+    pub fn cost_to_sell(&self, quantity: Units) -> SweepCost {
+        Self::sweep_cost(self.buy_map.iter().rev(), quantity)
+    }
+
+    fn sweep_cost<'a>(levels: impl Iterator<Item = (&'a Units, &'a PriceLevel)>, quantity: Units) -> SweepCost {
+        let mut cost = SweepCost { filled_quantity: 0, notional: 0, worst_price: None, fully_filled: quantity == 0 };
+        for (&price, level) in levels {
+            if cost.fully_filled {
+                break;
+            }
+            let fill = level.total_quantity().min(quantity - cost.filled_quantity);
+            cost.filled_quantity += fill;
+            cost.notional = cost.notional.saturating_add(notional(price, fill));
+            cost.worst_price = Some(price);
+            cost.fully_filled = cost.filled_quantity == quantity;
+        }
+        cost
+    }
+
+    /// Every resting buy order in price-time priority: best price first, oldest first within
+    /// a price.
+    pub fn iter_bids(&self) -> impl Iterator<Item = &Order> {
+        self.buy_map.values().rev().flat_map(|level| level.iter(&self.slab))
+    }
+
+    /// Every resting sell order in price-time priority.
+    pub fn iter_asks(&self) -> impl Iterator<Item = &Order> {
+        self.sell_map.values().flat_map(|level| level.iter(&self.slab))
+    }
+
+    /// Full order-by-order copy of the book, each side in price-time priority.
+    pub fn snapshot(&self) -> BookSnapshot {
+        let stops = self
+            .buy_stops
+            .iter()
+            .chain(self.sell_stops.iter())
+            .flat_map(|(&stop_price, orders)| orders.iter().map(move |order| (stop_price, order.clone())))
+            .collect();
+        BookSnapshot {
+            buys: self.iter_bids().cloned().collect(),
+            sells: self.iter_asks().cloned().collect(),
+            stops,
+            last_trade_price: self.last_trade_price,
+            last_trade_seq: self.trade_seq,
+        }
+    }
+
+    /// Same as `from_snapshot`.
+    pub fn restore(snapshot: BookSnapshot) -> Result<Self, LoadError> {
+        Self::from_snapshot(snapshot)
+    }
+
+    /// Rebuild a book from a snapshot; orders are re-queued by tier and sequence number so
+    /// their priority is preserved, short of ties a `PriorityPolicy` broke.
+    /// Configuration (policies, price band, trade history) is not part of the snapshot, and
+    /// pegged orders come back as plain limit orders at their current price.
+    ///
+    /// Fails with `LoadError::LevelOverflow` if a price level's quantity would overflow, which
+    /// only a snapshot that doesn't come from a book, e.g. one read from disk, can do.
+    pub fn from_snapshot(snapshot: BookSnapshot) -> Result<Self, LoadError> {
+        fn build(
+            mut orders: Vec<Order>,
+            side: Side,
+            slab: &mut OrderSlab,
+            order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        ) -> Result<BTreeMap<Units, PriceLevel>, LoadError> {
+            orders.sort_by_key(|o| (std::cmp::Reverse(o.tier), o.seq));
+            let mut price_map: BTreeMap<Units, PriceLevel> = BTreeMap::new();
+            for order in orders {
+                let (id, price) = (order.id, order.price);
+                let level = price_map.entry(price).or_insert_with(PriceLevel::new);
+                if !level.totals.fits(order.quantity, order.hidden_quantity) {
+                    return Err(LoadError::LevelOverflow { side, price });
+                }
+                let key = level.push_back(slab, order)?;
+                order_index.insert(id, (side, price, key));
+            }
+            Ok(price_map)
+        }
+
+        let mut ob = Self::new();
+        ob.last_order_seq = snapshot.buys.iter().chain(&snapshot.sells).map(|o| o.seq).max().unwrap_or(0);
+        ob.expiry_index = snapshot
+            .buys
+            .iter()
+            .chain(&snapshot.sells)
+            .filter_map(|o| o.expires_at.map(|expires_at| (expires_at, o.id)))
+            .collect();
+        ob.buy_map = build(snapshot.buys, Side::Buy, &mut ob.slab, &mut ob.order_index)?;
+        ob.sell_map = build(snapshot.sells, Side::Sell, &mut ob.slab, &mut ob.order_index)?;
+        for (stop_price, order) in snapshot.stops {
+            ob.hold_stop(stop_price, order);
+        }
+        ob.last_trade_price = snapshot.last_trade_price;
+        ob.trade_seq = snapshot.last_trade_seq;
+        Ok(ob)
+    }
+
+    pub(crate) fn record_history(&mut self) {
+        if self.trade_history_capacity == 0 {
+            return;
+        }
+        for trade in &self.trade_buffer {
+            if self.trade_history.len() == self.trade_history_capacity {
+                self.trade_history.pop_front();
+            }
+            self.trade_history.push_back(trade.clone());
+        }
+    }
+}
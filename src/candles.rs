@@ -0,0 +1,217 @@
+use std::collections::VecDeque;
+
+use crate::events::TradeEvent;
+use crate::types::Trade;
+use crate::units::{notional, widen, Units};
+
+/// Open, high, low, close and volume of the trades in one interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candle {
+    /// Start of the interval: a multiple of its length, in clock units
+    pub open_time: u64,
+    pub open: Units,
+    pub high: Units,
+    pub low: Units,
+    pub close: Units,
+    pub volume: u128,
+    /// Price times quantity of the trades, for the volume-weighted average price
+    pub notional: u128,
+    pub trade_count: u64,
+}
+
+impl Candle {
+    fn new(open_time: u64, price: Units, quantity: Units) -> Self {
+        let mut candle =
+            Candle { open_time, open: price, high: price, low: price, close: price, volume: 0, notional: 0, trade_count: 0 };
+        candle.add(price, quantity);
+        candle
+    }
+
+    fn add(&mut self, price: Units, quantity: Units) {
+        self.high = self.high.max(price);
+        self.low = self.low.min(price);
+        self.close = price;
+        self.volume = self.volume.saturating_add(widen(quantity));
+        self.notional = self.notional.saturating_add(notional(price, quantity));
+        self.trade_count += 1;
+    }
+
+    /// Volume-weighted average price of the candle's trades.
+    pub fn vwap(&self) -> f64 {
+        self.notional as f64 / self.volume as f64
+    }
+}
+
+// A candle and the trades that went into it, as (seq, price, quantity) in arrival order, so a
+// bust or correction can rebuild it
+#[derive(Debug, Clone)]
+struct Bar {
+    candle: Candle,
+    prints: Vec<(u64, Units, Units)>,
+}
+
+impl Bar {
+    fn new(open_time: u64, trade: &Trade) -> Self {
+        Self {
+            candle: Candle::new(open_time, trade.price, trade.quantity),
+            prints: vec![(trade.seq, trade.price, trade.quantity)],
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.candle.add(trade.price, trade.quantity);
+        self.prints.push((trade.seq, trade.price, trade.quantity));
+    }
+
+    // Recompute the candle from the prints left, if there are any
+    fn rebuild(&mut self) {
+        let mut prints = self.prints.iter();
+        if let Some(&(_, price, quantity)) = prints.next() {
+            self.candle = Candle::new(self.candle.open_time, price, quantity);
+            for &(_, price, quantity) in prints {
+                self.candle.add(price, quantity);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Series {
+    interval: u64,
+    completed: VecDeque<Bar>,
+    live: Option<Bar>,
+}
+
+/// Builds OHLCV candles from trades for several interval lengths at once.
+///
+/// Intervals are in the units of the book's clock (nanoseconds for `MonotonicClock`, so
+/// `60_000_000_000` for one-minute candles) and aligned to multiples of their length. A candle
+/// stays live until a trade arrives in a later interval or `close_until` passes its end;
+/// intervals without trades get no candle. Trades are expected in timestamp order, as books
+/// report them; one timestamped before the live candle is added to it.
+#[derive(Debug, Clone)]
+pub struct CandleAggregator {
+    series: Vec<Series>,
+    // Most completed candles kept per interval; 0 keeps them all
+    history: usize,
+}
+
+impl CandleAggregator {
+    /// Aggregator for the given interval lengths, keeping every completed candle. Zero lengths
+    /// and duplicates are ignored.
+    pub fn new(intervals: &[u64]) -> Self {
+        let mut series: Vec<Series> = Vec::with_capacity(intervals.len());
+        for &interval in intervals {
+            if interval > 0 && series.iter().all(|s| s.interval != interval) {
+                series.push(Series { interval, completed: VecDeque::new(), live: None });
+            }
+        }
+        Self { series, history: 0 }
+    }
+
+    /// Keep only the last `history` completed candles of each interval; 0 keeps them all.
+    pub fn with_history(mut self, history: usize) -> Self {
+        self.history = history;
+        for series in &mut self.series {
+            Self::trim(&mut series.completed, history);
+        }
+        self
+    }
+
+    pub fn intervals(&self) -> impl Iterator<Item = u64> + '_ {
+        self.series.iter().map(|s| s.interval)
+    }
+
+    pub fn on_trade(&mut self, trade: &Trade) {
+        for series in &mut self.series {
+            let open_time = trade.timestamp - trade.timestamp % series.interval;
+            match &mut series.live {
+                Some(live) if open_time <= live.candle.open_time => live.add(trade),
+                live => {
+                    if let Some(done) = live.replace(Bar::new(open_time, trade)) {
+                        series.completed.push_back(done);
+                        Self::trim(&mut series.completed, self.history);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn on_trades(&mut self, trades: &[Trade]) {
+        for trade in trades {
+            self.on_trade(trade);
+        }
+    }
+
+    /// Follow a trade bust or correction into the candles the trade went into, live or
+    /// completed, if still kept. Each is rebuilt from its other trades, and the trade as
+    /// corrected, as if the trade had never printed or printed as corrected; a candle left
+    /// without trades is dropped. Trades are matched by `seq`.
+    pub fn on_trade_event(&mut self, event: &TradeEvent) {
+        let (seq, timestamp, new) = match *event {
+            TradeEvent::Busted { seq, timestamp, .. } => (seq, timestamp, None),
+            TradeEvent::Corrected { seq, timestamp, price, quantity, .. } => (seq, timestamp, Some((price, quantity))),
+        };
+        for series in &mut self.series {
+            let open_time = timestamp - timestamp % series.interval;
+            let bar = match &mut series.live {
+                Some(live) if live.candle.open_time == open_time => Some(live),
+                _ => series.completed.iter_mut().find(|bar| bar.candle.open_time == open_time),
+            };
+            let Some(bar) = bar else {
+                continue;
+            };
+            let Some(index) = bar.prints.iter().position(|&(print, ..)| print == seq) else {
+                continue;
+            };
+            match new {
+                Some((price, quantity)) => bar.prints[index] = (seq, price, quantity),
+                None => {
+                    bar.prints.remove(index);
+                }
+            }
+            bar.rebuild();
+            if bar.prints.is_empty() {
+                if series.live.as_ref().is_some_and(|live| live.prints.is_empty()) {
+                    series.live = None;
+                } else {
+                    series.completed.retain(|bar| !bar.prints.is_empty());
+                }
+            }
+        }
+    }
+
+    /// Complete the live candles whose interval ends at or before `now`, for when no trade
+    /// has come along to close them.
+    pub fn close_until(&mut self, now: u64) {
+        for series in &mut self.series {
+            if series.live.as_ref().is_some_and(|live| live.candle.open_time.saturating_add(series.interval) <= now) {
+                series.completed.extend(series.live.take());
+                Self::trim(&mut series.completed, self.history);
+            }
+        }
+    }
+
+    /// Completed candles of `interval`, oldest first; empty for an interval not aggregated.
+    pub fn completed(&self, interval: u64) -> impl DoubleEndedIterator<Item = &Candle> + '_ {
+        self.find(interval).into_iter().flat_map(|series| series.completed.iter().map(|bar| &bar.candle))
+    }
+
+    /// The candle of `interval` still taking trades.
+    pub fn live(&self, interval: u64) -> Option<&Candle> {
+        self.find(interval)?.live.as_ref().map(|bar| &bar.candle)
+    }
+
+    fn find(&self, interval: u64) -> Option<&Series> {
+        self.series.iter().find(|s| s.interval == interval)
+    }
+
+    fn trim(completed: &mut VecDeque<Bar>, history: usize) {
+        if history > 0 {
+            while completed.len() > history {
+                completed.pop_front();
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+
+use crate::book::OrderBook;
+use crate::types::Side;
+use crate::units::Units;
+
+// Price levels touched since the last drain, while tracking is on: added, removed, or with
+// orders resting, trading, shrinking or leaving there
+#[derive(Debug, Default)]
+pub(crate) struct LevelChanges {
+    enabled: bool,
+    levels: HashSet<(Side, Units)>,
+}
+
+impl LevelChanges {
+    pub(crate) fn touch(&mut self, side: Side, price: Units) {
+        if self.enabled {
+            self.levels.insert((side, price));
+        }
+    }
+}
+
+impl OrderBook {
+    /// Record which price levels each call touches, for `drain_changes`. A touched level costs
+    /// one hash set insert, however big the book.
+    pub fn enable_change_tracking(&mut self) {
+        self.changes.enabled = true;
+    }
+
+    /// Price levels touched since tracking was enabled or the last drain, bids first, each
+    /// side by ascending price; empty without tracking. A level is listed once however often
+    /// it was touched, and may not have changed visibly (an iceberg refilled, or an order came
+    /// and went): look each up with `buy_at` or `sell_at`, `None` meaning it's gone.
+    /// `L2Publisher::publish_changes` turns them into feed updates.
+    pub fn drain_changes(&mut self) -> Vec<(Side, Units)> {
+        let mut levels: Vec<(Side, Units)> = self.changes.levels.drain().collect();
+        levels.sort_unstable_by_key(|&(side, price)| (side == Side::Sell, price));
+        levels
+    }
+}
@@ -0,0 +1,518 @@
+use std::fmt;
+
+use crate::accounts::Position;
+use crate::clock::TestClock;
+use crate::book::{BookSnapshot, OrderBook};
+use crate::error::InvariantViolation;
+use crate::oco::OcoMode;
+use crate::order::{ClientOrderId, NewOrder, Order};
+use crate::peg::Peg;
+use crate::seed::LoadError;
+use crate::types::{OrderType, PegReference, Side, TimeInForce, TrailingOffset};
+use crate::wal::LogEntry;
+
+const MAGIC: &[u8; 4] = b"OBCK";
+const VERSION: u8 = 9;
+
+/// Compact binary image of a book's state, and how much of its event log it covers.
+///
+/// It holds the resting orders, held stops, orders queued during a halt, trading state, OCO
+/// links, pegs and owners' positions, plus the sequence counters, so the log entries after `log_position` bring a
+/// restored book to where the original is. Configuration (policies, fees, checks, listeners)
+/// and statistics aren't included. The restored book gets a `TestClock` carrying on from the
+/// last reading the original took; `set_clock` swaps it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    log_position: usize,
+    state: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckpointError {
+    /// The bytes don't start with a checkpoint header
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// The data ends in the middle of a field
+    Truncated,
+    /// A field holds a value no checkpoint contains
+    Invalid(&'static str),
+    /// Rebuilding the book or replaying the tail ran into an `InvariantViolation`
+    Invariant(InvariantViolation),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::BadMagic => write!(f, "not a checkpoint"),
+            CheckpointError::UnsupportedVersion(version) => write!(f, "unsupported checkpoint version {}", version),
+            CheckpointError::Truncated => write!(f, "checkpoint is truncated"),
+            CheckpointError::Invalid(what) => write!(f, "invalid checkpoint: {}", what),
+            CheckpointError::Invariant(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<InvariantViolation> for CheckpointError {
+    fn from(err: InvariantViolation) -> Self {
+        CheckpointError::Invariant(err)
+    }
+}
+
+impl Checkpoint {
+    /// Number of log entries the checkpoint covers; restore with the entries from there on.
+    pub fn log_position(&self) -> usize {
+        self.log_position
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Writer(Vec::with_capacity(self.state.len() + 16));
+        out.0.extend_from_slice(MAGIC);
+        out.0.push(VERSION);
+        out.uint(self.log_position as u64);
+        out.0.extend_from_slice(&self.state);
+        out.0
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CheckpointError> {
+        let body = bytes.strip_prefix(MAGIC.as_slice()).ok_or(CheckpointError::BadMagic)?;
+        let (&version, body) = body.split_first().ok_or(CheckpointError::Truncated)?;
+        if version != VERSION {
+            return Err(CheckpointError::UnsupportedVersion(version));
+        }
+        let mut reader = Reader(body);
+        let log_position = usize::try_from(reader.u64()?).map_err(|_| CheckpointError::Invalid("log position"))?;
+        Ok(Self { log_position, state: reader.0.to_vec() })
+    }
+}
+
+impl OrderBook {
+    /// Capture the book's state, covering every entry logged so far (none if the log is off).
+    pub fn checkpoint(&self) -> Checkpoint {
+        let mut out = Writer(Vec::new());
+        let snapshot = self.snapshot();
+        for orders in [&snapshot.buys, &snapshot.sells] {
+            out.uint(orders.len() as u64);
+            for order in orders {
+                out.order(order);
+            }
+        }
+        out.uint(snapshot.stops.len() as u64);
+        for (stop_price, order) in &snapshot.stops {
+            out.uint(*stop_price);
+            out.new_order(order);
+        }
+        out.uint(self.halt.queue.len() as u64);
+        for order in &self.halt.queue {
+            out.new_order(order);
+        }
+        out.option(self.last_trade_price);
+        for value in [self.trade_seq, self.last_order_seq, self.next_order_id, self.last_time] {
+            out.uint(value);
+        }
+        out.bool(self.auction);
+        out.bool(self.halt.halted);
+        let links = self.oco.pairs();
+        out.uint(links.len() as u64);
+        for (first, second, mode) in links {
+            out.uint(first);
+            out.uint(second);
+            out.bool(mode == OcoMode::Reduce);
+        }
+        out.uint(self.pegs.orders.len() as u64);
+        for (&id, peg) in &self.pegs.orders {
+            out.uint(id);
+            out.peg_reference(peg.reference);
+            out.i64(peg.offset);
+        }
+        let mut positions: Vec<_> = self.accounts.positions().collect();
+        positions.sort_unstable_by_key(|&(owner, _)| owner);
+        out.uint(positions.len() as u64);
+        for (owner, position) in positions {
+            out.uint(owner);
+            out.position(position);
+        }
+        Checkpoint { log_position: self.log.as_ref().map_or(0, |log| log.len()), state: out.0 }
+    }
+
+    /// Rebuild a book from `checkpoint` and replay `tail`, the log entries recorded after it,
+    /// with `times`, the clock readings recorded with them (`EventLog::times` from the same
+    /// position). As with `apply_log`, tail entries that fail are skipped, short of an invariant
+    /// violation. The restored book starts without a log.
+    pub fn restore_checkpoint(
+        checkpoint: &Checkpoint,
+        tail: &[LogEntry],
+        times: &[Vec<u64>],
+    ) -> Result<Self, CheckpointError> {
+        let mut input = Reader(&checkpoint.state);
+        let mut sides = [Vec::new(), Vec::new()];
+        for orders in &mut sides {
+            for _ in 0..input.len()? {
+                orders.push(input.order()?);
+            }
+        }
+        let mut stops = Vec::new();
+        for _ in 0..input.len()? {
+            stops.push((input.uint()?, input.new_order()?));
+        }
+        let mut queue = Vec::new();
+        for _ in 0..input.len()? {
+            queue.push(input.new_order()?);
+        }
+        let last_trade_price = input.option()?;
+        let (trade_seq, last_order_seq, next_order_id) = (input.u64()?, input.u64()?, input.u64()?);
+        let last_time = input.u64()?;
+        let [buys, sells] = sides;
+        let snapshot = BookSnapshot { buys, sells, stops, last_trade_price, last_trade_seq: trade_seq };
+        let mut ob = OrderBook::from_snapshot(snapshot).map_err(|err| match err {
+            LoadError::Invariant(err) => CheckpointError::Invariant(err),
+            _ => CheckpointError::Invalid("level overflow"),
+        })?;
+        ob.last_order_seq = last_order_seq;
+        ob.next_order_id = next_order_id;
+        ob.last_time = last_time;
+        ob.halt.queue = queue;
+        ob.auction = input.bool()?;
+        ob.halt.halted = input.bool()?;
+        for _ in 0..input.len()? {
+            let (first, second) = (input.u64()?, input.u64()?);
+            let mode = if input.bool()? { OcoMode::Reduce } else { OcoMode::Cancel };
+            ob.oco.link(first, second, mode);
+        }
+        for _ in 0..input.len()? {
+            let id = input.u64()?;
+            let peg = Peg { reference: input.peg_reference()?, offset: input.i64()?, repriced_at: 0 };
+            ob.pegs.orders.insert(id, peg);
+        }
+        for _ in 0..input.len()? {
+            let owner = input.u64()?;
+            ob.accounts.positions.insert(owner, input.position()?);
+        }
+        if !input.0.is_empty() {
+            return Err(CheckpointError::Invalid("trailing bytes"));
+        }
+        ob.apply_entries(tail, times)?;
+        ob.clock = Box::new(TestClock::new(ob.last_time.saturating_add(1), 1));
+        Ok(ob)
+    }
+}
+
+// Integers are LEB128 varints, signed ones zigzag-encoded first
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn uint(&mut self, value: impl Into<u128>) {
+        let mut value = value.into();
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.uint(((value << 1) ^ (value >> 63)) as u64);
+    }
+
+    fn i128(&mut self, value: i128) {
+        self.uint(((value << 1) ^ (value >> 127)) as u128);
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+
+    fn option(&mut self, value: Option<impl Into<u128>>) {
+        match value {
+            Some(value) => {
+                self.bool(true);
+                self.uint(value);
+            }
+            None => self.bool(false),
+        }
+    }
+
+    fn side(&mut self, side: Side) {
+        self.bool(side == Side::Sell);
+    }
+
+    fn peg_reference(&mut self, reference: PegReference) {
+        self.0.push(match reference {
+            PegReference::Primary => 0,
+            PegReference::Market => 1,
+            PegReference::Mid => 2,
+        });
+    }
+
+    fn trailing_offset(&mut self, offset: TrailingOffset) {
+        match offset {
+            TrailingOffset::Amount(amount) => {
+                self.0.push(0);
+                self.uint(amount);
+            }
+            TrailingOffset::Bps(bps) => {
+                self.0.push(1);
+                self.uint(bps);
+            }
+        }
+    }
+
+    fn client_order_id(&mut self, id: Option<&ClientOrderId>) {
+        match id {
+            None => self.0.push(0),
+            Some(ClientOrderId::Text(text)) => {
+                self.0.push(1);
+                self.uint(text.len() as u64);
+                self.0.extend_from_slice(text.as_bytes());
+            }
+            Some(&ClientOrderId::Number(number)) => {
+                self.0.push(2);
+                self.uint(number);
+            }
+        }
+    }
+
+    fn order(&mut self, order: &Order) {
+        self.uint(order.id);
+        self.uint(order.price);
+        self.uint(order.quantity);
+        self.uint(order.timestamp);
+        self.uint(order.seq);
+        self.uint(order.hidden_quantity);
+        self.option(order.display_quantity);
+        self.option(order.owner);
+        self.uint(order.original_quantity);
+        self.uint(order.filled_quantity);
+        self.option(order.expires_at);
+        self.client_order_id(order.client_order_id.as_ref());
+        self.option(order.user_data);
+        self.option(order.min_quantity);
+        self.0.push(order.tier);
+        self.bool(order.post_only);
+        self.bool(order.reduce_only);
+    }
+
+    fn position(&mut self, position: &Position) {
+        self.i128(position.net_quantity);
+        self.uint(position.open_notional);
+        self.i128(position.realized_pnl);
+        self.uint(position.bought_quantity);
+        self.uint(position.sold_quantity);
+        self.uint(position.trades);
+    }
+
+    fn new_order(&mut self, order: &NewOrder) {
+        self.uint(order.id);
+        self.side(order.side);
+        match order.order_type {
+            OrderType::Limit { price } => {
+                self.0.push(0);
+                self.uint(price);
+            }
+            OrderType::Market => self.0.push(1),
+            OrderType::Stop { stop_price } => {
+                self.0.push(2);
+                self.uint(stop_price);
+            }
+            OrderType::StopLimit { stop_price, price } => {
+                self.0.push(3);
+                self.uint(stop_price);
+                self.uint(price);
+            }
+            OrderType::TrailingStop { offset } => {
+                self.0.push(4);
+                self.trailing_offset(offset);
+            }
+            OrderType::TrailingStopLimit { offset, limit_offset } => {
+                self.0.push(5);
+                self.trailing_offset(offset);
+                self.uint(limit_offset);
+            }
+            OrderType::Pegged { reference, offset } => {
+                self.0.push(6);
+                self.peg_reference(reference);
+                self.i64(offset);
+            }
+        }
+        self.uint(order.quantity);
+        self.0.push(match order.time_in_force {
+            TimeInForce::Gtc => 0,
+            TimeInForce::Ioc => 1,
+            TimeInForce::Fok => 2,
+        });
+        self.bool(order.post_only);
+        self.option(order.display_quantity);
+        self.option(order.owner);
+        self.option(order.expires_at);
+        self.client_order_id(order.client_order_id.as_ref());
+        self.option(order.user_data);
+        self.bool(order.reduce_only);
+        self.option(order.min_quantity);
+        self.option(order.notional);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, CheckpointError> {
+        let (&byte, rest) = self.0.split_first().ok_or(CheckpointError::Truncated)?;
+        self.0 = rest;
+        Ok(byte)
+    }
+
+    fn uint<T: TryFrom<u128>>(&mut self) -> Result<T, CheckpointError> {
+        let mut value = 0u128;
+        for shift in (0..128).step_by(7) {
+            let byte = self.byte()?;
+            value |= u128::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return T::try_from(value).map_err(|_| CheckpointError::Invalid("integer out of range"));
+            }
+        }
+        Err(CheckpointError::Invalid("varint too long"))
+    }
+
+    fn u64(&mut self) -> Result<u64, CheckpointError> {
+        self.uint()
+    }
+
+    fn i64(&mut self) -> Result<i64, CheckpointError> {
+        let raw = self.u64()?;
+        Ok((raw >> 1) as i64 ^ -((raw & 1) as i64))
+    }
+
+    fn i128(&mut self) -> Result<i128, CheckpointError> {
+        let raw: u128 = self.uint()?;
+        Ok((raw >> 1) as i128 ^ -((raw & 1) as i128))
+    }
+
+    // A count of items that follow; each takes at least a byte, which bounds it by what's left
+    fn len(&mut self) -> Result<usize, CheckpointError> {
+        let len = self.u64()?;
+        if len > self.0.len() as u64 {
+            return Err(CheckpointError::Truncated);
+        }
+        Ok(len as usize)
+    }
+
+    fn bool(&mut self) -> Result<bool, CheckpointError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CheckpointError::Invalid("flag")),
+        }
+    }
+
+    fn option<T: TryFrom<u128>>(&mut self) -> Result<Option<T>, CheckpointError> {
+        Ok(if self.bool()? { Some(self.uint()?) } else { None })
+    }
+
+    fn side(&mut self) -> Result<Side, CheckpointError> {
+        Ok(if self.bool()? { Side::Sell } else { Side::Buy })
+    }
+
+    fn peg_reference(&mut self) -> Result<PegReference, CheckpointError> {
+        match self.byte()? {
+            0 => Ok(PegReference::Primary),
+            1 => Ok(PegReference::Market),
+            2 => Ok(PegReference::Mid),
+            _ => Err(CheckpointError::Invalid("peg reference")),
+        }
+    }
+
+    fn trailing_offset(&mut self) -> Result<TrailingOffset, CheckpointError> {
+        match self.byte()? {
+            0 => Ok(TrailingOffset::Amount(self.uint()?)),
+            1 => Ok(TrailingOffset::Bps(self.u64()?)),
+            _ => Err(CheckpointError::Invalid("trailing offset")),
+        }
+    }
+
+    fn client_order_id(&mut self) -> Result<Option<ClientOrderId>, CheckpointError> {
+        match self.byte()? {
+            0 => Ok(None),
+            1 => {
+                let len = self.len()?;
+                let (text, rest) = self.0.split_at(len);
+                self.0 = rest;
+                let text = String::from_utf8(text.to_vec()).map_err(|_| CheckpointError::Invalid("client order id"))?;
+                Ok(Some(ClientOrderId::Text(text)))
+            }
+            2 => Ok(Some(ClientOrderId::Number(self.uint()?))),
+            _ => Err(CheckpointError::Invalid("client order id")),
+        }
+    }
+
+    fn order(&mut self) -> Result<Order, CheckpointError> {
+        Ok(Order {
+            id: self.uint()?,
+            price: self.uint()?,
+            quantity: self.uint()?,
+            timestamp: self.uint()?,
+            seq: self.uint()?,
+            hidden_quantity: self.uint()?,
+            display_quantity: self.option()?,
+            owner: self.option()?,
+            original_quantity: self.uint()?,
+            filled_quantity: self.uint()?,
+            expires_at: self.option()?,
+            client_order_id: self.client_order_id()?,
+            user_data: self.option()?,
+            min_quantity: self.option()?,
+            tier: self.byte()?,
+            post_only: self.bool()?,
+            reduce_only: self.bool()?,
+        })
+    }
+
+    fn position(&mut self) -> Result<Position, CheckpointError> {
+        Ok(Position {
+            net_quantity: self.i128()?,
+            open_notional: self.uint()?,
+            realized_pnl: self.i128()?,
+            bought_quantity: self.uint()?,
+            sold_quantity: self.uint()?,
+            trades: self.u64()?,
+        })
+    }
+
+    fn new_order(&mut self) -> Result<NewOrder, CheckpointError> {
+        let id = self.uint()?;
+        let side = self.side()?;
+        let order_type = match self.byte()? {
+            0 => OrderType::Limit { price: self.uint()? },
+            1 => OrderType::Market,
+            2 => OrderType::Stop { stop_price: self.uint()? },
+            3 => OrderType::StopLimit { stop_price: self.uint()?, price: self.uint()? },
+            4 => OrderType::TrailingStop { offset: self.trailing_offset()? },
+            5 => OrderType::TrailingStopLimit { offset: self.trailing_offset()?, limit_offset: self.uint()? },
+            6 => OrderType::Pegged { reference: self.peg_reference()?, offset: self.i64()? },
+            _ => return Err(CheckpointError::Invalid("order type")),
+        };
+        let quantity = self.uint()?;
+        let time_in_force = match self.byte()? {
+            0 => TimeInForce::Gtc,
+            1 => TimeInForce::Ioc,
+            2 => TimeInForce::Fok,
+            _ => return Err(CheckpointError::Invalid("time in force")),
+        };
+        Ok(NewOrder {
+            id,
+            side,
+            order_type,
+            quantity,
+            time_in_force,
+            post_only: self.bool()?,
+            display_quantity: self.option()?,
+            owner: self.option()?,
+            expires_at: self.option()?,
+            client_order_id: self.client_order_id()?,
+            user_data: self.option()?,
+            reduce_only: self.bool()?,
+            min_quantity: self.option()?,
+            notional: self.option()?,
+        })
+    }
+}
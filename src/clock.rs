@@ -0,0 +1,62 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of the timestamps a book puts on orders and trades, in nanoseconds.
+pub trait Clock: Send {
+    /// Current time; must never go backwards.
+    fn now(&mut self) -> u64;
+}
+
+/// Wall-clock time in nanoseconds since the Unix epoch, read once at construction and advanced
+/// with a monotonic timer so later system clock adjustments don't make it jump.
+#[derive(Debug, Clone, Copy)]
+pub struct MonotonicClock {
+    start: Instant,
+    start_nanos: u64,
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        let since_epoch = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Self { start: Instant::now(), start_nanos: since_epoch.as_nanos() as u64 }
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&mut self) -> u64 {
+        self.start_nanos.saturating_add(self.start.elapsed().as_nanos() as u64)
+    }
+}
+
+/// Deterministic clock: the first reading is `start`, each further one `step` later. The
+/// default (start 1, step 1) is what `OrderBook::new` uses, so replays reproduce timestamps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestClock {
+    next: u64,
+    step: u64,
+}
+
+impl Default for TestClock {
+    fn default() -> Self {
+        Self::new(1, 1)
+    }
+}
+
+impl TestClock {
+    pub fn new(start: u64, step: u64) -> Self {
+        Self { next: start, step }
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&mut self) -> u64 {
+        let now = self.next;
+        self.next = self.next.saturating_add(self.step);
+        now
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use arc_swap::ArcSwap;
+
+use crate::book::{Depth, OrderBook};
+use crate::order::Command;
+use crate::types::ExecutionReport;
+use crate::units::Units;
+
+/// Read-only picture of a book published after every command it runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarketView {
+    pub best_buy: Option<(Units, Units)>,
+    pub best_sell: Option<(Units, Units)>,
+    pub depth: Depth,
+    pub last_trade_price: Option<Units>,
+    pub last_trade_seq: u64,
+    pub order_count: usize,
+}
+
+/// The matching thread has stopped, so the command wasn't run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BookClosed;
+
+impl fmt::Display for BookClosed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the order book has shut down")
+    }
+}
+
+impl std::error::Error for BookClosed {}
+
+enum Request {
+    Batch(Vec<Command>, Sender<Vec<ExecutionReport>>),
+    Shutdown,
+}
+
+/// An order book owned by a dedicated matching thread.
+///
+/// Any number of threads submit commands through cloned `BookHandle`s; the commands are queued
+/// and run one at a time, in arrival order. After each batch the thread publishes a
+/// `MarketView` that readers load without taking a lock. Configure the book (policies,
+/// listeners, clock) before handing it over.
+pub struct ConcurrentOrderBook {
+    handle: BookHandle,
+    worker: JoinHandle<OrderBook>,
+}
+
+/// Cloneable, `Send` entry point to a `ConcurrentOrderBook`.
+#[derive(Clone)]
+pub struct BookHandle {
+    requests: Sender<Request>,
+    view: Arc<ArcSwap<MarketView>>,
+}
+
+impl ConcurrentOrderBook {
+    /// Start the matching thread; published views carry `depth_levels` levels per side.
+    pub fn new(book: OrderBook, depth_levels: usize) -> Self {
+        let (requests, queue) = mpsc::channel();
+        let view = Arc::new(ArcSwap::from_pointee(Self::view_of(&book, depth_levels)));
+        let published = Arc::clone(&view);
+        let worker = thread::spawn(move || Self::run(book, depth_levels, queue, &published));
+        Self { handle: BookHandle { requests, view }, worker }
+    }
+
+    pub fn handle(&self) -> BookHandle {
+        self.handle.clone()
+    }
+
+    /// Stop the matching thread once the commands queued before this call have run, and hand
+    /// the book back. Handles still around get `BookClosed` from then on, as does this call if
+    /// the matching thread panicked, taking the book with it.
+    pub fn shutdown(self) -> Result<OrderBook, BookClosed> {
+        let _ = self.handle.requests.send(Request::Shutdown);
+        self.worker.join().map_err(|_| BookClosed)
+    }
+
+    fn run(mut book: OrderBook, depth_levels: usize, queue: Receiver<Request>, view: &ArcSwap<MarketView>) -> OrderBook {
+        while let Ok(Request::Batch(commands, reply)) = queue.recv() {
+            let reports = book.apply_batch(&commands);
+            view.store(Arc::new(Self::view_of(&book, depth_levels)));
+            // The sender may have given up waiting; the commands ran regardless
+            let _ = reply.send(reports);
+        }
+        book
+    }
+
+    fn view_of(book: &OrderBook, depth_levels: usize) -> MarketView {
+        MarketView {
+            best_buy: book.best_buy(),
+            best_sell: book.best_sell(),
+            depth: book.depth(depth_levels),
+            last_trade_price: book.last_trade_price(),
+            last_trade_seq: book.last_trade_seq(),
+            order_count: book.order_count(),
+        }
+    }
+}
+
+impl BookHandle {
+    /// Run one command and wait for its report; see `OrderBook::apply_batch` for how
+    /// failures are reported.
+    pub fn execute(&self, command: Command) -> Result<ExecutionReport, BookClosed> {
+        self.execute_batch(vec![command])?.pop().ok_or(BookClosed)
+    }
+
+    /// Run `commands` back to back, with no other thread's commands in between.
+    pub fn execute_batch(&self, commands: Vec<Command>) -> Result<Vec<ExecutionReport>, BookClosed> {
+        let (reply, report) = mpsc::channel();
+        self.requests.send(Request::Batch(commands, reply)).map_err(|_| BookClosed)?;
+        report.recv().map_err(|_| BookClosed)
+    }
+
+    /// Latest published view, as of the last completed batch.
+    pub fn view(&self) -> Arc<MarketView> {
+        self.view.load_full()
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use crate::book::OrderBook;
+use crate::events::TradeEvent;
+use crate::types::Trade;
+use crate::units::{widen, Units};
+use crate::wal::LogEntry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrectionError {
+    /// No trade with this sequence number in the retained trade history
+    UnknownTrade(u64),
+    /// A corrected trade needs a positive price and quantity; bust it instead
+    InvalidCorrection,
+}
+
+impl fmt::Display for CorrectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorrectionError::UnknownTrade(seq) => write!(f, "no retained trade with sequence number {}", seq),
+            CorrectionError::InvalidCorrection => write!(f, "a corrected trade needs a positive price and quantity"),
+        }
+    }
+}
+
+impl std::error::Error for CorrectionError {}
+
+impl OrderBook {
+    /// Void a printed trade, as an exchange does with an erroneous one: it leaves the retained
+    /// trade history, the trade and volume statistics, the owners' positions and their fee
+    /// totals, and listeners get a `TradeEvent::Busted` to take it out of whatever they built
+    /// from the trade stream (see `CandleAggregator::on_trade_event`). Returns the trade.
+    ///
+    /// Only trades still in the history kept by `enable_trade_history` can be busted, so a log
+    /// replayed into a book without one skips its busts. Orders aren't restored: the fill
+    /// stands as far as the book is concerned, and held stops aren't checked against the last
+    /// trade price it falls back to.
+    pub fn bust_trade(&mut self, seq: u64) -> Result<Trade, CorrectionError> {
+        self.audited(|ob| ob.bust(seq))
+    }
+
+    fn bust(&mut self, seq: u64) -> Result<Trade, CorrectionError> {
+        let index = self.retained_trade(seq)?;
+        let best_before = self.best_prices();
+        self.log_command(LogEntry::BustTrade { seq });
+        let trade = self.trade_history.remove(index).ok_or(CorrectionError::UnknownTrade(seq))?;
+        self.unbook(&trade);
+        if index == self.trade_history.len() {
+            self.last_trade_price = self.trade_history.back().map(|trade| trade.price);
+        }
+        let (timestamp, price, quantity) = (trade.timestamp, trade.price, trade.quantity);
+        self.emit(TradeEvent::Busted { seq, timestamp, price, quantity });
+        self.dispatch_events(best_before);
+        Ok(trade)
+    }
+
+    /// Amend a printed trade to `price` and `quantity`, as an exchange does after a price or
+    /// size dispute. The history, statistics and positions are moved from the old terms to
+    /// the new, fees are charged again under the current schedule (none without one), and
+    /// listeners get a `TradeEvent::Corrected`. Returns the corrected trade.
+    ///
+    /// The same limits as `bust_trade` apply.
+    pub fn correct_trade(&mut self, seq: u64, price: Units, quantity: Units) -> Result<Trade, CorrectionError> {
+        self.audited(|ob| ob.correct(seq, price, quantity))
+    }
+
+    fn correct(&mut self, seq: u64, price: Units, quantity: Units) -> Result<Trade, CorrectionError> {
+        if price == 0 || quantity == 0 {
+            return Err(CorrectionError::InvalidCorrection);
+        }
+        let index = self.retained_trade(seq)?;
+        let best_before = self.best_prices();
+        self.log_command(LogEntry::CorrectTrade { seq, price, quantity });
+        let old = self.trade_history[index].clone();
+        self.unbook(&old);
+
+        let mut trade = Trade { price, quantity, maker_fee: 0, taker_fee: 0, ..old.clone() };
+        self.fees.charge(&mut trade);
+        self.accounts.record(&trade);
+        self.counters.trades += 1;
+        self.counters.volume += widen(quantity);
+        if index + 1 == self.trade_history.len() {
+            self.last_trade_price = Some(price);
+        }
+        self.trade_history[index] = trade.clone();
+
+        let (timestamp, old_price, old_quantity) = (old.timestamp, old.price, old.quantity);
+        self.emit(TradeEvent::Corrected { seq, timestamp, old_price, old_quantity, price, quantity });
+        self.dispatch_events(best_before);
+        Ok(trade)
+    }
+
+    // Position of trade `seq` in the history, which is in sequence order
+    fn retained_trade(&self, seq: u64) -> Result<usize, CorrectionError> {
+        self.trade_history.binary_search_by_key(&seq, |trade| trade.seq).map_err(|_| CorrectionError::UnknownTrade(seq))
+    }
+
+    // Take a trade back out of the statistics, positions and fee totals
+    fn unbook(&mut self, trade: &Trade) {
+        self.counters.trades = self.counters.trades.saturating_sub(1);
+        self.counters.volume = self.counters.volume.saturating_sub(widen(trade.quantity));
+        self.accounts.reverse(trade);
+        self.fees.refund(trade);
+    }
+}
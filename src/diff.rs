@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use crate::book::{OrderBook, PriceLevel};
+use crate::types::Side;
+use crate::units::Units;
+
+/// Where two books' resting orders differ, as found by `OrderBook::diff`. Pairs are `(this
+/// book, other book)`; levels are listed bids then asks, each by ascending price, and orders by
+/// id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookDiff {
+    pub levels_only_in_self: Vec<(Side, Units)>,
+    pub levels_only_in_other: Vec<(Side, Units)>,
+    pub level_mismatches: Vec<LevelMismatch>,
+    pub orders_only_in_self: Vec<u64>,
+    pub orders_only_in_other: Vec<u64>,
+    pub order_mismatches: Vec<OrderMismatch>,
+}
+
+impl BookDiff {
+    /// Whether the books rest the same orders at the same prices and quantities.
+    pub fn is_empty(&self) -> bool {
+        self.levels_only_in_self.is_empty()
+            && self.levels_only_in_other.is_empty()
+            && self.level_mismatches.is_empty()
+            && self.orders_only_in_self.is_empty()
+            && self.orders_only_in_other.is_empty()
+            && self.order_mismatches.is_empty()
+    }
+}
+
+/// A price level both books have with different totals or order counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LevelMismatch {
+    pub side: Side,
+    pub price: Units,
+    pub quantity: (Units, Units),
+    pub hidden_quantity: (Units, Units),
+    pub orders: (usize, usize),
+}
+
+/// An order resting in both books on a different side, at a different price or with a
+/// different visible or hidden quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderMismatch {
+    pub id: u64,
+    pub side: (Side, Side),
+    pub price: (Units, Units),
+    pub quantity: (Units, Units),
+    pub hidden_quantity: (Units, Units),
+}
+
+impl OrderBook {
+    /// Compare the resting orders of this book with `other`'s, e.g. a mirror kept in another
+    /// process: levels and orders only one of them has, and those both have that disagree.
+    /// Queue order within a level, held stops and configuration aren't compared. One pass over
+    /// each book's levels and orders, plus sorting what differs.
+    pub fn diff(&self, other: &OrderBook) -> BookDiff {
+        let mut diff = BookDiff::default();
+        for side in [Side::Buy, Side::Sell] {
+            diff_levels(side, self.levels(side), other.levels(side), &mut diff);
+        }
+
+        for (&id, &(side, price, key)) in &self.order_index {
+            let Some(&(other_side, other_price, other_key)) = other.order_index.get(&id) else {
+                diff.orders_only_in_self.push(id);
+                continue;
+            };
+            // A vacant slot is for `validate` to report
+            let (Some(node), Some(other_node)) = (self.slab.get(key), other.slab.get(other_key)) else {
+                continue;
+            };
+            let (order, other_order) = (&node.order, &other_node.order);
+            let state = (side, price, order.quantity, order.hidden_quantity);
+            let other_state = (other_side, other_price, other_order.quantity, other_order.hidden_quantity);
+            if state != other_state {
+                diff.order_mismatches.push(OrderMismatch {
+                    id,
+                    side: (side, other_side),
+                    price: (price, other_price),
+                    quantity: (order.quantity, other_order.quantity),
+                    hidden_quantity: (order.hidden_quantity, other_order.hidden_quantity),
+                });
+            }
+        }
+        diff.orders_only_in_other =
+            other.order_index.keys().copied().filter(|id| !self.order_index.contains_key(id)).collect();
+        diff.orders_only_in_self.sort_unstable();
+        diff.orders_only_in_other.sort_unstable();
+        diff.order_mismatches.sort_unstable_by_key(|mismatch| mismatch.id);
+        diff
+    }
+}
+
+// Walks both sides' levels together in price order
+fn diff_levels(
+    side: Side,
+    levels: &BTreeMap<Units, PriceLevel>,
+    other_levels: &BTreeMap<Units, PriceLevel>,
+    diff: &mut BookDiff,
+) {
+    let mut levels = levels.iter().peekable();
+    let mut other_levels = other_levels.iter().peekable();
+    loop {
+        match (levels.peek(), other_levels.peek()) {
+            (None, None) => break,
+            (Some(&(&price, _)), None) => {
+                diff.levels_only_in_self.push((side, price));
+                levels.next();
+            }
+            (None, Some(&(&price, _))) => {
+                diff.levels_only_in_other.push((side, price));
+                other_levels.next();
+            }
+            (Some(&(&price, level)), Some(&(&other_price, other_level))) => {
+                if price < other_price {
+                    diff.levels_only_in_self.push((side, price));
+                    levels.next();
+                } else if other_price < price {
+                    diff.levels_only_in_other.push((side, other_price));
+                    other_levels.next();
+                } else {
+                    let totals = (level.total_quantity(), level.hidden_quantity(), level.len());
+                    let other_totals = (other_level.total_quantity(), other_level.hidden_quantity(), other_level.len());
+                    if totals != other_totals {
+                        diff.level_mismatches.push(LevelMismatch {
+                            side,
+                            price,
+                            quantity: (totals.0, other_totals.0),
+                            hidden_quantity: (totals.1, other_totals.1),
+                            orders: (totals.2, other_totals.2),
+                        });
+                    }
+                    levels.next();
+                    other_levels.next();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+
+use crate::checkpoint::CheckpointError;
+use crate::corrections::CorrectionError;
+use crate::oco::LinkError;
+use crate::seed::LoadError;
+use crate::types::{CancelError, PlaceError};
+
+/// The book's own structures disagree, e.g. an indexed order has no price level. The call that
+/// finds it stops there, possibly part way through, and the book should be rebuilt from a
+/// checkpoint or its log before it's used again. With the `invariant-panics` feature it panics
+/// where it's found instead, for tests and fuzzing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvariantViolation(pub &'static str);
+
+impl InvariantViolation {
+    #[track_caller]
+    pub(crate) fn new(what: &'static str) -> Self {
+        if cfg!(feature = "invariant-panics") {
+            panic!("order book invariant violated: {}", what);
+        }
+        Self(what)
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "order book invariant violated: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvariantViolation {}
+
+// `expect` for lookups the book's invariants guarantee to succeed
+pub(crate) trait OrInvariant<T> {
+    fn or_invariant(self, what: &'static str) -> Result<T, InvariantViolation>;
+}
+
+impl<T> OrInvariant<T> for Option<T> {
+    #[track_caller]
+    fn or_invariant(self, what: &'static str) -> Result<T, InvariantViolation> {
+        match self {
+            Some(value) => Ok(value),
+            None => Err(InvariantViolation::new(what)),
+        }
+    }
+}
+
+impl<T, E> OrInvariant<T> for Result<T, E> {
+    #[track_caller]
+    fn or_invariant(self, what: &'static str) -> Result<T, InvariantViolation> {
+        match self {
+            Ok(value) => Ok(value),
+            Err(_) => Err(InvariantViolation::new(what)),
+        }
+    }
+}
+
+/// Any error an `OrderBook` call can fail with. Each call returns the narrowest type that
+/// covers it; this one takes all of them through `?`, with every invariant violation under
+/// `Invariant` whichever type carried it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBookError {
+    Place(PlaceError),
+    Cancel(CancelError),
+    Link(LinkError),
+    Load(LoadError),
+    Checkpoint(CheckpointError),
+    Correction(CorrectionError),
+    Invariant(InvariantViolation),
+}
+
+impl fmt::Display for OrderBookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderBookError::Place(err) => err.fmt(f),
+            OrderBookError::Cancel(err) => err.fmt(f),
+            OrderBookError::Link(err) => err.fmt(f),
+            OrderBookError::Load(err) => err.fmt(f),
+            OrderBookError::Checkpoint(err) => err.fmt(f),
+            OrderBookError::Correction(err) => err.fmt(f),
+            OrderBookError::Invariant(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for OrderBookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OrderBookError::Place(err) => Some(err),
+            OrderBookError::Cancel(err) => Some(err),
+            OrderBookError::Link(err) => Some(err),
+            OrderBookError::Load(err) => Some(err),
+            OrderBookError::Checkpoint(err) => Some(err),
+            OrderBookError::Correction(err) => Some(err),
+            OrderBookError::Invariant(err) => Some(err),
+        }
+    }
+}
+
+impl From<InvariantViolation> for OrderBookError {
+    fn from(err: InvariantViolation) -> Self {
+        OrderBookError::Invariant(err)
+    }
+}
+
+impl From<PlaceError> for OrderBookError {
+    fn from(err: PlaceError) -> Self {
+        match err {
+            PlaceError::Invariant(err) => OrderBookError::Invariant(err),
+            err => OrderBookError::Place(err),
+        }
+    }
+}
+
+impl From<CancelError> for OrderBookError {
+    fn from(err: CancelError) -> Self {
+        match err {
+            CancelError::Invariant(err) => OrderBookError::Invariant(err),
+            err => OrderBookError::Cancel(err),
+        }
+    }
+}
+
+impl From<LinkError> for OrderBookError {
+    fn from(err: LinkError) -> Self {
+        OrderBookError::Link(err)
+    }
+}
+
+impl From<LoadError> for OrderBookError {
+    fn from(err: LoadError) -> Self {
+        match err {
+            LoadError::Invariant(err) => OrderBookError::Invariant(err),
+            err => OrderBookError::Load(err),
+        }
+    }
+}
+
+impl From<CheckpointError> for OrderBookError {
+    fn from(err: CheckpointError) -> Self {
+        match err {
+            CheckpointError::Invariant(err) => OrderBookError::Invariant(err),
+            err => OrderBookError::Checkpoint(err),
+        }
+    }
+}
+
+impl From<CorrectionError> for OrderBookError {
+    fn from(err: CorrectionError) -> Self {
+        OrderBookError::Correction(err)
+    }
+}
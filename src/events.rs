@@ -0,0 +1,189 @@
+use std::sync::mpsc::Sender;
+
+use crate::audit::Audit;
+use crate::book::OrderBook;
+use crate::session::SessionPhase;
+use crate::types::{RejectReason, Side, Trade};
+use crate::units::Units;
+
+/// Lifecycle of a single order. `quantity` on fills is the traded amount, `remaining` what
+/// is still open afterwards (visible plus hidden).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderEvent {
+    Accepted { id: u64 },
+    Rested { id: u64, price: Units, quantity: Units },
+    PartiallyFilled { id: u64, price: Units, quantity: Units, remaining: Units },
+    Filled { id: u64, price: Units, quantity: Units },
+    Cancelled { id: u64, remaining: Units },
+    Rejected { id: u64, reason: RejectReason },
+    /// A held stop order reached its trigger price and is being submitted
+    Triggered { id: u64 },
+    /// A good-till order was taken off the book (or out of the held stops) by `expire`
+    Expired { id: u64, remaining: Units },
+    /// A pegged order moved to `price` after its reference, to the back of that level's queue
+    Repriced { id: u64, price: Units },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BookEvent {
+    LevelAdded { side: Side, price: Units },
+    LevelRemoved { side: Side, price: Units },
+    /// The best price on `side` moved; `None` when the side emptied
+    BestPriceChanged { side: Side, price: Option<Units> },
+}
+
+/// A transition made by `OrderBook::update_session`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionEvent {
+    /// The book entered `phase`; `time` is the clock reading that made it due
+    PhaseChanged { phase: SessionPhase, time: u64 },
+}
+
+/// A printed trade taken back or amended by `OrderBook::bust_trade` or `correct_trade`.
+/// `timestamp` is the trade's own, for finding the interval it was aggregated into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TradeEvent {
+    /// The trade is void
+    Busted { seq: u64, timestamp: u64, price: Units, quantity: Units },
+    /// The trade now stands at `price` and `quantity` instead of `old_price` and `old_quantity`
+    Corrected { seq: u64, timestamp: u64, old_price: Units, old_quantity: Units, price: Units, quantity: Units },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    Order(OrderEvent),
+    Book(BookEvent),
+    Session(SessionEvent),
+    Trade(TradeEvent),
+}
+
+impl From<OrderEvent> for Event {
+    fn from(event: OrderEvent) -> Self {
+        Event::Order(event)
+    }
+}
+
+impl From<BookEvent> for Event {
+    fn from(event: BookEvent) -> Self {
+        Event::Book(event)
+    }
+}
+
+impl From<SessionEvent> for Event {
+    fn from(event: SessionEvent) -> Self {
+        Event::Session(event)
+    }
+}
+
+impl From<TradeEvent> for Event {
+    fn from(event: TradeEvent) -> Self {
+        Event::Trade(event)
+    }
+}
+
+/// Receives book events once the call that produced them has finished updating the book.
+pub trait EventListener: Send {
+    fn on_event(&mut self, event: &Event);
+}
+
+impl<F: FnMut(&Event) + Send> EventListener for F {
+    fn on_event(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+/// Forwards events to a channel; a disconnected receiver is ignored.
+impl EventListener for Sender<Event> {
+    fn on_event(&mut self, event: &Event) {
+        let _ = self.send(*event);
+    }
+}
+
+impl OrderBook {
+    pub fn subscribe(&mut self, listener: impl EventListener + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    pub(crate) fn emit(&mut self, event: impl Into<Event>) {
+        let event = event.into();
+        trace_event(&event);
+        if let Some(audit) = &mut self.audit {
+            audit.event(event);
+        }
+        if !self.listeners.is_empty() {
+            self.events.push(event);
+        }
+    }
+
+    pub(crate) fn best_prices(&self) -> (Option<Units>, Option<Units>) {
+        (self.best_buy().map(|(price, _)| price), self.best_sell().map(|(price, _)| price))
+    }
+
+    // Emits BestPriceChanged against the best prices from before the operation, then hands
+    // every buffered event to the listeners. The audit record of the call sees the same events.
+    pub(crate) fn dispatch_events(&mut self, best_before: (Option<Units>, Option<Units>)) {
+        if self.listeners.is_empty() && !self.audit.as_ref().is_some_and(Audit::is_open) {
+            return;
+        }
+        let (buy, sell) = self.best_prices();
+        if buy != best_before.0 {
+            self.emit(BookEvent::BestPriceChanged { side: Side::Buy, price: buy });
+        }
+        if sell != best_before.1 {
+            self.emit(BookEvent::BestPriceChanged { side: Side::Sell, price: sell });
+        }
+
+        let mut events = std::mem::take(&mut self.events);
+        for event in events.drain(..) {
+            for listener in self.listeners.iter_mut() {
+                listener.on_event(&event);
+            }
+        }
+        self.events = events;
+    }
+}
+
+// With the `tracing` feature, order events are logged at debug level, book events at trace
+// level and session and trade events at info level; without it these compile to nothing. Fills
+// are covered by `trace_trade`.
+#[inline]
+fn trace_event(_event: &Event) {
+    #[cfg(feature = "tracing")]
+    match *_event {
+        Event::Order(OrderEvent::Accepted { id }) => tracing::debug!(id, "order accepted"),
+        Event::Order(OrderEvent::Rested { id, price, quantity }) => tracing::debug!(id, price, quantity, "order rested"),
+        Event::Order(OrderEvent::Cancelled { id, remaining }) => tracing::debug!(id, remaining, "order cancelled"),
+        Event::Order(OrderEvent::Rejected { id, reason }) => tracing::debug!(id, ?reason, "order rejected"),
+        Event::Order(OrderEvent::Triggered { id }) => tracing::debug!(id, "stop triggered"),
+        Event::Order(OrderEvent::Expired { id, remaining }) => tracing::debug!(id, remaining, "order expired"),
+        Event::Order(OrderEvent::Repriced { id, price }) => tracing::debug!(id, price, "order repriced"),
+        Event::Order(OrderEvent::PartiallyFilled { .. } | OrderEvent::Filled { .. }) => {}
+        Event::Book(BookEvent::LevelAdded { side, price }) => tracing::trace!(?side, price, "level added"),
+        Event::Book(BookEvent::LevelRemoved { side, price }) => tracing::trace!(?side, price, "level removed"),
+        Event::Book(BookEvent::BestPriceChanged { side, price }) => tracing::trace!(?side, ?price, "best price changed"),
+        Event::Session(SessionEvent::PhaseChanged { phase, time }) => tracing::info!(?phase, time, "session phase changed"),
+        Event::Trade(TradeEvent::Busted { seq, price, quantity, .. }) => tracing::info!(seq, price, quantity, "trade busted"),
+        Event::Trade(TradeEvent::Corrected { seq, price, quantity, .. }) => {
+            tracing::info!(seq, price, quantity, "trade corrected")
+        }
+    }
+}
+
+// Trades are logged at info level once they have their sequence number and timestamp
+#[inline]
+pub(crate) fn trace_trade(_trade: &Trade) {
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        seq = _trade.seq,
+        price = _trade.price,
+        quantity = _trade.quantity,
+        maker_id = _trade.maker_id,
+        taker_id = _trade.taker_id,
+        "trade"
+    );
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::book::OrderBook;
+use crate::error::{InvariantViolation, OrInvariant};
+use crate::instrument::{Instrument, InstrumentRegistry};
+use crate::order::{NewOrder, Order};
+use crate::types::{CancelError, ExecutionReport, OrderOutcome, PlaceError, PriceConfigError, RejectReason, Side};
+use crate::units::{widen, Units};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExchangeError {
+    UnknownSymbol(String),
+    DuplicateSymbol(String),
+    /// The instrument's price grid can't be traded on
+    InvalidPriceConfig(String, PriceConfigError),
+    /// The symbol's instrument is disabled and takes no new orders
+    SymbolDisabled(String),
+    /// The id has already been used on some book of this exchange
+    DuplicateOrderId(u64),
+    /// No resting order with this id on any book
+    UnknownOrder(u64),
+    Invariant(InvariantViolation),
+}
+
+impl fmt::Display for ExchangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExchangeError::UnknownSymbol(symbol) => write!(f, "unknown symbol {}", symbol),
+            ExchangeError::DuplicateSymbol(symbol) => write!(f, "symbol {} is already listed", symbol),
+            ExchangeError::InvalidPriceConfig(symbol, err) => write!(f, "symbol {}: {}", symbol, err),
+            ExchangeError::SymbolDisabled(symbol) => write!(f, "symbol {} is disabled", symbol),
+            ExchangeError::DuplicateOrderId(id) => write!(f, "order id {} is already in use", id),
+            ExchangeError::UnknownOrder(id) => write!(f, "no resting order with id {}", id),
+            ExchangeError::Invariant(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for ExchangeError {}
+
+impl From<PlaceError> for ExchangeError {
+    fn from(err: PlaceError) -> Self {
+        match err {
+            PlaceError::DuplicateId(id) => ExchangeError::DuplicateOrderId(id),
+            PlaceError::Invariant(err) => ExchangeError::Invariant(err),
+        }
+    }
+}
+
+impl From<CancelError> for ExchangeError {
+    fn from(err: CancelError) -> Self {
+        match err {
+            CancelError::UnknownOrder(id) => ExchangeError::UnknownOrder(id),
+            CancelError::Invariant(err) => ExchangeError::Invariant(err),
+        }
+    }
+}
+
+impl From<InvariantViolation> for ExchangeError {
+    fn from(err: InvariantViolation) -> Self {
+        ExchangeError::Invariant(err)
+    }
+}
+
+/// Activity of one symbol; trades and volume are the book's own counts, auctions included.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SymbolStats {
+    /// Orders submitted through the exchange that the book didn't reject
+    pub orders_accepted: u64,
+    pub trade_count: u64,
+    pub traded_volume: Units,
+    pub resting_orders: usize,
+}
+
+struct Listing {
+    book: OrderBook,
+    orders_accepted: u64,
+}
+
+/// One order book per symbol, with order ids unique across all of them. Each symbol's
+/// `Instrument` is checked before an order reaches its book.
+#[derive(Default)]
+pub struct Exchange {
+    listings: HashMap<String, Listing>,
+    instruments: InstrumentRegistry,
+    // Every id ever accepted -> the symbol it was sent to
+    order_symbols: HashMap<u64, String>,
+}
+
+impl Exchange {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// List `symbol` with no trading rules; shorthand for `add_instrument(Instrument::new(symbol))`.
+    pub fn add_symbol(&mut self, symbol: &str) -> Result<(), ExchangeError> {
+        self.add_instrument(Instrument::new(symbol))
+    }
+
+    /// List an instrument, enabled, on a new book set up with its price grid.
+    pub fn add_instrument(&mut self, instrument: Instrument) -> Result<(), ExchangeError> {
+        let (symbol, price_config) = (instrument.symbol.clone(), instrument.price_config);
+        self.instruments.add(instrument)?;
+        let mut book = OrderBook::new();
+        book.set_price_config(price_config).or_invariant("listed instruments have valid price grids")?;
+        self.listings.insert(symbol, Listing { book, orders_accepted: 0 });
+        Ok(())
+    }
+
+    /// Stop taking new orders for `symbol`; its resting orders stay on the book.
+    pub fn disable_symbol(&mut self, symbol: &str) -> Result<(), ExchangeError> {
+        self.instruments.disable(symbol)
+    }
+
+    pub fn enable_symbol(&mut self, symbol: &str) -> Result<(), ExchangeError> {
+        self.instruments.enable(symbol)
+    }
+
+    pub fn instruments(&self) -> &InstrumentRegistry {
+        &self.instruments
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.listings.keys().map(|s| s.as_str())
+    }
+
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.listings.get(symbol).map(|listing| &listing.book)
+    }
+
+    /// Mutable access for per-book configuration (price bands, policies, ...).
+    pub fn book_mut(&mut self, symbol: &str) -> Option<&mut OrderBook> {
+        self.listings.get_mut(symbol).map(|listing| &mut listing.book)
+    }
+
+    pub fn place_order(
+        &mut self,
+        symbol: &str,
+        side: Side,
+        price: Units,
+        quantity: Units,
+        id: u64,
+    ) -> Result<ExecutionReport, ExchangeError> {
+        self.submit(symbol, NewOrder::limit(side, price, quantity, id))
+    }
+
+    /// Send `order` to the book of `symbol`. The symbol's instrument rounds it onto its price
+    /// grid if set to; an order above its maximum size is rejected with `MaxOrderSize`, one
+    /// outside its trading hours with `OutsideTradingHours`, without reaching the book.
+    pub fn submit(&mut self, symbol: &str, order: NewOrder) -> Result<ExecutionReport, ExchangeError> {
+        if self.order_symbols.contains_key(&order.id) {
+            return Err(ExchangeError::DuplicateOrderId(order.id));
+        }
+        let listing = self
+            .listings
+            .get_mut(symbol)
+            .ok_or_else(|| ExchangeError::UnknownSymbol(symbol.to_string()))?;
+        let instrument = self.instruments.get(symbol).or_invariant("listed symbol has no instrument")?;
+        if !self.instruments.is_enabled(symbol) {
+            return Err(ExchangeError::SymbolDisabled(symbol.to_string()));
+        }
+
+        let order = if instrument.round_orders { instrument.round_order(order) } else { order };
+        let reject = if instrument.max_order_quantity.is_some_and(|max| order.quantity > max) {
+            Some(RejectReason::MaxOrderSize)
+        } else if instrument.trading_hours.is_some_and(|hours| !hours.contains(listing.book.peek_time())) {
+            Some(RejectReason::OutsideTradingHours)
+        } else {
+            None
+        };
+        if let Some(reason) = reject {
+            // The order never reaches the book, so neither does the reading the hours were checked at
+            listing.book.pending_times.clear();
+            let mut report = ExecutionReport::rejected(order.id, order.quantity, reason);
+            report.client_order_id = order.client_order_id;
+            report.user_data = order.user_data;
+            return Ok(report);
+        }
+
+        let report = listing.book.submit(order);
+        // A reading the book didn't take, say for a rejected or held order, is stale by the
+        // next call, so it goes whatever the outcome
+        listing.book.pending_times.clear();
+        let report = report?;
+        if !matches!(report.status, OrderOutcome::Rejected(_)) {
+            listing.orders_accepted += 1;
+            self.order_symbols.insert(report.order_id, symbol.to_string());
+        }
+        Ok(report)
+    }
+
+    pub fn cancel_order(&mut self, id: u64) -> Result<Order, ExchangeError> {
+        let listing = self.listing_for(id)?;
+        Ok(listing.book.cancel_order(id)?)
+    }
+
+    pub fn modify_order(&mut self, id: u64, new_price: Units, new_quantity: Units) -> Result<ExecutionReport, ExchangeError> {
+        let listing = self.listing_for(id)?;
+        Ok(listing.book.modify_order(id, new_price, new_quantity)?)
+    }
+
+    /// Symbol an order id was sent to.
+    pub fn symbol_of(&self, id: u64) -> Option<&str> {
+        self.order_symbols.get(&id).map(|s| s.as_str())
+    }
+
+    pub fn stats(&self, symbol: &str) -> Option<SymbolStats> {
+        self.listings.get(symbol).map(|listing| SymbolStats {
+            orders_accepted: listing.orders_accepted,
+            trade_count: listing.book.counters.trades,
+            traded_volume: listing.book.counters.volume.min(widen(Units::MAX)) as Units,
+            resting_orders: listing.book.order_count(),
+        })
+    }
+
+    /// Statistics summed over every symbol.
+    pub fn aggregate_stats(&self) -> SymbolStats {
+        self.listings.keys().filter_map(|symbol| self.stats(symbol)).fold(
+            SymbolStats::default(),
+            |total, stats| SymbolStats {
+                orders_accepted: total.orders_accepted.saturating_add(stats.orders_accepted),
+                trade_count: total.trade_count.saturating_add(stats.trade_count),
+                traded_volume: total.traded_volume.saturating_add(stats.traded_volume),
+                resting_orders: total.resting_orders + stats.resting_orders,
+            },
+        )
+    }
+
+    fn listing_for(&mut self, id: u64) -> Result<&mut Listing, ExchangeError> {
+        let symbol = self.order_symbols.get(&id).ok_or(ExchangeError::UnknownOrder(id))?;
+        Ok(self.listings.get_mut(symbol).or_invariant("order routed to an unlisted symbol")?)
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::book::{Depth, DepthLevel, OrderBook};
+use crate::types::Side;
+use crate::units::Units;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LevelAction {
+    Add,
+    Change,
+    /// The level is gone; quantity and order count are zero
+    Remove,
+}
+
+/// One price level's new visible quantity and order count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L2Update {
+    /// Feed sequence number: one more than the previous update's
+    pub seq: u64,
+    pub side: Side,
+    pub price: Units,
+    pub action: LevelAction,
+    pub quantity: Units,
+    pub order_count: usize,
+}
+
+/// The full visible book as of update `seq`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L2Snapshot {
+    pub seq: u64,
+    pub depth: Depth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarketData {
+    Update(L2Update),
+    Snapshot(L2Snapshot),
+}
+
+// Visible quantity and order count per price
+pub(crate) type Levels = BTreeMap<Units, (Units, usize)>;
+
+fn depth_of(bids: &Levels, asks: &Levels, levels: usize) -> Depth {
+    let level = |(&price, &(quantity, order_count)): (&Units, &(Units, usize))| DepthLevel { price, quantity, order_count };
+    Depth {
+        bids: bids.iter().rev().take(levels).map(level).collect(),
+        asks: asks.iter().take(levels).map(level).collect(),
+    }
+}
+
+/// Turns a book into an incremental L2 feed.
+///
+/// Each `publish` compares the book's visible levels with what was published last and emits an
+/// update per level that was added, changed or removed, in sequence. Every `snapshot_interval`
+/// updates it also emits a snapshot so late joiners and consumers that hit a gap can resync.
+/// Hidden iceberg quantity isn't published.
+#[derive(Debug, Clone, Default)]
+pub struct L2Publisher {
+    seq: u64,
+    bids: Levels,
+    asks: Levels,
+    snapshot_interval: u64,
+    since_snapshot: u64,
+}
+
+impl L2Publisher {
+    /// A publisher that has published an empty book; `snapshot_interval` 0 means snapshots
+    /// only on request.
+    pub fn new(snapshot_interval: u64) -> Self {
+        Self { snapshot_interval, ..Self::default() }
+    }
+
+    /// Sequence number of the last update published.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Everything published so far as a snapshot.
+    pub fn snapshot(&self) -> L2Snapshot {
+        L2Snapshot { seq: self.seq, depth: depth_of(&self.bids, &self.asks, usize::MAX) }
+    }
+
+    /// Updates bringing consumers from the last publish to `book`'s current levels, bids first,
+    /// each side by ascending price, followed by a snapshot when one is due.
+    pub fn publish(&mut self, book: &OrderBook) -> Vec<MarketData> {
+        let mut messages = Vec::new();
+        for side in [Side::Buy, Side::Sell] {
+            let current: Levels = book
+                .levels(side)
+                .iter()
+                .map(|(&price, level)| (price, (level.total_quantity(), level.len())))
+                .collect();
+            let published = match side {
+                Side::Buy => &mut self.bids,
+                Side::Sell => &mut self.asks,
+            };
+            let mut changes: Vec<(Units, LevelAction, (Units, usize))> = Vec::new();
+            for (&price, &level) in &current {
+                match published.get(&price) {
+                    None => changes.push((price, LevelAction::Add, level)),
+                    Some(&before) if before != level => changes.push((price, LevelAction::Change, level)),
+                    Some(_) => {}
+                }
+            }
+            for &price in published.keys().filter(|price| !current.contains_key(price)) {
+                changes.push((price, LevelAction::Remove, (0, 0)));
+            }
+            changes.sort_by_key(|&(price, _, _)| price);
+            *published = current;
+
+            for (price, action, (quantity, order_count)) in changes {
+                self.seq += 1;
+                messages.push(MarketData::Update(L2Update { seq: self.seq, side, price, action, quantity, order_count }));
+            }
+        }
+        self.snapshot_if_due(messages)
+    }
+
+    /// `publish` for a book tracking changes (see `OrderBook::enable_change_tracking`): only the
+    /// levels drained from it are compared, so the cost follows what changed rather than the
+    /// size of the book. The publisher must be the only one draining the book, and must have
+    /// published it as it stood when tracking was enabled, e.g. with a `publish` right after.
+    pub fn publish_changes(&mut self, book: &mut OrderBook) -> Vec<MarketData> {
+        let mut messages = Vec::new();
+        for (side, price) in book.drain_changes() {
+            let current = book.levels(side).get(&price).map(|level| (level.total_quantity(), level.len()));
+            let published = match side {
+                Side::Buy => &mut self.bids,
+                Side::Sell => &mut self.asks,
+            };
+            let action = match (published.get(&price), current) {
+                (None, Some(_)) => LevelAction::Add,
+                (Some(before), Some(level)) if *before != level => LevelAction::Change,
+                (Some(_), None) => LevelAction::Remove,
+                _ => continue,
+            };
+            let (quantity, order_count) = match current {
+                Some(level) => {
+                    published.insert(price, level);
+                    level
+                }
+                None => {
+                    published.remove(&price);
+                    (0, 0)
+                }
+            };
+            self.seq += 1;
+            messages.push(MarketData::Update(L2Update { seq: self.seq, side, price, action, quantity, order_count }));
+        }
+        self.snapshot_if_due(messages)
+    }
+
+    // Follow `messages` with a snapshot when one is due
+    fn snapshot_if_due(&mut self, mut messages: Vec<MarketData>) -> Vec<MarketData> {
+        self.since_snapshot += messages.len() as u64;
+        if self.snapshot_interval > 0 && self.since_snapshot >= self.snapshot_interval {
+            self.since_snapshot = 0;
+            messages.push(MarketData::Snapshot(self.snapshot()));
+        }
+        messages
+    }
+}
+
+/// Best bid and offer after a change to either, as `(price, visible quantity)`; `None` for an
+/// empty side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BboUpdate {
+    /// Ticker sequence number: one more than the previous update's
+    pub seq: u64,
+    pub bid: Option<(Units, Units)>,
+    pub ask: Option<(Units, Units)>,
+}
+
+/// How often a `BboTicker` may publish.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BboMode {
+    /// On every publish that finds the touch changed
+    #[default]
+    EveryChange,
+    /// At most once per `interval` clock units; changes in between are folded into the next
+    /// update, and dropped if the touch returns to what was last published
+    Conflated { interval: u64 },
+}
+
+/// Turns a book into a top-of-book feed, publishing only when the best price or the visible
+/// quantity at it changes on either side. Much lighter than an L2 feed for consumers that only
+/// watch the touch.
+///
+/// Call `publish` after each book operation (and, when conflating, on a timer as well so held
+/// changes go out once the interval has passed).
+#[derive(Debug, Clone, Default)]
+pub struct BboTicker {
+    mode: BboMode,
+    seq: u64,
+    bid: Option<(Units, Units)>,
+    ask: Option<(Units, Units)>,
+    // Time of the last update, for conflation
+    published_at: Option<u64>,
+}
+
+impl BboTicker {
+    /// A ticker that has published an empty book.
+    pub fn new(mode: BboMode) -> Self {
+        Self { mode, ..Self::default() }
+    }
+
+    /// Sequence number of the last update published.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// The touch as last published.
+    pub fn last(&self) -> BboUpdate {
+        BboUpdate { seq: self.seq, bid: self.bid, ask: self.ask }
+    }
+
+    /// An update if `book`'s touch differs from the last one published and the mode allows
+    /// publishing at `now` (a time on the book's clock, ignored unless conflating).
+    pub fn publish(&mut self, book: &OrderBook, now: u64) -> Option<BboUpdate> {
+        let (bid, ask) = (book.best_buy(), book.best_sell());
+        if (bid, ask) == (self.bid, self.ask) {
+            return None;
+        }
+        if let (BboMode::Conflated { interval }, Some(published_at)) = (self.mode, self.published_at) {
+            if now < published_at.saturating_add(interval) {
+                return None;
+            }
+        }
+        self.seq += 1;
+        self.bid = bid;
+        self.ask = ask;
+        self.published_at = Some(now);
+        Some(self.last())
+    }
+}
+
+/// An update arrived out of sequence; the mirror ignores updates until the next snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceGap {
+    pub expected: u64,
+    pub received: u64,
+}
+
+impl fmt::Display for SequenceGap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected update {}, received {}", self.expected, self.received)
+    }
+}
+
+impl std::error::Error for SequenceGap {}
+
+/// Consumer-side copy of a book's visible levels, maintained from an `L2Publisher` feed.
+#[derive(Debug, Clone)]
+pub struct BookMirror {
+    bids: Levels,
+    asks: Levels,
+    seq: u64,
+    synced: bool,
+}
+
+impl Default for BookMirror {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BookMirror {
+    /// A mirror of an empty book at sequence 0, matching a new publisher.
+    pub fn new() -> Self {
+        Self { bids: Levels::new(), asks: Levels::new(), seq: 0, synced: true }
+    }
+
+    /// Apply one feed message. Snapshots always apply and resync the mirror; updates it has
+    /// already seen are skipped. A missing update is reported once and every update after it
+    /// is refused until a snapshot arrives.
+    pub fn apply(&mut self, message: &MarketData) -> Result<(), SequenceGap> {
+        match message {
+            MarketData::Snapshot(snapshot) => {
+                let levels = |depth: &[DepthLevel]| -> Levels {
+                    depth.iter().map(|level| (level.price, (level.quantity, level.order_count))).collect()
+                };
+                self.bids = levels(&snapshot.depth.bids);
+                self.asks = levels(&snapshot.depth.asks);
+                self.seq = snapshot.seq;
+                self.synced = true;
+                Ok(())
+            }
+            MarketData::Update(update) => {
+                if self.synced && update.seq <= self.seq {
+                    return Ok(());
+                }
+                if !self.synced || update.seq != self.seq + 1 {
+                    self.synced = false;
+                    return Err(SequenceGap { expected: self.seq + 1, received: update.seq });
+                }
+                let levels = match update.side {
+                    Side::Buy => &mut self.bids,
+                    Side::Sell => &mut self.asks,
+                };
+                match update.action {
+                    LevelAction::Add | LevelAction::Change => {
+                        levels.insert(update.price, (update.quantity, update.order_count));
+                    }
+                    LevelAction::Remove => {
+                        levels.remove(&update.price);
+                    }
+                }
+                self.seq = update.seq;
+                Ok(())
+            }
+        }
+    }
+
+    /// False from a detected gap until the next snapshot.
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Sequence number of the last update or snapshot applied.
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    pub fn best_buy(&self) -> Option<(Units, Units)> {
+        self.bids.last_key_value().map(|(&price, &(quantity, _))| (price, quantity))
+    }
+
+    pub fn best_sell(&self) -> Option<(Units, Units)> {
+        self.asks.first_key_value().map(|(&price, &(quantity, _))| (price, quantity))
+    }
+
+    /// Best `levels` levels per side, in the same form as `OrderBook::depth`.
+    pub fn depth(&self, levels: usize) -> Depth {
+        depth_of(&self.bids, &self.asks, levels)
+    }
+
+    pub(crate) fn levels(&self, side: Side) -> &Levels {
+        match side {
+            Side::Buy => &self.bids,
+            Side::Sell => &self.asks,
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use crate::book::OrderBook;
+use crate::types::{Side, Trade};
+use crate::units::{notional, Units};
+
+/// How a fee that isn't a whole number of units is rounded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FeeRounding {
+    /// Towards negative infinity: fees round down, rebates round up in size
+    Down,
+    /// Towards positive infinity: fees round up, rebates round down in size
+    #[default]
+    Up,
+    /// To the nearest unit, halves away from zero
+    Nearest,
+}
+
+/// Fees charged on every trade, in the units of price times quantity. A participant's fee is
+/// its rate applied to the trade's notional, plus the flat fee for the side it traded on.
+/// Negative rates and flat fees are rebates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeSchedule {
+    /// Rate for the resting order, in basis points of notional
+    pub maker_bps: i64,
+    /// Rate for the incoming order, in basis points of notional
+    pub taker_bps: i64,
+    /// Per-trade fee for the buyer
+    pub buy_flat: i64,
+    /// Per-trade fee for the seller
+    pub sell_flat: i64,
+    pub rounding: FeeRounding,
+}
+
+impl FeeSchedule {
+    /// `(maker fee, taker fee)` of a trade of `quantity` at `price` against an incoming order
+    /// on `taker_side`, saturating at the range of `i64`.
+    pub fn fees(&self, price: Units, quantity: Units, taker_side: Side) -> (i64, i64) {
+        let notional = i128::try_from(notional(price, quantity)).unwrap_or(i128::MAX);
+        let (maker_flat, taker_flat) = match taker_side {
+            Side::Buy => (self.sell_flat, self.buy_flat),
+            Side::Sell => (self.buy_flat, self.sell_flat),
+        };
+        let fee = |bps: i64, flat: i64| {
+            let fee = self.rounding.divide(notional.saturating_mul(i128::from(bps)), 10_000) + i128::from(flat);
+            fee.clamp(i128::from(i64::MIN), i128::from(i64::MAX)) as i64
+        };
+        (fee(self.maker_bps, maker_flat), fee(self.taker_bps, taker_flat))
+    }
+}
+
+impl FeeRounding {
+    fn divide(self, amount: i128, divisor: i128) -> i128 {
+        let (quotient, remainder) = (amount.div_euclid(divisor), amount.rem_euclid(divisor));
+        match self {
+            FeeRounding::Down => quotient,
+            FeeRounding::Up => quotient + i128::from(remainder > 0),
+            FeeRounding::Nearest => {
+                // A remainder of exactly half rounds away from zero
+                let half_up = if amount >= 0 { remainder * 2 >= divisor } else { remainder * 2 > divisor };
+                quotient + i128::from(half_up)
+            }
+        }
+    }
+}
+
+/// Fees a participant was charged since the book was created; negative totals are net rebates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeTotals {
+    pub maker_fees: i128,
+    pub taker_fees: i128,
+    pub maker_trades: u64,
+    pub taker_trades: u64,
+}
+
+impl FeeTotals {
+    pub fn net(&self) -> i128 {
+        self.maker_fees + self.taker_fees
+    }
+}
+
+// The book's fee schedule and what it has charged so far
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Fees {
+    pub(crate) schedule: Option<FeeSchedule>,
+    pub(crate) totals: HashMap<u64, FeeTotals>,
+}
+
+impl Fees {
+    // Fill in the fees of a new trade and add them to the owners' totals
+    pub(crate) fn charge(&mut self, trade: &mut Trade) {
+        let Some(schedule) = self.schedule else {
+            return;
+        };
+        (trade.maker_fee, trade.taker_fee) = schedule.fees(trade.price, trade.quantity, trade.taker_side);
+        if let Some(owner) = trade.maker_owner {
+            let totals = self.totals.entry(owner).or_default();
+            totals.maker_fees += i128::from(trade.maker_fee);
+            totals.maker_trades += 1;
+        }
+        if let Some(owner) = trade.taker_owner {
+            let totals = self.totals.entry(owner).or_default();
+            totals.taker_fees += i128::from(trade.taker_fee);
+            totals.taker_trades += 1;
+        }
+    }
+
+    // Take a busted or corrected trade's fees back out of the owners' totals. Owners with no
+    // totals weren't charged for it.
+    pub(crate) fn refund(&mut self, trade: &Trade) {
+        if let Some(totals) = trade.maker_owner.and_then(|owner| self.totals.get_mut(&owner)) {
+            totals.maker_fees -= i128::from(trade.maker_fee);
+            totals.maker_trades = totals.maker_trades.saturating_sub(1);
+        }
+        if let Some(totals) = trade.taker_owner.and_then(|owner| self.totals.get_mut(&owner)) {
+            totals.taker_fees -= i128::from(trade.taker_fee);
+            totals.taker_trades = totals.taker_trades.saturating_sub(1);
+        }
+    }
+}
+
+impl OrderBook {
+    /// Charge fees on every trade from now on, reported on each `Trade` and summed per owner.
+    pub fn set_fee_schedule(&mut self, schedule: FeeSchedule) {
+        self.fees.schedule = Some(schedule);
+    }
+
+    /// Stop charging fees; totals charged so far are kept.
+    pub fn clear_fee_schedule(&mut self) {
+        self.fees.schedule = None;
+    }
+
+    pub fn fee_schedule(&self) -> Option<FeeSchedule> {
+        self.fees.schedule
+    }
+
+    /// Fees charged to orders of `owner`; orders without an owner aren't tracked.
+    pub fn fee_totals(&self, owner: u64) -> FeeTotals {
+        self.fees.totals.get(&owner).copied().unwrap_or_default()
+    }
+
+    /// Every owner charged a fee so far, with its totals, in no particular order.
+    pub fn all_fee_totals(&self) -> impl Iterator<Item = (u64, &FeeTotals)> {
+        self.fees.totals.iter().map(|(&owner, totals)| (owner, totals))
+    }
+}
@@ -0,0 +1,382 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver};
+
+use crate::book::OrderBook;
+use crate::events::{BookEvent, Event, OrderEvent, SessionEvent, TradeEvent};
+use crate::types::{CancelError, ExecutionReport, OrderOutcome, PlaceError, Side, Trade};
+use crate::units::{widen, Units};
+
+pub const OB_OK: i32 = 0;
+/// A required pointer argument was null
+pub const OB_ERR_NULL: i32 = -1;
+/// `side` is neither `OB_BUY` nor `OB_SELL`
+pub const OB_ERR_SIDE: i32 = -2;
+/// A resting or held stop order already has the id
+pub const OB_ERR_DUPLICATE_ID: i32 = -3;
+/// No resting order has the id
+pub const OB_ERR_UNKNOWN_ORDER: i32 = -4;
+/// The book detected a bug in itself and needs rebuilding
+pub const OB_ERR_INTERNAL: i32 = -5;
+/// The side has no orders
+pub const OB_ERR_EMPTY: i32 = -6;
+
+pub const OB_BUY: u8 = 0;
+pub const OB_SELL: u8 = 1;
+
+pub const OB_STATUS_FILLED: u32 = 1;
+pub const OB_STATUS_RESTED: u32 = 2;
+pub const OB_STATUS_CANCELLED: u32 = 3;
+pub const OB_STATUS_PENDING: u32 = 4;
+pub const OB_STATUS_REJECTED: u32 = 5;
+
+pub const OB_EVENT_ACCEPTED: u32 = 1;
+pub const OB_EVENT_RESTED: u32 = 2;
+pub const OB_EVENT_PARTIALLY_FILLED: u32 = 3;
+pub const OB_EVENT_FILLED: u32 = 4;
+pub const OB_EVENT_CANCELLED: u32 = 5;
+pub const OB_EVENT_REJECTED: u32 = 6;
+pub const OB_EVENT_TRIGGERED: u32 = 7;
+pub const OB_EVENT_EXPIRED: u32 = 8;
+pub const OB_EVENT_REPRICED: u32 = 9;
+pub const OB_EVENT_LEVEL_ADDED: u32 = 10;
+pub const OB_EVENT_LEVEL_REMOVED: u32 = 11;
+pub const OB_EVENT_BEST_PRICE_CHANGED: u32 = 12;
+pub const OB_EVENT_SESSION_PHASE_CHANGED: u32 = 13;
+pub const OB_EVENT_TRADE_BUSTED: u32 = 14;
+pub const OB_EVENT_TRADE_CORRECTED: u32 = 15;
+
+/// Opaque handle to a book and the events and trades not yet polled from it, for embedding the
+/// engine in C and C++ programs through include/orderbook.h (generated with `cbindgen --config
+/// cbindgen.toml --output include/orderbook.h`).
+///
+/// Functions take the handle from `ob_book_new` and return an `OB_*` status code, writing
+/// results to caller-provided out-parameters and buffers. Events and trades queue up in the
+/// handle until polled. A handle may move between threads but not be shared by them.
+pub struct ObBook {
+    book: OrderBook,
+    events: Receiver<Event>,
+    trades: VecDeque<Trade>,
+}
+
+/// What `ob_place_order` and `ob_place_market_order` did with an order. `reject_reason` is
+/// set with `OB_STATUS_REJECTED`: the `RejectReason` variant's position in its declaration,
+/// counting from 1. The call's trades are queued for `ob_poll_trades`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObReport {
+    pub order_id: u64,
+    pub status: u32,
+    pub reject_reason: u32,
+    pub filled_quantity: u64,
+    pub remaining_quantity: u64,
+    pub trade_count: u64,
+}
+
+/// One book event, `kind` being an `OB_EVENT_*` code. Fields an event doesn't carry are zero:
+/// order events set `id` and whichever of `price`, `quantity` and `remaining` apply; level and
+/// best-price events set `side` and `price`, with `has_price` false when a side emptied;
+/// trade busts and corrections set `id` to the trade's sequence number and `price` and
+/// `quantity` to what it was busted at or corrected to; `code` is the reject reason (as in
+/// `ObReport`) or the session phase, counting from 1.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObEvent {
+    pub kind: u32,
+    pub code: u32,
+    pub id: u64,
+    pub side: u8,
+    pub has_price: bool,
+    pub price: u64,
+    pub quantity: u64,
+    pub remaining: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ObTrade {
+    pub price: u64,
+    pub quantity: u64,
+    pub maker_id: u64,
+    pub taker_id: u64,
+    pub seq: u64,
+    pub timestamp: u64,
+}
+
+/// A new empty book; free it with `ob_book_free`.
+#[no_mangle]
+pub extern "C" fn ob_book_new() -> *mut ObBook {
+    let (tx, events) = mpsc::channel();
+    let mut book = OrderBook::new();
+    book.subscribe(tx);
+    Box::into_raw(Box::new(ObBook { book, events, trades: VecDeque::new() }))
+}
+
+/// Free a book; null is ignored.
+///
+/// # Safety
+/// `book` is null or a handle from `ob_book_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn ob_book_free(book: *mut ObBook) {
+    if !book.is_null() {
+        // SAFETY: the handle came from Box::into_raw in ob_book_new and is freed only once
+        drop(unsafe { Box::from_raw(book) });
+    }
+}
+
+/// Enter a limit order, writing what became of it to `report` unless that is null.
+///
+/// # Safety
+/// `book` is a live handle from `ob_book_new`; `report` is null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ob_place_order(
+    book: *mut ObBook,
+    side: u8,
+    price: u64,
+    quantity: u64,
+    id: u64,
+    report: *mut ObReport,
+) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let Some(book) = (unsafe { book.as_mut() }) else {
+        return OB_ERR_NULL;
+    };
+    let Some(side) = parse_side(side) else {
+        return OB_ERR_SIDE;
+    };
+    let result = book.book.place_order(side, price as Units, quantity as Units, id);
+    // SAFETY: guaranteed by the caller
+    unsafe { book.finish_placing(result, report) }
+}
+
+/// Enter a market order, writing what became of it to `report` unless that is null.
+///
+/// # Safety
+/// `book` is a live handle from `ob_book_new`; `report` is null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ob_place_market_order(
+    book: *mut ObBook,
+    side: u8,
+    quantity: u64,
+    id: u64,
+    report: *mut ObReport,
+) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let Some(book) = (unsafe { book.as_mut() }) else {
+        return OB_ERR_NULL;
+    };
+    let Some(side) = parse_side(side) else {
+        return OB_ERR_SIDE;
+    };
+    let result = book.book.place_market_order(side, quantity as Units, id);
+    // SAFETY: guaranteed by the caller
+    unsafe { book.finish_placing(result, report) }
+}
+
+/// Cancel a resting order, writing its unfilled quantity to `remaining` unless that is null.
+///
+/// # Safety
+/// `book` is a live handle from `ob_book_new`; `remaining` is null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ob_cancel(book: *mut ObBook, id: u64, remaining: *mut u64) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let Some(book) = (unsafe { book.as_mut() }) else {
+        return OB_ERR_NULL;
+    };
+    match book.book.cancel_order(id) {
+        Ok(order) => {
+            // SAFETY: guaranteed by the caller
+            if let Some(remaining) = unsafe { remaining.as_mut() } {
+                *remaining = narrow(order.remaining_quantity());
+            }
+            OB_OK
+        }
+        Err(CancelError::UnknownOrder(_)) => OB_ERR_UNKNOWN_ORDER,
+        Err(CancelError::Invariant(_)) => OB_ERR_INTERNAL,
+    }
+}
+
+/// Best price and visible quantity on `side`, or `OB_ERR_EMPTY`.
+///
+/// # Safety
+/// `book` is a live handle from `ob_book_new`; `price` and `quantity` are valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ob_best_price(book: *const ObBook, side: u8, price: *mut u64, quantity: *mut u64) -> i32 {
+    // SAFETY: guaranteed by the caller
+    let (Some(book), Some(price), Some(quantity)) = (unsafe { (book.as_ref(), price.as_mut(), quantity.as_mut()) })
+    else {
+        return OB_ERR_NULL;
+    };
+    let best = match parse_side(side) {
+        Some(Side::Buy) => book.book.best_buy(),
+        Some(Side::Sell) => book.book.best_sell(),
+        None => return OB_ERR_SIDE,
+    };
+    let Some((best_price, best_quantity)) = best else {
+        return OB_ERR_EMPTY;
+    };
+    (*price, *quantity) = (narrow(best_price), narrow(best_quantity));
+    OB_OK
+}
+
+/// Move up to `capacity` of the oldest unpolled events into `events`, returning how many.
+///
+/// # Safety
+/// `book` is a live handle from `ob_book_new`; `events` is valid for `capacity` writes.
+#[no_mangle]
+pub unsafe extern "C" fn ob_poll_events(book: *mut ObBook, events: *mut ObEvent, capacity: usize) -> usize {
+    // SAFETY: guaranteed by the caller
+    let Some(book) = (unsafe { book.as_mut() }) else {
+        return 0;
+    };
+    let mut count = 0;
+    while count < capacity {
+        let Ok(event) = book.events.try_recv() else {
+            break;
+        };
+        // SAFETY: count < capacity, so within the caller's buffer
+        unsafe { events.add(count).write(ObEvent::from(event)) };
+        count += 1;
+    }
+    count
+}
+
+/// Move up to `capacity` of the oldest unpolled trades into `trades`, returning how many.
+///
+/// # Safety
+/// `book` is a live handle from `ob_book_new`; `trades` is valid for `capacity` writes.
+#[no_mangle]
+pub unsafe extern "C" fn ob_poll_trades(book: *mut ObBook, trades: *mut ObTrade, capacity: usize) -> usize {
+    // SAFETY: guaranteed by the caller
+    let Some(book) = (unsafe { book.as_mut() }) else {
+        return 0;
+    };
+    let count = capacity.min(book.trades.len());
+    for (n, trade) in book.trades.drain(..count).enumerate() {
+        // SAFETY: n < count <= capacity, so within the caller's buffer
+        unsafe { trades.add(n).write(ObTrade::from(&trade)) };
+    }
+    count
+}
+
+impl ObBook {
+    // Queues the call's trades and fills in the caller's report
+    unsafe fn finish_placing(&mut self, result: Result<ExecutionReport, PlaceError>, report: *mut ObReport) -> i32 {
+        let placed = match result {
+            Ok(placed) => placed,
+            Err(PlaceError::DuplicateId(_)) => return OB_ERR_DUPLICATE_ID,
+            Err(PlaceError::Invariant(_)) => return OB_ERR_INTERNAL,
+        };
+        self.trades.extend(placed.trades.iter().cloned());
+        // SAFETY: guaranteed by the caller
+        if let Some(report) = unsafe { report.as_mut() } {
+            *report = ObReport::from(&placed);
+        }
+        OB_OK
+    }
+}
+
+impl From<&ExecutionReport> for ObReport {
+    fn from(report: &ExecutionReport) -> Self {
+        let (status, reject_reason) = match report.status {
+            OrderOutcome::Filled => (OB_STATUS_FILLED, 0),
+            OrderOutcome::Rested => (OB_STATUS_RESTED, 0),
+            OrderOutcome::Cancelled => (OB_STATUS_CANCELLED, 0),
+            OrderOutcome::Pending => (OB_STATUS_PENDING, 0),
+            OrderOutcome::Rejected(reason) => (OB_STATUS_REJECTED, reason as u32 + 1),
+        };
+        Self {
+            order_id: report.order_id,
+            status,
+            reject_reason,
+            filled_quantity: narrow(report.filled_quantity),
+            remaining_quantity: narrow(report.remaining_quantity),
+            trade_count: report.trades.len() as u64,
+        }
+    }
+}
+
+impl From<Event> for ObEvent {
+    fn from(event: Event) -> Self {
+        let order = |kind, id| ObEvent { kind, id, ..ObEvent::default() };
+        let level = |kind, side, price: Option<Units>| ObEvent {
+            kind,
+            side: match side {
+                Side::Buy => OB_BUY,
+                Side::Sell => OB_SELL,
+            },
+            has_price: price.is_some(),
+            price: price.map_or(0, narrow),
+            ..ObEvent::default()
+        };
+        match event {
+            Event::Order(OrderEvent::Accepted { id }) => order(OB_EVENT_ACCEPTED, id),
+            Event::Order(OrderEvent::Rested { id, price, quantity }) => {
+                ObEvent { has_price: true, price: narrow(price), quantity: narrow(quantity), ..order(OB_EVENT_RESTED, id) }
+            }
+            Event::Order(OrderEvent::PartiallyFilled { id, price, quantity, remaining }) => ObEvent {
+                has_price: true,
+                price: narrow(price),
+                quantity: narrow(quantity),
+                remaining: narrow(remaining),
+                ..order(OB_EVENT_PARTIALLY_FILLED, id)
+            },
+            Event::Order(OrderEvent::Filled { id, price, quantity }) => {
+                ObEvent { has_price: true, price: narrow(price), quantity: narrow(quantity), ..order(OB_EVENT_FILLED, id) }
+            }
+            Event::Order(OrderEvent::Cancelled { id, remaining }) => {
+                ObEvent { remaining: narrow(remaining), ..order(OB_EVENT_CANCELLED, id) }
+            }
+            Event::Order(OrderEvent::Rejected { id, reason }) => {
+                ObEvent { code: reason as u32 + 1, ..order(OB_EVENT_REJECTED, id) }
+            }
+            Event::Order(OrderEvent::Triggered { id }) => order(OB_EVENT_TRIGGERED, id),
+            Event::Order(OrderEvent::Expired { id, remaining }) => {
+                ObEvent { remaining: narrow(remaining), ..order(OB_EVENT_EXPIRED, id) }
+            }
+            Event::Order(OrderEvent::Repriced { id, price }) => {
+                ObEvent { has_price: true, price: narrow(price), ..order(OB_EVENT_REPRICED, id) }
+            }
+            Event::Book(BookEvent::LevelAdded { side, price }) => level(OB_EVENT_LEVEL_ADDED, side, Some(price)),
+            Event::Book(BookEvent::LevelRemoved { side, price }) => level(OB_EVENT_LEVEL_REMOVED, side, Some(price)),
+            Event::Book(BookEvent::BestPriceChanged { side, price }) => level(OB_EVENT_BEST_PRICE_CHANGED, side, price),
+            Event::Session(SessionEvent::PhaseChanged { phase, .. }) => {
+                ObEvent { kind: OB_EVENT_SESSION_PHASE_CHANGED, code: phase as u32 + 1, ..ObEvent::default() }
+            }
+            Event::Trade(TradeEvent::Busted { seq, price, quantity, .. }) => {
+                ObEvent { has_price: true, price: narrow(price), quantity: narrow(quantity), ..order(OB_EVENT_TRADE_BUSTED, seq) }
+            }
+            Event::Trade(TradeEvent::Corrected { seq, price, quantity, .. }) => ObEvent {
+                has_price: true,
+                price: narrow(price),
+                quantity: narrow(quantity),
+                ..order(OB_EVENT_TRADE_CORRECTED, seq)
+            },
+        }
+    }
+}
+
+impl From<&Trade> for ObTrade {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            price: narrow(trade.price),
+            quantity: narrow(trade.quantity),
+            maker_id: trade.maker_id,
+            taker_id: trade.taker_id,
+            seq: trade.seq,
+            timestamp: trade.timestamp,
+        }
+    }
+}
+
+fn parse_side(side: u8) -> Option<Side> {
+    match side {
+        OB_BUY => Some(Side::Buy),
+        OB_SELL => Some(Side::Sell),
+        _ => None,
+    }
+}
+
+// A price or quantity for C, saturating above u64::MAX (which only the `wide` feature can reach)
+fn narrow(value: Units) -> u64 {
+    u64::try_from(widen(value)).unwrap_or(u64::MAX)
+}
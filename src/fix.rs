@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::book::OrderBook;
+use crate::clock::{Clock, MonotonicClock};
+use crate::order::NewOrder;
+use crate::types::{ExecutionReport, OrderOutcome, Side, TimeInForce, Trade};
+use crate::units::{notional, widen, Units};
+
+const SOH: u8 = 0x01;
+const BEGIN_STRING: &str = "FIX.4.4";
+
+/// Why a FIX message was refused at the session level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixError {
+    /// Not a sequence of `tag=value` fields ending in SOH, or the wrong begin string
+    Malformed(String),
+    BadBodyLength,
+    BadChecksum,
+    MissingField(u32),
+    InvalidField(u32),
+    /// Only a Logon is accepted before logging on
+    NotLoggedOn,
+    /// MsgSeqNum went backwards; the session can't continue
+    SequenceTooLow { expected: u64, received: u64 },
+}
+
+impl fmt::Display for FixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixError::Malformed(reason) => write!(f, "malformed FIX message: {}", reason),
+            FixError::BadBodyLength => write!(f, "BodyLength(9) doesn't match the message"),
+            FixError::BadChecksum => write!(f, "CheckSum(10) doesn't match the message"),
+            FixError::MissingField(tag) => write!(f, "required tag {} missing", tag),
+            FixError::InvalidField(tag) => write!(f, "tag {} has an invalid value", tag),
+            FixError::NotLoggedOn => write!(f, "session not logged on"),
+            FixError::SequenceTooLow { expected, received } => {
+                write!(f, "MsgSeqNum too low: expected {}, received {}", expected, received)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FixError {}
+
+/// A FIX message as its body fields in order, MsgType(35) first. BeginString, BodyLength and
+/// CheckSum are dealt with by `parse` and `encode`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        Self { fields: vec![(35, msg_type.to_string())] }
+    }
+
+    pub fn msg_type(&self) -> Option<&str> {
+        self.get(35)
+    }
+
+    /// First value of `tag`.
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields.iter().find(|(t, _)| *t == tag).map(|(_, value)| value.as_str())
+    }
+
+    pub fn fields(&self) -> &[(u32, String)] {
+        &self.fields
+    }
+
+    /// Set `tag`, replacing its first value or appending it.
+    pub fn set(&mut self, tag: u32, value: impl ToString) -> &mut Self {
+        let value = value.to_string();
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((tag, value)),
+        }
+        self
+    }
+
+    pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.set(tag, value);
+        self
+    }
+
+    /// Parse one complete message, checking its begin string, body length and checksum.
+    pub fn parse(raw: &[u8]) -> Result<Self, FixError> {
+        let text = std::str::from_utf8(raw).map_err(|_| FixError::Malformed("not UTF-8".into()))?;
+        let body = text.strip_suffix('\u{1}').ok_or_else(|| FixError::Malformed("doesn't end in SOH".into()))?;
+        let mut fields = Vec::new();
+        for field in body.split('\u{1}') {
+            let (tag, value) = field.split_once('=').ok_or_else(|| FixError::Malformed(format!("field {:?}", field)))?;
+            let tag: u32 = tag.parse().map_err(|_| FixError::Malformed(format!("tag {:?}", tag)))?;
+            fields.push((tag, value.to_string()));
+        }
+
+        if fields.first().map(|(tag, value)| (*tag, value.as_str())) != Some((8, BEGIN_STRING)) {
+            return Err(FixError::Malformed("BeginString(8) must be FIX.4.4 and come first".into()));
+        }
+        let Some((10, checksum)) = fields.last() else { return Err(FixError::MissingField(10)) };
+        let checksum_at = raw.len() - checksum.len() - 4;
+        if checksum.parse::<u32>().ok() != Some(Self::checksum(&raw[..checksum_at])) {
+            return Err(FixError::BadChecksum);
+        }
+        let Some((9, length)) = fields.get(1) else { return Err(FixError::MissingField(9)) };
+        let body_start = BEGIN_STRING.len() + 3 + length.len() + 3;
+        if length.parse::<usize>().ok() != checksum_at.checked_sub(body_start) {
+            return Err(FixError::BadBodyLength);
+        }
+        fields.drain(..2);
+        fields.pop();
+        if fields.first().map(|(tag, _)| *tag) != Some(35) {
+            return Err(FixError::MissingField(35));
+        }
+        Ok(Self { fields })
+    }
+
+    /// The message on the wire, with BeginString, BodyLength and CheckSum added.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (tag, value) in &self.fields {
+            body.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+            body.push(SOH);
+        }
+        let mut out = format!("8={}\u{1}9={}\u{1}", BEGIN_STRING, body.len()).into_bytes();
+        out.extend_from_slice(&body);
+        let checksum = Self::checksum(&out);
+        out.extend_from_slice(format!("10={:03}\u{1}", checksum).as_bytes());
+        out
+    }
+
+    fn checksum(bytes: &[u8]) -> u32 {
+        bytes.iter().map(|&b| u32::from(b)).sum::<u32>() % 256
+    }
+
+    fn required(&self, tag: u32) -> Result<&str, FixError> {
+        self.get(tag).ok_or(FixError::MissingField(tag))
+    }
+
+    fn quantity(&self, tag: u32) -> Result<Units, FixError> {
+        self.required(tag)?.parse().map_err(|_| FixError::InvalidField(tag))
+    }
+}
+
+// What the gateway remembers of an order entered through it
+struct ClientOrder {
+    id: u64,
+    side: Side,
+    symbol: Option<String>,
+    // Total order quantity as FIX counts it: filled plus open
+    quantity: Units,
+    cum_qty: Units,
+    notional: u128,
+}
+
+/// A minimal FIX 4.4 acceptor in front of one order book.
+///
+/// Handles Logon, Heartbeat, TestRequest and Logout at the session level (no resend or gap
+/// fill) and maps NewOrderSingle(D), OrderCancelRequest(F) and OrderCancelReplaceRequest(G)
+/// onto the book, answering with ExecutionReports(8) and OrderCancelRejects(9). Fills of
+/// resting orders entered through the gateway are reported as they happen. ClOrdIDs are
+/// mapped to engine ids from `OrderBook::next_order_id`; prices use the book's `PriceConfig`.
+pub struct FixGateway {
+    book: OrderBook,
+    sender_comp_id: String,
+    target_comp_id: String,
+    clock: Box<dyn Clock>,
+    logged_on: bool,
+    next_in_seq: u64,
+    next_out_seq: u64,
+    last_exec_id: u64,
+    orders: HashMap<String, ClientOrder>,
+    // Engine id -> current ClOrdID
+    client_ids: HashMap<u64, String>,
+}
+
+impl FixGateway {
+    /// A gateway identifying itself as `sender_comp_id` to the counterparty `target_comp_id`,
+    /// stamping SendingTime from the system clock.
+    pub fn new(book: OrderBook, sender_comp_id: &str, target_comp_id: &str) -> Self {
+        Self::with_clock(book, sender_comp_id, target_comp_id, Box::new(MonotonicClock::new()))
+    }
+
+    /// `new` with the clock SendingTime is read from, in nanoseconds since the Unix epoch.
+    pub fn with_clock(book: OrderBook, sender_comp_id: &str, target_comp_id: &str, clock: Box<dyn Clock>) -> Self {
+        Self {
+            book,
+            sender_comp_id: sender_comp_id.to_string(),
+            target_comp_id: target_comp_id.to_string(),
+            clock,
+            logged_on: false,
+            next_in_seq: 1,
+            next_out_seq: 1,
+            last_exec_id: 0,
+            orders: HashMap::new(),
+            client_ids: HashMap::new(),
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn is_logged_on(&self) -> bool {
+        self.logged_on
+    }
+
+    /// Handle one raw message, returning the raw replies.
+    pub fn handle(&mut self, raw: &[u8]) -> Result<Vec<Vec<u8>>, FixError> {
+        let message = FixMessage::parse(raw)?;
+        Ok(self.handle_message(&message)?.iter().map(FixMessage::encode).collect())
+    }
+
+    /// Handle one parsed message. Replies come back with their session header filled in.
+    pub fn handle_message(&mut self, message: &FixMessage) -> Result<Vec<FixMessage>, FixError> {
+        let seq: u64 = message.required(34)?.parse().map_err(|_| FixError::InvalidField(34))?;
+        if seq < self.next_in_seq {
+            return Err(FixError::SequenceTooLow { expected: self.next_in_seq, received: seq });
+        }
+        self.next_in_seq = seq + 1;
+        let msg_type = message.required(35)?;
+        if !self.logged_on && msg_type != "A" {
+            return Err(FixError::NotLoggedOn);
+        }
+
+        let replies = match msg_type {
+            "A" => {
+                self.logged_on = true;
+                vec![FixMessage::new("A").with(98, 0).with(108, message.get(108).unwrap_or("30"))]
+            }
+            "0" => Vec::new(),
+            "1" => vec![FixMessage::new("0").with(112, message.required(112)?)],
+            "5" => {
+                self.logged_on = false;
+                vec![FixMessage::new("5")]
+            }
+            "D" => self.new_order_single(message)?,
+            "F" => self.cancel_request(message)?,
+            "G" => self.replace_request(message)?,
+            other => vec![FixMessage::new("3")
+                .with(45, seq)
+                .with(372, other)
+                .with(373, 11)
+                .with(58, "unsupported message type")],
+        };
+        Ok(replies.into_iter().map(|reply| self.outgoing(reply)).collect())
+    }
+
+    fn outgoing(&mut self, message: FixMessage) -> FixMessage {
+        let seq = self.next_out_seq;
+        self.next_out_seq += 1;
+        let mut fields = vec![
+            message.fields[0].clone(),
+            (49, self.sender_comp_id.clone()),
+            (56, self.target_comp_id.clone()),
+            (34, seq.to_string()),
+            (52, sending_time(self.clock.now())),
+        ];
+        fields.extend(message.fields.into_iter().skip(1));
+        FixMessage { fields }
+    }
+
+    fn new_order_single(&mut self, message: &FixMessage) -> Result<Vec<FixMessage>, FixError> {
+        let cl_ord_id = message.required(11)?.to_string();
+        let side = parse_side(message)?;
+        let quantity = message.quantity(38)?;
+        let order = match message.required(40)? {
+            "1" => NewOrder::market(side, quantity, 0),
+            "2" => {
+                let price = self.book.price_config().parse_price(message.required(44)?).ok_or(FixError::InvalidField(44))?;
+                NewOrder::limit(side, price, quantity, 0)
+            }
+            _ => return Err(FixError::InvalidField(40)),
+        };
+        let time_in_force = match message.get(59).unwrap_or("0") {
+            "0" | "1" => TimeInForce::Gtc,
+            "3" => TimeInForce::Ioc,
+            "4" => TimeInForce::Fok,
+            _ => return Err(FixError::InvalidField(59)),
+        };
+        let symbol = message.get(55).map(str::to_string);
+        if self.orders.contains_key(&cl_ord_id) {
+            let order = ClientOrder { id: 0, side, symbol, quantity, cum_qty: 0, notional: 0 };
+            return Ok(vec![self.report_for(&cl_ord_id, &order, '8', '8').with(58, "duplicate ClOrdID")]);
+        }
+
+        let report = match self.book.submit_with_new_id(order.with_time_in_force(time_in_force)) {
+            Ok(report) => report,
+            Err(err) => {
+                let order = ClientOrder { id: 0, side, symbol, quantity, cum_qty: 0, notional: 0 };
+                return Ok(vec![self.report_for(&cl_ord_id, &order, '8', '8').with(58, err.to_string())]);
+            }
+        };
+        let id = report.order_id;
+        let order = ClientOrder { id, side, symbol, quantity, cum_qty: 0, notional: 0 };
+        if let OrderOutcome::Rejected(reason) = report.status {
+            return Ok(vec![self.report_for(&cl_ord_id, &order, '8', '8').with(58, format!("{:?}", reason))]);
+        }
+        self.orders.insert(cl_ord_id.clone(), order);
+        self.client_ids.insert(id, cl_ord_id.clone());
+        let mut replies: Vec<FixMessage> = self.report(&cl_ord_id, '0', None).into_iter().collect();
+        self.fills(&report, &mut replies);
+        self.finish(id, &report, &mut replies);
+        Ok(replies)
+    }
+
+    fn cancel_request(&mut self, message: &FixMessage) -> Result<Vec<FixMessage>, FixError> {
+        let orig = message.required(41)?;
+        let cl_ord_id = message.required(11)?.to_string();
+        let Some(id) = self.orders.get(orig).map(|order| order.id) else {
+            return Ok(vec![cancel_reject(&cl_ord_id, orig, '1', "unknown order")]);
+        };
+        if self.book.cancel_order(id).is_err() {
+            return Ok(vec![cancel_reject(&cl_ord_id, orig, '1', "order is no longer open")]);
+        }
+        self.rename(orig, &cl_ord_id);
+        let reply = self.report(&cl_ord_id, '4', None).map(|reply| reply.with(41, orig));
+        self.forget(id);
+        Ok(reply.into_iter().collect())
+    }
+
+    fn replace_request(&mut self, message: &FixMessage) -> Result<Vec<FixMessage>, FixError> {
+        let orig = message.required(41)?;
+        let cl_ord_id = message.required(11)?.to_string();
+        let quantity = message.quantity(38)?;
+        let price = self.book.price_config().parse_price(message.required(44)?).ok_or(FixError::InvalidField(44))?;
+        let Some((id, cum_qty)) = self.orders.get(orig).map(|order| (order.id, order.cum_qty)) else {
+            return Ok(vec![cancel_reject(&cl_ord_id, orig, '1', "unknown order")]);
+        };
+        // FIX quantities include what has already filled; the engine amends the open part
+        let Some(open) = quantity.checked_sub(cum_qty).filter(|&open| open > 0) else {
+            return Ok(vec![cancel_reject(&cl_ord_id, orig, '0', "quantity not above the filled quantity")]);
+        };
+        // A rejected replace leaves the order resting as it was
+        let report = match self.book.modify_order(id, price, open) {
+            Ok(report) if matches!(report.status, OrderOutcome::Rejected(_)) => {
+                return Ok(vec![cancel_reject(&cl_ord_id, orig, '0', "replace rejected")]);
+            }
+            Ok(report) => report,
+            Err(_) => return Ok(vec![cancel_reject(&cl_ord_id, orig, '1', "order is no longer open")]),
+        };
+        self.rename(orig, &cl_ord_id);
+        if let Some(order) = self.orders.get_mut(&cl_ord_id) {
+            order.quantity = quantity;
+        }
+        let mut replies: Vec<FixMessage> = self.report(&cl_ord_id, '5', None).map(|reply| reply.with(41, orig)).into_iter().collect();
+        self.fills(&report, &mut replies);
+        self.finish(id, &report, &mut replies);
+        Ok(replies)
+    }
+
+    // Trade reports for every order of the gateway on either side of the call's trades
+    fn fills(&mut self, report: &ExecutionReport, replies: &mut Vec<FixMessage>) {
+        for &Trade { price, quantity, maker_id, taker_id, .. } in &report.trades {
+            for id in [maker_id, taker_id] {
+                let Some(cl_ord_id) = self.client_ids.get(&id).cloned() else { continue };
+                let Some(order) = self.orders.get_mut(&cl_ord_id) else { continue };
+                order.cum_qty += quantity;
+                order.notional += notional(price, quantity);
+                replies.extend(self.report(&cl_ord_id, 'F', Some((price, quantity))));
+            }
+        }
+        // Makers that filled in full have left the book
+        for trade in &report.trades {
+            if self.book.order_status(trade.maker_id).is_none() {
+                self.forget(trade.maker_id);
+            }
+        }
+    }
+
+    // Report a dropped remainder and stop tracking orders that are done
+    fn finish(&mut self, id: u64, report: &ExecutionReport, replies: &mut Vec<FixMessage>) {
+        if report.status == OrderOutcome::Cancelled {
+            if let Some(cl_ord_id) = self.client_ids.get(&id).cloned() {
+                replies.extend(self.report(&cl_ord_id, '4', None));
+            }
+        }
+        if self.book.order_status(id).is_none() {
+            self.forget(id);
+        }
+    }
+
+    fn rename(&mut self, orig: &str, cl_ord_id: &str) {
+        if let Some(order) = self.orders.remove(orig) {
+            self.client_ids.insert(order.id, cl_ord_id.to_string());
+            self.orders.insert(cl_ord_id.to_string(), order);
+        }
+    }
+
+    fn forget(&mut self, id: u64) {
+        if let Some(cl_ord_id) = self.client_ids.remove(&id) {
+            self.orders.remove(&cl_ord_id);
+        }
+    }
+
+    // Execution report on a tracked order; `None` for a ClOrdID the gateway doesn't track
+    fn report(&mut self, cl_ord_id: &str, exec_type: char, last: Option<(Units, Units)>) -> Option<FixMessage> {
+        let order = self.orders.get(cl_ord_id)?;
+        let status = match exec_type {
+            '4' => '4',
+            _ if order.cum_qty == order.quantity => '2',
+            _ if order.cum_qty > 0 => '1',
+            _ => '0',
+        };
+        let mut message = self.report_fields(cl_ord_id, order, exec_type, status);
+        if let Some((price, quantity)) = last {
+            message.set(31, self.book.price_config().format_price(price)).set(32, quantity);
+        }
+        self.last_exec_id += 1;
+        Some(message.with(17, self.last_exec_id))
+    }
+
+    fn report_for(&mut self, cl_ord_id: &str, order: &ClientOrder, exec_type: char, status: char) -> FixMessage {
+        self.last_exec_id += 1;
+        self.report_fields(cl_ord_id, order, exec_type, status).with(17, self.last_exec_id)
+    }
+
+    fn report_fields(&self, cl_ord_id: &str, order: &ClientOrder, exec_type: char, status: char) -> FixMessage {
+        let done = matches!(status, '2' | '4' | '8');
+        let config = self.book.price_config();
+        let average = match order.cum_qty {
+            0 => 0,
+            filled => (order.notional / widen(filled)) as Units,
+        };
+        let mut message = FixMessage::new("8")
+            .with(37, order.id)
+            .with(11, cl_ord_id)
+            .with(150, exec_type)
+            .with(39, status)
+            .with(54, if order.side == Side::Buy { '1' } else { '2' })
+            .with(38, order.quantity)
+            .with(14, order.cum_qty)
+            .with(151, if done { 0 } else { order.quantity - order.cum_qty })
+            .with(6, config.format_price(average));
+        if let Some(symbol) = &order.symbol {
+            message.set(55, symbol);
+        }
+        message
+    }
+}
+
+fn parse_side(message: &FixMessage) -> Result<Side, FixError> {
+    match message.required(54)? {
+        "1" => Ok(Side::Buy),
+        "2" => Ok(Side::Sell),
+        _ => Err(FixError::InvalidField(54)),
+    }
+}
+
+fn cancel_reject(cl_ord_id: &str, orig: &str, reason: char, text: &str) -> FixMessage {
+    FixMessage::new("9")
+        .with(37, "NONE")
+        .with(11, cl_ord_id)
+        .with(41, orig)
+        .with(39, '8')
+        .with(434, if reason == '1' { '1' } else { '2' })
+        .with(102, reason)
+        .with(58, text)
+}
+
+// UTCTimestamp (YYYYMMDD-HH:MM:SS.sss) of nanoseconds since the Unix epoch
+fn sending_time(nanos: u64) -> String {
+    let secs = nanos / 1_000_000_000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}:{:02}:{:02}.{:03}",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60,
+        nanos % 1_000_000_000 / 1_000_000
+    )
+}
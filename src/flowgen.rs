@@ -0,0 +1,202 @@
+use std::io::{self, Write};
+
+use crate::order::{Command, NewOrder};
+use crate::sim::SimRng;
+use crate::types::{OrderType, Side};
+use crate::units::{widen, Units};
+
+/// Shape of the order flow a `FlowGenerator` produces. Times are in nanoseconds; ratios are
+/// probabilities per command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlowConfig {
+    /// Mean commands per second outside bursts; arrivals are Poisson, so the gaps between
+    /// commands are exponentially distributed
+    pub rate: f64,
+    /// Where the mid price starts, rounded down to a tick
+    pub mid_price: Units,
+    pub tick_size: Units,
+    /// Chance that a command first moves the mid one tick, up or down with equal odds
+    pub mid_step: f64,
+    /// Mean distance of limit prices from the mid in ticks, exponentially distributed so most
+    /// orders land near it
+    pub mean_offset: f64,
+    /// Share of new orders priced through the mid rather than behind it, so they usually trade
+    pub aggressive_ratio: f64,
+    /// Share of new orders sent as market orders
+    pub market_ratio: f64,
+    /// Share of commands cancelling one of the limit orders generated earlier
+    pub cancel_ratio: f64,
+    /// Quantities are uniform in `min_quantity..=max_quantity`
+    pub min_quantity: Units,
+    pub max_quantity: Units,
+    /// Chance that a command starts a burst, during which commands arrive `burst_multiplier`
+    /// times faster
+    pub burst_probability: f64,
+    pub burst_multiplier: f64,
+    /// Mean number of commands in a burst
+    pub burst_length: f64,
+}
+
+impl Default for FlowConfig {
+    fn default() -> Self {
+        Self {
+            rate: 1000.0,
+            mid_price: 10_000,
+            tick_size: 1,
+            mid_step: 0.05,
+            mean_offset: 5.0,
+            aggressive_ratio: 0.1,
+            market_ratio: 0.02,
+            cancel_ratio: 0.3,
+            min_quantity: 1,
+            max_quantity: 100,
+            burst_probability: 0.01,
+            burst_multiplier: 10.0,
+            burst_length: 50.0,
+        }
+    }
+}
+
+/// Endless random order flow for benchmarks, fuzzing seeds and demo data, as `(timestamp,
+/// command)` pairs ready for `Simulator::send_at`, or `apply_batch` once the timestamps are
+/// dropped. The same seed and config always give the same flow. Ids count up from 1.
+///
+/// Cancels pick uniformly among the generator's limit orders not yet cancelled, which may
+/// since have filled; `forget` takes an order out of the running once a report says so.
+#[derive(Debug, Clone)]
+pub struct FlowGenerator {
+    config: FlowConfig,
+    rng: SimRng,
+    mid: Units,
+    // Nanoseconds since the flow started, kept fractional so short gaps don't round away
+    time: f64,
+    next_id: u64,
+    live: Vec<u64>,
+    burst_left: u64,
+}
+
+impl FlowGenerator {
+    /// Panics if a rate, ratio or quantity range in `config` makes no sense: rates and the
+    /// burst multiplier must be positive, ratios between 0 and 1 with `aggressive_ratio +
+    /// market_ratio` at most 1, and `1 <= min_quantity <= max_quantity`.
+    pub fn new(seed: u64, config: FlowConfig) -> Self {
+        let ratio = |value: f64| (0.0..=1.0).contains(&value);
+        assert!(config.rate > 0.0 && config.rate.is_finite(), "rate must be positive");
+        assert!(config.burst_multiplier > 0.0 && config.burst_multiplier.is_finite(), "burst multiplier must be positive");
+        assert!(config.mean_offset >= 0.0 && config.burst_length >= 0.0, "means must not be negative");
+        assert!(
+            [config.mid_step, config.aggressive_ratio, config.market_ratio, config.cancel_ratio, config.burst_probability]
+                .into_iter()
+                .all(ratio),
+            "ratios must be between 0 and 1"
+        );
+        assert!(config.aggressive_ratio + config.market_ratio <= 1.0, "aggressive and market ratios add up to over 1");
+        assert!(config.tick_size > 0, "tick size must be positive");
+        assert!(config.min_quantity > 0 && config.min_quantity <= config.max_quantity, "invalid quantity range");
+        let mid = (config.mid_price / config.tick_size * config.tick_size).max(config.tick_size);
+        Self { config, rng: SimRng::new(seed), mid, time: 0.0, next_id: 1, live: Vec::new(), burst_left: 0 }
+    }
+
+    /// Current mid price of the random walk the prices are drawn around.
+    pub fn mid(&self) -> Units {
+        self.mid
+    }
+
+    /// Stop cancelling order `id`, e.g. once it has filled.
+    pub fn forget(&mut self, id: u64) {
+        if let Some(index) = self.live.iter().position(|&live| live == id) {
+            self.live.swap_remove(index);
+        }
+    }
+
+    /// Write the next `count` commands as an order-flow CSV file that `replay` reads.
+    pub fn write_csv<W: Write>(&mut self, count: usize, mut output: W) -> io::Result<()> {
+        writeln!(output, "timestamp,side,price,qty,id,action")?;
+        for (timestamp, command) in self.by_ref().take(count) {
+            match command {
+                Command::Place(order) => {
+                    let side = match order.side {
+                        Side::Buy => "buy",
+                        Side::Sell => "sell",
+                    };
+                    let price = match order.order_type {
+                        OrderType::Limit { price } => price.to_string(),
+                        _ => String::new(),
+                    };
+                    writeln!(output, "{},{},{},{},{},new", timestamp, side, price, order.quantity, order.id)?;
+                }
+                Command::Cancel { id } => writeln!(output, "{},,,,{},cancel", timestamp, id)?,
+                Command::Modify { id, price, quantity } => {
+                    writeln!(output, "{},,{},{},{},modify", timestamp, price, quantity, id)?
+                }
+            }
+        }
+        output.flush()
+    }
+
+    // Exponentially distributed with the given mean
+    fn exponential(&mut self, mean: f64) -> f64 {
+        -(1.0 - self.rng.next_f64()).ln() * mean
+    }
+
+    fn advance_time(&mut self) -> u64 {
+        if self.burst_left == 0 && self.rng.next_f64() < self.config.burst_probability {
+            self.burst_left = self.exponential(self.config.burst_length).ceil().max(1.0) as u64;
+        }
+        let mut rate = self.config.rate;
+        if self.burst_left > 0 {
+            self.burst_left -= 1;
+            rate *= self.config.burst_multiplier;
+        }
+        self.time += self.exponential(1e9 / rate);
+        self.time as u64
+    }
+
+    fn step_mid(&mut self) {
+        if self.rng.next_f64() < self.config.mid_step {
+            let tick = self.config.tick_size;
+            self.mid = if self.rng.below(2) == 0 { self.mid.saturating_add(tick) } else { (self.mid - tick).max(tick) };
+        }
+    }
+
+    fn new_order(&mut self) -> NewOrder {
+        let side = if self.rng.below(2) == 0 { Side::Buy } else { Side::Sell };
+        let spread = u64::try_from(widen(self.config.max_quantity - self.config.min_quantity)).unwrap_or(u64::MAX);
+        let quantity = self.config.min_quantity + self.rng.below(spread.saturating_add(1)) as Units;
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let kind = self.rng.next_f64();
+        if kind < self.config.market_ratio {
+            return NewOrder::market(side, quantity, id);
+        }
+        // Passive orders sit at least a tick behind the mid, aggressive ones at or through it
+        let aggressive = kind < self.config.market_ratio + self.config.aggressive_ratio;
+        let min_ticks = if aggressive { 0 } else { 1 };
+        let ticks = (self.exponential(self.config.mean_offset).ceil() as Units).max(min_ticks);
+        let offset = ticks.saturating_mul(self.config.tick_size);
+        let price = match (side, aggressive) {
+            (Side::Buy, false) | (Side::Sell, true) => self.mid.saturating_sub(offset).max(self.config.tick_size),
+            (Side::Sell, false) | (Side::Buy, true) => self.mid.saturating_add(offset),
+        };
+        if self.config.cancel_ratio > 0.0 {
+            self.live.push(id);
+        }
+        NewOrder::limit(side, price, quantity, id)
+    }
+}
+
+impl Iterator for FlowGenerator {
+    type Item = (u64, Command);
+
+    fn next(&mut self) -> Option<(u64, Command)> {
+        let timestamp = self.advance_time();
+        self.step_mid();
+        if !self.live.is_empty() && self.rng.next_f64() < self.config.cancel_ratio {
+            let id = self.live.swap_remove(self.rng.below(self.live.len() as u64) as usize);
+            return Some((timestamp, Command::Cancel { id }));
+        }
+        Some((timestamp, Command::Place(self.new_order())))
+    }
+}
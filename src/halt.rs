@@ -0,0 +1,171 @@
+use crate::book::OrderBook;
+use crate::error::{InvariantViolation, OrderBookError};
+use crate::order::NewOrder;
+use crate::types::{CancelError, ExecutionReport, OrderOutcome, PlaceError, Trade};
+use crate::units::{widen, Units};
+use crate::wal::LogEntry;
+
+/// Whether and how a book is trading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TradingState {
+    /// Continuous matching
+    Open,
+    /// No matching; new orders are rejected or queued according to the halt policy
+    Halted,
+    /// Collecting orders for an auction (see `start_auction`)
+    AuctionOnly,
+}
+
+/// What happens to orders submitted while the book is halted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HaltPolicy {
+    #[default]
+    Reject,
+    /// Hold them and submit them in arrival order on `resume`
+    Queue,
+}
+
+/// Halts the book when a trade prints more than `max_move_bps` away from the reference
+/// price: that of the first trade of the current window. A window lasts `window` clock units
+/// and the next trade after it ends starts a new one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CircuitBreaker {
+    pub max_move_bps: u64,
+    pub window: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Halt {
+    pub(crate) halted: bool,
+    pub(crate) policy: HaltPolicy,
+    // Orders submitted during the halt under the queue policy, in arrival order
+    pub(crate) queue: Vec<NewOrder>,
+    pub(crate) breaker: Option<CircuitBreaker>,
+    // (timestamp, price) of the trade that opened the breaker's current window
+    pub(crate) reference: Option<(u64, Units)>,
+}
+
+impl Halt {
+    pub(crate) fn is_queued(&self, id: u64) -> bool {
+        self.queue.iter().any(|order| order.id == id)
+    }
+
+    // Run the breaker over new trades; true if one of them trips it
+    pub(crate) fn trips(&mut self, trades: &[Trade]) -> bool {
+        let Some(breaker) = self.breaker else {
+            return false;
+        };
+        for trade in trades {
+            let (start, reference) = match self.reference {
+                Some((start, reference)) if trade.timestamp.saturating_sub(start) <= breaker.window => (start, reference),
+                _ => (trade.timestamp, trade.price),
+            };
+            self.reference = Some((start, reference));
+            let moved = widen(trade.price.abs_diff(reference)).saturating_mul(10_000);
+            if moved > u128::from(breaker.max_move_bps).saturating_mul(widen(reference)) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl OrderBook {
+    pub fn trading_state(&self) -> TradingState {
+        if self.halt.halted {
+            TradingState::Halted
+        } else if self.auction {
+            TradingState::AuctionOnly
+        } else {
+            TradingState::Open
+        }
+    }
+
+    /// Stop matching until `resume`. Resting orders stay on the book and can still be
+    /// cancelled or reduced. Held stops don't trigger meanwhile; they're checked again with the
+    /// first order after the halt.
+    pub fn halt(&mut self) {
+        self.log_command(LogEntry::Halt);
+        self.halt.halted = true;
+    }
+
+    /// Lift a halt, returning to continuous trading (or to the auction the book was in), and
+    /// submit the queued orders in arrival order, one report each. The circuit breaker starts
+    /// a new window with the next trade.
+    pub fn resume(&mut self) -> Result<Vec<ExecutionReport>, OrderBookError> {
+        self.audited(Self::resume_trading)
+    }
+
+    fn resume_trading(&mut self) -> Result<Vec<ExecutionReport>, OrderBookError> {
+        self.log_command(LogEntry::Resume);
+        self.halt.halted = false;
+        self.halt.reference = None;
+        // The queued orders were logged when they were submitted
+        let ((reports, result), times) = self.unlogged(|ob| {
+            let mut reports = Vec::with_capacity(ob.halt.queue.len());
+            for order in std::mem::take(&mut ob.halt.queue) {
+                match ob.submit(order) {
+                    Ok(report) => reports.push(report),
+                    Err(PlaceError::DuplicateId(_)) => {
+                        return (reports, Err(InvariantViolation::new("queued ids are kept unique")));
+                    }
+                    Err(PlaceError::Invariant(err)) => return (reports, Err(err)),
+                }
+            }
+            (reports, Ok(()))
+        });
+        self.log_times(times);
+        result?;
+        Ok(reports)
+    }
+
+    pub fn set_halt_policy(&mut self, policy: HaltPolicy) {
+        self.halt.policy = policy;
+    }
+
+    pub fn halt_policy(&self) -> HaltPolicy {
+        self.halt.policy
+    }
+
+    /// Halt automatically on large price moves; `None` turns the breaker off.
+    pub fn set_circuit_breaker(&mut self, breaker: Option<CircuitBreaker>) {
+        self.halt.breaker = breaker;
+        self.halt.reference = None;
+    }
+
+    pub fn circuit_breaker(&self) -> Option<CircuitBreaker> {
+        self.halt.breaker
+    }
+
+    /// Orders waiting for the book to resume, in arrival order.
+    pub fn queued_orders(&self) -> &[NewOrder] {
+        &self.halt.queue
+    }
+
+    /// Drop an order queued during a halt.
+    pub fn cancel_queued(&mut self, id: u64) -> Result<NewOrder, CancelError> {
+        let position = self.halt.queue.iter().position(|order| order.id == id).ok_or(CancelError::UnknownOrder(id))?;
+        self.log_command(LogEntry::CancelQueued { id });
+        self.counters.cancels += 1;
+        Ok(self.halt.queue.remove(position))
+    }
+
+    // Hold an order submitted during a halt under the queue policy
+    pub(crate) fn queue_order(&mut self, order: NewOrder) -> ExecutionReport {
+        let report = ExecutionReport {
+            order_id: order.id,
+            status: OrderOutcome::Pending,
+            filled_quantity: 0,
+            remaining_quantity: order.quantity,
+            resting_id: None,
+            trades: Vec::new(),
+            client_order_id: order.client_order_id.clone(),
+            user_data: order.user_data,
+        };
+        self.halt.queue.push(order);
+        report
+    }
+}
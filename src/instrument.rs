@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::exchange::ExchangeError;
+use crate::order::NewOrder;
+use crate::types::{OrderType, PriceConfig, Side};
+use crate::units::Units;
+
+pub(crate) const DAY_NANOS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Daily window in which an instrument takes new orders, as nanoseconds since midnight UTC on
+/// the book's clock. A window whose `close` is before its `open` runs over midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradingHours {
+    pub open: u64,
+    pub close: u64,
+}
+
+impl TradingHours {
+    /// Whether `now`, in nanoseconds since the Unix epoch, falls inside the window.
+    pub fn contains(&self, now: u64) -> bool {
+        let time_of_day = now % DAY_NANOS;
+        if self.open <= self.close {
+            (self.open..self.close).contains(&time_of_day)
+        } else {
+            time_of_day >= self.open || time_of_day < self.close
+        }
+    }
+}
+
+/// Trading rules of one symbol, checked by the `Exchange` before an order reaches its book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Instrument {
+    pub symbol: String,
+    /// Tick size, lot size and price scale; the symbol's book is set up with it
+    pub price_config: PriceConfig,
+    pub max_order_quantity: Option<Units>,
+    pub trading_hours: Option<TradingHours>,
+    /// Snap prices and quantities onto the grid (see `round_order`) instead of letting the
+    /// book reject them
+    pub round_orders: bool,
+}
+
+impl Instrument {
+    /// An instrument with the default price grid and no limits.
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            price_config: PriceConfig::default(),
+            max_order_quantity: None,
+            trading_hours: None,
+            round_orders: false,
+        }
+    }
+
+    pub fn with_price_config(mut self, price_config: PriceConfig) -> Self {
+        self.price_config = price_config;
+        self
+    }
+
+    pub fn with_max_order_quantity(mut self, max_order_quantity: Units) -> Self {
+        self.max_order_quantity = Some(max_order_quantity);
+        self
+    }
+
+    pub fn with_trading_hours(mut self, trading_hours: TradingHours) -> Self {
+        self.trading_hours = Some(trading_hours);
+        self
+    }
+
+    pub fn rounding_orders(mut self) -> Self {
+        self.round_orders = true;
+        self
+    }
+
+    /// `price` on the tick grid, rounded away from the market: down for a buy, up for a sell.
+    pub fn round_price(&self, side: Side, price: Units) -> Units {
+        let tick_size = self.price_config.tick_size;
+        let below = price - price % tick_size;
+        match side {
+            Side::Buy => below,
+            Side::Sell if below == price => price,
+            Side::Sell => below.checked_add(tick_size).unwrap_or(below),
+        }
+    }
+
+    /// `quantity` rounded down to whole lots.
+    pub fn round_quantity(&self, quantity: Units) -> Units {
+        quantity - quantity % self.price_config.lot_size
+    }
+
+    /// `order` with its limit and stop prices rounded by `round_price` (a stop price as the
+    /// opposite side's, so it triggers no sooner) and its quantity and iceberg slice rounded
+    /// down to whole lots. Pegged and trailing offsets are left as they are.
+    pub fn round_order(&self, mut order: NewOrder) -> NewOrder {
+        let side = order.side;
+        let stop_side = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        order.order_type = match order.order_type {
+            OrderType::Limit { price } => OrderType::Limit { price: self.round_price(side, price) },
+            OrderType::Stop { stop_price } => OrderType::Stop { stop_price: self.round_price(stop_side, stop_price) },
+            OrderType::StopLimit { stop_price, price } => OrderType::StopLimit {
+                stop_price: self.round_price(stop_side, stop_price),
+                price: self.round_price(side, price),
+            },
+            order_type => order_type,
+        };
+        order.quantity = self.round_quantity(order.quantity);
+        order.display_quantity = order.display_quantity.map(|display| self.round_quantity(display));
+        order
+    }
+}
+
+/// The instruments an exchange lists, each enabled or not. A disabled instrument takes no new
+/// orders; what rests on its book can still be cancelled or amended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstrumentRegistry {
+    instruments: HashMap<String, (Instrument, bool)>,
+}
+
+impl InstrumentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// List `instrument`, enabled, provided its price grid is valid.
+    pub fn add(&mut self, instrument: Instrument) -> Result<(), ExchangeError> {
+        if self.instruments.contains_key(&instrument.symbol) {
+            return Err(ExchangeError::DuplicateSymbol(instrument.symbol));
+        }
+        if let Err(err) = instrument.price_config.validate() {
+            return Err(ExchangeError::InvalidPriceConfig(instrument.symbol, err));
+        }
+        self.instruments.insert(instrument.symbol.clone(), (instrument, true));
+        Ok(())
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(symbol).map(|(instrument, _)| instrument)
+    }
+
+    pub fn is_enabled(&self, symbol: &str) -> bool {
+        self.instruments.get(symbol).is_some_and(|&(_, enabled)| enabled)
+    }
+
+    pub fn enable(&mut self, symbol: &str) -> Result<(), ExchangeError> {
+        self.set_enabled(symbol, true)
+    }
+
+    pub fn disable(&mut self, symbol: &str) -> Result<(), ExchangeError> {
+        self.set_enabled(symbol, false)
+    }
+
+    /// Every instrument, enabled or not, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values().map(|(instrument, _)| instrument)
+    }
+
+    fn set_enabled(&mut self, symbol: &str, enabled: bool) -> Result<(), ExchangeError> {
+        let entry = self.instruments.get_mut(symbol).ok_or_else(|| ExchangeError::UnknownSymbol(symbol.to_string()))?;
+        entry.1 = enabled;
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::fmt;
+
+use crate::order::{Command, NewOrder};
+use crate::types::{OrderType, Side, TimeInForce, Trade};
+use crate::units::Units;
+
+/// A message of the binary session format, loosely modelled on NASDAQ ITCH.
+///
+/// Orders are GTC limit orders; market and other order types aren't representable, nor are
+/// prices and quantities beyond `u64` with the `wide` feature. `Execute`
+/// reports a resting order trading and `Trade` the full print; both are output only and ignored
+/// on replay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WireMessage {
+    Add { timestamp: u64, id: u64, side: Side, price: u64, quantity: u64 },
+    Execute { timestamp: u64, id: u64, quantity: u64, match_number: u64 },
+    Cancel { timestamp: u64, id: u64 },
+    Replace { timestamp: u64, id: u64, price: u64, quantity: u64 },
+    Trade { timestamp: u64, match_number: u64, price: u64, quantity: u64, maker_id: u64, taker_id: u64 },
+}
+
+/// Why `decode_event` couldn't read a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireError {
+    /// The buffer ends inside a message; more bytes are needed
+    Truncated,
+    UnknownType(u8),
+    /// The length prefix doesn't match the message type's size
+    BadLength { message_type: u8, length: usize },
+    BadSide(u8),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WireError::Truncated => write!(f, "truncated message"),
+            WireError::UnknownType(t) => write!(f, "unknown message type {:#04x}", t),
+            WireError::BadLength { message_type, length } => {
+                write!(f, "message type {:?} can't be {} bytes", *message_type as char, length)
+            }
+            WireError::BadSide(side) => write!(f, "invalid side {:#04x}", side),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+// Body sizes after the type byte; every message is framed as a big-endian u16 length (type
+// byte included) followed by the type and its fields, all integers big-endian.
+const ADD: (u8, usize) = (b'A', 33);
+const EXECUTE: (u8, usize) = (b'E', 32);
+const CANCEL: (u8, usize) = (b'D', 16);
+const REPLACE: (u8, usize) = (b'U', 32);
+const TRADE: (u8, usize) = (b'P', 48);
+
+impl WireMessage {
+    /// Encoded size, length prefix included.
+    pub fn encoded_len(&self) -> usize {
+        2 + 1 + self.kind().1
+    }
+
+    fn kind(&self) -> (u8, usize) {
+        match self {
+            WireMessage::Add { .. } => ADD,
+            WireMessage::Execute { .. } => EXECUTE,
+            WireMessage::Cancel { .. } => CANCEL,
+            WireMessage::Replace { .. } => REPLACE,
+            WireMessage::Trade { .. } => TRADE,
+        }
+    }
+
+    /// The session message for a command, if it has one.
+    pub fn from_command(command: &Command, timestamp: u64) -> Option<Self> {
+        match *command {
+            Command::Place(NewOrder {
+                id,
+                side,
+                order_type: OrderType::Limit { price },
+                quantity,
+                time_in_force: TimeInForce::Gtc,
+                post_only: false,
+                display_quantity: None,
+                ..
+            }) => Some(WireMessage::Add { timestamp, id, side, price: wire(price)?, quantity: wire(quantity)? }),
+            Command::Place(_) => None,
+            Command::Cancel { id } => Some(WireMessage::Cancel { timestamp, id }),
+            Command::Modify { id, price, quantity } => {
+                Some(WireMessage::Replace { timestamp, id, price: wire(price)?, quantity: wire(quantity)? })
+            }
+        }
+    }
+
+    /// The execution of the resting order and the print of a trade; `None` if they don't fit
+    /// the wire format.
+    pub fn from_trade(trade: &Trade) -> Option<[Self; 2]> {
+        let Trade { price, quantity, maker_id, taker_id, seq, timestamp, .. } = *trade;
+        let (price, quantity) = (wire(price)?, wire(quantity)?);
+        Some([
+            WireMessage::Execute { timestamp, id: maker_id, quantity, match_number: seq },
+            WireMessage::Trade { timestamp, match_number: seq, price, quantity, maker_id, taker_id },
+        ])
+    }
+
+    /// The command replaying this message, `None` for output-only messages.
+    pub fn to_command(&self) -> Option<Command> {
+        match *self {
+            WireMessage::Add { id, side, price, quantity, .. } => {
+                Some(Command::Place(NewOrder::limit(side, Units::from(price), Units::from(quantity), id)))
+            }
+            WireMessage::Cancel { id, .. } => Some(Command::Cancel { id }),
+            WireMessage::Replace { id, price, quantity, .. } => {
+                Some(Command::Modify { id, price: Units::from(price), quantity: Units::from(quantity) })
+            }
+            WireMessage::Execute { .. } | WireMessage::Trade { .. } => None,
+        }
+    }
+}
+
+// A price or quantity as a wire field; generic since `Units` is `u64` itself without the
+// `wide` feature
+fn wire(value: impl TryInto<u64>) -> Option<u64> {
+    value.try_into().ok()
+}
+
+/// Append `message` to `out`.
+pub fn encode_event(message: &WireMessage, out: &mut Vec<u8>) {
+    let (message_type, body) = message.kind();
+    out.reserve(message.encoded_len());
+    out.extend_from_slice(&(1 + body as u16).to_be_bytes());
+    out.push(message_type);
+    let fields: &[u64] = match *message {
+        WireMessage::Add { timestamp, id, side, price, quantity } => {
+            out.extend_from_slice(&timestamp.to_be_bytes());
+            out.extend_from_slice(&id.to_be_bytes());
+            out.push(match side {
+                Side::Buy => b'B',
+                Side::Sell => b'S',
+            });
+            &[price, quantity]
+        }
+        WireMessage::Execute { timestamp, id, quantity, match_number } => &[timestamp, id, quantity, match_number],
+        WireMessage::Cancel { timestamp, id } => &[timestamp, id],
+        WireMessage::Replace { timestamp, id, price, quantity } => &[timestamp, id, price, quantity],
+        WireMessage::Trade { timestamp, match_number, price, quantity, maker_id, taker_id } => {
+            &[timestamp, match_number, price, quantity, maker_id, taker_id]
+        }
+    };
+    for value in fields {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+/// Read the message at the start of `input`, returning it with the bytes that follow. Fields
+/// are read in place from `input`; nothing is copied or allocated.
+pub fn decode_event(input: &[u8]) -> Result<(WireMessage, &[u8]), WireError> {
+    let (length, rest) = input.split_first_chunk::<2>().ok_or(WireError::Truncated)?;
+    let length = u16::from_be_bytes(*length) as usize;
+    if rest.len() < length {
+        return Err(WireError::Truncated);
+    }
+    let (frame, rest) = rest.split_at(length);
+    let (&message_type, body) = frame.split_first().ok_or(WireError::BadLength { message_type: 0, length })?;
+    let expected = match message_type {
+        b'A' => ADD.1,
+        b'E' => EXECUTE.1,
+        b'D' => CANCEL.1,
+        b'U' => REPLACE.1,
+        b'P' => TRADE.1,
+        other => return Err(WireError::UnknownType(other)),
+    };
+    if body.len() != expected {
+        return Err(WireError::BadLength { message_type, length });
+    }
+    let u64_at = |offset: usize| {
+        let bytes = body.get(offset..offset + 8).and_then(|bytes| bytes.try_into().ok());
+        bytes.map(u64::from_be_bytes).ok_or(WireError::BadLength { message_type, length })
+    };
+
+    let message = match message_type {
+        b'A' => {
+            let side = match body[16] {
+                b'B' => Side::Buy,
+                b'S' => Side::Sell,
+                other => return Err(WireError::BadSide(other)),
+            };
+            WireMessage::Add { timestamp: u64_at(0)?, id: u64_at(8)?, side, price: u64_at(17)?, quantity: u64_at(25)? }
+        }
+        b'E' => WireMessage::Execute { timestamp: u64_at(0)?, id: u64_at(8)?, quantity: u64_at(16)?, match_number: u64_at(24)? },
+        b'D' => WireMessage::Cancel { timestamp: u64_at(0)?, id: u64_at(8)? },
+        b'U' => WireMessage::Replace { timestamp: u64_at(0)?, id: u64_at(8)?, price: u64_at(16)?, quantity: u64_at(24)? },
+        _ => WireMessage::Trade {
+            timestamp: u64_at(0)?,
+            match_number: u64_at(8)?,
+            price: u64_at(16)?,
+            quantity: u64_at(24)?,
+            maker_id: u64_at(32)?,
+            taker_id: u64_at(40)?,
+        },
+    };
+    Ok((message, rest))
+}
+
+/// Iterator over the messages of an encoded session, stopping at the first error.
+pub fn decode_all(mut input: &[u8]) -> impl Iterator<Item = Result<WireMessage, WireError>> + '_ {
+    std::iter::from_fn(move || {
+        if input.is_empty() {
+            return None;
+        }
+        match decode_event(input) {
+            Ok((message, rest)) => {
+                input = rest;
+                Some(Ok(message))
+            }
+            Err(err) => {
+                input = &[];
+                Some(Err(err))
+            }
+        }
+    })
+}
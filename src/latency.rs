@@ -0,0 +1,157 @@
+use crate::book::OrderBook;
+
+// Recording starts with a `Stopwatch` and ends with `OrderBook::record_latency`; without the
+// `latency` feature both compile to nothing.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "latency")]
+    start: std::time::Instant,
+}
+
+impl Stopwatch {
+    #[inline]
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "latency")]
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operation {
+    Place,
+    Cancel,
+    Match,
+}
+
+impl OrderBook {
+    #[inline]
+    pub(crate) fn record_latency(&mut self, _operation: Operation, _stopwatch: Stopwatch) {
+        #[cfg(feature = "latency")]
+        {
+            let nanos = u64::try_from(_stopwatch.start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+            let histogram = match _operation {
+                Operation::Place => &mut self.latency.place,
+                Operation::Cancel => &mut self.latency.cancel,
+                Operation::Match => &mut self.latency.matching,
+            };
+            histogram.record(nanos);
+        }
+    }
+}
+
+#[cfg(feature = "latency")]
+pub use histogram::{LatencyHistogram, LatencyReport};
+
+#[cfg(feature = "latency")]
+mod histogram {
+    use crate::book::OrderBook;
+
+    // Each power of two is split into 2^SUB_BUCKET_BITS linear buckets, so a recorded value is
+    // known to within about 3%; values below 2^SUB_BUCKET_BITS are exact
+    const SUB_BUCKET_BITS: u32 = 5;
+    const SUB_BUCKETS: u64 = 1 << SUB_BUCKET_BITS;
+    const BUCKETS: usize = (SUB_BUCKETS * (64 - SUB_BUCKET_BITS as u64 + 1)) as usize;
+
+    /// Distribution of latencies in nanoseconds, in log-linear buckets like an HDR histogram:
+    /// fixed memory, constant-time recording, and quantiles to about 3% of the true value.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct LatencyHistogram {
+        counts: Box<[u64]>,
+        count: u64,
+        min: u64,
+        max: u64,
+        sum: u128,
+    }
+
+    impl Default for LatencyHistogram {
+        fn default() -> Self {
+            Self { counts: vec![0; BUCKETS].into_boxed_slice(), count: 0, min: u64::MAX, max: 0, sum: 0 }
+        }
+    }
+
+    impl LatencyHistogram {
+        pub fn record(&mut self, nanos: u64) {
+            self.counts[bucket(nanos)] += 1;
+            self.count += 1;
+            self.min = self.min.min(nanos);
+            self.max = self.max.max(nanos);
+            self.sum += u128::from(nanos);
+        }
+
+        pub fn count(&self) -> u64 {
+            self.count
+        }
+
+        /// Smallest value recorded; `None` if nothing was.
+        pub fn min(&self) -> Option<u64> {
+            (self.count > 0).then_some(self.min)
+        }
+
+        pub fn max(&self) -> Option<u64> {
+            (self.count > 0).then_some(self.max)
+        }
+
+        pub fn mean(&self) -> Option<f64> {
+            (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+        }
+
+        /// Value at or below which `percentile` percent of the recordings fall, as the top of
+        /// its bucket (never above the largest value recorded); `None` if nothing was recorded.
+        pub fn percentile(&self, percentile: f64) -> Option<u64> {
+            if self.count == 0 {
+                return None;
+            }
+            let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (index, &count) in self.counts.iter().enumerate() {
+                seen += count;
+                if seen >= rank {
+                    return Some(bucket_top(index).min(self.max));
+                }
+            }
+            Some(self.max)
+        }
+    }
+
+    fn bucket(value: u64) -> usize {
+        if value < SUB_BUCKETS {
+            return value as usize;
+        }
+        let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+        (SUB_BUCKETS * (u64::from(shift) + 1) + (value >> shift) - SUB_BUCKETS) as usize
+    }
+
+    // Largest value that lands in bucket `index`
+    fn bucket_top(index: usize) -> u64 {
+        let index = index as u64;
+        if index < SUB_BUCKETS {
+            return index;
+        }
+        let shift = index / SUB_BUCKETS - 1;
+        let base = SUB_BUCKETS + index % SUB_BUCKETS;
+        ((base + 1) << shift).wrapping_sub(1)
+    }
+
+    /// Latencies measured inside the book with the `latency` feature, in nanoseconds of wall
+    /// time. `place` covers whole `submit` calls, `matching` just the matching of an incoming
+    /// order against the book, `cancel` whole `cancel_order` calls.
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct LatencyReport {
+        pub place: LatencyHistogram,
+        pub cancel: LatencyHistogram,
+        pub matching: LatencyHistogram,
+    }
+
+    impl OrderBook {
+        pub fn latency_report(&self) -> &LatencyReport {
+            &self.latency
+        }
+
+        /// Start every histogram afresh, e.g. after a warm-up.
+        pub fn reset_latency(&mut self) {
+            self.latency = LatencyReport::default();
+        }
+    }
+}
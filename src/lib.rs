@@ -0,0 +1,118 @@
+mod accounts;
+mod analytics;
+mod audit;
+mod auction;
+mod book;
+mod candles;
+mod changes;
+mod checkpoint;
+mod clock;
+mod concurrent;
+mod corrections;
+mod diff;
+mod error;
+mod events;
+mod exchange;
+mod feed;
+mod flowgen;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fees;
+mod halt;
+#[cfg(feature = "fix")]
+mod fix;
+mod instrument;
+mod itch;
+mod latency;
+mod match_policy;
+mod matching;
+mod oco;
+mod order;
+mod peg;
+mod pipeline;
+mod priority;
+#[cfg(feature = "python")]
+mod python;
+mod repl;
+mod replay;
+mod risk;
+mod seed;
+mod session;
+mod sim;
+mod slab;
+#[cfg(feature = "rest")]
+mod rest;
+mod spsc;
+mod stats;
+mod strategy;
+mod trailing;
+#[cfg(feature = "tui")]
+mod tui;
+mod types;
+mod units;
+mod validate;
+mod wal;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "ws")]
+mod ws;
+
+pub use accounts::{Accounts, Position};
+pub use analytics::BookAnalytics;
+pub use audit::{AuditRecord, AuditSink, AuditWriter};
+pub use auction::AuctionResult;
+pub use book::{BookSnapshot, Depth, DepthLevel, OrderBook, PriceLevel, SweepCost};
+pub use candles::{Candle, CandleAggregator};
+pub use checkpoint::{Checkpoint, CheckpointError};
+pub use clock::{Clock, MonotonicClock, TestClock};
+pub use concurrent::{BookClosed, BookHandle, ConcurrentOrderBook, MarketView};
+pub use corrections::CorrectionError;
+pub use diff::{BookDiff, LevelMismatch, OrderMismatch};
+pub use error::{InvariantViolation, OrderBookError};
+pub use events::{BookEvent, Event, EventListener, OrderEvent, SessionEvent, TradeEvent};
+pub use exchange::{Exchange, ExchangeError, SymbolStats};
+#[cfg(feature = "ffi")]
+pub use ffi::*;
+pub use feed::{
+    BboMode, BboTicker, BboUpdate, BookMirror, L2Publisher, L2Snapshot, L2Update, LevelAction, MarketData, SequenceGap,
+};
+pub use fees::{FeeRounding, FeeSchedule, FeeTotals};
+pub use flowgen::{FlowConfig, FlowGenerator};
+#[cfg(feature = "fix")]
+pub use fix::{FixError, FixGateway, FixMessage};
+pub use halt::{CircuitBreaker, HaltPolicy, TradingState};
+pub use instrument::{Instrument, InstrumentRegistry, TradingHours};
+pub use itch::{decode_all, decode_event, encode_event, WireError, WireMessage};
+#[cfg(feature = "latency")]
+pub use latency::{LatencyHistogram, LatencyReport};
+pub use match_policy::{Fifo, MatchPolicy, ProRata, TopOrderProRata};
+pub use oco::{LinkError, OcoMode};
+pub use order::{ClientOrderId, Command, NewOrder, Order, OrderStatus, QueuePosition};
+pub use pipeline::Pipeline;
+pub use priority::{PriorityPolicy, TieBreak};
+#[cfg(feature = "python")]
+pub use python::{python_module, PyExecutionReport, PyOrderBook, PyTrade};
+pub use repl::{render_depth, ParseCommandError, Repl, ReplCommand, REPL_HELP};
+pub use replay::{replay, FlowFormat, ReplayError, ReplaySpeed, ReplaySummary};
+#[cfg(feature = "rest")]
+pub use rest::{http_router, serve_http, CancelledOrder, ErrorBody, OrderRequest, PlacedOrder, RestConfig};
+pub use risk::{order_notional, BuyingPower, BuyingPowerCheck, PreTradeCheck, RiskLimits};
+pub use seed::{LoadError, RestingOrder};
+pub use session::{SessionPhase, SessionSchedule};
+pub use sim::{Delivery, Latency, LatencyModel, SimClock, SimMessage, SimRng, Simulator};
+pub use spsc::{spsc_channel, Consumer, Producer, WaitStrategy};
+pub use stats::BookStats;
+pub use strategy::TradeStrategy;
+#[cfg(feature = "tui")]
+pub use tui::LadderView;
+pub use types::{
+    BandRemainder, BandWidth, CancelError, ExecutionReport, MarketRemainder, OrderOutcome, OrderType, PegReference,
+    PlaceError, PostOnlyPolicy, PriceConfig, PriceConfigError, ProtectionBand, ReduceOnlyPolicy, RejectReason, SelfTradePolicy, Side, StopTrigger,
+    TimeInForce, Trade, TrailingOffset,
+};
+pub use units::Units;
+pub use wal::{EventLog, LogEntry, LogError};
+#[cfg(feature = "wasm")]
+pub use wasm::{WasmDepth, WasmDepthLevel, WasmExecutionReport, WasmOrderBook, WasmTrade};
+#[cfg(feature = "ws")]
+pub use ws::{serve, Channel, ClientMessage, ServerMessage, WsConfig};
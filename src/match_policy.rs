@@ -0,0 +1,104 @@
+use crate::book::OrderBook;
+use crate::units::{widen, Units};
+
+/// How an incoming order's quantity is shared among the resting orders of the price level it
+/// trades at. The book asks once per round: every order whose visible quantity runs out then
+/// refills from its iceberg reserve at the back of the queue, or leaves, and the next round
+/// shares what the incoming order has left.
+pub trait MatchPolicy: Send {
+    /// Share `quantity` among `resting`, the visible quantities of the level's orders in time
+    /// priority, writing each order's share to the same place in `fills`, which starts zeroed.
+    /// Shares should add up to `quantity` or to the level's total, whichever is smaller. The book
+    /// cuts a share down to what the order shows and the incoming order has left, and fills the
+    /// front order if a round shares out nothing.
+    fn allocate(&self, quantity: Units, resting: &[Units], fills: &mut [Units]);
+
+    /// Whether `allocate` fills orders one at a time in time priority, as `Fifo` does, so the
+    /// book can walk the queue without asking it.
+    fn is_fifo(&self) -> bool {
+        false
+    }
+}
+
+/// Price-time priority: the oldest order fills first. The book's default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fifo;
+
+impl MatchPolicy for Fifo {
+    fn allocate(&self, mut quantity: Units, resting: &[Units], fills: &mut [Units]) {
+        for (&available, fill) in resting.iter().zip(fills) {
+            *fill = available.min(quantity);
+            quantity -= *fill;
+        }
+    }
+
+    fn is_fifo(&self) -> bool {
+        true
+    }
+}
+
+/// Every order at the level gets a share in proportion to its visible quantity, rounded down;
+/// what rounding leaves goes to the orders in time priority.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProRata;
+
+impl MatchPolicy for ProRata {
+    fn allocate(&self, quantity: Units, resting: &[Units], fills: &mut [Units]) {
+        pro_rata(quantity, resting, fills);
+    }
+}
+
+/// The oldest order at the level fills first, up to `cap` if set; the rest of the incoming
+/// quantity is shared pro rata over what every order then has left.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TopOrderProRata {
+    pub cap: Option<Units>,
+}
+
+impl MatchPolicy for TopOrderProRata {
+    fn allocate(&self, quantity: Units, resting: &[Units], fills: &mut [Units]) {
+        let Some((&top, _)) = resting.split_first() else {
+            return;
+        };
+        let top_fill = top.min(quantity).min(self.cap.unwrap_or(Units::MAX));
+        let mut left = resting.to_vec();
+        left[0] -= top_fill;
+        pro_rata(quantity - top_fill, &left, fills);
+        fills[0] += top_fill;
+    }
+}
+
+// Shares of `quantity` in proportion to `resting`, rounded down, with the remainder handed out
+// in time priority
+fn pro_rata(quantity: Units, resting: &[Units], fills: &mut [Units]) {
+    let total: u128 = resting.iter().map(|&available| widen(available)).sum();
+    if widen(quantity) >= total {
+        fills.copy_from_slice(resting);
+        return;
+    }
+    // Here quantity < total, so each share is below the order's own quantity
+    let mut left = quantity;
+    for (&available, fill) in resting.iter().zip(fills.iter_mut()) {
+        let share = match widen(available).checked_mul(widen(quantity)) {
+            Some(product) => product / total,
+            None => widen(available) / (total / widen(quantity)),
+        };
+        *fill = (share as Units).min(left);
+        left -= *fill;
+    }
+    for (&available, fill) in resting.iter().zip(fills) {
+        let extra = (available - *fill).min(left);
+        *fill += extra;
+        left -= extra;
+    }
+}
+
+impl OrderBook {
+    /// How orders at a price level share an incoming order; `Fifo` unless set. Self-trade
+    /// prevention under a policy other than `Fifo` looks at the whole level before anything
+    /// trades there: the incoming order is cancelled, or its owner's resting orders are
+    /// cancelled or decremented, first. Auction uncrosses always fill in time priority.
+    pub fn set_match_policy(&mut self, policy: impl MatchPolicy + 'static) {
+        self.match_policy = Box::new(policy);
+    }
+}
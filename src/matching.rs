@@ -0,0 +1,1272 @@
+use std::collections::HashMap;
+use std::ops::Bound::{Excluded, Unbounded};
+
+use crate::accounts::Accounts;
+use crate::audit::Audit;
+use crate::book::{OrderBook, PriceLevel};
+use crate::error::{InvariantViolation, OrInvariant};
+use crate::events::{trace_trade, BookEvent, Event, OrderEvent};
+use crate::fees::Fees;
+use crate::halt::HaltPolicy;
+use crate::latency::{Operation, Stopwatch};
+use crate::match_policy::MatchPolicy;
+use crate::order::{ClientOrderId, Command, NewOrder, Order};
+use crate::peg::Peg;
+use crate::slab::OrderSlab;
+use crate::stats::Counters;
+use crate::types::{
+    BandRemainder, CancelError, ExecutionReport, MarketRemainder, OrderOutcome, OrderType, PlaceError,
+    PostOnlyPolicy, ReduceOnlyPolicy, RejectReason, SelfTradePolicy, Side, StopTrigger, TimeInForce, Trade, TrailingOffset,
+};
+use crate::trailing::triggered_order;
+use crate::units::{widen, Units};
+use crate::wal::LogEntry;
+
+// What becomes of an order that passes `admit`: it trades and maybe rests at `price`, or is held
+// as a stop until `stop_price`. Returned straight to the caller, so never worth boxing
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Admission {
+    Trade { price: Units, cancel_beyond_band: bool },
+    Hold { stop_price: Units, order: NewOrder },
+}
+
+// The incoming order as seen by the matching loop
+pub(crate) struct Taker {
+    pub(crate) id: u64,
+    pub(crate) side: Side,
+    pub(crate) owner: Option<u64>,
+    pub(crate) remaining: Units,
+    // Set once self-trade prevention has cancelled the rest of the order
+    pub(crate) cancelled: bool,
+    // Set once the order has passed over resting orders whose minimum fill it couldn't meet
+    pub(crate) skipped: bool,
+    // What is left of a notional buy's notional, and the quantity it no longer pays for
+    pub(crate) budget: Option<Units>,
+    pub(crate) dropped: Units,
+    pub(crate) client_order_id: Option<ClientOrderId>,
+    pub(crate) user_data: Option<u64>,
+}
+
+impl Taker {
+    // Cut a notional buy down to the whole lots the rest of its notional buys at `price`
+    fn afford(&mut self, price: Units, lot_size: Units) {
+        let Some(budget) = self.budget else {
+            return;
+        };
+        let affordable = budget.checked_div(price).unwrap_or(Units::MAX);
+        let affordable = affordable - affordable % lot_size;
+        if affordable < self.remaining {
+            self.dropped += self.remaining - affordable;
+            self.remaining = affordable;
+        }
+    }
+}
+
+impl OrderBook {
+    /// Place a limit order; shorthand for `submit(NewOrder::limit(side, price, quantity, id))`.
+    pub fn place_order(&mut self, side: Side, price: Units, quantity: Units, id: u64) -> Result<ExecutionReport, PlaceError> {
+        self.submit(NewOrder::limit(side, price, quantity, id))
+    }
+
+    /// Place a market order; shorthand for `submit(NewOrder::market(side, quantity, id))`.
+    pub fn place_market_order(&mut self, side: Side, quantity: Units, id: u64) -> Result<ExecutionReport, PlaceError> {
+        self.submit(NewOrder::market(side, quantity, id))
+    }
+
+    /// Submit `order` under an id picked by the book, which is returned in the report's
+    /// `order_id`; whatever id the order carries is ignored.
+    pub fn submit_with_new_id(&mut self, mut order: NewOrder) -> Result<ExecutionReport, PlaceError> {
+        order.id = self.next_order_id();
+        self.submit(order).map_err(|err| match err {
+            PlaceError::DuplicateId(_) => PlaceError::Invariant(InvariantViolation::new("generated id is in use")),
+            err => err,
+        })
+    }
+
+    /// An id no resting or held stop order has. Ids are handed out in increasing order, skipping
+    /// any in use; callers choosing their own ids as well may still collide with later ones.
+    pub fn next_order_id(&mut self) -> u64 {
+        loop {
+            self.next_order_id += 1;
+            if !self.id_in_use(self.next_order_id) {
+                return self.next_order_id;
+            }
+        }
+    }
+
+    pub(crate) fn id_in_use(&self, id: u64) -> bool {
+        self.order_index.contains_key(&id) || self.stop_index.contains_key(&id) || self.halt.is_queued(id)
+    }
+
+    /// Enter an order. The report's trades are those generated by the order, followed by those
+    /// of any stop orders it triggered. Fails if the id is already used by a resting or held
+    /// stop order; the book is left untouched then. An `InvariantViolation` may leave it part
+    /// way through the order.
+    ///
+    /// A limit order whose resting size would overflow the total quantity of its price level,
+    /// or whose price is outside the price band, is rejected: no trades, book left untouched.
+    /// Market orders never rest; what they can't fill is handled according to the market
+    /// remainder policy. IOC orders drop their remainder and FOK orders trade only if they can
+    /// be filled in full. Post-only orders that would cross are rejected or repriced according
+    /// to the post-only policy, reduce-only orders that would add to their owner's position
+    /// according to the reduce-only policy. Stop orders are held off the book until triggered. During an
+    /// auction limit orders rest without matching (see `start_auction`); while the book is
+    /// halted orders are rejected or queued according to the halt policy (see `halt`). Trades
+    /// are passed to the book's strategies, whose follow-up trades are appended to the report.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(id = order.id)))]
+    pub fn submit(&mut self, order: NewOrder) -> Result<ExecutionReport, PlaceError> {
+        let stopwatch = Stopwatch::start();
+        let result = self.audited(|ob| ob.submit_order(order));
+        self.record_latency(Operation::Place, stopwatch);
+        result
+    }
+
+    fn submit_order(&mut self, order: NewOrder) -> Result<ExecutionReport, PlaceError> {
+        if self.id_in_use(order.id) {
+            return Err(PlaceError::DuplicateId(order.id));
+        }
+        self.log_command(LogEntry::Submit(order.clone()));
+        let (id, quantity, user_data) = (order.id, order.quantity, order.user_data);
+        let client_order_id = order.client_order_id.clone();
+        self.trade_buffer.clear();
+        self.triggered_stops.clear();
+        if self.halt.halted && self.halt.policy == HaltPolicy::Queue {
+            let report = self.queue_order(order);
+            self.last_outcome = Some(report.status);
+            return Ok(report);
+        }
+        let best_before = self.best_prices();
+        let outcome = self.execute(order)?;
+        self.last_outcome = Some(outcome);
+        if !self.auction {
+            self.activate_stops()?;
+        }
+        self.reprice_pegs()?;
+        self.settle_oco()?;
+        self.record_history();
+        self.dispatch_events(best_before);
+        let mut report = self.execution_report(id, quantity, outcome);
+        report.client_order_id = client_order_id;
+        report.user_data = user_data;
+        let follow_up_trades = self.run_strategies(&report.trades);
+        report.trades.extend(follow_up_trades);
+        Ok(report)
+    }
+
+    /// Run `commands` in order, one report each. Failures that would be errors on the single
+    /// calls are reported as rejections: `DuplicateId` for a place, `UnknownOrder` for a cancel
+    /// or modify, `Internal` for an invariant violation. A successful cancel reports `Cancelled`
+    /// with the quantity taken off the book.
+    pub fn apply_batch(&mut self, commands: &[Command]) -> Vec<ExecutionReport> {
+        let mut reports = Vec::with_capacity(commands.len());
+        for command in commands {
+            reports.push(self.apply_command(command));
+        }
+        reports
+    }
+
+    pub(crate) fn apply_command(&mut self, command: &Command) -> ExecutionReport {
+        match *command {
+            Command::Place(ref order) => self.submit(order.clone()).unwrap_or_else(|err| {
+                let reason = match err {
+                    PlaceError::DuplicateId(_) => RejectReason::DuplicateId,
+                    PlaceError::Invariant(_) => RejectReason::Internal,
+                };
+                let mut report = ExecutionReport::rejected(order.id, order.quantity, reason);
+                report.client_order_id = order.client_order_id.clone();
+                report.user_data = order.user_data;
+                report
+            }),
+            Command::Cancel { id } => match self.cancel_order(id) {
+                Ok(order) => ExecutionReport {
+                    order_id: id,
+                    status: OrderOutcome::Cancelled,
+                    filled_quantity: 0,
+                    remaining_quantity: order.remaining_quantity(),
+                    resting_id: None,
+                    trades: Vec::new(),
+                    client_order_id: order.client_order_id,
+                    user_data: order.user_data,
+                },
+                Err(err) => ExecutionReport::rejected(id, 0, cancel_reject_reason(err)),
+            },
+            Command::Modify { id, price, quantity } => self
+                .modify_order(id, price, quantity)
+                .unwrap_or_else(|err| ExecutionReport::rejected(id, quantity, cancel_reject_reason(err))),
+        }
+    }
+
+    // Report on order `id` from the trades left in trade_buffer by the current call
+    pub(crate) fn execution_report(&self, id: u64, quantity: Units, status: OrderOutcome) -> ExecutionReport {
+        let filled_quantity = self
+            .trade_buffer
+            .iter()
+            .filter(|trade| trade.taker_id == id || trade.maker_id == id)
+            .map(|trade| trade.quantity)
+            .sum();
+        let resting = self.order_status(id).filter(|_| status == OrderOutcome::Rested);
+        let (status, remaining_quantity) = match resting {
+            Some(resting) => (status, resting.remaining_quantity),
+            // Rested, then taken off the book by a stop order it triggered
+            None if status == OrderOutcome::Rested => {
+                let remaining = quantity.saturating_sub(filled_quantity);
+                let status = if remaining == 0 { OrderOutcome::Filled } else { OrderOutcome::Cancelled };
+                (status, remaining)
+            }
+            // A reduce-only order may have been cut down before it filled
+            None if status == OrderOutcome::Filled => (status, 0),
+            None => (status, quantity.saturating_sub(filled_quantity)),
+        };
+        ExecutionReport {
+            order_id: id,
+            status,
+            filled_quantity,
+            remaining_quantity,
+            resting_id: resting.map(|_| id),
+            trades: self.trade_buffer.clone(),
+            client_order_id: None,
+            user_data: None,
+        }
+    }
+
+    // Runs one order against the book, appending to trade_buffer
+    fn execute(&mut self, order: NewOrder) -> Result<OrderOutcome, InvariantViolation> {
+        let id = order.id;
+        let outcome = self.execute_order(order)?;
+        if let OrderOutcome::Rejected(reason) = outcome {
+            self.counters.rejects += 1;
+            self.emit(OrderEvent::Rejected { id, reason });
+        }
+        Ok(outcome)
+    }
+
+    // Run `order` through every check that can reject it, without touching the book, cutting its
+    // quantity to what a reduce-only or notional order may take
+    pub(crate) fn admit(&mut self, order: &mut NewOrder) -> Result<Admission, RejectReason> {
+        let NewOrder {
+            side,
+            order_type,
+            mut quantity,
+            time_in_force,
+            post_only,
+            display_quantity,
+            owner,
+            min_quantity,
+            notional,
+            ..
+        } = *order;
+        if quantity == 0 || notional == Some(0) {
+            return Err(RejectReason::ZeroQuantity);
+        }
+        if min_quantity.is_some() && display_quantity.is_some() {
+            return Err(RejectReason::MinQuantity);
+        }
+        if notional.is_some()
+            && (side == Side::Sell || display_quantity.is_some() || min_quantity.is_some() || post_only || order.reduce_only)
+        {
+            return Err(RejectReason::NotionalOrder);
+        }
+        let config = self.price_config;
+        if !config.is_whole_lot(quantity) || display_quantity.is_some_and(|display| !config.is_whole_lot(display)) {
+            return Err(RejectReason::OffLot);
+        }
+        let off_tick = match order_type {
+            OrderType::Limit { price } => !config.is_on_tick(price),
+            OrderType::Market => false,
+            OrderType::Stop { stop_price } => !config.is_on_tick(stop_price),
+            OrderType::StopLimit { stop_price, price } => !config.is_on_tick(stop_price) || !config.is_on_tick(price),
+            OrderType::Pegged { .. } => false,
+            OrderType::TrailingStop { offset } => matches!(offset, TrailingOffset::Amount(amount) if !config.is_on_tick(amount)),
+            OrderType::TrailingStopLimit { offset, limit_offset } => {
+                matches!(offset, TrailingOffset::Amount(amount) if !config.is_on_tick(amount))
+                    || !config.is_on_tick(limit_offset)
+            }
+        };
+        if off_tick {
+            return Err(RejectReason::OffTick);
+        }
+        if self.halt.halted {
+            return Err(RejectReason::Halted);
+        }
+        // Only orders that can wait for the uncross are taken during an auction
+        if self.auction && (order_type == OrderType::Market || time_in_force != TimeInForce::Gtc) {
+            return Err(RejectReason::AuctionInProgress);
+        }
+        // Stops are checked against the position when they trigger, not while held
+        let held = matches!(
+            order_type,
+            OrderType::Stop { .. }
+                | OrderType::StopLimit { .. }
+                | OrderType::TrailingStop { .. }
+                | OrderType::TrailingStopLimit { .. }
+        );
+        if order.reduce_only && !held {
+            let reducible = self.reducible_quantity(side, owner, quantity);
+            if reducible == 0 || (reducible < quantity && self.reduce_only_policy == ReduceOnlyPolicy::Reject) {
+                return Err(RejectReason::ReduceOnly);
+            }
+            quantity = reducible;
+            order.quantity = reducible;
+        }
+        self.run_pre_trade_checks(order)?;
+
+        // Whether the protection band drops what it keeps from trading instead of resting it
+        let mut cancel_beyond_band = false;
+        let price = match order_type {
+            OrderType::Limit { price } => {
+                let price = if post_only && !self.auction {
+                    match self.post_only_price(side, price) {
+                        Some(price) => price,
+                        None => return Err(RejectReason::WouldCross),
+                    }
+                } else {
+                    price
+                };
+                if self.outside_price_band(price) {
+                    return Err(RejectReason::PriceBand);
+                }
+                let price = match self.protection_limit(side, price) {
+                    Some(limit) => {
+                        cancel_beyond_band =
+                            self.protection_band.is_some_and(|band| band.remainder == BandRemainder::Cancel);
+                        limit
+                    }
+                    None => price,
+                };
+                if self.would_overflow_level(side, price, quantity) {
+                    return Err(RejectReason::LevelOverflow);
+                }
+                price
+            }
+            OrderType::Pegged { reference, offset } => {
+                let Some(price) = self.peg_price(side, reference, offset) else {
+                    return Err(RejectReason::NoReferencePrice);
+                };
+                if self.outside_price_band(price) {
+                    return Err(RejectReason::PriceBand);
+                }
+                if self.would_overflow_level(side, price, quantity) {
+                    return Err(RejectReason::LevelOverflow);
+                }
+                price
+            }
+            OrderType::Market => {
+                if post_only {
+                    return Err(RejectReason::WouldCross);
+                }
+                let price = match side {
+                    Side::Buy => Units::MAX,
+                    Side::Sell => 0,
+                };
+                let price = self.protection_limit(side, price).unwrap_or(price);
+                if self.market_remainder == MarketRemainder::Reject
+                    && self.fillable_quantity(side, price, quantity, owner) < quantity
+                {
+                    return Err(RejectReason::InsufficientLiquidity);
+                }
+                price
+            }
+            OrderType::Stop { stop_price } => {
+                let order = NewOrder { order_type: OrderType::Market, ..order.clone() };
+                return Ok(Admission::Hold { stop_price, order });
+            }
+            OrderType::StopLimit { stop_price, price } => {
+                let order = NewOrder { order_type: OrderType::Limit { price }, ..order.clone() };
+                return Ok(Admission::Hold { stop_price, order });
+            }
+            OrderType::TrailingStop { offset } | OrderType::TrailingStopLimit { offset, .. } => {
+                let (buy_reference, sell_reference) = self.stop_references();
+                let reference = match side {
+                    Side::Buy => buy_reference,
+                    Side::Sell => sell_reference,
+                };
+                let Some(reference) = reference else {
+                    return Err(RejectReason::NoReferencePrice);
+                };
+                // Held as submitted and converted when triggered
+                let stop_price = self.trailing_stop_price(side, offset, reference);
+                return Ok(Admission::Hold { stop_price, order: order.clone() });
+            }
+        };
+
+        // A notional buy becomes the quantity it pays for: what it can fill now, and what the rest
+        // buys at its limit price if it may rest
+        if let Some(notional) = notional {
+            let (fillable, left, spent) = if self.auction { (0, notional, false) } else { self.notional_quantity(price, notional) };
+            let resting = if order_type != OrderType::Market && time_in_force == TimeInForce::Gtc {
+                left.checked_div(price).map_or(0, |resting| resting - resting % config.lot_size)
+            } else {
+                0
+            };
+            let must_spend = time_in_force == TimeInForce::Fok
+                || (order_type == OrderType::Market && self.market_remainder == MarketRemainder::Reject);
+            quantity = quantity.min(fillable.saturating_add(resting));
+            if quantity == 0 || (must_spend && !spent) {
+                return Err(RejectReason::InsufficientLiquidity);
+            }
+            order.quantity = quantity;
+        }
+
+        // Scan before touching the book so a kill leaves it unchanged
+        if time_in_force == TimeInForce::Fok && self.fillable_quantity(side, price, quantity, owner) < quantity {
+            return Err(RejectReason::InsufficientLiquidity);
+        }
+        // Short of its minimum the order doesn't trade; only a limit order clear of the book
+        // can rest instead
+        if let Some(min) = min_quantity.filter(|_| !self.auction) {
+            let restable = order_type != OrderType::Market && time_in_force == TimeInForce::Gtc && !self.crosses(side, price);
+            if self.fillable_quantity(side, price, quantity, owner) < min.min(quantity) && !restable {
+                return Err(RejectReason::InsufficientLiquidity);
+            }
+        }
+        Ok(Admission::Trade { price, cancel_beyond_band })
+    }
+
+    fn execute_order(&mut self, mut order: NewOrder) -> Result<OrderOutcome, InvariantViolation> {
+        let (price, cancel_beyond_band) = match self.admit(&mut order) {
+            Ok(Admission::Trade { price, cancel_beyond_band }) => (price, cancel_beyond_band),
+            Ok(Admission::Hold { stop_price, order }) => {
+                self.emit(OrderEvent::Accepted { id: order.id });
+                self.hold_stop(stop_price, order);
+                return Ok(OrderOutcome::Pending);
+            }
+            Err(reason) => return Ok(OrderOutcome::Rejected(reason)),
+        };
+        let NewOrder {
+            id,
+            side,
+            order_type,
+            quantity,
+            time_in_force,
+            post_only,
+            display_quantity,
+            owner,
+            expires_at,
+            reduce_only,
+            min_quantity,
+            notional,
+            ..
+        } = order;
+        let config = self.price_config;
+        self.emit(OrderEvent::Accepted { id });
+        let timestamp = self.now();
+        let seq = self.next_order_seq();
+        let mut taker = Taker {
+            id,
+            side,
+            owner,
+            remaining: quantity,
+            cancelled: false,
+            skipped: false,
+            budget: notional,
+            dropped: 0,
+            client_order_id: order.client_order_id,
+            user_data: order.user_data,
+        };
+        let first_trade = self.trade_buffer.len();
+        if !self.auction {
+            let stopwatch = Stopwatch::start();
+            self.match_order(side, price, &mut taker)?;
+            self.record_latency(Operation::Match, stopwatch);
+        }
+        for trade in &mut self.trade_buffer[first_trade..] {
+            self.trade_seq += 1;
+            trade.seq = self.trade_seq;
+            trade.timestamp = timestamp;
+            trace_trade(trade);
+        }
+        if let Some(audit) = &mut self.audit {
+            audit.trades(&self.trade_buffer[first_trade..]);
+        }
+        if self.trade_buffer.len() > first_trade {
+            self.counters.matches += 1;
+            self.count_trades(first_trade);
+            if self.halt.trips(&self.trade_buffer[first_trade..]) {
+                self.halt.halted = true;
+            }
+        }
+        let rests = order_type != OrderType::Market && time_in_force == TimeInForce::Gtc;
+        if rests {
+            taker.afford(price, config.lot_size);
+        }
+        let quantity = quantity - taker.dropped;
+        let remaining_quantity = taker.remaining;
+        if let Some(trade) = self.trade_buffer.last() {
+            self.last_trade_price = Some(trade.price);
+        }
+        let outcome = if taker.cancelled {
+            self.emit(OrderEvent::Cancelled { id, remaining: remaining_quantity });
+            OrderOutcome::Cancelled
+        } else if remaining_quantity == 0 {
+            OrderOutcome::Filled
+        } else if rests
+            && !cancel_beyond_band
+            // Resting would cross the orders it passed over
+            && !taker.skipped
+        {
+            let visible = display_quantity.map_or(remaining_quantity, |display| display.clamp(1, remaining_quantity));
+            self.rest_order(
+                side,
+                Order {
+                    id,
+                    price,
+                    quantity: visible,
+                    timestamp,
+                    seq,
+                    hidden_quantity: remaining_quantity - visible,
+                    display_quantity,
+                    owner,
+                    original_quantity: quantity,
+                    filled_quantity: quantity - remaining_quantity,
+                    expires_at,
+                    client_order_id: taker.client_order_id,
+                    user_data: taker.user_data,
+                    min_quantity,
+                    tier: self.priority_policy.tier(owner),
+                    post_only,
+                    reduce_only,
+                },
+            )?;
+            match order_type {
+                OrderType::Pegged { reference, offset } => {
+                    self.pegs.orders.insert(id, Peg { reference, offset, repriced_at: timestamp });
+                }
+                _ => {
+                    self.pegs.orders.remove(&id);
+                }
+            }
+            self.emit(OrderEvent::Rested { id, price, quantity: remaining_quantity });
+            OrderOutcome::Rested
+        } else {
+            self.emit(OrderEvent::Cancelled { id, remaining: remaining_quantity });
+            OrderOutcome::Cancelled
+        };
+        Ok(outcome)
+    }
+
+    /// What became of the most recently submitted order.
+    pub fn last_outcome(&self) -> Option<OrderOutcome> {
+        self.last_outcome
+    }
+
+    /// What happens to the part of a market order the book can't fill.
+    pub fn set_market_remainder(&mut self, policy: MarketRemainder) {
+        self.market_remainder = policy;
+    }
+
+    /// What happens when an order meets a resting order with the same owner.
+    pub fn set_self_trade_policy(&mut self, policy: SelfTradePolicy) {
+        self.self_trade_policy = policy;
+    }
+
+    /// What happens to a post-only order that would take liquidity.
+    pub fn set_post_only_policy(&mut self, policy: PostOnlyPolicy) {
+        self.post_only_policy = policy;
+    }
+
+    /// What happens to a reduce-only order for more than its owner's open position on the other
+    /// side. The position is the one tracked by `accounts`, as it stands when the order is
+    /// entered or, for a stop, when it triggers; an order without an owner has none. Resting
+    /// reduce-only orders aren't cut back if the position shrinks afterwards.
+    pub fn set_reduce_only_policy(&mut self, policy: ReduceOnlyPolicy) {
+        self.reduce_only_policy = policy;
+    }
+
+    /// Which price stop orders are triggered by. Stops are checked after every submitted order.
+    pub fn set_stop_trigger(&mut self, trigger: StopTrigger) {
+        self.stop_trigger = trigger;
+    }
+
+    /// Ids of the stop orders triggered by the last `submit`, in activation order.
+    pub fn triggered_stops(&self) -> &[u64] {
+        &self.triggered_stops
+    }
+
+    /// Remove a stop order that hasn't triggered yet, returning the order it would have
+    /// submitted.
+    pub fn cancel_stop(&mut self, id: u64) -> Result<NewOrder, CancelError> {
+        self.audited(|ob| ob.cancel_held_stop(id))
+    }
+
+    fn cancel_held_stop(&mut self, id: u64) -> Result<NewOrder, CancelError> {
+        let best_before = self.best_prices();
+        let order = self.take_stop(id)?.ok_or(CancelError::UnknownOrder(id))?;
+        self.log_command(LogEntry::CancelStop { id });
+        self.counters.cancels += 1;
+        self.emit(OrderEvent::Cancelled { id, remaining: order.quantity });
+        self.settle_oco()?;
+        self.dispatch_events(best_before);
+        Ok(order)
+    }
+
+    pub(crate) fn take_stop(&mut self, id: u64) -> Result<Option<NewOrder>, InvariantViolation> {
+        let Some((side, stop_price)) = self.stop_index.remove(&id) else {
+            return Ok(None);
+        };
+        self.trailing_stops.remove(&id);
+        let stops = match side {
+            Side::Buy => &mut self.buy_stops,
+            Side::Sell => &mut self.sell_stops,
+        };
+        let pending = stops.get_mut(&stop_price).or_invariant("indexed stop has no trigger level")?;
+        let position = pending.iter().position(|o| o.id == id).or_invariant("indexed stop missing")?;
+        let order = pending.remove(position);
+        if pending.is_empty() {
+            stops.remove(&stop_price);
+        }
+        Ok(Some(order))
+    }
+
+    // Add the trades in trade_buffer from `first` on to the counters
+    pub(crate) fn count_trades(&mut self, first: usize) {
+        let trades = &self.trade_buffer[first..];
+        self.counters.trades += trades.len() as u64;
+        self.counters.volume += trades.iter().map(|trade| widen(trade.quantity)).sum::<u128>();
+    }
+
+    // Sequence numbers count per book, so replaying the same orders reproduces them exactly
+    pub(crate) fn next_order_seq(&mut self) -> u64 {
+        self.last_order_seq += 1;
+        self.last_order_seq
+    }
+
+    pub(crate) fn hold_stop(&mut self, stop_price: Units, order: NewOrder) {
+        let side = order.side;
+        if let Some(expires_at) = order.expires_at {
+            self.expiry_index.insert((expires_at, order.id));
+        }
+        self.stop_index.insert(order.id, (side, stop_price));
+        if let OrderType::TrailingStop { offset } | OrderType::TrailingStopLimit { offset, .. } = order.order_type {
+            self.trailing_stops.insert(order.id, offset);
+        }
+        let stops = match side {
+            Side::Buy => &mut self.buy_stops,
+            Side::Sell => &mut self.sell_stops,
+        };
+        stops.entry(stop_price).or_default().push(order);
+    }
+
+    // Buy stops trigger when the reference price rises to their stop price, sell stops when it
+    // falls to it. Each activation can move the market and trigger further stops.
+    pub(crate) fn activate_stops(&mut self) -> Result<(), InvariantViolation> {
+        while !self.halt.halted {
+            let (buy_reference, sell_reference) = self.stop_references();
+            self.trail_stops(buy_reference, sell_reference)?;
+            let buy = buy_reference.and_then(|reference| {
+                self.buy_stops.range(..=reference).next().map(|(&stop, _)| (Side::Buy, stop))
+            });
+            let sell = sell_reference.and_then(|reference| {
+                self.sell_stops.range(reference..).next_back().map(|(&stop, _)| (Side::Sell, stop))
+            });
+            let Some((side, stop_price)) = buy.or(sell) else {
+                break;
+            };
+
+            let stops = match side {
+                Side::Buy => &mut self.buy_stops,
+                Side::Sell => &mut self.sell_stops,
+            };
+            let pending = stops.get_mut(&stop_price).or_invariant("triggered stop level is gone")?;
+            if pending.is_empty() {
+                return Err(InvariantViolation::new("empty stop trigger level"));
+            }
+            let order = pending.remove(0);
+            if pending.is_empty() {
+                stops.remove(&stop_price);
+            }
+            self.stop_index.remove(&order.id);
+            self.trailing_stops.remove(&order.id);
+            self.triggered_stops.push(order.id);
+            self.emit(OrderEvent::Triggered { id: order.id });
+            self.execute(triggered_order(order, stop_price))?;
+        }
+        Ok(())
+    }
+
+    // Prices buy and sell stops are triggered by
+    pub(crate) fn stop_references(&self) -> (Option<Units>, Option<Units>) {
+        match self.stop_trigger {
+            StopTrigger::LastTrade => (self.last_trade_price, self.last_trade_price),
+            StopTrigger::BestPrice => (self.best_sell().map(|(price, _)| price), self.best_buy().map(|(price, _)| price)),
+        }
+    }
+
+    // Part of `quantity` a reduce-only order may trade: no more than closes its owner's position,
+    // in whole lots
+    fn reducible_quantity(&self, side: Side, owner: Option<u64>, quantity: Units) -> Units {
+        let Some(owner) = owner else {
+            return 0;
+        };
+        let net = self.accounts.position(owner).net_quantity;
+        let open = match side {
+            Side::Buy if net < 0 => net.unsigned_abs(),
+            Side::Sell if net > 0 => net.unsigned_abs(),
+            _ => return 0,
+        };
+        let reducible = widen(quantity).min(open) as Units;
+        reducible - reducible % self.price_config.lot_size
+    }
+
+    // Whether an order on `side` at `price` meets the opposite touch
+    fn crosses(&self, side: Side, price: Units) -> bool {
+        match side {
+            Side::Buy => self.best_sell().is_some_and(|(best, _)| price >= best),
+            Side::Sell => self.best_buy().is_some_and(|(best, _)| price <= best),
+        }
+    }
+
+    // Price a post-only order may rest at, or None if it has to be rejected
+    fn post_only_price(&self, side: Side, price: Units) -> Option<Units> {
+        if !self.crosses(side, price) {
+            return Some(price);
+        }
+        match self.post_only_policy {
+            PostOnlyPolicy::Reject => None,
+            // Step one tick behind the opposite touch
+            PostOnlyPolicy::Slide => match side {
+                Side::Buy => self.best_sell()?.0.checked_sub(self.price_config.tick_size),
+                Side::Sell => self.best_buy()?.0.checked_add(self.price_config.tick_size),
+            },
+        }
+    }
+
+    // Match against the opposite side up to the limit `price`, leaving the unfilled quantity
+    // in `taker.remaining`
+    fn match_order(&mut self, side: Side, price: Units, taker: &mut Taker) -> Result<(), InvariantViolation> {
+        let self_trade_policy = self.self_trade_policy;
+        // A buy matches against the sell side from the lowest price up, a sell against the buy
+        // side from the highest price down
+        let opposite = match side {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+        };
+        let lot_size = self.price_config.lot_size;
+        // Price of the last level the taker had to leave orders at; matching goes on beyond it
+        let mut passed = None;
+        while taker.remaining > 0 && !taker.cancelled {
+            let price_map = match side {
+                Side::Buy => &mut self.sell_map,
+                Side::Sell => &mut self.buy_map,
+            };
+            let best = match (side, passed) {
+                (Side::Buy, None) => price_map.iter_mut().next(),
+                (Side::Buy, Some(passed)) => price_map.range_mut((Excluded(passed), Unbounded)).next(),
+                (Side::Sell, None) => price_map.iter_mut().next_back(),
+                (Side::Sell, Some(passed)) => price_map.range_mut(..passed).next_back(),
+            };
+            let Some((&best_price, level)) = best else {
+                break;
+            };
+            let crosses = match side {
+                Side::Buy => price >= best_price,
+                Side::Sell => price <= best_price,
+            };
+            if !crosses {
+                break;
+            }
+            taker.afford(best_price, lot_size);
+            if taker.remaining == 0 {
+                break;
+            }
+            self.changes.touch(opposite, best_price);
+
+            let first_trade = self.trade_buffer.len();
+            let first_event = self.events.len();
+            let auditing = self.audit.as_ref().is_some_and(Audit::is_open);
+            let events = (!self.listeners.is_empty() || auditing).then_some(&mut self.events);
+            Self::match_level(
+                level,
+                best_price,
+                taker,
+                self_trade_policy,
+                self.match_policy.as_ref(),
+                &mut self.slab,
+                &mut self.trade_buffer,
+                &mut self.order_index,
+                &mut self.last_order_seq,
+                &mut self.fees,
+                &mut self.accounts,
+                &mut self.counters,
+                events,
+            )?;
+            // Fills are buffered for the audit record even without listeners; dropped here if so
+            if let (true, Some(audit)) = (auditing, &mut self.audit) {
+                for &event in &self.events[first_event..] {
+                    audit.event(event);
+                }
+                if self.listeners.is_empty() {
+                    self.events.truncate(first_event);
+                }
+            }
+
+            // remove this price level if empty
+            if level.is_empty() {
+                price_map.remove(&best_price);
+                self.emit(BookEvent::LevelRemoved { side: opposite, price: best_price });
+            } else if taker.remaining > 0 && !taker.cancelled {
+                passed = Some(best_price);
+                taker.skipped = true;
+            }
+            self.apply_oco_fills(first_trade)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn rest_order(&mut self, side: Side, order: Order) -> Result<(), InvariantViolation> {
+        let price = order.price;
+        if let Some(expires_at) = order.expires_at {
+            self.expiry_index.insert((expires_at, order.id));
+        }
+        let id = order.id;
+        if !self.levels(side).contains_key(&price) {
+            self.emit(BookEvent::LevelAdded { side, price });
+        }
+        let price_map = match side {
+            Side::Buy => &mut self.buy_map,
+            Side::Sell => &mut self.sell_map,
+        };
+        self.changes.touch(side, price);
+        let policy = &self.priority_policy;
+        let key = price_map
+            .entry(price)
+            .or_insert_with(PriceLevel::new)
+            .insert(&mut self.slab, order, |order, other| policy.ahead(order, other))?;
+        self.order_index.insert(id, (side, price, key));
+        Ok(())
+    }
+
+    // Whole lots a buy limited at `price` could fill right now for `notional`, walking the sell
+    // side, with the notional left over and whether it was spent before the book ran out
+    fn notional_quantity(&self, price: Units, notional: Units) -> (Units, Units, bool) {
+        let lot_size = self.price_config.lot_size;
+        let (mut quantity, mut left): (Units, Units) = (0, notional);
+        for (&level_price, level) in self.sell_map.range(..=price) {
+            let affordable = left.checked_div(level_price).unwrap_or(Units::MAX);
+            let fill = (affordable - affordable % lot_size).min(level.open_quantity());
+            quantity = quantity.saturating_add(fill);
+            left -= level_price * fill;
+            if fill < level.open_quantity() {
+                return (quantity, left, true);
+            }
+        }
+        (quantity, left, left == 0)
+    }
+
+    // How much of `quantity` an order on `side` limited at `price` could fill right now, passing
+    // over orders with a minimum it can't meet. With an owner the walk follows price-time
+    // priority so self-trade prevention is accounted for.
+    //
+    // Under a shared match policy the minimums are checked in time priority too, while the
+    // level's shares may fall differently.
+    fn fillable_quantity(&self, side: Side, price: Units, quantity: Units, owner: Option<u64>) -> Units {
+        let mut available: Units = 0;
+        let levels: Box<dyn Iterator<Item = &PriceLevel>> = match side {
+            Side::Buy => Box::new(self.sell_map.range(..=price).map(|(_, level)| level)),
+            Side::Sell => Box::new(self.buy_map.range(price..).rev().map(|(_, level)| level)),
+        };
+
+        let Some(owner) = owner else {
+            for level in levels {
+                if available == quantity {
+                    break;
+                }
+                // With room for the whole level every order in it trades, minimums or not
+                if level.open_quantity() <= quantity - available {
+                    available += level.open_quantity();
+                    continue;
+                }
+                // Icebergs ahead of an order with a minimum are counted whole, so this errs low
+                // when their refills would have let the order trade after them
+                for order in level.iter(&self.slab) {
+                    if order.min_fill() <= quantity - available {
+                        available = available.saturating_add(order.remaining_quantity());
+                        if available >= quantity {
+                            return quantity;
+                        }
+                    }
+                }
+            }
+            return available.min(quantity);
+        };
+
+        let shared = !self.match_policy.is_fifo();
+        for level in levels {
+            // Under a shared policy the owner's orders are dealt with before the level trades
+            if shared
+                && self.self_trade_policy != SelfTradePolicy::CancelMaker
+                && level.iter(&self.slab).any(|order| order.owner == Some(owner))
+            {
+                return available.min(quantity);
+            }
+            for order in level.iter(&self.slab) {
+                if order.owner == Some(owner) {
+                    if self.self_trade_policy == SelfTradePolicy::CancelMaker {
+                        continue;
+                    }
+                    return available.min(quantity);
+                }
+                if order.min_fill() > quantity - available {
+                    continue;
+                }
+                available = available.saturating_add(order.quantity).saturating_add(order.hidden_quantity);
+                if available >= quantity {
+                    return quantity;
+                }
+            }
+        }
+        available.min(quantity)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn match_level(
+        level: &mut PriceLevel,
+        price: Units,
+        taker: &mut Taker,
+        self_trade_policy: SelfTradePolicy,
+        match_policy: &dyn MatchPolicy,
+        slab: &mut OrderSlab,
+        trades: &mut Vec<Trade>,
+        order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        last_order_seq: &mut u64,
+        fees: &mut Fees,
+        accounts: &mut Accounts,
+        counters: &mut Counters,
+        mut events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        if !match_policy.is_fifo() {
+            return Self::match_level_shared(
+                level,
+                price,
+                taker,
+                self_trade_policy,
+                match_policy,
+                slab,
+                trades,
+                order_index,
+                last_order_seq,
+                fees,
+                accounts,
+                counters,
+                events,
+            );
+        }
+        // Last order passed over for a minimum the taker can't meet; those stay where they are
+        let mut passed: Option<usize> = None;
+        loop {
+            let next = match passed {
+                Some(passed) => slab.node(passed)?.next,
+                None => level.front(),
+            };
+            let Some(key) = next else {
+                break;
+            };
+            let order = slab.order_mut(key)?;
+            if order.min_fill() > taker.remaining {
+                passed = Some(key);
+                continue;
+            }
+            if taker.owner.is_some() && order.owner == taker.owner {
+                match self_trade_policy {
+                    SelfTradePolicy::CancelTaker => {
+                        taker.cancelled = true;
+                        break;
+                    }
+                    SelfTradePolicy::CancelMaker | SelfTradePolicy::CancelBoth => {
+                        Self::cancel_maker(level, slab, key, order_index, counters, events.as_deref_mut())?;
+                        if self_trade_policy == SelfTradePolicy::CancelBoth {
+                            taker.cancelled = true;
+                            break;
+                        }
+                        continue;
+                    }
+                    SelfTradePolicy::Decrement => {
+                        Self::decrement_maker(level, slab, key, taker, order_index, counters, last_order_seq, events.as_deref_mut())?;
+                        if taker.remaining == 0 {
+                            taker.cancelled = true;
+                            break;
+                        }
+                        continue;
+                    }
+                }
+            }
+
+            let trade_qty = order.quantity.min(taker.remaining);
+            Self::fill_maker(order, level, price, trade_qty, taker, trades, fees, accounts, events.as_deref_mut())?;
+            if slab.order(key)?.quantity == 0 {
+                Self::replenish(level, slab, key, order_index, last_order_seq, None)?;
+            }
+
+            if taker.remaining == 0 {
+                break;
+            }
+        }
+        level.debug_assert_totals(slab);
+        Ok(())
+    }
+
+    // Match against a level under a policy that shares each round among all its orders. The
+    // taker's own orders are dealt with first, since everything at the level trades at once.
+    #[allow(clippy::too_many_arguments)]
+    fn match_level_shared(
+        level: &mut PriceLevel,
+        price: Units,
+        taker: &mut Taker,
+        self_trade_policy: SelfTradePolicy,
+        match_policy: &dyn MatchPolicy,
+        slab: &mut OrderSlab,
+        trades: &mut Vec<Trade>,
+        order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        last_order_seq: &mut u64,
+        fees: &mut Fees,
+        accounts: &mut Accounts,
+        counters: &mut Counters,
+        mut events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        if let Some(owner) = taker.owner {
+            let own: Vec<usize> = level.keys(slab).filter(|&key| slab.get(key).is_some_and(|node| node.order.owner == Some(owner))).collect();
+            if !own.is_empty() {
+                if self_trade_policy == SelfTradePolicy::CancelTaker {
+                    taker.cancelled = true;
+                    return Ok(());
+                }
+                for key in own {
+                    if self_trade_policy == SelfTradePolicy::Decrement {
+                        Self::decrement_maker(level, slab, key, taker, order_index, counters, last_order_seq, events.as_deref_mut())?;
+                    } else {
+                        Self::cancel_maker(level, slab, key, order_index, counters, events.as_deref_mut())?;
+                    }
+                    if taker.remaining == 0 {
+                        break;
+                    }
+                }
+                if self_trade_policy == SelfTradePolicy::CancelBoth || taker.remaining == 0 {
+                    taker.cancelled = true;
+                    return Ok(());
+                }
+            }
+        }
+
+        // Orders with a minimum the taker can't meet sit the rounds out, and shares below an
+        // order's minimum are withdrawn for the next round
+        let (mut keys, mut resting, mut fills) = (Vec::new(), Vec::new(), Vec::new());
+        while taker.remaining > 0 {
+            keys.clear();
+            resting.clear();
+            for key in level.keys(slab) {
+                let order = slab.order(key)?;
+                if order.min_fill() <= taker.remaining {
+                    keys.push(key);
+                    resting.push(order.quantity);
+                }
+            }
+            if keys.is_empty() {
+                break;
+            }
+            fills.clear();
+            fills.resize(keys.len(), 0);
+            match_policy.allocate(taker.remaining, &resting, &mut fills);
+            for (fill, &key) in fills.iter_mut().zip(&keys) {
+                if *fill < slab.order(key)?.min_fill() {
+                    *fill = 0;
+                }
+            }
+            if fills.iter().all(|&fill| fill == 0) {
+                fills[0] = resting[0];
+            }
+            for (&key, &fill) in keys.iter().zip(&fills) {
+                let order = slab.order_mut(key)?;
+                let trade_qty = fill.min(order.quantity).min(taker.remaining);
+                if trade_qty > 0 && trade_qty >= order.min_fill() {
+                    Self::fill_maker(order, level, price, trade_qty, taker, trades, fees, accounts, events.as_deref_mut())?;
+                }
+            }
+            // Orders left showing nothing refill at the back, in time priority, or leave
+            for &key in &keys {
+                if slab.order(key)?.quantity == 0 {
+                    Self::replenish(level, slab, key, order_index, last_order_seq, None)?;
+                }
+            }
+        }
+        level.debug_assert_totals(slab);
+        Ok(())
+    }
+
+    // Trade `trade_qty` of the taker against a resting order of `level`
+    #[allow(clippy::too_many_arguments)]
+    fn fill_maker(
+        order: &mut Order,
+        level: &mut PriceLevel,
+        price: Units,
+        trade_qty: Units,
+        taker: &mut Taker,
+        trades: &mut Vec<Trade>,
+        fees: &mut Fees,
+        accounts: &mut Accounts,
+        events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        let mut trade = Trade {
+            price,
+            quantity: trade_qty,
+            maker_id: order.id,
+            taker_id: taker.id,
+            taker_side: taker.side,
+            seq: 0,
+            timestamp: 0,
+            maker_fee: 0,
+            taker_fee: 0,
+            maker_client_order_id: order.client_order_id.clone(),
+            taker_client_order_id: taker.client_order_id.clone(),
+            maker_user_data: order.user_data,
+            taker_user_data: taker.user_data,
+            maker_owner: order.owner,
+            taker_owner: taker.owner,
+        };
+        fees.charge(&mut trade);
+        accounts.record(&trade);
+        trades.push(trade);
+
+        order.quantity -= trade_qty;
+        order.filled_quantity += trade_qty;
+        level.totals.remove(trade_qty, 0)?;
+        taker.remaining -= trade_qty;
+        if let Some(budget) = &mut taker.budget {
+            *budget -= price * trade_qty;
+        }
+
+        if let Some(events) = events {
+            let maker_remaining = order.quantity + order.hidden_quantity;
+            for (id, remaining) in [(order.id, maker_remaining), (taker.id, taker.remaining)] {
+                let event = if remaining == 0 {
+                    OrderEvent::Filled { id, price, quantity: trade_qty }
+                } else {
+                    OrderEvent::PartiallyFilled { id, price, quantity: trade_qty, remaining }
+                };
+                events.push(event.into());
+            }
+        }
+        Ok(())
+    }
+
+    // Self-trade prevention: take the taker's own resting order off the book
+    fn cancel_maker(
+        level: &mut PriceLevel,
+        slab: &mut OrderSlab,
+        key: usize,
+        order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        counters: &mut Counters,
+        events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        let maker = level.remove(slab, key)?;
+        level.totals.remove(maker.quantity, maker.hidden_quantity)?;
+        order_index.remove(&maker.id);
+        counters.cancels += 1;
+        if let Some(events) = events {
+            let remaining = maker.quantity + maker.hidden_quantity;
+            events.push(OrderEvent::Cancelled { id: maker.id, remaining }.into());
+        }
+        Ok(())
+    }
+
+    // Self-trade prevention: shrink the taker and its own resting order by their overlap
+    // without trading, visible quantity first; a maker left with nothing counts as cancelled
+    #[allow(clippy::too_many_arguments)]
+    fn decrement_maker(
+        level: &mut PriceLevel,
+        slab: &mut OrderSlab,
+        key: usize,
+        taker: &mut Taker,
+        order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        counters: &mut Counters,
+        last_order_seq: &mut u64,
+        events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        let order = slab.order_mut(key)?;
+        let overlap = taker.remaining.min(order.quantity + order.hidden_quantity);
+        let from_visible = overlap.min(order.quantity);
+        order.quantity -= from_visible;
+        order.hidden_quantity -= overlap - from_visible;
+        level.totals.remove(from_visible, overlap - from_visible)?;
+        taker.remaining -= overlap;
+        if order.quantity == 0 {
+            if order.hidden_quantity == 0 {
+                counters.cancels += 1;
+            }
+            Self::replenish(level, slab, key, order_index, last_order_seq, events)?;
+        }
+        Ok(())
+    }
+
+    // The front order has no visible quantity left: replenish it from its iceberg reserve
+    // (losing time priority), or take it off the book
+    pub(crate) fn replenish_front(
+        level: &mut PriceLevel,
+        slab: &mut OrderSlab,
+        order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        last_order_seq: &mut u64,
+        events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        let key = level.front().or_invariant("replenishing an empty level")?;
+        Self::replenish(level, slab, key, order_index, last_order_seq, events)
+    }
+
+    // As `replenish_front`, for the order at `key` wherever it is in the queue
+    fn replenish(
+        level: &mut PriceLevel,
+        slab: &mut OrderSlab,
+        key: usize,
+        order_index: &mut HashMap<u64, (Side, Units, usize)>,
+        last_order_seq: &mut u64,
+        events: Option<&mut Vec<Event>>,
+    ) -> Result<(), InvariantViolation> {
+        let order = slab.order_mut(key)?;
+        if order.hidden_quantity > 0 {
+            let slice = order.display_quantity.unwrap_or(order.hidden_quantity).clamp(1, order.hidden_quantity);
+            order.quantity = slice;
+            order.hidden_quantity -= slice;
+            *last_order_seq += 1;
+            order.seq = *last_order_seq;
+            level.totals.reveal(slice)?;
+            level.move_to_back(slab, key)?;
+        } else {
+            let order = level.remove(slab, key)?;
+            order_index.remove(&order.id);
+            if let Some(events) = events {
+                events.push(OrderEvent::Cancelled { id: order.id, remaining: 0 }.into());
+            }
+        }
+        level.debug_assert_totals(slab);
+        Ok(())
+    }
+
+    // An order that would cross can't share a price with a resting level on its own side
+    // (the book would already be crossed), so checking the full quantity here is exact.
+    pub(crate) fn would_overflow_level(&self, side: Side, price: Units, quantity: Units) -> bool {
+        self.levels(side)
+            .get(&price)
+            .is_some_and(|level| level.open_quantity().checked_add(quantity).is_none())
+    }
+
+    // Edge of the protection band if it is tighter than the order's own limit `price`
+    fn protection_limit(&self, side: Side, price: Units) -> Option<Units> {
+        let band = self.protection_band.filter(|_| !self.auction)?;
+        let tick_size = self.price_config.tick_size;
+        match side {
+            Side::Buy => Some(band.limit(side, self.best_sell()?.0, tick_size)).filter(|&limit| limit < price),
+            Side::Sell => Some(band.limit(side, self.best_buy()?.0, tick_size)).filter(|&limit| limit > price),
+        }
+    }
+
+    fn outside_price_band(&self, price: Units) -> bool {
+        match (self.price_band, self.last_trade_price) {
+            (Some(max_deviation), Some(last)) => price.abs_diff(last) > max_deviation,
+            _ => false,
+        }
+    }
+}
+
+fn cancel_reject_reason(err: CancelError) -> RejectReason {
+    match err {
+        CancelError::UnknownOrder(_) => RejectReason::UnknownOrder,
+        CancelError::Invariant(_) => RejectReason::Internal,
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::book::OrderBook;
+use crate::error::{InvariantViolation, OrInvariant};
+use crate::events::{BookEvent, OrderEvent};
+use crate::types::Side;
+use crate::units::Units;
+use crate::wal::LogEntry;
+
+/// What a fill on one leg of a one-cancels-other pair does to the other leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OcoMode {
+    /// Cancel it on the first fill
+    #[default]
+    Cancel,
+    /// Reduce it by the filled quantity, cancelling it once nothing is left
+    Reduce,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkError {
+    /// No resting or held stop order with this id
+    UnknownOrder(u64),
+    /// The order is already one leg of a pair
+    AlreadyLinked(u64),
+    /// Both legs name the same order
+    SameOrder(u64),
+}
+
+impl fmt::Display for LinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkError::UnknownOrder(id) => write!(f, "no resting or held stop order with id {}", id),
+            LinkError::AlreadyLinked(id) => write!(f, "order {} is already linked", id),
+            LinkError::SameOrder(id) => write!(f, "order {} can't be linked to itself", id),
+        }
+    }
+}
+
+impl std::error::Error for LinkError {}
+
+// Both directions of every link
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OcoLinks {
+    partners: HashMap<u64, (u64, OcoMode)>,
+}
+
+impl OcoLinks {
+    pub(crate) fn partner(&self, id: u64) -> Option<(u64, OcoMode)> {
+        self.partners.get(&id).copied()
+    }
+
+    pub(crate) fn link(&mut self, first: u64, second: u64, mode: OcoMode) {
+        self.partners.insert(first, (second, mode));
+        self.partners.insert(second, (first, mode));
+    }
+
+    // Each pair once, lower id first, in id order
+    pub(crate) fn pairs(&self) -> Vec<(u64, u64, OcoMode)> {
+        let mut pairs: Vec<_> = self
+            .partners
+            .iter()
+            .filter(|(&id, &(partner, _))| id < partner)
+            .map(|(&id, &(partner, mode))| (id, partner, mode))
+            .collect();
+        pairs.sort_unstable_by_key(|&(first, _, _)| first);
+        pairs
+    }
+
+    // Remove the pair `id` belongs to, returning its partner
+    pub(crate) fn unlink(&mut self, id: u64) -> Option<(u64, OcoMode)> {
+        let (partner, mode) = self.partners.remove(&id)?;
+        self.partners.remove(&partner);
+        Some((partner, mode))
+    }
+}
+
+impl OrderBook {
+    /// Link two orders, each resting or held as a stop, so that a fill on either cancels or
+    /// reduces the other according to `mode`. Once one leg leaves the book for good (filled,
+    /// cancelled or expired) the other is cancelled as well. A cancel/replace through
+    /// `modify_order` keeps the link.
+    pub fn link_oco(&mut self, first: u64, second: u64, mode: OcoMode) -> Result<(), LinkError> {
+        if first == second {
+            return Err(LinkError::SameOrder(first));
+        }
+        for id in [first, second] {
+            if !self.is_live(id) {
+                return Err(LinkError::UnknownOrder(id));
+            }
+            if self.oco.partner(id).is_some() {
+                return Err(LinkError::AlreadyLinked(id));
+            }
+        }
+        self.log_command(LogEntry::LinkOco { first, second, mode });
+        self.oco.link(first, second, mode);
+        Ok(())
+    }
+
+    /// Dissolve the pair `id` belongs to, leaving both orders as they are; returns the other
+    /// leg.
+    pub fn unlink_oco(&mut self, id: u64) -> Option<u64> {
+        let (partner, _) = self.oco.unlink(id)?;
+        self.log_command(LogEntry::UnlinkOco { id });
+        Some(partner)
+    }
+
+    /// The order linked to `id`, if any.
+    pub fn oco_partner(&self, id: u64) -> Option<u64> {
+        self.oco.partner(id).map(|(partner, _)| partner)
+    }
+
+    // Apply the fills in trade_buffer from `first` on to the partners of linked orders. Called
+    // after each price level while matching, so a sweep doesn't reach a leg whose partner it
+    // has already filled.
+    pub(crate) fn apply_oco_fills(&mut self, first: usize) -> Result<(), InvariantViolation> {
+        if self.oco.partners.is_empty() {
+            return Ok(());
+        }
+        let fills: Vec<(u64, Units)> = self.trade_buffer[first..]
+            .iter()
+            .flat_map(|trade| [(trade.maker_id, trade.quantity), (trade.taker_id, trade.quantity)])
+            .collect();
+        for (id, quantity) in fills {
+            let Some((partner, mode)) = self.oco.partner(id) else {
+                continue;
+            };
+            let partner_gone = match mode {
+                OcoMode::Cancel => {
+                    self.cancel_leg(partner)?;
+                    true
+                }
+                OcoMode::Reduce => !self.reduce_leg(partner, quantity)?,
+            };
+            if partner_gone {
+                self.oco.unlink(id);
+            }
+        }
+        Ok(())
+    }
+
+    // Cancel the remaining leg of every pair one of whose legs has left the book
+    pub(crate) fn settle_oco(&mut self) -> Result<(), InvariantViolation> {
+        if self.oco.partners.is_empty() {
+            return Ok(());
+        }
+        let gone: Vec<u64> = self.oco.partners.keys().copied().filter(|&id| !self.is_live(id)).collect();
+        for id in gone {
+            if let Some((partner, _)) = self.oco.unlink(id) {
+                self.cancel_leg(partner)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn is_live(&self, id: u64) -> bool {
+        self.order_index.contains_key(&id) || self.stop_index.contains_key(&id)
+    }
+
+    // Take a leg off the book or out of the held stops, if it's still there
+    fn cancel_leg(&mut self, id: u64) -> Result<(), InvariantViolation> {
+        let remaining = if let Some((order, emptied)) = self.take_resting(id)? {
+            if let Some((side, price)) = emptied {
+                self.emit(BookEvent::LevelRemoved { side, price });
+            }
+            order.remaining_quantity()
+        } else if let Some(order) = self.take_stop(id)? {
+            order.quantity
+        } else {
+            return Ok(());
+        };
+        self.counters.cancels += 1;
+        self.emit(OrderEvent::Cancelled { id, remaining });
+        Ok(())
+    }
+
+    // Reduce a leg by `quantity`, cancelling it if nothing would be left; false if it's gone
+    fn reduce_leg(&mut self, id: u64, quantity: Units) -> Result<bool, InvariantViolation> {
+        if let Some(&(side, price, key)) = self.order_index.get(&id) {
+            let level = match side {
+                Side::Buy => self.buy_map.get_mut(&price),
+                Side::Sell => self.sell_map.get_mut(&price),
+            };
+            let level = level.or_invariant("indexed order has no price level")?;
+            let order = self.slab.order_mut(key)?;
+            let open = order.quantity + order.hidden_quantity;
+            if quantity < open {
+                self.changes.touch(side, price);
+                // Like a reduction through `modify_order`: the reserve goes first
+                let left = open - quantity;
+                let hidden = order.hidden_quantity.min(left.saturating_sub(order.quantity));
+                let visible = left - hidden;
+                level.totals.remove(order.quantity - visible, order.hidden_quantity - hidden)?;
+                order.hidden_quantity = hidden;
+                order.quantity = visible;
+                level.debug_assert_totals(&self.slab);
+                return Ok(true);
+            }
+        } else if let Some(&(side, stop_price)) = self.stop_index.get(&id) {
+            let stops = match side {
+                Side::Buy => &mut self.buy_stops,
+                Side::Sell => &mut self.sell_stops,
+            };
+            let order = stops.get_mut(&stop_price).and_then(|held| held.iter_mut().find(|o| o.id == id));
+            let order = order.or_invariant("indexed stop missing")?;
+            if quantity < order.quantity {
+                order.quantity -= quantity;
+                return Ok(true);
+            }
+        }
+        self.cancel_leg(id)?;
+        Ok(false)
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt;
+
+use crate::types::{OrderType, PegReference, Side, TimeInForce, TrailingOffset};
+use crate::units::Units;
+
+/// Id a client gives its order, e.g. the one its order management system knows it by. The
+/// book never looks at it, only hands it back with the order's trades and reports.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClientOrderId {
+    Text(String),
+    Number(u128),
+}
+
+impl fmt::Display for ClientOrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientOrderId::Text(text) => f.write_str(text),
+            ClientOrderId::Number(number) => write!(f, "{}", number),
+        }
+    }
+}
+
+impl From<&str> for ClientOrderId {
+    fn from(text: &str) -> Self {
+        ClientOrderId::Text(text.to_string())
+    }
+}
+
+impl From<String> for ClientOrderId {
+    fn from(text: String) -> Self {
+        ClientOrderId::Text(text)
+    }
+}
+
+impl From<u64> for ClientOrderId {
+    fn from(number: u64) -> Self {
+        ClientOrderId::Number(number.into())
+    }
+}
+
+impl From<u128> for ClientOrderId {
+    fn from(number: u128) -> Self {
+        ClientOrderId::Number(number)
+    }
+}
+
+/// An order as submitted to the book.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NewOrder {
+    pub id: u64,
+    pub side: Side,
+    pub order_type: OrderType,
+    pub quantity: Units,
+    pub time_in_force: TimeInForce,
+    /// Only add liquidity, never take it
+    pub post_only: bool,
+    /// Iceberg slice size; the remainder rests hidden
+    pub display_quantity: Option<Units>,
+    /// Participant the order belongs to, for self-trade prevention
+    pub owner: Option<u64>,
+    /// Time from which `OrderBook::expire` takes the order off the book
+    pub expires_at: Option<u64>,
+    /// Only close out the owner's position, never open or add to it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reduce_only: bool,
+    /// Smallest fill the order takes part in, the quantity itself for all-or-none
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub min_quantity: Option<Units>,
+    /// Quote amount a buy spends, in the units of price times quantity; `quantity` caps what it
+    /// buys
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub notional: Option<Units>,
+    /// Carried to the order's trades and execution reports
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub client_order_id: Option<ClientOrderId>,
+    /// Opaque value for the caller's own use, carried like `client_order_id`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub user_data: Option<u64>,
+}
+
+impl NewOrder {
+    fn new(side: Side, order_type: OrderType, quantity: Units, id: u64) -> Self {
+        Self {
+            id,
+            side,
+            order_type,
+            quantity,
+            time_in_force: TimeInForce::Gtc,
+            post_only: false,
+            display_quantity: None,
+            owner: None,
+            expires_at: None,
+            reduce_only: false,
+            min_quantity: None,
+            notional: None,
+            client_order_id: None,
+            user_data: None,
+        }
+    }
+
+    pub fn limit(side: Side, price: Units, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::Limit { price }, quantity, id)
+    }
+
+    pub fn market(side: Side, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::Market, quantity, id)
+    }
+
+    pub fn stop(side: Side, stop_price: Units, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::Stop { stop_price }, quantity, id)
+    }
+
+    pub fn stop_limit(side: Side, stop_price: Units, price: Units, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::StopLimit { stop_price, price }, quantity, id)
+    }
+
+    pub fn trailing_stop(side: Side, offset: TrailingOffset, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::TrailingStop { offset }, quantity, id)
+    }
+
+    pub fn trailing_stop_limit(side: Side, offset: TrailingOffset, limit_offset: Units, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::TrailingStopLimit { offset, limit_offset }, quantity, id)
+    }
+
+    pub fn pegged(side: Side, reference: PegReference, offset: i64, quantity: Units, id: u64) -> Self {
+        Self::new(side, OrderType::Pegged { reference, offset }, quantity, id)
+    }
+
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn post_only(mut self) -> Self {
+        self.post_only = true;
+        self
+    }
+
+    /// Trade at most the owner's open position on the other side (see
+    /// `OrderBook::set_reduce_only_policy`).
+    pub fn reduce_only(mut self) -> Self {
+        self.reduce_only = true;
+        self
+    }
+
+    /// Trade only in fills of at least `min_quantity`, or what is left of the order if less.
+    /// On arrival the order trades only if that much can fill at once; a limit order that
+    /// can't and doesn't cross the book rests, anything else is rejected for insufficient
+    /// liquidity. Once resting, incoming orders too small to fill that much pass it over.
+    /// Not available for icebergs, whose fills are capped at the visible slice.
+    pub fn with_min_quantity(mut self, min_quantity: Units) -> Self {
+        self.min_quantity = Some(min_quantity);
+        self
+    }
+
+    /// Fill the whole order in one trade or not at all: a minimum quantity of all of it.
+    pub fn all_or_none(mut self) -> Self {
+        self.min_quantity = Some(self.quantity);
+        self
+    }
+
+    /// Buy for at most `notional` in quote units instead of a set quantity: `quantity` only caps
+    /// it. The book converts the notional as the order walks the sell side, buying whole lots
+    /// at each price for what is left of it, and a limit order that may rest does so with what
+    /// the rest buys at its limit price. An order whose notional buys nothing is rejected for
+    /// insufficient liquidity, as are FOK orders, and market orders under
+    /// `MarketRemainder::Reject`, that the book can't spend it all on. Not available for sells,
+    /// icebergs, or minimum-quantity, post-only and reduce-only orders.
+    pub fn with_notional(mut self, notional: Units) -> Self {
+        self.notional = Some(notional);
+        self
+    }
+
+    pub fn with_owner(mut self, owner: u64) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Good till `expires_at`, in the time units passed to `OrderBook::expire`.
+    pub fn good_till(mut self, expires_at: u64) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Show at most `display_quantity` on the book, keeping the rest in reserve.
+    pub fn iceberg(mut self, display_quantity: Units) -> Self {
+        self.display_quantity = Some(display_quantity);
+        self
+    }
+
+    pub fn with_client_order_id(mut self, client_order_id: impl Into<ClientOrderId>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    pub fn with_user_data(mut self, user_data: u64) -> Self {
+        self.user_data = Some(user_data);
+        self
+    }
+}
+
+/// One operation of a batch passed to `OrderBook::apply_batch`.
+// Most commands are places, so boxing them would only add an allocation apiece
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Command {
+    Place(NewOrder),
+    Cancel { id: u64 },
+    Modify { id: u64, price: Units, quantity: Units },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Order {
+    pub id: u64,
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub price: Units,
+    /// Visible quantity
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub quantity: Units,
+    /// Time the order was entered, according to the book's clock
+    pub timestamp: u64,
+    /// Arrival sequence number deciding time priority within a price level and tier; renewed
+    /// when an iceberg refills
+    pub seq: u64,
+    /// Iceberg reserve not shown on the book
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub hidden_quantity: Units,
+    /// Iceberg slice size used to replenish `quantity` from the reserve
+    #[cfg_attr(
+        all(feature = "serde", feature = "wide"),
+        serde(default, deserialize_with = "crate::units::deserialize_optional_units")
+    )]
+    pub display_quantity: Option<Units>,
+    pub owner: Option<u64>,
+    /// Quantity the order was submitted with
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub original_quantity: Units,
+    /// Quantity traded so far, including fills before it rested
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub filled_quantity: Units,
+    pub expires_at: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub client_order_id: Option<ClientOrderId>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub user_data: Option<u64>,
+    /// Smallest fill the order takes part in, as submitted
+    #[cfg_attr(
+        all(feature = "serde", feature = "wide"),
+        serde(default, deserialize_with = "crate::units::deserialize_optional_units")
+    )]
+    #[cfg_attr(all(feature = "serde", not(feature = "wide")), serde(default))]
+    pub min_quantity: Option<Units>,
+    /// Priority tier of the owner when the order was entered; higher tiers queue first within
+    /// a price level (see `PriorityPolicy`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub tier: u8,
+    /// Submitted post-only; a modify's replacement is too
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub post_only: bool,
+    /// Submitted reduce-only; a modify's replacement is too
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reduce_only: bool,
+}
+
+impl Order {
+    /// Open quantity, visible plus hidden.
+    pub fn remaining_quantity(&self) -> Units {
+        self.quantity.saturating_add(self.hidden_quantity)
+    }
+
+    // Smallest fill the order can take now: its minimum, capped at what is left of it
+    pub(crate) fn min_fill(&self) -> Units {
+        self.min_quantity.map_or(0, |min| min.min(self.remaining_quantity()))
+    }
+}
+
+/// Where a resting order stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderStatus {
+    pub side: Side,
+    pub price: Units,
+    pub original_quantity: Units,
+    pub remaining_quantity: Units,
+    pub filled_quantity: Units,
+    /// Orders ahead of this one at its price level
+    pub queue_position: usize,
+}
+
+/// What stands between a resting order and its next fill at its price level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueuePosition {
+    pub side: Side,
+    pub price: Units,
+    /// Orders ahead of this one at its price level
+    pub orders_ahead: usize,
+    /// Visible quantity of those orders, which trades before any of this one. Iceberg reserves
+    /// ahead aren't counted: each refill goes to the back of its tier.
+    pub quantity_ahead: Units,
+}
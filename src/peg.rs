@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use crate::book::{OrderBook, PriceLevel};
+use crate::error::{InvariantViolation, OrInvariant};
+use crate::events::{BookEvent, OrderEvent};
+use crate::types::{PegReference, Side};
+use crate::units::Units;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Peg {
+    pub(crate) reference: PegReference,
+    pub(crate) offset: i64,
+    // Clock reading of the last reprice, for the throttle
+    pub(crate) repriced_at: u64,
+}
+
+// Resting pegged orders by id. Entries of orders that left the book are dropped on the next
+// repricing pass, or replaced when the id rests again.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pegs {
+    pub(crate) orders: BTreeMap<u64, Peg>,
+    pub(crate) reprice_interval: u64,
+}
+
+impl OrderBook {
+    /// Reprice each pegged order at most once per `interval` clock units; with zero (the
+    /// default) they follow every change of their reference. A throttled order catches up on
+    /// the first book change after its interval has passed.
+    pub fn set_peg_reprice_interval(&mut self, interval: u64) {
+        self.pegs.reprice_interval = interval;
+    }
+
+    /// Ids of the resting pegged orders, in id order.
+    pub fn pegged_orders(&self) -> impl Iterator<Item = u64> + '_ {
+        self.pegs.orders.keys().copied().filter(|id| self.order_index.contains_key(id))
+    }
+
+    // Price a pegged order on `side` would rest at now: on the tick at or behind the reference
+    // plus offset, and at least a tick inside the opposite best price. None if the reference
+    // side is empty or the price would fall below one tick.
+    pub(crate) fn peg_price(&self, side: Side, reference: PegReference, offset: i64) -> Option<Units> {
+        let bid = self.best_buy().map(|(price, _)| price);
+        let ask = self.best_sell().map(|(price, _)| price);
+        let (reference_bid, reference_ask) = (self.unpegged_best(Side::Buy), self.unpegged_best(Side::Sell));
+        let base = match (reference, side) {
+            (PegReference::Primary, Side::Buy) | (PegReference::Market, Side::Sell) => reference_bid?,
+            (PegReference::Primary, Side::Sell) | (PegReference::Market, Side::Buy) => reference_ask?,
+            (PegReference::Mid, side) => {
+                let (bid, ask) = (reference_bid?, reference_ask?);
+                // Rounded away from the opposite side
+                let odd = bid.abs_diff(ask) % 2;
+                match side {
+                    Side::Buy => bid.midpoint(ask),
+                    Side::Sell => bid.midpoint(ask) + odd,
+                }
+            }
+        };
+        let tick = self.price_config.tick_size;
+        let price = if offset < 0 {
+            base.checked_sub(Units::from(offset.unsigned_abs()))?
+        } else {
+            base.checked_add(Units::from(offset.unsigned_abs()))?
+        };
+        let price = match side {
+            Side::Buy => ask.map_or(price, |ask| price.min(ask.saturating_sub(tick))),
+            Side::Sell => bid.map_or(price, |bid| price.max(bid.saturating_add(tick))),
+        };
+        let price = match side {
+            Side::Buy => price - price % tick,
+            Side::Sell => price.checked_next_multiple_of(tick)?,
+        };
+        (price > 0).then_some(price)
+    }
+
+    // Move every pegged order whose reference has moved, in id order
+    pub(crate) fn reprice_pegs(&mut self) -> Result<(), InvariantViolation> {
+        if self.pegs.orders.is_empty() {
+            return Ok(());
+        }
+        let interval = self.pegs.reprice_interval;
+        // The default test clock ticks on every reading, so only read it when throttling
+        let now = if interval > 0 { self.now() } else { 0 };
+        let ids: Vec<u64> = self.pegs.orders.keys().copied().collect();
+        for id in ids {
+            let Some(&(side, price, _)) = self.order_index.get(&id) else {
+                self.pegs.orders.remove(&id);
+                continue;
+            };
+            let peg = self.pegs.orders[&id];
+            if interval > 0 && now.saturating_sub(peg.repriced_at) < interval {
+                continue;
+            }
+            let Some(target) = self.peg_price(side, peg.reference, peg.offset) else {
+                continue;
+            };
+            if target == price || self.would_overflow_level(side, target, self.resting_quantity(id)) {
+                continue;
+            }
+            let (mut order, emptied) = self.take_resting(id)?.or_invariant("indexed peg isn't resting")?;
+            if let Some((side, price)) = emptied {
+                self.emit(BookEvent::LevelRemoved { side, price });
+            }
+            order.price = target;
+            order.seq = self.next_order_seq();
+            self.rest_order(side, order)?;
+            self.pegs.orders.insert(id, Peg { repriced_at: now, ..peg });
+            self.emit(OrderEvent::Repriced { id, price: target });
+        }
+        Ok(())
+    }
+
+    // Best price on `side` among levels holding an order that isn't pegged, so pegs don't
+    // follow each other or themselves
+    fn unpegged_best(&self, side: Side) -> Option<Units> {
+        let levels = self.levels(side);
+        let unpegged = |(&price, level): (&Units, &PriceLevel)| {
+            level.iter(&self.slab).any(|order| !self.pegs.orders.contains_key(&order.id)).then_some(price)
+        };
+        match side {
+            Side::Buy => levels.iter().rev().find_map(unpegged),
+            Side::Sell => levels.iter().find_map(unpegged),
+        }
+    }
+
+    fn resting_quantity(&self, id: u64) -> Units {
+        self.order_status(id).map_or(0, |status| status.remaining_quantity)
+    }
+}
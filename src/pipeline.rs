@@ -0,0 +1,71 @@
+use std::thread::{self, JoinHandle};
+
+use crate::book::OrderBook;
+use crate::concurrent::BookClosed;
+use crate::order::Command;
+use crate::spsc::{spsc_channel, Consumer, Producer, WaitStrategy};
+use crate::types::ExecutionReport;
+
+/// A matching thread fed through a lock-free command ring, answering through a report ring.
+///
+/// Meant to be driven by one thread; it's the low-latency counterpart to
+/// `ConcurrentOrderBook`. Commands run in the order sent, one report each, and the rings'
+/// capacity bounds how far the sender can run ahead.
+pub struct Pipeline {
+    commands: Producer<Command>,
+    reports: Consumer<ExecutionReport>,
+    wait: WaitStrategy,
+    worker: JoinHandle<OrderBook>,
+}
+
+impl Pipeline {
+    /// Move `book` onto a matching thread. `wait` is used on both sides: by the matching
+    /// thread waiting for commands and by `recv` waiting for reports.
+    pub fn spawn(book: OrderBook, capacity: usize, wait: WaitStrategy) -> Self {
+        let (commands, mut queue) = spsc_channel::<Command>(capacity);
+        let (mut output, reports) = spsc_channel(capacity);
+        let worker = thread::spawn(move || {
+            let mut book = book;
+            while let Some(command) = queue.pop(wait) {
+                let report = book.apply_command(&command);
+                // Once the pipeline is shut down nobody reads the reports; keep draining commands
+                let _ = output.push(report);
+            }
+            book
+        });
+        Self { commands, reports, wait, worker }
+    }
+
+    /// Queue a command, spinning while the command ring is full. Reports aren't buffered beyond
+    /// the report ring either, so receive them before running more than twice the capacity ahead.
+    /// Fails if the matching thread has stopped.
+    pub fn send(&mut self, command: Command) -> Result<(), BookClosed> {
+        self.commands.push(command).map_err(|_| BookClosed)
+    }
+
+    /// Next report, if one is ready.
+    pub fn try_recv(&mut self) -> Option<ExecutionReport> {
+        self.reports.try_pop()
+    }
+
+    /// Wait for the next report; fails if the matching thread stopped without sending one.
+    pub fn recv(&mut self) -> Result<ExecutionReport, BookClosed> {
+        self.reports.pop(self.wait).ok_or(BookClosed)
+    }
+
+    /// Send one command and wait for its report. Reports of earlier commands must have been
+    /// received first.
+    pub fn execute(&mut self, command: Command) -> Result<ExecutionReport, BookClosed> {
+        self.send(command)?;
+        self.recv()
+    }
+
+    /// Let the matching thread finish the queued commands and hand the book back; reports not
+    /// yet received are dropped. Fails if the matching thread panicked, taking the book with it.
+    pub fn shutdown(self) -> Result<OrderBook, BookClosed> {
+        let Self { commands, reports, worker, .. } = self;
+        drop(commands);
+        drop(reports);
+        worker.join().map_err(|_| BookClosed)
+    }
+}
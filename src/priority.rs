@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use crate::book::OrderBook;
+use crate::error::InvariantViolation;
+use crate::order::Order;
+
+/// Which of two orders entered at the same time queues first, tiers being equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TieBreak {
+    /// The one that reached the book first, as with plain price-time priority
+    #[default]
+    Arrival,
+    /// The one with the lower order id
+    LowerId,
+    /// The larger one as submitted, then the one that reached the book first
+    LargerQuantity,
+}
+
+impl TieBreak {
+    // Whether `order` goes ahead of `other`, entered at the same time
+    fn ahead(self, order: &Order, other: &Order) -> bool {
+        match self {
+            TieBreak::Arrival => false,
+            TieBreak::LowerId => order.id < other.id,
+            TieBreak::LargerQuantity => order.original_quantity > other.original_quantity,
+        }
+    }
+}
+
+/// Queue order within a price level, set with `OrderBook::set_priority_policy`. Orders queue
+/// by their owner's tier, highest first, so a designated market maker in a higher tier goes
+/// ahead of everyone else at its price; then by arrival, with `tie_break` deciding between
+/// orders of the same timestamp. The default puts every owner in tier 0 and breaks no ties,
+/// which is plain price-time priority.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriorityPolicy {
+    /// Tier by owner; owners not listed, and orders without one, are in tier 0
+    pub tiers: HashMap<u64, u8>,
+    pub tie_break: TieBreak,
+}
+
+impl PriorityPolicy {
+    pub fn with_tier(mut self, owner: u64, tier: u8) -> Self {
+        self.tiers.insert(owner, tier);
+        self
+    }
+
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn tier(&self, owner: Option<u64>) -> u8 {
+        owner.and_then(|owner| self.tiers.get(&owner).copied()).unwrap_or(0)
+    }
+
+    // Whether `order`, resting now, queues ahead of `other`, already resting at its price
+    pub(crate) fn ahead(&self, order: &Order, other: &Order) -> bool {
+        order.tier > other.tier
+            || (order.tier == other.tier && order.timestamp == other.timestamp && self.tie_break.ahead(order, other))
+    }
+
+    // Whether `first` may be queued just ahead of `second`; for `validate`
+    pub(crate) fn in_order(&self, first: &Order, second: &Order) -> bool {
+        first.tier > second.tier
+            || (first.tier == second.tier
+                && (first.seq < second.seq
+                    || (first.timestamp == second.timestamp && self.tie_break.ahead(first, second))))
+    }
+}
+
+impl OrderBook {
+    /// How orders queue within a price level; plain price-time priority unless set. An order
+    /// keeps the tier its owner had when it was entered, and an iceberg refill goes behind
+    /// the orders of its own tier. Resting orders are queued again under the new policy as if
+    /// they had arrived in sequence order; iceberg slices count from their last refill.
+    ///
+    /// Like the match policy, it's configuration: snapshots and checkpoints keep each order's
+    /// tier but come back in tier and arrival order, without the tie breaks.
+    pub fn set_priority_policy(&mut self, policy: PriorityPolicy) -> Result<(), InvariantViolation> {
+        self.priority_policy = policy;
+        let policy = &self.priority_policy;
+        for level in self.buy_map.values_mut().chain(self.sell_map.values_mut()) {
+            level.requeue(&mut self.slab, |order, other| policy.ahead(order, other))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+// The code #[pymethods] generates for fallible methods converts their errors into PyErr
+#![allow(clippy::useless_conversion)]
+
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::{PyKeyError, PyOverflowError, PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::book::{DepthLevel, OrderBook};
+use crate::types::{CancelError, ExecutionReport, OrderOutcome, PlaceError, Side, Trade};
+use crate::units::{widen, Units};
+
+// Bid and ask arrays returned by `OrderBook.depth`
+type DepthArrays<'py> = (Bound<'py, PyArray2<u64>>, Bound<'py, PyArray2<u64>>);
+
+// Trades kept for `OrderBook.trades` unless the constructor says otherwise
+const TRADE_HISTORY: usize = 1000;
+
+/// `OrderBook` as a Python class. Sides are given as "buy" or "sell"; ids left out are taken
+/// from the book's own counter.
+#[pyclass(name = "OrderBook", module = "orderbook")]
+pub struct PyOrderBook {
+    book: OrderBook,
+}
+
+/// A trade as seen from Python.
+#[pyclass(name = "Trade", module = "orderbook", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct PyTrade {
+    price: Units,
+    quantity: Units,
+    maker_id: u64,
+    taker_id: u64,
+    seq: u64,
+    timestamp: u64,
+}
+
+/// An execution report as seen from Python. `status` is "filled", "rested", "cancelled",
+/// "pending" or "rejected", with the reason in `reject_reason`.
+#[pyclass(name = "ExecutionReport", module = "orderbook", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct PyExecutionReport {
+    order_id: u64,
+    status: &'static str,
+    reject_reason: Option<String>,
+    filled_quantity: Units,
+    remaining_quantity: Units,
+    resting_id: Option<u64>,
+    trades: Vec<PyTrade>,
+}
+
+impl From<&Trade> for PyTrade {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            price: trade.price,
+            quantity: trade.quantity,
+            maker_id: trade.maker_id,
+            taker_id: trade.taker_id,
+            seq: trade.seq,
+            timestamp: trade.timestamp,
+        }
+    }
+}
+
+impl From<ExecutionReport> for PyExecutionReport {
+    fn from(report: ExecutionReport) -> Self {
+        let (status, reject_reason) = match report.status {
+            OrderOutcome::Filled => ("filled", None),
+            OrderOutcome::Rested => ("rested", None),
+            OrderOutcome::Cancelled => ("cancelled", None),
+            OrderOutcome::Pending => ("pending", None),
+            OrderOutcome::Rejected(reason) => ("rejected", Some(format!("{:?}", reason))),
+        };
+        Self {
+            order_id: report.order_id,
+            status,
+            reject_reason,
+            filled_quantity: report.filled_quantity,
+            remaining_quantity: report.remaining_quantity,
+            resting_id: report.resting_id,
+            trades: report.trades.iter().map(PyTrade::from).collect(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyTrade {
+    fn __repr__(&self) -> String {
+        format!(
+            "Trade(price={}, quantity={}, maker_id={}, taker_id={}, seq={})",
+            self.price, self.quantity, self.maker_id, self.taker_id, self.seq
+        )
+    }
+}
+
+#[pymethods]
+impl PyExecutionReport {
+    fn __repr__(&self) -> String {
+        format!(
+            "ExecutionReport(order_id={}, status='{}', filled_quantity={}, remaining_quantity={}, trades={})",
+            self.order_id,
+            self.status,
+            self.filled_quantity,
+            self.remaining_quantity,
+            self.trades.len()
+        )
+    }
+}
+
+#[pymethods]
+impl PyOrderBook {
+    /// An empty book keeping the last `trade_history` trades for `trades`.
+    #[new]
+    #[pyo3(signature = (trade_history = TRADE_HISTORY))]
+    fn new(trade_history: usize) -> Self {
+        let mut book = OrderBook::new();
+        book.enable_trade_history(trade_history);
+        Self { book }
+    }
+
+    /// Enter a limit order, returning its execution report.
+    #[pyo3(signature = (side, price, quantity, id = None))]
+    fn place(&mut self, side: &str, price: Units, quantity: Units, id: Option<u64>) -> PyResult<PyExecutionReport> {
+        let side = parse_side(side)?;
+        let id = id.unwrap_or_else(|| self.book.next_order_id());
+        let report = self.book.place_order(side, price, quantity, id).map_err(place_error)?;
+        Ok(report.into())
+    }
+
+    /// Enter a market order, returning its execution report.
+    #[pyo3(signature = (side, quantity, id = None))]
+    fn place_market(&mut self, side: &str, quantity: Units, id: Option<u64>) -> PyResult<PyExecutionReport> {
+        let side = parse_side(side)?;
+        let id = id.unwrap_or_else(|| self.book.next_order_id());
+        let report = self.book.place_market_order(side, quantity, id).map_err(place_error)?;
+        Ok(report.into())
+    }
+
+    /// Cancel a resting order, returning its unfilled quantity; `KeyError` if it isn't resting.
+    fn cancel(&mut self, id: u64) -> PyResult<Units> {
+        let order = self.book.cancel_order(id).map_err(cancel_error)?;
+        Ok(order.remaining_quantity())
+    }
+
+    /// Amend a resting order; see `OrderBook::modify_order`.
+    fn modify(&mut self, id: u64, price: Units, quantity: Units) -> PyResult<PyExecutionReport> {
+        let report = self.book.modify_order(id, price, quantity).map_err(cancel_error)?;
+        Ok(report.into())
+    }
+
+    /// `(price, visible quantity)` of the best bid, or `None`.
+    fn best_bid(&self) -> Option<(Units, Units)> {
+        self.book.best_buy()
+    }
+
+    fn best_ask(&self) -> Option<(Units, Units)> {
+        self.book.best_sell()
+    }
+
+    fn spread(&self) -> Option<Units> {
+        self.book.spread()
+    }
+
+    fn mid_price(&self) -> Option<f64> {
+        self.book.mid_price()
+    }
+
+    /// The top `levels` of each side as `(bids, asks)`, two `uint64` numpy arrays of shape
+    /// `(n, 3)` holding price, visible quantity and order count per row, best price first.
+    #[pyo3(signature = (levels = 10))]
+    fn depth<'py>(&self, py: Python<'py>, levels: usize) -> PyResult<DepthArrays<'py>> {
+        let depth = self.book.depth(levels);
+        Ok((depth_array(&depth.bids)?.into_pyarray_bound(py), depth_array(&depth.asks)?.into_pyarray_bound(py)))
+    }
+
+    /// Trades with a sequence number above `since` from the kept history, oldest first.
+    #[pyo3(signature = (since = 0))]
+    fn trades(&self, since: u64) -> Vec<PyTrade> {
+        self.book.trades_since(since).map(PyTrade::from).collect()
+    }
+
+    /// Number of resting orders.
+    fn __len__(&self) -> usize {
+        self.book.iter_bids().count() + self.book.iter_asks().count()
+    }
+}
+
+/// The `orderbook` Python module. `maturin develop` builds and installs it; pyproject.toml
+/// turns on the `python` feature.
+#[pymodule]
+#[pyo3(name = "orderbook")]
+pub fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyOrderBook>()?;
+    module.add_class::<PyTrade>()?;
+    module.add_class::<PyExecutionReport>()?;
+    Ok(())
+}
+
+fn parse_side(side: &str) -> PyResult<Side> {
+    match side {
+        "buy" => Ok(Side::Buy),
+        "sell" => Ok(Side::Sell),
+        _ => Err(PyValueError::new_err(format!("side must be 'buy' or 'sell', not {:?}", side))),
+    }
+}
+
+fn place_error(err: PlaceError) -> PyErr {
+    match err {
+        PlaceError::DuplicateId(_) => PyValueError::new_err(err.to_string()),
+        PlaceError::Invariant(_) => PyRuntimeError::new_err(err.to_string()),
+    }
+}
+
+fn cancel_error(err: CancelError) -> PyErr {
+    match err {
+        CancelError::UnknownOrder(_) => PyKeyError::new_err(err.to_string()),
+        CancelError::Invariant(_) => PyRuntimeError::new_err(err.to_string()),
+    }
+}
+
+fn depth_array(levels: &[DepthLevel]) -> PyResult<Array2<u64>> {
+    // Only reachable with the `wide` feature
+    let narrow = |value: Units| {
+        u64::try_from(widen(value)).map_err(|_| PyOverflowError::new_err(format!("{} doesn't fit a uint64", value)))
+    };
+    let mut cells = Vec::with_capacity(levels.len() * 3);
+    for level in levels {
+        cells.extend([narrow(level.price)?, narrow(level.quantity)?, level.order_count as u64]);
+    }
+    Array2::from_shape_vec((levels.len(), 3), cells).map_err(|err| PyRuntimeError::new_err(err.to_string()))
+}
@@ -0,0 +1,182 @@
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+use crate::book::{Depth, OrderBook};
+use crate::order::NewOrder;
+use crate::types::{ExecutionReport, Side, Trade};
+use crate::units::Units;
+
+// Trades kept for the `trades` command
+const TRADE_HISTORY: usize = 1000;
+
+pub const REPL_HELP: &str = "\
+buy QTY[@PRICE]          enter a buy order, a market order without a price
+sell QTY[@PRICE]         enter a sell order
+cancel ID                cancel a resting order
+modify ID QTY@PRICE      change the price and quantity of a resting order
+depth [LEVELS]           show the book, 10 levels per side by default
+trades [COUNT]           show the most recent trades, 10 by default
+help                     show this text
+quit                     leave";
+
+/// One line of REPL input, parsed with `str::parse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplCommand {
+    Order { side: Side, quantity: Units, price: Option<Units> },
+    Cancel(u64),
+    Modify { id: u64, quantity: Units, price: Units },
+    Depth(usize),
+    Trades(usize),
+    Help,
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCommandError(String);
+
+impl fmt::Display for ParseCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParseCommandError {}
+
+impl FromStr for ReplCommand {
+    type Err = ParseCommandError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command = match words.as_slice() {
+            [side @ ("buy" | "sell"), order] => {
+                let side = if *side == "buy" { Side::Buy } else { Side::Sell };
+                let (quantity, price) = match order.split_once('@') {
+                    Some((quantity, price)) => (quantity, Some(number(price)?)),
+                    None => (*order, None),
+                };
+                ReplCommand::Order { side, quantity: number(quantity)?, price }
+            }
+            ["cancel", id] => ReplCommand::Cancel(number(id)?),
+            ["modify", id, order] => {
+                let (quantity, price) = order
+                    .split_once('@')
+                    .ok_or_else(|| ParseCommandError(format!("expected QTY@PRICE, got {}", order)))?;
+                ReplCommand::Modify { id: number(id)?, quantity: number(quantity)?, price: number(price)? }
+            }
+            ["depth"] => ReplCommand::Depth(10),
+            ["depth", levels] => ReplCommand::Depth(number(levels)?),
+            ["trades"] => ReplCommand::Trades(10),
+            ["trades", count] => ReplCommand::Trades(number(count)?),
+            ["help"] => ReplCommand::Help,
+            ["quit" | "exit"] => ReplCommand::Quit,
+            _ => return Err(ParseCommandError(format!("unknown command: {}", line.trim()))),
+        };
+        Ok(command)
+    }
+}
+
+fn number<T: FromStr>(word: &str) -> Result<T, ParseCommandError> {
+    word.parse().map_err(|_| ParseCommandError(format!("not a number: {}", word)))
+}
+
+/// A single book driven by text commands, for teaching and reproducing scenarios by hand.
+/// Order ids are picked by the book.
+pub struct Repl {
+    book: OrderBook,
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        let mut book = OrderBook::new();
+        book.enable_trade_history(TRADE_HISTORY);
+        Self { book }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn book_mut(&mut self) -> &mut OrderBook {
+        &mut self.book
+    }
+
+    /// Run `command` and return what to print; `Quit` does nothing.
+    pub fn execute(&mut self, command: ReplCommand) -> String {
+        match command {
+            ReplCommand::Order { side, quantity, price } => {
+                let order = match price {
+                    Some(price) => NewOrder::limit(side, price, quantity, 0),
+                    None => NewOrder::market(side, quantity, 0),
+                };
+                match self.book.submit_with_new_id(order) {
+                    Ok(report) => render_report(&report),
+                    Err(err) => err.to_string(),
+                }
+            }
+            ReplCommand::Cancel(id) => match self.book.cancel_order(id) {
+                Ok(order) => format!("cancelled #{}: {} @ {}", id, order.quantity, order.price),
+                Err(err) => err.to_string(),
+            },
+            ReplCommand::Modify { id, quantity, price } => match self.book.modify_order(id, price, quantity) {
+                Ok(report) => render_report(&report),
+                Err(err) => err.to_string(),
+            },
+            ReplCommand::Depth(levels) => render_depth(&self.book.depth(levels)),
+            ReplCommand::Trades(count) => {
+                let trades: Vec<&Trade> = self.book.last_n_trades(count).collect();
+                if trades.is_empty() {
+                    return "no trades".to_string();
+                }
+                trades.into_iter().map(render_trade).collect::<Vec<_>>().join("\n")
+            }
+            ReplCommand::Help => REPL_HELP.to_string(),
+            ReplCommand::Quit => String::new(),
+        }
+    }
+}
+
+fn render_report(report: &ExecutionReport) -> String {
+    let mut out = format!(
+        "#{} {:?}: filled {}, remaining {}",
+        report.order_id, report.status, report.filled_quantity, report.remaining_quantity
+    );
+    for trade in &report.trades {
+        out.push('\n');
+        out.push_str(&render_trade(trade));
+    }
+    out
+}
+
+fn render_trade(trade: &Trade) -> String {
+    format!("  trade {} @ {} (maker #{}, taker #{})", trade.quantity, trade.price, trade.maker_id, trade.taker_id)
+}
+
+/// A price ladder with asks above bids, highest price first, e.g.
+///
+/// ```text
+///          BID     PRICE  ASK
+///                     11  50 (1)
+///                     10  150 (2)
+///      100 (1)         9
+/// ```
+pub fn render_depth(depth: &Depth) -> String {
+    if depth.bids.is_empty() && depth.asks.is_empty() {
+        return "empty book".to_string();
+    }
+    let mut out = format!("{:>12}  {:>8}  {}", "BID", "PRICE", "ASK");
+    for level in depth.asks.iter().rev() {
+        let ask = format!("{} ({})", level.quantity, level.order_count);
+        let _ = write!(out, "\n{:>12}  {:>8}  {}", "", level.price, ask);
+    }
+    for level in &depth.bids {
+        let bid = format!("{} ({})", level.quantity, level.order_count);
+        let _ = write!(out, "\n{:>12}  {:>8}", bid, level.price);
+    }
+    out
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::book::OrderBook;
+use crate::order::NewOrder;
+use crate::types::{Side, Trade};
+use crate::units::Units;
+
+/// Layout of an order-flow file and of the trade file written while replaying it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowFormat {
+    /// `timestamp,side,price,qty,id,action` rows, with an optional header row
+    Csv,
+    /// One object per line with the same fields, e.g.
+    /// `{"timestamp":5,"side":"buy","price":100,"qty":10,"id":1,"action":"new"}`;
+    /// reading it needs the `serde` feature
+    JsonLines,
+}
+
+impl FlowFormat {
+    /// `JsonLines` for `.jsonl`, `.ndjson` and `.json` files, `Csv` otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl" | "ndjson" | "json") => FlowFormat::JsonLines,
+            _ => FlowFormat::Csv,
+        }
+    }
+}
+
+/// How fast records are fed to the book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    AsFastAsPossible,
+    /// Keep the gaps between record timestamps, taken as nanoseconds and divided by the factor:
+    /// 1.0 is the original pace, 10.0 ten times faster. A factor of 0 or less doesn't wait.
+    Paced(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FlowAction {
+    /// A limit order, or a market order when the record has no price
+    New,
+    Cancel,
+    /// Change the price and quantity of a resting order
+    Modify,
+}
+
+// One row of an order-flow file. `side` is only needed by `New`, `price` and `quantity` by
+// `New` and `Modify`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FlowRecord {
+    timestamp: u64,
+    side: Option<Side>,
+    price: Option<Units>,
+    quantity: Option<Units>,
+    id: u64,
+    action: FlowAction,
+}
+
+/// What a replay did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    pub records: usize,
+    pub trades: usize,
+    /// Records the book refused: duplicate ids, cancels and modifies of orders not resting
+    pub failed: usize,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    /// Line `line` (1-based) isn't a valid record
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "replay i/o error: {}", err),
+            ReplayError::Parse { line, message } => write!(f, "order flow line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+/// Drive the order flow read from `input` through `book`, writing every trade to `output` in
+/// the same format, tagged with the timestamp of the record that caused it. Records are read
+/// and applied one at a time, so a malformed line stops the replay after everything before it
+/// has been applied. CSV output starts with a header row.
+pub fn replay<R: BufRead, W: Write>(
+    book: &mut OrderBook,
+    input: R,
+    format: FlowFormat,
+    mut output: W,
+    speed: ReplaySpeed,
+) -> Result<ReplaySummary, ReplayError> {
+    let mut summary = ReplaySummary::default();
+    // Wall-clock time and timestamp of the first record, which pacing is measured from
+    let mut origin: Option<(Instant, u64)> = None;
+    if format == FlowFormat::Csv {
+        writeln!(output, "timestamp,price,qty,maker_id,taker_id")?;
+    }
+    for (n, line) in input.lines().enumerate() {
+        let line = line?;
+        let parse_error = |message| ReplayError::Parse { line: n + 1, message };
+        if line.trim().is_empty() || (n == 0 && format == FlowFormat::Csv && line.starts_with("timestamp")) {
+            continue;
+        }
+        let record = match format {
+            FlowFormat::Csv => parse_csv(&line),
+            FlowFormat::JsonLines => parse_json(&line),
+        }
+        .map_err(parse_error)?;
+
+        match speed {
+            ReplaySpeed::Paced(factor) if factor > 0.0 => {
+                let (start, first) = *origin.get_or_insert((Instant::now(), record.timestamp));
+                let offset = Duration::from_nanos(record.timestamp.saturating_sub(first)).div_f64(factor);
+                if let Some(wait) = (start + offset).checked_duration_since(Instant::now()) {
+                    thread::sleep(wait);
+                }
+            }
+            _ => {}
+        }
+
+        summary.records += 1;
+        let trades = match apply(book, &record).map_err(parse_error)? {
+            Some(trades) => trades,
+            None => {
+                summary.failed += 1;
+                continue;
+            }
+        };
+        for trade in &trades {
+            write_trade(&mut output, format, record.timestamp, trade)?;
+        }
+        summary.trades += trades.len();
+    }
+    output.flush()?;
+    Ok(summary)
+}
+
+// The record's trades, `None` if the book refused it; an error for a record without the
+// fields its action needs, which `record` already turns away
+fn apply(book: &mut OrderBook, record: &FlowRecord) -> Result<Option<Vec<Trade>>, String> {
+    let missing = |field: &str| format!("missing {}", field);
+    Ok(match record.action {
+        FlowAction::New => {
+            let side = record.side.ok_or_else(|| missing("side"))?;
+            let quantity = record.quantity.ok_or_else(|| missing("qty"))?;
+            let order = match record.price {
+                Some(price) => NewOrder::limit(side, price, quantity, record.id),
+                None => NewOrder::market(side, quantity, record.id),
+            };
+            book.submit(order).ok().map(|report| report.trades)
+        }
+        FlowAction::Cancel => book.cancel_order(record.id).ok().map(|_| Vec::new()),
+        FlowAction::Modify => {
+            let price = record.price.ok_or_else(|| missing("price"))?;
+            let quantity = record.quantity.ok_or_else(|| missing("qty"))?;
+            book.modify_order(record.id, price, quantity).ok().map(|report| report.trades)
+        }
+    })
+}
+
+fn write_trade<W: Write>(output: &mut W, format: FlowFormat, timestamp: u64, trade: &Trade) -> io::Result<()> {
+    match format {
+        FlowFormat::Csv => writeln!(
+            output,
+            "{},{},{},{},{}",
+            timestamp, trade.price, trade.quantity, trade.maker_id, trade.taker_id
+        ),
+        FlowFormat::JsonLines => writeln!(
+            output,
+            "{{\"timestamp\":{},\"price\":{},\"qty\":{},\"maker_id\":{},\"taker_id\":{}}}",
+            timestamp, trade.price, trade.quantity, trade.maker_id, trade.taker_id
+        ),
+    }
+}
+
+fn parse_csv(line: &str) -> Result<FlowRecord, String> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    let [timestamp, side, price, quantity, id, action] = fields[..] else {
+        return Err(format!("expected 6 fields, got {}", fields.len()));
+    };
+    let optional = |field: &str| if field.is_empty() { Ok(None) } else { number(field).map(Some) };
+    let side = if side.is_empty() { None } else { Some(side) };
+    record(number(timestamp)?, side, optional(price)?, optional(quantity)?, number(id)?, action)
+}
+
+#[cfg(feature = "serde")]
+fn parse_json(line: &str) -> Result<FlowRecord, String> {
+    #[derive(serde::Deserialize)]
+    struct JsonRecord<'a> {
+        timestamp: u64,
+        side: Option<&'a str>,
+        price: Option<Units>,
+        qty: Option<Units>,
+        id: u64,
+        action: &'a str,
+    }
+
+    let json: JsonRecord = serde_json::from_str(line).map_err(|err| err.to_string())?;
+    record(json.timestamp, json.side, json.price, json.qty, json.id, json.action)
+}
+
+#[cfg(not(feature = "serde"))]
+fn parse_json(_line: &str) -> Result<FlowRecord, String> {
+    Err("reading JSON lines needs the serde feature".to_string())
+}
+
+fn number<T: FromStr>(field: &str) -> Result<T, String> {
+    field.parse().map_err(|_| format!("invalid number {:?}", field))
+}
+
+// Check that the record has the fields its action needs
+fn record(
+    timestamp: u64,
+    side: Option<&str>,
+    price: Option<Units>,
+    quantity: Option<Units>,
+    id: u64,
+    action: &str,
+) -> Result<FlowRecord, String> {
+    let side = match side.map(str::to_ascii_lowercase).as_deref() {
+        Some("buy" | "b") => Some(Side::Buy),
+        Some("sell" | "s") => Some(Side::Sell),
+        Some(other) => return Err(format!("invalid side {:?}", other)),
+        None => None,
+    };
+    let action = match action.to_ascii_lowercase().as_str() {
+        "new" | "add" => FlowAction::New,
+        "cancel" => FlowAction::Cancel,
+        "modify" => FlowAction::Modify,
+        other => return Err(format!("invalid action {:?}", other)),
+    };
+    match action {
+        FlowAction::New if side.is_none() => return Err("missing side".to_string()),
+        FlowAction::New | FlowAction::Modify if quantity.is_none() => return Err("missing qty".to_string()),
+        FlowAction::Modify if price.is_none() => return Err("missing price".to_string()),
+        _ => {}
+    }
+    Ok(FlowRecord { timestamp, side, price, quantity, id, action })
+}
@@ -0,0 +1,211 @@
+use std::io;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::book::Depth;
+use crate::exchange::{Exchange, ExchangeError};
+use crate::order::{NewOrder, Order};
+use crate::types::{ExecutionReport, Side, Trade};
+use crate::units::Units;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestConfig {
+    /// Levels per side returned by `GET /book/depth` when the query doesn't say
+    pub depth_levels: usize,
+    /// Trades kept per book for `GET /trades`, on books that don't keep a history already
+    pub trade_history: usize,
+}
+
+impl Default for RestConfig {
+    fn default() -> Self {
+        Self { depth_levels: 10, trade_history: 1000 }
+    }
+}
+
+/// Body of `POST /orders`, e.g.
+/// `{"symbol":"ACME","side":"Buy","price":100,"quantity":5,"id":1}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: Side,
+    /// Limit price; a market order when left out
+    pub price: Option<Units>,
+    pub quantity: Units,
+    pub id: u64,
+}
+
+/// Answer to `POST /orders`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacedOrder {
+    pub symbol: String,
+    pub report: ExecutionReport,
+}
+
+/// Answer to `DELETE /orders/{id}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CancelledOrder {
+    pub symbol: String,
+    pub order: Order,
+}
+
+/// Body of every non-2xx response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DepthQuery {
+    symbol: String,
+    levels: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TradesQuery {
+    symbol: String,
+    /// Only trades with a sequence number above this
+    since: Option<u64>,
+    /// Only the most recent `limit` of them
+    limit: Option<usize>,
+}
+
+enum Request {
+    Place(String, Box<NewOrder>, oneshot::Sender<Result<ExecutionReport, ExchangeError>>),
+    Cancel(u64, oneshot::Sender<Result<(String, Order), ExchangeError>>),
+    Depth(String, usize, oneshot::Sender<Result<Depth, ExchangeError>>),
+    Trades(TradesQuery, oneshot::Sender<Result<Vec<Trade>, ExchangeError>>),
+}
+
+// Requests queued for the engine before handlers wait to enqueue
+const REQUEST_BUFFER: usize = 1024;
+
+#[derive(Clone)]
+struct Engine {
+    requests: mpsc::Sender<Request>,
+    depth_levels: usize,
+}
+
+/// Routes for `POST /orders`, `DELETE /orders/{id}`, `GET /book/depth?symbol=..&levels=..` and
+/// `GET /trades?symbol=..&since=..&limit=..`, all speaking JSON.
+///
+/// Spawns the task that owns `exchange` and runs requests in arrival order, so it has to be
+/// called from within a Tokio runtime.
+pub fn http_router(mut exchange: Exchange, config: RestConfig) -> Router {
+    let symbols: Vec<String> = exchange.symbols().map(str::to_string).collect();
+    for symbol in symbols {
+        let Some(book) = exchange.book_mut(&symbol) else { continue };
+        if book.trade_history_capacity == 0 {
+            book.enable_trade_history(config.trade_history);
+        }
+    }
+    let (requests, queue) = mpsc::channel(REQUEST_BUFFER);
+    tokio::spawn(run_engine(exchange, queue));
+    Router::new()
+        .route("/orders", post(place))
+        .route("/orders/{id}", delete(cancel))
+        .route("/book/depth", get(depth))
+        .route("/trades", get(trades))
+        .with_state(Engine { requests, depth_levels: config.depth_levels })
+}
+
+/// Serve `exchange` over HTTP to every connection accepted on `listener`; see `http_router`.
+pub async fn serve_http(listener: TcpListener, exchange: Exchange, config: RestConfig) -> io::Result<()> {
+    axum::serve(listener, http_router(exchange, config)).await
+}
+
+async fn run_engine(mut exchange: Exchange, mut queue: mpsc::Receiver<Request>) {
+    while let Some(request) = queue.recv().await {
+        // A handler whose client went away meanwhile no longer waits for the reply
+        match request {
+            Request::Place(symbol, order, reply) => {
+                let _ = reply.send(exchange.submit(&symbol, *order));
+            }
+            Request::Cancel(id, reply) => {
+                let symbol = exchange.symbol_of(id).map(str::to_string).ok_or(ExchangeError::UnknownOrder(id));
+                let result = symbol.and_then(|symbol| Ok((symbol, exchange.cancel_order(id)?)));
+                let _ = reply.send(result);
+            }
+            Request::Depth(symbol, levels, reply) => {
+                let depth = exchange.book(&symbol).map(|book| book.depth(levels));
+                let _ = reply.send(depth.ok_or(ExchangeError::UnknownSymbol(symbol)));
+            }
+            Request::Trades(query, reply) => {
+                let trades = exchange.book(&query.symbol).map(|book| {
+                    let mut trades: Vec<Trade> = book.trades_since(query.since.unwrap_or(0)).cloned().collect();
+                    let skip = query.limit.map_or(0, |limit| trades.len().saturating_sub(limit));
+                    trades.drain(..skip);
+                    trades
+                });
+                let _ = reply.send(trades.ok_or(ExchangeError::UnknownSymbol(query.symbol)));
+            }
+        }
+    }
+}
+
+async fn place(State(engine): State<Engine>, Json(request): Json<OrderRequest>) -> Result<Json<PlacedOrder>, ApiError> {
+    let order = match request.price {
+        Some(price) => NewOrder::limit(request.side, price, request.quantity, request.id),
+        None => NewOrder::market(request.side, request.quantity, request.id),
+    };
+    let symbol = request.symbol;
+    let report = engine.call(|reply| Request::Place(symbol.clone(), Box::new(order), reply)).await?;
+    Ok(Json(PlacedOrder { symbol, report }))
+}
+
+async fn cancel(State(engine): State<Engine>, Path(id): Path<u64>) -> Result<Json<CancelledOrder>, ApiError> {
+    let (symbol, order) = engine.call(|reply| Request::Cancel(id, reply)).await?;
+    Ok(Json(CancelledOrder { symbol, order }))
+}
+
+async fn depth(State(engine): State<Engine>, Query(query): Query<DepthQuery>) -> Result<Json<Depth>, ApiError> {
+    let levels = query.levels.unwrap_or(engine.depth_levels);
+    Ok(Json(engine.call(|reply| Request::Depth(query.symbol, levels, reply)).await?))
+}
+
+async fn trades(State(engine): State<Engine>, Query(query): Query<TradesQuery>) -> Result<Json<Vec<Trade>>, ApiError> {
+    Ok(Json(engine.call(|reply| Request::Trades(query, reply)).await?))
+}
+
+impl Engine {
+    // Send a request to the engine task and wait for its answer
+    async fn call<T>(
+        &self,
+        request: impl FnOnce(oneshot::Sender<Result<T, ExchangeError>>) -> Request,
+    ) -> Result<T, ApiError> {
+        let (reply, answer) = oneshot::channel();
+        self.requests.send(request(reply)).await.map_err(|_| ApiError::Closed)?;
+        answer.await.map_err(|_| ApiError::Closed)?.map_err(ApiError::Exchange)
+    }
+}
+
+enum ApiError {
+    Exchange(ExchangeError),
+    /// The engine task is gone
+    Closed,
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error) = match self {
+            ApiError::Exchange(err) => {
+                let status = match err {
+                    ExchangeError::UnknownSymbol(_) | ExchangeError::UnknownOrder(_) => StatusCode::NOT_FOUND,
+                    ExchangeError::DuplicateSymbol(_) | ExchangeError::DuplicateOrderId(_) => StatusCode::CONFLICT,
+                    ExchangeError::SymbolDisabled(_) => StatusCode::FORBIDDEN,
+                    ExchangeError::InvalidPriceConfig(..) => StatusCode::UNPROCESSABLE_ENTITY,
+                    ExchangeError::Invariant(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                (status, err.to_string())
+            }
+            ApiError::Closed => (StatusCode::SERVICE_UNAVAILABLE, "the exchange has shut down".to_string()),
+        };
+        (status, Json(ErrorBody { error })).into_response()
+    }
+}
@@ -0,0 +1,140 @@
+use crate::book::OrderBook;
+use crate::order::NewOrder;
+use crate::types::{OrderType, RejectReason, Side};
+use crate::units::{notional, widen, Units};
+
+/// A check run on every order before it can match, rest or be held as a stop. Checks run in
+/// the order they were added and the first to fail rejects the order with its reason.
+pub trait PreTradeCheck: Send {
+    fn check(&mut self, order: &NewOrder, book: &OrderBook) -> Result<(), RejectReason>;
+}
+
+impl<F: FnMut(&NewOrder, &OrderBook) -> Result<(), RejectReason> + Send> PreTradeCheck for F {
+    fn check(&mut self, order: &NewOrder, book: &OrderBook) -> Result<(), RejectReason> {
+        self(order, book)
+    }
+}
+
+/// Static limits on single orders; `None` turns a limit off.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RiskLimits {
+    pub max_order_quantity: Option<Units>,
+    /// Largest price times quantity; see `order_notional` for how it's priced
+    pub max_notional: Option<u128>,
+    /// Furthest a limit price may be from the last trade price, in basis points of it
+    pub max_deviation_from_last_bps: Option<u64>,
+    /// Furthest a limit price may be from the mid price, in basis points of it
+    pub max_deviation_from_mid_bps: Option<u64>,
+    /// Most orders an owner may have resting at once, the new one included. Checking it costs
+    /// a scan of the book.
+    pub max_open_orders_per_owner: Option<usize>,
+}
+
+impl PreTradeCheck for RiskLimits {
+    fn check(&mut self, order: &NewOrder, book: &OrderBook) -> Result<(), RejectReason> {
+        if self.max_order_quantity.is_some_and(|max| order.quantity > max) {
+            return Err(RejectReason::MaxOrderSize);
+        }
+        if let (Some(max), Some(notional)) = (self.max_notional, order_notional(order, book)) {
+            if notional > max {
+                return Err(RejectReason::MaxNotional);
+            }
+        }
+        if let Some(price) = limit_price(order) {
+            let deviates = |max_bps: Option<u64>, reference: Option<f64>| match (max_bps, reference) {
+                (Some(max_bps), Some(reference)) => {
+                    (price as f64 - reference).abs() * 10_000.0 > max_bps as f64 * reference
+                }
+                _ => false,
+            };
+            if deviates(self.max_deviation_from_last_bps, book.last_trade_price().map(|price| price as f64))
+                || deviates(self.max_deviation_from_mid_bps, book.mid_price())
+            {
+                return Err(RejectReason::PriceDeviation);
+            }
+        }
+        if let (Some(max), Some(owner)) = (self.max_open_orders_per_owner, order.owner) {
+            if book.open_orders(owner).count() >= max {
+                return Err(RejectReason::OpenOrderLimit);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Source of how much an owner may still trade, e.g. a cash or margin ledger kept outside
+/// the book.
+pub trait BuyingPower: Send {
+    /// Notional the owner can commit to a new order on `side`.
+    fn available(&self, owner: u64, side: Side) -> u128;
+}
+
+/// Rejects orders whose notional exceeds their owner's buying power. Orders without an owner,
+/// and market orders with nothing to price them against, pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuyingPowerCheck<B>(pub B);
+
+impl<B: BuyingPower> PreTradeCheck for BuyingPowerCheck<B> {
+    fn check(&mut self, order: &NewOrder, book: &OrderBook) -> Result<(), RejectReason> {
+        let (Some(owner), Some(notional)) = (order.owner, order_notional(order, book)) else {
+            return Ok(());
+        };
+        if notional > self.0.available(owner, order.side) {
+            return Err(RejectReason::InsufficientBuyingPower);
+        }
+        Ok(())
+    }
+}
+
+/// Price times quantity of an order: at its limit price, at the stop price for stop orders,
+/// at the price it would rest at for pegged orders, and at the best opposite price for market
+/// orders and trailing stops (`None` if there's nothing to price it against). A notional order
+/// counts at most its notional.
+pub fn order_notional(order: &NewOrder, book: &OrderBook) -> Option<u128> {
+    let price = match order.order_type {
+        OrderType::Limit { price } | OrderType::StopLimit { price, .. } => price,
+        OrderType::Stop { stop_price } => stop_price,
+        OrderType::Pegged { reference, offset } => book.peg_price(order.side, reference, offset)?,
+        OrderType::Market | OrderType::TrailingStop { .. } | OrderType::TrailingStopLimit { .. } => match order.side {
+            Side::Buy => book.best_sell()?.0,
+            Side::Sell => book.best_buy()?.0,
+        },
+    };
+    let value = notional(price, order.quantity);
+    Some(order.notional.map_or(value, |notional| value.min(widen(notional))))
+}
+
+fn limit_price(order: &NewOrder) -> Option<Units> {
+    match order.order_type {
+        OrderType::Limit { price } | OrderType::StopLimit { price, .. } => Some(price),
+        OrderType::Market
+        | OrderType::Stop { .. }
+        | OrderType::TrailingStop { .. }
+        | OrderType::TrailingStopLimit { .. }
+        | OrderType::Pegged { .. } => None,
+    }
+}
+
+impl OrderBook {
+    /// Append `check` to the checks every order goes through before matching.
+    pub fn add_pre_trade_check(&mut self, check: impl PreTradeCheck + 'static) {
+        self.pre_trade_checks.push(Box::new(check));
+    }
+
+    pub fn clear_pre_trade_checks(&mut self) {
+        self.pre_trade_checks.clear();
+    }
+
+    // First failing check's reason, if any
+    pub(crate) fn run_pre_trade_checks(&mut self, order: &NewOrder) -> Result<(), RejectReason> {
+        if self.pre_trade_checks.is_empty() {
+            return Ok(());
+        }
+        // Taken out for the duration so the checks can look at the book
+        let mut checks = std::mem::take(&mut self.pre_trade_checks);
+        let result = checks.iter_mut().try_for_each(|check| check.check(order, self));
+        self.pre_trade_checks = checks;
+        result
+    }
+}
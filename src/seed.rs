@@ -0,0 +1,146 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::book::OrderBook;
+use crate::error::InvariantViolation;
+use crate::events::OrderEvent;
+use crate::order::Order;
+use crate::types::Side;
+use crate::units::Units;
+
+/// An order to put straight onto the book with `OrderBook::load`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestingOrder {
+    pub id: u64,
+    pub side: Side,
+    pub price: Units,
+    /// Open quantity, visible plus hidden
+    pub quantity: Units,
+    /// Iceberg slice size; the remainder rests hidden
+    pub display_quantity: Option<Units>,
+    pub owner: Option<u64>,
+    pub expires_at: Option<u64>,
+}
+
+impl RestingOrder {
+    pub fn new(side: Side, price: Units, quantity: Units, id: u64) -> Self {
+        Self { id, side, price, quantity, display_quantity: None, owner: None, expires_at: None }
+    }
+}
+
+/// Why `OrderBook::load` refused a set of orders. Each names the first offending order (or
+/// price level) found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    ZeroQuantity(u64),
+    OffTick(u64),
+    OffLot(u64),
+    /// Used twice in the input, or by an order already on the book or held as a stop
+    DuplicateId(u64),
+    /// The best bid would be at or above the best ask
+    Crossed { bid: Units, ask: Units },
+    /// The total quantity of a price level wouldn't fit in `Units`
+    LevelOverflow { side: Side, price: Units },
+    Invariant(InvariantViolation),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::ZeroQuantity(id) => write!(f, "order {} has zero quantity", id),
+            LoadError::OffTick(id) => write!(f, "order {} isn't on a tick", id),
+            LoadError::OffLot(id) => write!(f, "order {} isn't a whole number of lots", id),
+            LoadError::DuplicateId(id) => write!(f, "order id {} is already in use", id),
+            LoadError::Crossed { bid, ask } => write!(f, "book would be crossed: bid {} >= ask {}", bid, ask),
+            LoadError::LevelOverflow { side, price } => write!(f, "{:?} level at {} would overflow", side, price),
+            LoadError::Invariant(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<InvariantViolation> for LoadError {
+    fn from(err: InvariantViolation) -> Self {
+        LoadError::Invariant(err)
+    }
+}
+
+impl OrderBook {
+    /// Put `orders` on the book as they are, without matching, in O(n log n). Within a price
+    /// level they queue in input order, behind any orders already there. Everything is checked
+    /// first, the result not being crossed included; on error the book is left untouched.
+    ///
+    /// Meant for seeding a book, e.g. from an exchange snapshot. Listeners get the usual rested
+    /// and level events, but the load isn't recorded in the event log.
+    pub fn load(&mut self, orders: impl IntoIterator<Item = RestingOrder>) -> Result<(), LoadError> {
+        let orders: Vec<RestingOrder> = orders.into_iter().collect();
+        let config = self.price_config;
+        let mut ids = HashSet::with_capacity(orders.len());
+        let mut levels: HashMap<(Side, Units), Units> = HashMap::new();
+        let (mut bid, mut ask) = self.best_prices();
+        for order in &orders {
+            let id = order.id;
+            if order.quantity == 0 {
+                return Err(LoadError::ZeroQuantity(id));
+            }
+            if !config.is_on_tick(order.price) {
+                return Err(LoadError::OffTick(id));
+            }
+            if !config.is_whole_lot(order.quantity) || order.display_quantity.is_some_and(|d| !config.is_whole_lot(d)) {
+                return Err(LoadError::OffLot(id));
+            }
+            if self.id_in_use(id) || !ids.insert(id) {
+                return Err(LoadError::DuplicateId(id));
+            }
+            let (side, price) = (order.side, order.price);
+            let total = levels
+                .entry((side, price))
+                .or_insert_with(|| self.levels(side).get(&price).map_or(0, |level| level.open_quantity()));
+            *total = total.checked_add(order.quantity).ok_or(LoadError::LevelOverflow { side, price })?;
+            match side {
+                Side::Buy => bid = bid.max(Some(price)),
+                Side::Sell => ask = Some(ask.map_or(price, |ask| ask.min(price))),
+            }
+        }
+        if let (Some(bid), Some(ask)) = (bid, ask) {
+            if bid >= ask {
+                return Err(LoadError::Crossed { bid, ask });
+            }
+        }
+
+        let best_before = self.best_prices();
+        let timestamp = self.read_clock();
+        for order in orders {
+            let RestingOrder { id, side, price, quantity, display_quantity, owner, expires_at } = order;
+            let visible = display_quantity.map_or(quantity, |display| display.clamp(1, quantity));
+            let seq = self.next_order_seq();
+            self.rest_order(
+                side,
+                Order {
+                    id,
+                    price,
+                    quantity: visible,
+                    timestamp,
+                    seq,
+                    hidden_quantity: quantity - visible,
+                    display_quantity,
+                    owner,
+                    original_quantity: quantity,
+                    filled_quantity: 0,
+                    expires_at,
+                    client_order_id: None,
+                    user_data: None,
+                    min_quantity: None,
+                    tier: self.priority_policy.tier(owner),
+                    post_only: false,
+                    reduce_only: false,
+                },
+            )?;
+            self.emit(OrderEvent::Rested { id, price, quantity });
+        }
+        self.dispatch_events(best_before);
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use crate::auction::AuctionResult;
+use crate::book::OrderBook;
+use crate::error::OrderBookError;
+use crate::events::SessionEvent;
+use crate::instrument::DAY_NANOS;
+
+/// Part of the trading day a book is in under its session schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SessionPhase {
+    /// Halted; new orders are rejected or queued according to the halt policy
+    Closed,
+    /// Collecting orders for the opening auction
+    PreOpen,
+    /// Continuous matching
+    Continuous,
+    /// Collecting orders for the closing auction
+    ClosingAuction,
+}
+
+/// Times of one trading day, as nanoseconds since midnight UTC on the book's clock, in the
+/// order given: collection for the opening auction starts at `pre_open` and the auction runs at
+/// `open`; collection for the closing auction, if any, starts at `closing_auction` and it runs
+/// at `close`, after which the book is closed until the next day's `pre_open`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionSchedule {
+    pub pre_open: u64,
+    pub open: u64,
+    pub closing_auction: Option<u64>,
+    pub close: u64,
+}
+
+impl SessionSchedule {
+    /// Phase the schedule puts the book in at `now`, in nanoseconds since the Unix epoch.
+    pub fn phase_at(&self, now: u64) -> SessionPhase {
+        let time_of_day = now % DAY_NANOS;
+        if time_of_day < self.pre_open || time_of_day >= self.close {
+            SessionPhase::Closed
+        } else if time_of_day < self.open {
+            SessionPhase::PreOpen
+        } else if self.closing_auction.is_some_and(|start| time_of_day >= start) {
+            SessionPhase::ClosingAuction
+        } else {
+            SessionPhase::Continuous
+        }
+    }
+
+    // Phase after `phase` in the day's cycle
+    fn next(&self, phase: SessionPhase) -> SessionPhase {
+        match phase {
+            SessionPhase::Closed => SessionPhase::PreOpen,
+            SessionPhase::PreOpen => SessionPhase::Continuous,
+            SessionPhase::Continuous if self.closing_auction.is_some() => SessionPhase::ClosingAuction,
+            SessionPhase::Continuous | SessionPhase::ClosingAuction => SessionPhase::Closed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Session {
+    pub(crate) schedule: SessionSchedule,
+    // None until the first `update_session` puts the book in its scheduled phase
+    pub(crate) phase: Option<SessionPhase>,
+}
+
+impl OrderBook {
+    /// Run the book on `schedule` from the next `update_session`, or stop following one.
+    pub fn set_session_schedule(&mut self, schedule: Option<SessionSchedule>) {
+        self.session = schedule.map(|schedule| Session { schedule, phase: None });
+    }
+
+    /// Phase the book was last put in by `update_session`.
+    pub fn session_phase(&self) -> Option<SessionPhase> {
+        self.session.and_then(|session| session.phase)
+    }
+
+    /// Read the book's clock and make every transition of the schedule that has come due, in
+    /// order, returning the results of the auctions run. Entering pre-open or the closing
+    /// auction starts collecting orders (`start_auction`); opening runs the opening auction and
+    /// closing the closing one (`run_auction`), and closing then halts the book. Entering any
+    /// phase other than closed lifts a halt, submitting what it queued. Each transition is sent
+    /// to the listeners as a `SessionEvent` and logged as the calls it makes.
+    ///
+    /// The first call after a schedule is set moves straight to the scheduled phase. Nothing
+    /// happens between calls, so call it whenever the clock may have moved on, e.g. before each
+    /// order of a simulation.
+    pub fn update_session(&mut self) -> Result<Vec<AuctionResult>, OrderBookError> {
+        let Some(session) = self.session else {
+            return Ok(Vec::new());
+        };
+        let now = self.read_clock();
+        let target = session.schedule.phase_at(now);
+        let mut results = Vec::new();
+        let mut phase = session.phase;
+        while phase != Some(target) {
+            let next = phase.map_or(target, |phase| session.schedule.next(phase));
+            if let Some(result) = self.enter_phase(next)? {
+                results.push(result);
+            }
+            phase = Some(next);
+            if let Some(session) = &mut self.session {
+                session.phase = phase;
+            }
+            self.emit(SessionEvent::PhaseChanged { phase: next, time: now });
+            self.dispatch_events(self.best_prices());
+        }
+        Ok(results)
+    }
+
+    fn enter_phase(&mut self, phase: SessionPhase) -> Result<Option<AuctionResult>, OrderBookError> {
+        let collecting = matches!(phase, SessionPhase::PreOpen | SessionPhase::ClosingAuction);
+        if collecting && !self.auction {
+            self.start_auction();
+        }
+        // Lifted before an opening auction runs, so what the halt queued takes part
+        if phase != SessionPhase::Closed && self.halt.halted {
+            self.resume()?;
+        }
+        let result = if !collecting && self.auction { Some(self.run_auction()?) } else { None };
+        if phase == SessionPhase::Closed && !self.halt.halted {
+            self.halt();
+        }
+        Ok(result)
+    }
+}
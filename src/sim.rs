@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+
+use crate::book::OrderBook;
+use crate::clock::Clock;
+use crate::events::Event;
+use crate::order::Command;
+use crate::types::ExecutionReport;
+
+/// Small seeded generator (SplitMix64): the same seed always gives the same sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`; `bound` must be non-zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "empty range");
+        // Lemire's multiply-shift; the slight bias is irrelevant for simulation
+        ((u128::from(self.next_u64()) * u128::from(bound)) >> 64) as u64
+    }
+
+    /// Uniform in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Delay of a message between a participant and the book, in clock units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Latency {
+    Fixed(u64),
+    /// Drawn uniformly from `min..=max` for every message
+    Uniform { min: u64, max: u64 },
+}
+
+impl Latency {
+    pub fn sample(&self, rng: &mut SimRng) -> u64 {
+        match *self {
+            Latency::Fixed(delay) => delay,
+            Latency::Uniform { min, max } => min + rng.below(max.saturating_sub(min).saturating_add(1).max(1)),
+        }
+    }
+}
+
+/// Latencies applied by a `Simulator`: `inbound` from sending a command to the book acting on
+/// it, `outbound` from the book acting to the report and events reaching the participant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyModel {
+    pub inbound: Latency,
+    pub outbound: Latency,
+}
+
+impl Default for LatencyModel {
+    fn default() -> Self {
+        Self { inbound: Latency::Fixed(0), outbound: Latency::Fixed(0) }
+    }
+}
+
+/// Clock reading a shared simulated time; it only moves when the simulator moves it.
+#[derive(Debug, Clone, Default)]
+pub struct SimClock(Arc<AtomicU64>);
+
+impl SimClock {
+    pub fn set(&self, now: u64) {
+        self.0.store(now, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+impl Clock for SimClock {
+    fn now(&mut self) -> u64 {
+        self.get()
+    }
+}
+
+/// What reaches the participant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimMessage {
+    /// Answer to a command
+    Report(ExecutionReport),
+    /// A book event raised while the book acted on a command
+    Event(Event),
+}
+
+/// A message and the simulated time it's delivered at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub time: u64,
+    pub message: SimMessage,
+}
+
+enum Scheduled {
+    Arrival(Command),
+    Delivery(SimMessage),
+}
+
+/// Runs a book in simulated time. Commands are sent at a time, reach the book after the
+/// inbound latency and are answered after the outbound one; all randomness comes from one
+/// seeded generator, so the same seed, configuration and commands replay exactly.
+///
+/// Drive it by taking deliveries in time order with `next_delivery` and sending new commands
+/// in response; they're sent at the time of the last delivery.
+pub struct Simulator {
+    book: OrderBook,
+    clock: SimClock,
+    rng: SimRng,
+    latency: LatencyModel,
+    events: Receiver<Event>,
+    // Keyed by (time, scheduling order) so ties keep the order things were scheduled in
+    queue: BTreeMap<(u64, u64), Scheduled>,
+    scheduled: u64,
+}
+
+impl Simulator {
+    pub fn new(seed: u64, latency: LatencyModel) -> Self {
+        let clock = SimClock::default();
+        let mut book = OrderBook::with_clock(Box::new(clock.clone()));
+        let (sender, events) = mpsc::channel();
+        book.subscribe(sender);
+        Self {
+            book,
+            clock,
+            rng: SimRng::new(seed),
+            latency,
+            events,
+            queue: BTreeMap::new(),
+            scheduled: 0,
+        }
+    }
+
+    /// Current simulated time: that of the last arrival or delivery processed.
+    pub fn now(&self) -> u64 {
+        self.clock.get()
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    /// The book, e.g. to configure or seed it before the run. Changes made through it happen
+    /// at the current simulated time, without latency, and their events aren't delivered.
+    pub fn book_mut(&mut self) -> &mut OrderBook {
+        &mut self.book
+    }
+
+    /// The simulation's generator, for participants that need randomness too.
+    pub fn rng(&mut self) -> &mut SimRng {
+        &mut self.rng
+    }
+
+    /// Send `command` now.
+    pub fn send(&mut self, command: Command) {
+        self.send_at(self.now(), command);
+    }
+
+    /// Send `command` at `time` (not before now); it reaches the book after the inbound latency.
+    pub fn send_at(&mut self, time: u64, command: Command) {
+        let arrival = time.max(self.now()).saturating_add(self.latency.inbound.sample(&mut self.rng));
+        self.schedule(arrival, Scheduled::Arrival(command));
+    }
+
+    /// Process arrivals up to the next delivery and return it; `None` once nothing is left.
+    pub fn next_delivery(&mut self) -> Option<Delivery> {
+        while let Some(((time, _), item)) = self.queue.pop_first() {
+            self.clock.set(time);
+            match item {
+                Scheduled::Arrival(command) => self.arrive(time, &command),
+                Scheduled::Delivery(message) => return Some(Delivery { time, message }),
+            }
+        }
+        None
+    }
+
+    /// Every delivery up to and including `time`, in order, leaving the clock at `time`.
+    pub fn run_until(&mut self, time: u64) -> Vec<Delivery> {
+        let mut deliveries = Vec::new();
+        while self.queue.first_key_value().is_some_and(|(&(next, _), _)| next <= time) {
+            match self.next_delivery() {
+                Some(delivery) => deliveries.push(delivery),
+                None => break,
+            }
+        }
+        self.clock.set(time.max(self.now()));
+        deliveries
+    }
+
+    fn arrive(&mut self, time: u64, command: &Command) {
+        // Events of changes made through `book_mut` aren't delivered
+        while self.events.try_recv().is_ok() {}
+        let report = self.book.apply_command(command);
+        let delivered = time.saturating_add(self.latency.outbound.sample(&mut self.rng));
+        self.schedule(delivered, Scheduled::Delivery(SimMessage::Report(report)));
+        while let Ok(event) = self.events.try_recv() {
+            self.schedule(delivered, Scheduled::Delivery(SimMessage::Event(event)));
+        }
+    }
+
+    fn schedule(&mut self, time: u64, item: Scheduled) {
+        self.scheduled += 1;
+        self.queue.insert((time, self.scheduled), item);
+    }
+}
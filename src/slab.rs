@@ -0,0 +1,93 @@
+use crate::error::{InvariantViolation, OrInvariant};
+use crate::order::Order;
+
+// Storage for every resting order. Price levels queue their orders as doubly linked lists
+// through the slots instead of each owning a buffer, so resting an order or emptying a level
+// doesn't allocate. Freed slots are reused before the slab grows: once it has held as many
+// orders as the book ever holds at once, churn allocates nothing.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OrderSlab {
+    slots: Vec<Slot>,
+    // Most recently freed slot, heading a list through the vacant slots
+    free: Option<usize>,
+}
+
+// Vacant slots are waiting to be reused, so they're as big as the orders that fill them
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+enum Slot {
+    Occupied(Node),
+    Vacant { next_free: Option<usize> },
+}
+
+// An order and its neighbours in its level's queue
+#[derive(Debug, Clone)]
+pub(crate) struct Node {
+    pub(crate) order: Order,
+    pub(crate) prev: Option<usize>,
+    pub(crate) next: Option<usize>,
+}
+
+impl OrderSlab {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self { slots: Vec::with_capacity(capacity), free: None }
+    }
+
+    // Store an order outside any queue, returning its key
+    pub(crate) fn insert(&mut self, order: Order) -> Result<usize, InvariantViolation> {
+        let node = Slot::Occupied(Node { order, prev: None, next: None });
+        match self.free {
+            Some(key) => {
+                let next_free = match self.slots.get(key) {
+                    Some(&Slot::Vacant { next_free }) => Some(next_free),
+                    _ => None,
+                };
+                self.free = next_free.or_invariant("free list points at an occupied slab slot")?;
+                self.slots[key] = node;
+                Ok(key)
+            }
+            None => {
+                self.slots.push(node);
+                Ok(self.slots.len() - 1)
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: usize) -> Result<Node, InvariantViolation> {
+        self.get(key).or_invariant("removing a vacant slab slot")?;
+        let slot = std::mem::replace(&mut self.slots[key], Slot::Vacant { next_free: self.free });
+        self.free = Some(key);
+        match slot {
+            Slot::Occupied(node) => Ok(node),
+            Slot::Vacant { .. } => Err(InvariantViolation::new("removing a vacant slab slot")),
+        }
+    }
+
+    // `node` for keys that may not be trusted
+    pub(crate) fn get(&self, key: usize) -> Option<&Node> {
+        match self.slots.get(key) {
+            Some(Slot::Occupied(node)) => Some(node),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn node(&self, key: usize) -> Result<&Node, InvariantViolation> {
+        self.get(key).or_invariant("reading a vacant slab slot")
+    }
+
+    pub(crate) fn node_mut(&mut self, key: usize) -> Result<&mut Node, InvariantViolation> {
+        match self.slots.get_mut(key) {
+            Some(Slot::Occupied(node)) => Some(node),
+            _ => None,
+        }
+        .or_invariant("reading a vacant slab slot")
+    }
+
+    pub(crate) fn order(&self, key: usize) -> Result<&Order, InvariantViolation> {
+        Ok(&self.node(key)?.order)
+    }
+
+    pub(crate) fn order_mut(&mut self, key: usize) -> Result<&mut Order, InvariantViolation> {
+        Ok(&mut self.node_mut(key)?.order)
+    }
+}
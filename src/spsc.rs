@@ -0,0 +1,184 @@
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, Thread};
+
+/// How a side of a ring waits for the other: spinning keeps latency lowest at the cost of a
+/// busy core, parking gives the core up until woken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitStrategy {
+    /// Spin, yielding to the scheduler now and then so a spinner sharing a core with the other
+    /// side doesn't starve it
+    #[default]
+    BusySpin,
+    Park,
+}
+
+// Spins between yields while busy-waiting
+const SPINS_PER_YIELD: u32 = 1 << 10;
+
+fn spin(spins: &mut u32) {
+    *spins += 1;
+    if spins.is_multiple_of(SPINS_PER_YIELD) {
+        thread::yield_now();
+    } else {
+        std::hint::spin_loop();
+    }
+}
+
+// Bounded single-producer single-consumer queue. `head` is only written by the consumer and
+// `tail` only by the producer; both count up forever and are reduced modulo the capacity.
+struct Ring<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    mask: usize,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    producer_alive: AtomicBool,
+    consumer_alive: AtomicBool,
+    // Set while the consumer is parked, so the producer knows to wake it
+    consumer_parked: AtomicBool,
+    consumer_thread: Mutex<Option<Thread>>,
+}
+
+// SAFETY: a slot is accessed by one side at a time, handed over through the Release/Acquire
+// pairs on `head` and `tail`; values only move between threads, so `T: Send` suffices.
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn wake_consumer(&self) {
+        // The lock only guards a thread handle, so a poisoned one is still good to use
+        if self.consumer_parked.load(Ordering::SeqCst) {
+            if let Some(thread) = self.consumer_thread.lock().unwrap_or_else(PoisonError::into_inner).as_ref() {
+                thread.unpark();
+            }
+        }
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let tail = *self.tail.get_mut();
+        for index in *self.head.get_mut()..tail {
+            // SAFETY: slots between head and tail hold values that were pushed and not popped
+            unsafe { self.slots[index & self.mask].get_mut().assume_init_drop() };
+        }
+    }
+}
+
+/// Sending half of a ring made by `spsc_channel`.
+pub struct Producer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// Receiving half of a ring made by `spsc_channel`.
+pub struct Consumer<T> {
+    ring: Arc<Ring<T>>,
+}
+
+/// A bounded lock-free ring for exactly one producer and one consumer thread. The capacity is
+/// rounded up to a power of two.
+pub fn spsc_channel<T: Send>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    let capacity = capacity.max(1).next_power_of_two();
+    let ring = Arc::new(Ring {
+        slots: (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect(),
+        mask: capacity - 1,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        producer_alive: AtomicBool::new(true),
+        consumer_alive: AtomicBool::new(true),
+        consumer_parked: AtomicBool::new(false),
+        consumer_thread: Mutex::new(None),
+    });
+    (Producer { ring: Arc::clone(&ring) }, Consumer { ring })
+}
+
+impl<T: Send> Producer<T> {
+    /// Push without waiting; gives the value back if the ring is full or the consumer is gone.
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let ring = &*self.ring;
+        let tail = ring.tail.load(Ordering::Relaxed);
+        if tail - ring.head.load(Ordering::Acquire) == ring.slots.len() || !ring.consumer_alive.load(Ordering::Relaxed) {
+            return Err(value);
+        }
+        // SAFETY: the slot at `tail` is outside head..tail, so the consumer won't touch it
+        // until the store below publishes it
+        unsafe { (*ring.slots[tail & ring.mask].get()).write(value) };
+        ring.tail.store(tail + 1, Ordering::SeqCst);
+        ring.wake_consumer();
+        Ok(())
+    }
+
+    /// Push, spinning while the ring is full; gives the value back if the consumer is gone.
+    pub fn push(&mut self, mut value: T) -> Result<(), T> {
+        let mut spins = 0;
+        loop {
+            match self.try_push(value) {
+                Ok(()) => return Ok(()),
+                Err(rejected) if self.ring.consumer_alive.load(Ordering::Relaxed) => {
+                    value = rejected;
+                    spin(&mut spins);
+                }
+                Err(rejected) => return Err(rejected),
+            }
+        }
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.ring.producer_alive.store(false, Ordering::SeqCst);
+        self.ring.wake_consumer();
+    }
+}
+
+impl<T: Send> Consumer<T> {
+    pub fn try_pop(&mut self) -> Option<T> {
+        let ring = &*self.ring;
+        let head = ring.head.load(Ordering::Relaxed);
+        if head == ring.tail.load(Ordering::Acquire) {
+            return None;
+        }
+        // SAFETY: the producer published this slot with its store to `tail` and won't reuse it
+        // until the store below hands it back
+        let value = unsafe { (*ring.slots[head & ring.mask].get()).assume_init_read() };
+        ring.head.store(head + 1, Ordering::Release);
+        Some(value)
+    }
+
+    /// Wait for the next value; `None` once the ring is empty and the producer is gone.
+    pub fn pop(&mut self, wait: WaitStrategy) -> Option<T> {
+        let mut spins = 0;
+        loop {
+            if let Some(value) = self.try_pop() {
+                return Some(value);
+            }
+            if !self.ring.producer_alive.load(Ordering::SeqCst) {
+                // Values pushed just before the producer went away
+                return self.try_pop();
+            }
+            match wait {
+                WaitStrategy::BusySpin => spin(&mut spins),
+                WaitStrategy::Park => self.park(),
+            }
+        }
+    }
+
+    fn park(&self) {
+        let ring = &*self.ring;
+        *ring.consumer_thread.lock().unwrap_or_else(PoisonError::into_inner) = Some(thread::current());
+        ring.consumer_parked.store(true, Ordering::SeqCst);
+        // Re-check after announcing the park so a push in between isn't missed
+        let empty = ring.head.load(Ordering::Relaxed) == ring.tail.load(Ordering::SeqCst);
+        if empty && ring.producer_alive.load(Ordering::SeqCst) {
+            thread::park();
+        }
+        ring.consumer_parked.store(false, Ordering::SeqCst);
+    }
+}
+
+impl<T> Drop for Consumer<T> {
+    fn drop(&mut self) {
+        self.ring.consumer_alive.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::book::{OrderBook, PriceLevel};
+use crate::units::widen;
+
+// Engine activity since the book was created; not carried by snapshots
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Counters {
+    pub(crate) trades: u64,
+    pub(crate) volume: u128,
+    pub(crate) matches: u64,
+    pub(crate) cancels: u64,
+    pub(crate) rejects: u64,
+}
+
+/// Point-in-time statistics of a book, from `OrderBook::stats`. Resting quantities include
+/// hidden iceberg quantity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookStats {
+    /// Resting orders (held stop orders not included)
+    pub order_count: usize,
+    pub buy_levels: usize,
+    pub sell_levels: usize,
+    pub buy_quantity: u128,
+    pub sell_quantity: u128,
+    pub stop_count: usize,
+    /// Trades printed since the book was created, auction trades included
+    pub trade_count: u64,
+    pub traded_volume: u128,
+    /// Orders that traded on arrival, triggered stops included
+    pub matches: u64,
+    /// Orders and stops taken off the book by a cancel call, or makers by self-trade prevention
+    pub cancels: u64,
+    /// Orders rejected with a `RejectReason`
+    pub rejects: u64,
+}
+
+impl OrderBook {
+    pub fn stats(&self) -> BookStats {
+        let quantity = |level: &PriceLevel| widen(level.open_quantity());
+        BookStats {
+            order_count: self.order_count(),
+            buy_levels: self.buy_map.len(),
+            sell_levels: self.sell_map.len(),
+            buy_quantity: self.buy_map.values().map(quantity).sum(),
+            sell_quantity: self.sell_map.values().map(quantity).sum(),
+            stop_count: self.stop_index.len(),
+            trade_count: self.counters.trades,
+            traded_volume: self.counters.volume,
+            matches: self.counters.matches,
+            cancels: self.counters.cancels,
+            rejects: self.counters.rejects,
+        }
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl BookStats {
+    /// The statistics in the Prometheus text exposition format, every sample labelled with
+    /// `book="<book>"`.
+    pub fn to_prometheus(&self, book: &str) -> String {
+        use std::fmt::Write;
+
+        let book = book.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+        // (name, help, [(side label, value)]); an empty side means no side label
+        type Gauge<'a> = (&'a str, &'a str, &'a [(&'a str, u128)]);
+        let gauges: [Gauge; 4] = [
+            ("orderbook_orders", "Resting orders", &[("", self.order_count as u128)]),
+            ("orderbook_levels", "Price levels", &[("buy", self.buy_levels as u128), ("sell", self.sell_levels as u128)]),
+            ("orderbook_resting_quantity", "Resting quantity", &[("buy", self.buy_quantity), ("sell", self.sell_quantity)]),
+            ("orderbook_stops", "Held stop orders", &[("", self.stop_count as u128)]),
+        ];
+        let counters: [(&str, &str, u128); 5] = [
+            ("orderbook_trades_total", "Trades printed", self.trade_count.into()),
+            ("orderbook_traded_volume_total", "Quantity traded", self.traded_volume),
+            ("orderbook_matches_total", "Orders that traded on arrival", self.matches.into()),
+            ("orderbook_cancels_total", "Orders cancelled", self.cancels.into()),
+            ("orderbook_rejects_total", "Orders rejected", self.rejects.into()),
+        ];
+
+        let mut out = String::new();
+        for (name, help, samples) in gauges {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+            for &(side, value) in samples {
+                if side.is_empty() {
+                    let _ = writeln!(out, "{}{{book=\"{}\"}} {}", name, book, value);
+                } else {
+                    let _ = writeln!(out, "{}{{book=\"{}\",side=\"{}\"}} {}", name, book, side, value);
+                }
+            }
+        }
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+            let _ = writeln!(out, "{}{{book=\"{}\"}} {}", name, book, value);
+        }
+        out
+    }
+}
@@ -0,0 +1,58 @@
+use crate::book::OrderBook;
+use crate::order::Command;
+use crate::types::Trade;
+
+/// Conditional order logic run inside the book: sees every trade as soon as its call has
+/// finished matching, and can answer with follow-up commands (to place an exit order, cancel
+/// the other leg of a pair, ...) that run before the call returns.
+pub trait TradeStrategy: Send {
+    fn on_trade(&mut self, trade: &Trade, book: &OrderBook, actions: &mut Vec<Command>);
+}
+
+impl<F: FnMut(&Trade, &OrderBook, &mut Vec<Command>) + Send> TradeStrategy for F {
+    fn on_trade(&mut self, trade: &Trade, book: &OrderBook, actions: &mut Vec<Command>) {
+        self(trade, book, actions)
+    }
+}
+
+impl OrderBook {
+    /// Run `strategy` on the trades of every later call. Follow-up commands are applied as if
+    /// the caller had made them, right after the call: each is logged and dispatches its own
+    /// events, and their trades go to the strategies in turn until no more commands come. A
+    /// book replaying the log therefore mustn't have the strategies installed.
+    pub fn add_strategy(&mut self, strategy: impl TradeStrategy + 'static) {
+        self.strategies.push(Box::new(strategy));
+    }
+
+    pub fn clear_strategies(&mut self) {
+        self.strategies.clear();
+    }
+
+    // Feed `trades` to the strategies and apply what they ask for, returning the trades of the
+    // follow-up commands
+    pub(crate) fn run_strategies(&mut self, trades: &[Trade]) -> Vec<Trade> {
+        if self.strategies.is_empty() || trades.is_empty() {
+            return Vec::new();
+        }
+        // Taken out for the duration: follow-ups go through `submit` and their trades are
+        // handled by this loop rather than by nested calls
+        let mut strategies = std::mem::take(&mut self.strategies);
+        let mut follow_up_trades = Vec::new();
+        let mut pending = trades.to_vec();
+        let mut actions = Vec::new();
+        while !pending.is_empty() {
+            for trade in &pending {
+                for strategy in &mut strategies {
+                    strategy.on_trade(trade, self, &mut actions);
+                }
+            }
+            pending.clear();
+            for command in actions.drain(..) {
+                pending.extend(self.apply_command(&command).trades);
+            }
+            follow_up_trades.extend_from_slice(&pending);
+        }
+        self.strategies = strategies;
+        follow_up_trades
+    }
+}
@@ -0,0 +1,84 @@
+use crate::book::OrderBook;
+use crate::error::{InvariantViolation, OrInvariant};
+use crate::order::NewOrder;
+use crate::types::{bps_of, OrderType, Side, TrailingOffset};
+use crate::units::Units;
+
+impl TrailingOffset {
+    // Distance from `reference`, rounded down to a multiple of `tick_size`
+    fn distance(self, reference: Units, tick_size: Units) -> Units {
+        match self {
+            TrailingOffset::Amount(amount) => amount,
+            TrailingOffset::Bps(bps) => {
+                let distance = bps_of(reference, bps);
+                distance - distance % tick_size
+            }
+        }
+    }
+}
+
+impl OrderBook {
+    /// Current stop price of a held stop order, trailing or not.
+    pub fn stop_price(&self, id: u64) -> Option<Units> {
+        self.stop_index.get(&id).map(|&(_, stop_price)| stop_price)
+    }
+
+    // Stop price a trailing stop on `side` would have with the reference price at `reference`:
+    // above it for a buy, below it for a sell
+    pub(crate) fn trailing_stop_price(&self, side: Side, offset: TrailingOffset, reference: Units) -> Units {
+        let distance = offset.distance(reference, self.price_config.tick_size);
+        match side {
+            Side::Buy => reference.saturating_add(distance),
+            Side::Sell => reference.saturating_sub(distance),
+        }
+    }
+
+    // Move trailing stops after the reference prices: buy stops down as the price falls, sell
+    // stops up as it rises. A stop that moves goes to the back of its new trigger price.
+    pub(crate) fn trail_stops(
+        &mut self,
+        buy_reference: Option<Units>,
+        sell_reference: Option<Units>,
+    ) -> Result<(), InvariantViolation> {
+        if self.trailing_stops.is_empty() {
+            return Ok(());
+        }
+        let trailing: Vec<(u64, TrailingOffset)> = self.trailing_stops.iter().map(|(&id, &offset)| (id, offset)).collect();
+        for (id, offset) in trailing {
+            let &(side, stop_price) = self.stop_index.get(&id).or_invariant("trailing stop isn't indexed")?;
+            let reference = match side {
+                Side::Buy => buy_reference,
+                Side::Sell => sell_reference,
+            };
+            let Some(reference) = reference else {
+                continue;
+            };
+            let trailed = self.trailing_stop_price(side, offset, reference);
+            let tighter = match side {
+                Side::Buy => trailed < stop_price,
+                Side::Sell => trailed > stop_price,
+            };
+            if tighter {
+                let order = self.take_stop(id)?.or_invariant("trailing stop isn't held")?;
+                self.hold_stop(trailed, order);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The order a stop becomes once triggered at `stop_price`; plain stops are held already converted
+pub(crate) fn triggered_order(order: NewOrder, stop_price: Units) -> NewOrder {
+    let order_type = match order.order_type {
+        OrderType::TrailingStop { .. } => OrderType::Market,
+        OrderType::TrailingStopLimit { limit_offset, .. } => {
+            let price = match order.side {
+                Side::Buy => stop_price.saturating_add(limit_offset),
+                Side::Sell => stop_price.saturating_sub(limit_offset),
+            };
+            OrderType::Limit { price }
+        }
+        order_type => order_type,
+    };
+    NewOrder { order_type, ..order }
+}
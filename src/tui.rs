@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Cell, List, ListItem, Paragraph, Row, Table};
+use ratatui::Frame;
+
+use crate::book::{DepthLevel, OrderBook};
+use crate::events::{BookEvent, Event, OrderEvent};
+use crate::order::Command;
+use crate::repl::{Repl, ReplCommand};
+use crate::types::{Side, Trade};
+use crate::units::Units;
+
+// Trades and events kept for the scrolling panes, newest first
+const HISTORY: usize = 200;
+
+/// Terminal view of a single book for demos and debugging: a price ladder with asks above
+/// bids, the last trades and book events scrolling beside it, and an order-entry line taking
+/// the REPL's commands (`buy 10@100`, `cancel 3`, ...). Levels touched by the latest command
+/// are highlighted, as told by the book's event stream.
+///
+/// It only draws and reacts to keys; the `tui` binary owns the terminal and the event loop.
+pub struct LadderView {
+    repl: Repl,
+    events: Receiver<Event>,
+    trades: VecDeque<Trade>,
+    log: VecDeque<String>,
+    // Prices of the levels the latest command changed; no price is on both sides at once
+    touched: Vec<Units>,
+    input: String,
+    status: String,
+}
+
+impl Default for LadderView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LadderView {
+    pub fn new() -> Self {
+        let mut repl = Repl::new();
+        let (tx, events) = mpsc::channel();
+        repl.book_mut().subscribe(tx);
+        Self {
+            repl,
+            events,
+            trades: VecDeque::new(),
+            log: VecDeque::new(),
+            touched: Vec::new(),
+            input: String::new(),
+            status: "type a command and press Enter, `help` for the list, Esc to quit".to_string(),
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        self.repl.book()
+    }
+
+    /// Run a command from elsewhere, e.g. a `FlowGenerator` feeding the demo.
+    pub fn apply(&mut self, command: &Command) {
+        self.repl.book_mut().apply_batch(std::slice::from_ref(command));
+        self.refresh();
+    }
+
+    /// Edit or submit the order-entry line. Returns false when the view should close: on Esc,
+    /// Ctrl-C or a `quit` command.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if key.kind == KeyEventKind::Release {
+            return true;
+        }
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char(c) => self.input.push(c),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::Enter => return self.submit(),
+            _ => {}
+        }
+        true
+    }
+
+    fn submit(&mut self) -> bool {
+        let line = std::mem::take(&mut self.input);
+        if line.trim().is_empty() {
+            return true;
+        }
+        match line.parse::<ReplCommand>() {
+            Ok(ReplCommand::Quit) => return false,
+            Ok(ReplCommand::Help) => self.status = "buy|sell QTY[@PRICE], cancel ID, modify ID QTY@PRICE, quit".to_string(),
+            Ok(command) => {
+                let output = self.repl.execute(command);
+                self.status = format!("{}: {}", line.trim(), output.lines().next().unwrap_or(""));
+                self.refresh();
+            }
+            Err(err) => self.status = err.to_string(),
+        }
+        true
+    }
+
+    // Takes what the book reported since the last call
+    fn refresh(&mut self) {
+        self.touched.clear();
+        while let Ok(event) = self.events.try_recv() {
+            let price = match event {
+                Event::Order(
+                    OrderEvent::Rested { price, .. }
+                    | OrderEvent::PartiallyFilled { price, .. }
+                    | OrderEvent::Filled { price, .. },
+                ) => Some(price),
+                Event::Book(BookEvent::LevelAdded { price, .. } | BookEvent::LevelRemoved { price, .. }) => Some(price),
+                _ => None,
+            };
+            if let Some(price) = price.filter(|price| !self.touched.contains(price)) {
+                self.touched.push(price);
+            }
+            push_front(&mut self.log, describe(&event));
+        }
+        let last_seq = self.trades.front().map_or(0, |trade| trade.seq);
+        for trade in self.repl.book().trades_since(last_seq) {
+            push_front(&mut self.trades, trade.clone());
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [main, input] = Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(frame.area());
+        let [ladder, side] = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(main);
+        let [trades, events] = Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(side);
+
+        self.render_ladder(frame, ladder);
+        let trade_items = self.trades.iter().map(|trade| {
+            ListItem::new(format!("{} @ {}  #{} <- #{}", trade.quantity, trade.price, trade.maker_id, trade.taker_id))
+        });
+        frame.render_widget(List::new(trade_items).block(Block::bordered().title(" Trades ")), trades);
+        let event_items = self.log.iter().map(|line| ListItem::new(line.as_str()));
+        frame.render_widget(List::new(event_items).block(Block::bordered().title(" Events ")), events);
+
+        let prompt = format!("> {}", self.input);
+        let block = Block::bordered().title(format!(" {} ", self.status));
+        frame.render_widget(Paragraph::new(prompt.as_str()).block(block), input);
+        let cursor = input.x + 1 + prompt.chars().count() as u16;
+        frame.set_cursor_position(Position::new(cursor.min(input.right().saturating_sub(2)), input.y + 1));
+    }
+
+    // Asks above bids, highest price first, as many levels per side as fit
+    fn render_ladder(&self, frame: &mut Frame, area: Rect) {
+        let levels = usize::from(area.height.saturating_sub(3) / 2);
+        let depth = self.book().depth(levels);
+        let row = |side: Side, level: &DepthLevel| {
+            let size = format!("{} ({})", level.quantity, level.order_count);
+            let (bid, ask, color) = match side {
+                Side::Buy => (size, String::new(), Color::Green),
+                Side::Sell => (String::new(), size, Color::Red),
+            };
+            let mut style = Style::new().fg(color);
+            if self.touched.contains(&level.price) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Row::new([Cell::from(bid), Cell::from(level.price.to_string()), Cell::from(ask)]).style(style)
+        };
+        let rows: Vec<Row> = depth
+            .asks
+            .iter()
+            .rev()
+            .map(|level| row(Side::Sell, level))
+            .chain(depth.bids.iter().map(|level| row(Side::Buy, level)))
+            .collect();
+
+        let title = match self.book().spread() {
+            Some(spread) => format!(" Book (spread {}) ", spread),
+            None => " Book ".to_string(),
+        };
+        let widths = [Constraint::Fill(1), Constraint::Length(12), Constraint::Fill(1)];
+        let header = Row::new(["BID", "PRICE", "ASK"]).style(Style::new().add_modifier(Modifier::BOLD));
+        frame.render_widget(Table::new(rows, widths).header(header).block(Block::bordered().title(title)), area);
+    }
+}
+
+fn push_front<T>(history: &mut VecDeque<T>, item: T) {
+    history.push_front(item);
+    history.truncate(HISTORY);
+}
+
+fn describe(event: &Event) -> String {
+    match event {
+        Event::Order(event) => format!("{:?}", event),
+        Event::Book(event) => format!("{:?}", event),
+        Event::Session(event) => format!("{:?}", event),
+        Event::Trade(event) => format!("{:?}", event),
+    }
+}
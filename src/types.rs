@@ -0,0 +1,475 @@
+use std::fmt;
+
+use crate::error::InvariantViolation;
+use crate::order::ClientOrderId;
+use crate::units::{notional, widen, Units};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trade {
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub price: Units,
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub quantity: Units,
+    pub maker_id: u64,
+    pub taker_id: u64,
+    /// Side of the incoming order; the maker was on the other side
+    pub taker_side: Side,
+    /// Per-book trade sequence number, starting at 1
+    pub seq: u64,
+    /// Time of the trade according to the book's clock
+    pub timestamp: u64,
+    /// Fee charged to the maker under the book's fee schedule, negative for a rebate
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub maker_fee: i64,
+    /// Fee charged to the taker under the book's fee schedule, negative for a rebate
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub taker_fee: i64,
+    /// Client order id and user data of the maker and taker orders, as submitted
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub maker_client_order_id: Option<ClientOrderId>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub taker_client_order_id: Option<ClientOrderId>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub maker_user_data: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub taker_user_data: Option<u64>,
+    /// Owners of the maker and taker orders, for the positions and fee totals the trade went to
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub maker_owner: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub taker_owner: Option<u64>,
+}
+
+impl Trade {
+    /// Price times quantity: the quote amount that changed hands for `quantity`.
+    pub fn notional(&self) -> u128 {
+        notional(self.price, self.quantity)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderType {
+    Limit { price: Units },
+    /// Sweeps the opposite side at any price and never rests
+    Market,
+    /// Held off the book, then submitted as a market order once triggered
+    Stop { stop_price: Units },
+    /// Held off the book, then submitted as a limit order at `price` once triggered
+    StopLimit { stop_price: Units, price: Units },
+    /// Stop order whose stop price follows the reference price at `offset` as the market moves
+    /// in the order's favour, then submitted as a market order once triggered
+    TrailingStop { offset: TrailingOffset },
+    /// Trailing stop submitted as a limit order `limit_offset` beyond its stop price (above it
+    /// for a buy, below for a sell) once triggered
+    TrailingStopLimit { offset: TrailingOffset, limit_offset: Units },
+    /// Rests at `reference` plus `offset` (which may be negative) and follows the reference as
+    /// it moves. Pegged orders never take liquidity: their price stays at least a tick inside
+    /// the opposite best price.
+    Pegged { reference: PegReference, offset: i64 },
+}
+
+/// Price a pegged order follows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PegReference {
+    /// Best price on the order's own side
+    Primary,
+    /// Best price on the opposite side
+    Market,
+    /// Midpoint of the best bid and ask
+    Mid,
+}
+
+/// Distance a trailing stop keeps from the reference price.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrailingOffset {
+    /// A fixed amount in raw price units
+    Amount(Units),
+    /// Basis points of the reference price, rounded in to the tick
+    Bps(u64),
+}
+
+/// Reference price that stop orders are triggered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopTrigger {
+    LastTrade,
+    /// Best ask for buy stops, best bid for sell stops
+    BestPrice,
+}
+
+/// Policy for the unfilled part of a market order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MarketRemainder {
+    /// Fill what the book can and drop the rest
+    Cancel,
+    /// Don't trade at all unless the whole quantity can be filled
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeInForce {
+    /// Good till cancelled: the remainder rests on the book
+    #[default]
+    Gtc,
+    /// Immediate or cancel: fill what is possible, drop the remainder
+    Ioc,
+    /// Fill or kill: fill the whole quantity immediately or don't trade at all
+    Fok,
+}
+
+/// Policy for a post-only order that would cross the spread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PostOnlyPolicy {
+    Reject,
+    /// Reprice one tick behind the opposite best price
+    Slide,
+}
+
+/// What happens to a reduce-only order for more than the position it can close.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ReduceOnlyPolicy {
+    /// Cut the order down to the position, in whole lots
+    #[default]
+    Trim,
+    Reject,
+}
+
+/// What happens when an order would trade against a resting order with the same owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfTradePolicy {
+    /// Drop the rest of the incoming order
+    #[default]
+    CancelTaker,
+    /// Cancel the resting order and keep matching
+    CancelMaker,
+    CancelBoth,
+    /// Reduce both orders by the overlapping quantity without trading
+    Decrement,
+}
+
+/// How far past the opposite best price an aggressive order may trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BandWidth {
+    /// A number of ticks of the book's tick size
+    Ticks(u64),
+    /// Basis points of the opposite best price, rounded in to the tick
+    Bps(u64),
+}
+
+/// What happens to the part of an aggressive order the protection band stops from trading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BandRemainder {
+    /// Rest at the edge of the band; market, IOC and FOK orders drop it as usual
+    #[default]
+    Rest,
+    Cancel,
+}
+
+/// Collar on aggressive orders: on arrival an order only sweeps levels within `width` of the
+/// opposite best price, however far its own limit reaches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProtectionBand {
+    pub width: BandWidth,
+    pub remainder: BandRemainder,
+}
+
+impl ProtectionBand {
+    /// Furthest price an order on `side` may trade at when the opposite best price is `touch`.
+    pub fn limit(&self, side: Side, touch: Units, tick_size: Units) -> Units {
+        let offset = match self.width {
+            BandWidth::Ticks(ticks) => Units::from(ticks).saturating_mul(tick_size),
+            BandWidth::Bps(bps) => {
+                let offset = bps_of(touch, bps);
+                offset - offset % tick_size
+            }
+        };
+        match side {
+            Side::Buy => touch.saturating_add(offset),
+            Side::Sell => touch.saturating_sub(offset),
+        }
+    }
+}
+
+// `bps` basis points of `amount`, rounded down and saturating at `Units::MAX`
+pub(crate) fn bps_of(amount: Units, bps: u64) -> Units {
+    let value = widen(amount).saturating_mul(u128::from(bps)) / 10_000;
+    Units::try_from(value).unwrap_or(Units::MAX)
+}
+
+/// Price and quantity grid of a book. Prices are integers in units of `10^-price_scale`, so
+/// with `price_scale: 2` the raw price 12345 is 123.45; they must be multiples of `tick_size`
+/// and quantities multiples of `lot_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PriceConfig {
+    pub tick_size: Units,
+    pub price_scale: u32,
+    pub lot_size: Units,
+}
+
+impl Default for PriceConfig {
+    fn default() -> Self {
+        Self { tick_size: 1, price_scale: 0, lot_size: 1 }
+    }
+}
+
+/// What's wrong with a `PriceConfig` a book can't use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceConfigError {
+    ZeroTickSize,
+    ZeroLotSize,
+    /// `10^price_scale` doesn't fit in 64 bits
+    ScaleTooLarge,
+}
+
+impl fmt::Display for PriceConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PriceConfigError::ZeroTickSize => write!(f, "tick size is zero"),
+            PriceConfigError::ZeroLotSize => write!(f, "lot size is zero"),
+            PriceConfigError::ScaleTooLarge => write!(f, "price scale is too large"),
+        }
+    }
+}
+
+impl std::error::Error for PriceConfigError {}
+
+impl PriceConfig {
+    /// Check the grid is one a book can trade on.
+    pub fn validate(&self) -> Result<(), PriceConfigError> {
+        if self.tick_size == 0 {
+            return Err(PriceConfigError::ZeroTickSize);
+        }
+        if self.lot_size == 0 {
+            return Err(PriceConfigError::ZeroLotSize);
+        }
+        if 10u64.checked_pow(self.price_scale).is_none() {
+            return Err(PriceConfigError::ScaleTooLarge);
+        }
+        Ok(())
+    }
+
+    pub fn is_on_tick(&self, price: Units) -> bool {
+        price.is_multiple_of(self.tick_size)
+    }
+
+    pub fn is_whole_lot(&self, quantity: Units) -> bool {
+        quantity.is_multiple_of(self.lot_size)
+    }
+
+    fn unit(&self) -> Units {
+        Units::pow(10, self.price_scale)
+    }
+
+    /// Decimal form of a raw price, e.g. "123.45".
+    pub fn format_price(&self, price: Units) -> String {
+        if self.price_scale == 0 {
+            return price.to_string();
+        }
+        let unit = self.unit();
+        format!("{}.{:0width$}", price / unit, price % unit, width = self.price_scale as usize)
+    }
+
+    /// Raw price of a decimal string such as "123.45"; `None` if it isn't a plain non-negative
+    /// decimal, has more fractional digits than `price_scale` or doesn't fit.
+    pub fn parse_price(&self, decimal: &str) -> Option<Units> {
+        let (whole, fraction) = decimal.split_once('.').unwrap_or((decimal, ""));
+        let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+        if whole.is_empty() || !digits(whole) || !digits(fraction) || fraction.len() > self.price_scale as usize {
+            return None;
+        }
+        let padded = format!("{:0<width$}", fraction, width = self.price_scale as usize);
+        let fraction: Units = if padded.is_empty() { 0 } else { padded.parse().ok()? };
+        whole.parse::<Units>().ok()?.checked_mul(self.unit())?.checked_add(fraction)
+    }
+
+    pub fn price_to_f64(&self, price: Units) -> f64 {
+        price as f64 / self.unit() as f64
+    }
+}
+
+/// What became of a submitted order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderOutcome {
+    /// Traded in full
+    Filled,
+    /// The unfilled quantity rests on the book (possibly after some trades)
+    Rested,
+    /// The unfilled quantity was dropped (IOC or market order, self-trade prevention or the
+    /// protection band)
+    Cancelled,
+    /// A stop order is held until its trigger price is reached, or an order submitted during a
+    /// halt is queued until trading resumes
+    Pending,
+    /// Nothing traded and the book is unchanged
+    Rejected(RejectReason),
+}
+
+/// What a call that enters an order did, with the trades it produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionReport {
+    pub order_id: u64,
+    /// What became of the order on arrival
+    pub status: OrderOutcome,
+    /// Quantity of the order traded during the call
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub filled_quantity: Units,
+    /// Open quantity on the book if the order rests, otherwise the quantity that was dropped,
+    /// rejected or is held as a stop
+    #[cfg_attr(all(feature = "serde", feature = "wide"), serde(deserialize_with = "crate::units::deserialize_units"))]
+    pub remaining_quantity: Units,
+    /// Id the remainder rests under (the order's own), `None` if nothing rests
+    pub resting_id: Option<u64>,
+    /// Every trade of the call, including those of stop orders it triggered and of strategy
+    /// follow-up commands
+    pub trades: Vec<Trade>,
+    /// The order's own, as submitted
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub client_order_id: Option<ClientOrderId>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub user_data: Option<u64>,
+}
+
+impl ExecutionReport {
+    pub(crate) fn rejected(order_id: u64, quantity: Units, reason: RejectReason) -> Self {
+        Self {
+            order_id,
+            status: OrderOutcome::Rejected(reason),
+            filled_quantity: 0,
+            remaining_quantity: quantity,
+            resting_id: None,
+            trades: Vec::new(),
+            client_order_id: None,
+            user_data: None,
+        }
+    }
+
+    /// Traded some but not all of its quantity.
+    pub fn is_partially_filled(&self) -> bool {
+        self.filled_quantity > 0 && self.remaining_quantity > 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RejectReason {
+    ZeroQuantity,
+    /// The resting quantity would overflow its price level's total
+    LevelOverflow,
+    /// The price is too far from the last trade price
+    PriceBand,
+    /// A post-only order would have taken liquidity
+    WouldCross,
+    /// Not enough liquidity for a FOK order, an order's minimum quantity or notional, or a
+    /// market order under the reject policy
+    InsufficientLiquidity,
+    /// A price isn't a multiple of the book's tick size
+    OffTick,
+    /// The quantity or iceberg slice isn't a multiple of the book's lot size
+    OffLot,
+    /// A batched order reused the id of a resting or held stop order
+    DuplicateId,
+    /// A batched cancel or modify named an order that isn't on the book
+    UnknownOrder,
+    /// Market, IOC and FOK orders aren't accepted while an auction is collecting orders
+    AuctionInProgress,
+    /// The quantity is above the book's `RiskLimits::max_order_quantity`
+    MaxOrderSize,
+    /// The notional is above the book's `RiskLimits::max_notional`
+    MaxNotional,
+    /// The limit price is too far, in relative terms, from the last trade or mid price
+    PriceDeviation,
+    /// The owner already has as many resting orders as it may
+    OpenOrderLimit,
+    /// The owner's buying power doesn't cover the order's notional
+    InsufficientBuyingPower,
+    /// The book is halted and doesn't queue orders
+    Halted,
+    /// A trailing stop or pegged order has no reference price to follow yet
+    NoReferencePrice,
+    /// The instrument's trading hours are over, or haven't started
+    OutsideTradingHours,
+    /// A reduce-only order would open or add to its owner's position, or is for more than the
+    /// position under `ReduceOnlyPolicy::Reject`
+    ReduceOnly,
+    /// A minimum quantity was given for an iceberg order
+    MinQuantity,
+    /// A notional was given for a sell, an iceberg, or a minimum-quantity, post-only or
+    /// reduce-only order
+    NotionalOrder,
+    /// A modify would move or grow a pegged order, whose price follows its reference
+    Pegged,
+    /// A batched command ran into an `InvariantViolation`; the book needs rebuilding
+    Internal,
+}
+
+/// Why an order couldn't be entered at all. Unlike a `RejectReason`, these are mistakes by the
+/// caller, or a fault in the book, rather than market conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceError {
+    /// A resting or held stop order already has this id
+    DuplicateId(u64),
+    Invariant(InvariantViolation),
+}
+
+impl fmt::Display for PlaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaceError::DuplicateId(id) => write!(f, "order id {} is already in use", id),
+            PlaceError::Invariant(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for PlaceError {}
+
+impl From<InvariantViolation> for PlaceError {
+    fn from(err: InvariantViolation) -> Self {
+        PlaceError::Invariant(err)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CancelError {
+    /// No resting order with this id
+    UnknownOrder(u64),
+    Invariant(InvariantViolation),
+}
+
+impl fmt::Display for CancelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CancelError::UnknownOrder(id) => write!(f, "no resting order with id {}", id),
+            CancelError::Invariant(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CancelError {}
+
+impl From<InvariantViolation> for CancelError {
+    fn from(err: InvariantViolation) -> Self {
+        CancelError::Invariant(err)
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(all(feature = "serde", feature = "wide"))]
+use std::fmt;
+
+/// Integer type of raw prices and quantities: `u64`, or `u128` with the `wide` feature for
+/// assets whose scaled amounts don't fit in 64 bits, such as tokens with 18 decimals.
+#[cfg(not(feature = "wide"))]
+pub type Units = u64;
+#[cfg(feature = "wide")]
+pub type Units = u128;
+
+// An addition to a level's totals left the range of `Units`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OverflowError;
+
+/// `price` times `quantity` as a `u128`, saturating (which only the `wide` feature can reach).
+pub(crate) fn notional(price: Units, quantity: Units) -> u128 {
+    widen(price).saturating_mul(widen(quantity))
+}
+
+/// A price or quantity as a `u128`, whatever the width of `Units`.
+#[cfg_attr(feature = "wide", allow(clippy::useless_conversion))]
+pub(crate) fn widen(value: Units) -> u128 {
+    u128::from(value)
+}
+
+/// Deserialize a `Units` field that may sit inside an internally tagged enum, such as the
+/// WebSocket messages. Serde buffers such fields in a form without 128-bit integers, so
+/// self-describing formats are asked for whatever integer they hold instead of a `u128`.
+#[cfg(all(feature = "serde", feature = "wide"))]
+pub(crate) fn deserialize_units<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Units, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_any(UnitsVisitor)
+    } else {
+        deserializer.deserialize_u128(UnitsVisitor)
+    }
+}
+
+/// `deserialize_units` for optional fields; pair it with `serde(default)`.
+#[cfg(all(feature = "serde", feature = "wide"))]
+pub(crate) fn deserialize_optional_units<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Units>, D::Error> {
+    deserializer.deserialize_option(OptionalUnitsVisitor)
+}
+
+#[cfg(all(feature = "serde", feature = "wide"))]
+struct UnitsVisitor;
+
+#[cfg(all(feature = "serde", feature = "wide"))]
+impl serde::de::Visitor<'_> for UnitsVisitor {
+    type Value = Units;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an unsigned integer")
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Units, E> {
+        Ok(Units::from(value))
+    }
+
+    fn visit_u128<E: serde::de::Error>(self, value: u128) -> Result<Units, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Units, E> {
+        Units::try_from(value).map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(value), &self))
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "wide"))]
+struct OptionalUnitsVisitor;
+
+#[cfg(all(feature = "serde", feature = "wide"))]
+impl<'de> serde::de::Visitor<'de> for OptionalUnitsVisitor {
+    type Value = Option<Units>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an unsigned integer or null")
+    }
+
+    fn visit_none<E: serde::de::Error>(self) -> Result<Option<Units>, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: serde::de::Error>(self) -> Result<Option<Units>, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Option<Units>, D::Error> {
+        deserialize_units(deserializer).map(Some)
+    }
+}
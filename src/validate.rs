@@ -0,0 +1,111 @@
+use crate::book::OrderBook;
+use crate::error::InvariantViolation;
+use crate::order::Order;
+use crate::types::Side;
+use crate::units::Units;
+
+impl OrderBook {
+    /// Check the book's internal consistency and return every violation found: price levels
+    /// and their queues agree with the order and stop indexes, level totals match their orders,
+    /// queues are in priority order, no resting order or held stop is empty, no id is used twice,
+    /// and the book isn't crossed outside an auction. Nothing is changed.
+    ///
+    /// It walks the whole book, so it's meant for debug builds and tests, or for checking a book
+    /// rebuilt by `replay` or `restore_checkpoint` before trading on it.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+        let mut fail = |what: &'static str| violations.push(InvariantViolation(what));
+
+        let mut resting = 0;
+        for side in [Side::Buy, Side::Sell] {
+            for (&price, level) in self.levels(side) {
+                if level.is_empty() {
+                    fail("empty price level left in the book");
+                    continue;
+                }
+                let keys = match level.checked_keys(&self.slab) {
+                    Ok(keys) => keys,
+                    Err(violation) => {
+                        fail(violation.0);
+                        continue;
+                    }
+                };
+                resting += keys.len();
+                let (mut visible, mut hidden): (Units, Units) = (0, 0);
+                let mut last: Option<&Order> = None;
+                for key in keys {
+                    let Some(node) = self.slab.get(key) else {
+                        fail("level queue links to a vacant slot");
+                        continue;
+                    };
+                    let order = &node.order;
+                    visible = visible.saturating_add(order.quantity);
+                    hidden = hidden.saturating_add(order.hidden_quantity);
+                    if order.price != price {
+                        fail("resting order is queued at another price");
+                    }
+                    if order.quantity == 0 {
+                        fail("resting order has no visible quantity");
+                    }
+                    if last.is_some_and(|last| !self.priority_policy.in_order(last, order)) {
+                        fail("level queue is out of priority order");
+                    }
+                    last = Some(order);
+                    if self.order_index.get(&order.id) != Some(&(side, price, key)) {
+                        fail("order index entry doesn't match the resting order");
+                    }
+                    if self.stop_index.contains_key(&order.id) || self.halt.is_queued(order.id) {
+                        fail("resting order's id is also held as a stop or queued");
+                    }
+                }
+                if (visible, hidden) != (level.total_quantity(), level.hidden_quantity()) {
+                    fail("level totals don't match its orders");
+                }
+            }
+        }
+        if resting != self.order_index.len() {
+            fail("order index holds orders that aren't resting");
+        }
+
+        let mut held = 0;
+        for (side, stops) in [(Side::Buy, &self.buy_stops), (Side::Sell, &self.sell_stops)] {
+            for (&stop_price, orders) in stops {
+                if orders.is_empty() {
+                    fail("empty stop trigger level left in the book");
+                }
+                held += orders.len();
+                for order in orders {
+                    if order.side != side {
+                        fail("stop is held on the wrong side");
+                    }
+                    if order.quantity == 0 {
+                        fail("held stop has no quantity");
+                    }
+                    if self.stop_index.get(&order.id) != Some(&(side, stop_price)) {
+                        fail("stop index entry doesn't match the held stop");
+                    }
+                }
+            }
+        }
+        if held != self.stop_index.len() {
+            fail("stop index holds stops that aren't held");
+        }
+        if self.trailing_stops.keys().any(|id| !self.stop_index.contains_key(id)) {
+            fail("trailing stop isn't held");
+        }
+
+        if !self.auction {
+            if let (Some((bid, _)), Some((ask, _))) = (self.best_buy(), self.best_sell()) {
+                if bid >= ask {
+                    fail("book is crossed outside an auction");
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
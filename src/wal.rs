@@ -0,0 +1,561 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::book::OrderBook;
+use crate::error::{InvariantViolation, OrderBookError};
+use crate::oco::OcoMode;
+use crate::order::{ClientOrderId, NewOrder};
+use crate::types::{OrderType, PegReference, Side, TimeInForce, TrailingOffset};
+use crate::units::Units;
+
+/// One state-changing call on a book.
+// Most entries are submits, so boxing them would only add an allocation apiece
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogEntry {
+    Submit(NewOrder),
+    Cancel { id: u64 },
+    CancelStop { id: u64 },
+    Modify { id: u64, price: Units, quantity: Units },
+    StartAuction,
+    RunAuction,
+    Expire { now: u64 },
+    Halt,
+    Resume,
+    CancelQueued { id: u64 },
+    LinkOco { first: u64, second: u64, mode: OcoMode },
+    UnlinkOco { id: u64 },
+    BustTrade { seq: u64 },
+    CorrectTrade { seq: u64, price: Units, quantity: Units },
+}
+
+/// Append-only record of the calls made on a book, in the order they were made, each with the
+/// clock readings the book took carrying it out.
+///
+/// Written out as one line per entry, so a file can be appended to as the book runs and read
+/// back after a crash.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventLog {
+    entries: Vec<LogEntry>,
+    // Clock readings by entry, in the order they were taken
+    times: Vec<Vec<u64>>,
+}
+
+#[derive(Debug)]
+pub enum LogError {
+    Io(io::Error),
+    /// Line `line` (1-based) isn't a valid entry
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogError::Io(err) => write!(f, "log i/o error: {}", err),
+            LogError::Parse { line, message } => write!(f, "log line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for LogError {}
+
+impl From<io::Error> for LogError {
+    fn from(err: io::Error) -> Self {
+        LogError::Io(err)
+    }
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn append(&mut self, entry: LogEntry) {
+        self.entries.push(entry);
+        self.times.push(Vec::new());
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Clock readings taken carrying out each entry, index for index with `entries`.
+    pub fn times(&self) -> &[Vec<u64>] {
+        &self.times
+    }
+
+    // Add clock readings to the last entry
+    pub(crate) fn record_times(&mut self, times: impl IntoIterator<Item = u64>) {
+        if let Some(last) = self.times.last_mut() {
+            last.extend(times);
+        }
+    }
+
+    // Drop the entries from `len` on, returning the clock readings taken carrying them out
+    pub(crate) fn unwind(&mut self, len: usize) -> Vec<u64> {
+        self.entries.truncate(len);
+        self.times.drain(len.min(self.times.len())..).flatten().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write one line per entry, followed by ` @` and its clock readings if it has any.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (entry, times) in self.entries.iter().zip(&self.times) {
+            write!(writer, "{}", entry)?;
+            if !times.is_empty() {
+                write!(writer, " @")?;
+                for time in times {
+                    write!(writer, " {}", time)?;
+                }
+            }
+            writeln!(writer)?;
+        }
+        writer.flush()
+    }
+
+    /// Read a log written by `write_to`; blank lines are skipped.
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Self, LogError> {
+        let mut log = Self::new();
+        for (n, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (entry, times) = line.split_once(" @ ").unwrap_or((&line, ""));
+            let parse = || {
+                let times = times.split_whitespace().map(|time| time.parse().map_err(|_| format!("invalid time {:?}", time)));
+                Ok::<_, String>((parse_entry(entry)?, times.collect::<Result<Vec<u64>, _>>()?))
+            };
+            let (entry, times) = parse().map_err(|message| LogError::Parse { line: n + 1, message })?;
+            log.append(entry);
+            log.record_times(times);
+        }
+        Ok(log)
+    }
+}
+
+// Format: `submit <id> <buy|sell> <quantity> <gtc|ioc|fok> <post_only 0|1> <display|-> <owner|->
+// <limit P | market | stop S | stop_limit S P> [gtd <expires_at>] [cl_ord_id <n:N | s:text>]
+// [user_data <N>] [reduce_only] [min_qty <N>] [notional <N>]`, with the text id percent-encoded
+// so it stays one field, `cancel <id>`, `cancel_stop <id>`, `modify <id> <price> <quantity>`, `start_auction`,
+// `run_auction`, `expire <now>`, `halt`, `resume`, `cancel_queued <id>`
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn optional(value: Option<impl fmt::Display>) -> String {
+            value.map_or_else(|| "-".to_string(), |v| v.to_string())
+        }
+
+        match self {
+            LogEntry::Submit(order) => {
+                let side = match order.side {
+                    Side::Buy => "buy",
+                    Side::Sell => "sell",
+                };
+                let time_in_force = match order.time_in_force {
+                    TimeInForce::Gtc => "gtc",
+                    TimeInForce::Ioc => "ioc",
+                    TimeInForce::Fok => "fok",
+                };
+                write!(
+                    f,
+                    "submit {} {} {} {} {} {} {} ",
+                    order.id,
+                    side,
+                    order.quantity,
+                    time_in_force,
+                    order.post_only as u8,
+                    optional(order.display_quantity),
+                    optional(order.owner)
+                )?;
+                match order.order_type {
+                    OrderType::Limit { price } => write!(f, "limit {}", price)?,
+                    OrderType::Market => write!(f, "market")?,
+                    OrderType::Stop { stop_price } => write!(f, "stop {}", stop_price)?,
+                    OrderType::StopLimit { stop_price, price } => write!(f, "stop_limit {} {}", stop_price, price)?,
+                    OrderType::Pegged { reference, offset } => {
+                        let reference = match reference {
+                            PegReference::Primary => "primary",
+                            PegReference::Market => "market",
+                            PegReference::Mid => "mid",
+                        };
+                        write!(f, "pegged {} {}", reference, offset)?
+                    }
+                    OrderType::TrailingStop { offset } => write!(f, "trailing_stop {}", TrailingField(offset))?,
+                    OrderType::TrailingStopLimit { offset, limit_offset } => {
+                        write!(f, "trailing_stop_limit {} {}", TrailingField(offset), limit_offset)?
+                    }
+                }
+                if let Some(expires_at) = order.expires_at {
+                    write!(f, " gtd {}", expires_at)?;
+                }
+                if let Some(id) = &order.client_order_id {
+                    write!(f, " cl_ord_id {}", ClientOrderIdField(id))?;
+                }
+                if let Some(user_data) = order.user_data {
+                    write!(f, " user_data {}", user_data)?;
+                }
+                if order.reduce_only {
+                    write!(f, " reduce_only")?;
+                }
+                if let Some(min_quantity) = order.min_quantity {
+                    write!(f, " min_qty {}", min_quantity)?;
+                }
+                if let Some(notional) = order.notional {
+                    write!(f, " notional {}", notional)?;
+                }
+                Ok(())
+            }
+            LogEntry::Cancel { id } => write!(f, "cancel {}", id),
+            LogEntry::CancelStop { id } => write!(f, "cancel_stop {}", id),
+            LogEntry::Modify { id, price, quantity } => write!(f, "modify {} {} {}", id, price, quantity),
+            LogEntry::StartAuction => write!(f, "start_auction"),
+            LogEntry::RunAuction => write!(f, "run_auction"),
+            LogEntry::Expire { now } => write!(f, "expire {}", now),
+            LogEntry::Halt => write!(f, "halt"),
+            LogEntry::Resume => write!(f, "resume"),
+            LogEntry::CancelQueued { id } => write!(f, "cancel_queued {}", id),
+            LogEntry::LinkOco { first, second, mode } => {
+                let mode = match mode {
+                    OcoMode::Cancel => "cancel",
+                    OcoMode::Reduce => "reduce",
+                };
+                write!(f, "link_oco {} {} {}", first, second, mode)
+            }
+            LogEntry::UnlinkOco { id } => write!(f, "unlink_oco {}", id),
+            LogEntry::BustTrade { seq } => write!(f, "bust_trade {}", seq),
+            LogEntry::CorrectTrade { seq, price, quantity } => write!(f, "correct_trade {} {} {}", seq, price, quantity),
+        }
+    }
+}
+
+// "amount N" or "bps N"
+struct TrailingField(TrailingOffset);
+
+impl fmt::Display for TrailingField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            TrailingOffset::Amount(amount) => write!(f, "amount {}", amount),
+            TrailingOffset::Bps(bps) => write!(f, "bps {}", bps),
+        }
+    }
+}
+
+// "n:N" or "s:text", with whitespace, `%` and anything unprintable in the text as %XX
+struct ClientOrderIdField<'a>(&'a ClientOrderId);
+
+impl fmt::Display for ClientOrderIdField<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            ClientOrderId::Number(number) => write!(f, "n:{}", number),
+            ClientOrderId::Text(text) => {
+                f.write_str("s:")?;
+                for &byte in text.as_bytes() {
+                    if byte.is_ascii_graphic() && byte != b'%' {
+                        write!(f, "{}", byte as char)?;
+                    } else {
+                        write!(f, "%{:02X}", byte)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn parse_client_order_id(field: &str) -> Result<ClientOrderId, String> {
+    let invalid = || format!("invalid client order id {:?}", field);
+    if let Some(number) = field.strip_prefix("n:") {
+        return number.parse().map(ClientOrderId::Number).map_err(|_| invalid());
+    }
+    let encoded = field.strip_prefix("s:").ok_or_else(invalid)?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        if encoded[i] == b'%' {
+            let hex = encoded.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok()).ok_or_else(invalid)?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            i += 3;
+        } else {
+            bytes.push(encoded[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(bytes).map(ClientOrderId::Text).map_err(|_| invalid())
+}
+
+fn parse_entry(line: &str) -> Result<LogEntry, String> {
+    let mut fields = line.split_whitespace();
+    fn next<'a>(fields: &mut impl Iterator<Item = &'a str>, name: &str) -> Result<&'a str, String> {
+        fields.next().ok_or_else(|| format!("missing {}", name))
+    }
+    fn number<T: FromStr>(field: &str) -> Result<T, String> {
+        field.parse().map_err(|_| format!("invalid number {:?}", field))
+    }
+    fn optional<T: FromStr>(field: &str) -> Result<Option<T>, String> {
+        if field == "-" {
+            Ok(None)
+        } else {
+            number(field).map(Some)
+        }
+    }
+    fn trailing_offset<'a>(fields: &mut impl Iterator<Item = &'a str>) -> Result<TrailingOffset, String> {
+        let kind = next(fields, "trailing offset kind")?;
+        let value = next(fields, "trailing offset")?;
+        match kind {
+            "amount" => Ok(TrailingOffset::Amount(number(value)?)),
+            "bps" => Ok(TrailingOffset::Bps(number(value)?)),
+            other => Err(format!("invalid trailing offset kind {:?}", other)),
+        }
+    }
+
+    let entry = match next(&mut fields, "entry kind")? {
+        "submit" => {
+            let id = number(next(&mut fields, "id")?)?;
+            let side = match next(&mut fields, "side")? {
+                "buy" => Side::Buy,
+                "sell" => Side::Sell,
+                other => return Err(format!("invalid side {:?}", other)),
+            };
+            let quantity = number(next(&mut fields, "quantity")?)?;
+            let time_in_force = match next(&mut fields, "time in force")? {
+                "gtc" => TimeInForce::Gtc,
+                "ioc" => TimeInForce::Ioc,
+                "fok" => TimeInForce::Fok,
+                other => return Err(format!("invalid time in force {:?}", other)),
+            };
+            let post_only = match next(&mut fields, "post-only flag")? {
+                "0" => false,
+                "1" => true,
+                other => return Err(format!("invalid post-only flag {:?}", other)),
+            };
+            let display_quantity = optional(next(&mut fields, "display quantity")?)?;
+            let owner = optional(next(&mut fields, "owner")?)?;
+            let order_type = match next(&mut fields, "order type")? {
+                "limit" => OrderType::Limit { price: number(next(&mut fields, "price")?)? },
+                "market" => OrderType::Market,
+                "stop" => OrderType::Stop { stop_price: number(next(&mut fields, "stop price")?)? },
+                "stop_limit" => OrderType::StopLimit {
+                    stop_price: number(next(&mut fields, "stop price")?)?,
+                    price: number(next(&mut fields, "price")?)?,
+                },
+                "pegged" => OrderType::Pegged {
+                    reference: match next(&mut fields, "peg reference")? {
+                        "primary" => PegReference::Primary,
+                        "market" => PegReference::Market,
+                        "mid" => PegReference::Mid,
+                        other => return Err(format!("invalid peg reference {:?}", other)),
+                    },
+                    offset: {
+                        let field = next(&mut fields, "peg offset")?;
+                        field.parse().map_err(|_| format!("invalid offset {:?}", field))?
+                    },
+                },
+                "trailing_stop" => OrderType::TrailingStop { offset: trailing_offset(&mut fields)? },
+                "trailing_stop_limit" => OrderType::TrailingStopLimit {
+                    offset: trailing_offset(&mut fields)?,
+                    limit_offset: number(next(&mut fields, "limit offset")?)?,
+                },
+                other => return Err(format!("invalid order type {:?}", other)),
+            };
+            let (mut expires_at, mut client_order_id, mut user_data, mut reduce_only) = (None, None, None, false);
+            let (mut min_quantity, mut notional) = (None, None);
+            while let Some(field) = fields.next() {
+                match field {
+                    "gtd" if expires_at.is_none() => expires_at = Some(number(next(&mut fields, "expiry")?)?),
+                    "cl_ord_id" if client_order_id.is_none() => {
+                        client_order_id = Some(parse_client_order_id(next(&mut fields, "client order id")?)?)
+                    }
+                    "user_data" if user_data.is_none() => user_data = Some(number(next(&mut fields, "user data")?)?),
+                    "reduce_only" if !reduce_only => reduce_only = true,
+                    "min_qty" if min_quantity.is_none() => {
+                        min_quantity = Some(number(next(&mut fields, "minimum quantity")?)?)
+                    }
+                    "notional" if notional.is_none() => notional = Some(number(next(&mut fields, "notional")?)?),
+                    other => return Err(format!("unexpected field {:?}", other)),
+                }
+            }
+            LogEntry::Submit(NewOrder {
+                id,
+                side,
+                order_type,
+                quantity,
+                time_in_force,
+                post_only,
+                display_quantity,
+                owner,
+                expires_at,
+                reduce_only,
+                min_quantity,
+                notional,
+                client_order_id,
+                user_data,
+            })
+        }
+        "cancel" => LogEntry::Cancel { id: number(next(&mut fields, "id")?)? },
+        "cancel_stop" => LogEntry::CancelStop { id: number(next(&mut fields, "id")?)? },
+        "modify" => LogEntry::Modify {
+            id: number(next(&mut fields, "id")?)?,
+            price: number(next(&mut fields, "price")?)?,
+            quantity: number(next(&mut fields, "quantity")?)?,
+        },
+        "start_auction" => LogEntry::StartAuction,
+        "run_auction" => LogEntry::RunAuction,
+        "expire" => LogEntry::Expire { now: number(next(&mut fields, "time")?)? },
+        "halt" => LogEntry::Halt,
+        "resume" => LogEntry::Resume,
+        "cancel_queued" => LogEntry::CancelQueued { id: number(next(&mut fields, "id")?)? },
+        "link_oco" => LogEntry::LinkOco {
+            first: number(next(&mut fields, "first id")?)?,
+            second: number(next(&mut fields, "second id")?)?,
+            mode: match next(&mut fields, "mode")? {
+                "cancel" => OcoMode::Cancel,
+                "reduce" => OcoMode::Reduce,
+                other => return Err(format!("invalid link mode {:?}", other)),
+            },
+        },
+        "unlink_oco" => LogEntry::UnlinkOco { id: number(next(&mut fields, "id")?)? },
+        "bust_trade" => LogEntry::BustTrade { seq: number(next(&mut fields, "seq")?)? },
+        "correct_trade" => LogEntry::CorrectTrade {
+            seq: number(next(&mut fields, "seq")?)?,
+            price: number(next(&mut fields, "price")?)?,
+            quantity: number(next(&mut fields, "quantity")?)?,
+        },
+        other => return Err(format!("unknown entry kind {:?}", other)),
+    };
+    if fields.next().is_some() {
+        return Err("trailing fields".to_string());
+    }
+    Ok(entry)
+}
+
+impl OrderBook {
+    /// Start recording every submit, cancel, modify, auction, expiry, halt, resume, link, bust
+    /// and correction call into an event log.
+    pub fn enable_log(&mut self) {
+        self.log.get_or_insert_with(EventLog::new);
+    }
+
+    pub fn log(&self) -> Option<&EventLog> {
+        self.log.as_ref()
+    }
+
+    /// Hand over the recorded log; recording stops until `enable_log` is called again.
+    pub fn take_log(&mut self) -> Option<EventLog> {
+        self.log.take()
+    }
+
+    /// Rebuild a book by replaying `log` into a new book. Timestamps, trade sequence numbers and
+    /// queue order come out identical to the book that recorded it, provided that book started
+    /// empty with the default configuration; the clock readings come from the log, whatever
+    /// clock took them.
+    pub fn replay(log: &EventLog) -> Result<Self, OrderBookError> {
+        let mut ob = Self::new();
+        ob.apply_log(log)?;
+        Ok(ob)
+    }
+
+    /// Replay `log` into this book, e.g. one configured like the book that recorded it, reading
+    /// the clock readings recorded with each entry in place of this book's clock. Entries that
+    /// fail, which only happens if this book has diverged from the recording one, are skipped;
+    /// an invariant violation stops the replay.
+    pub fn apply_log(&mut self, log: &EventLog) -> Result<(), OrderBookError> {
+        Ok(self.apply_entries(log.entries(), log.times())?)
+    }
+
+    // Replay `entries`, each with the clock readings in `times` at its index, if any
+    pub(crate) fn apply_entries(&mut self, entries: &[LogEntry], times: &[Vec<u64>]) -> Result<(), InvariantViolation> {
+        for (n, entry) in entries.iter().enumerate() {
+            self.pending_times = times.get(n).cloned().unwrap_or_default().into();
+            let result = match entry {
+                LogEntry::Submit(order) => self.submit(order.clone()).map(drop).map_err(OrderBookError::from),
+                LogEntry::Cancel { id } => self.cancel_order(*id).map(drop).map_err(OrderBookError::from),
+                LogEntry::CancelStop { id } => self.cancel_stop(*id).map(drop).map_err(OrderBookError::from),
+                LogEntry::Modify { id, price, quantity } => {
+                    self.modify_order(*id, *price, *quantity).map(drop).map_err(OrderBookError::from)
+                }
+                LogEntry::StartAuction => {
+                    self.start_auction();
+                    Ok(())
+                }
+                LogEntry::RunAuction => self.run_auction().map(drop),
+                LogEntry::Expire { now } => self.expire(*now).map(drop),
+                LogEntry::Halt => {
+                    self.halt();
+                    Ok(())
+                }
+                LogEntry::Resume => self.resume().map(drop),
+                LogEntry::CancelQueued { id } => self.cancel_queued(*id).map(drop).map_err(OrderBookError::from),
+                LogEntry::LinkOco { first, second, mode } => {
+                    self.link_oco(*first, *second, *mode).map_err(OrderBookError::from)
+                }
+                LogEntry::UnlinkOco { id } => {
+                    self.unlink_oco(*id);
+                    Ok(())
+                }
+                LogEntry::BustTrade { seq } => self.bust_trade(*seq).map(drop).map_err(OrderBookError::from),
+                LogEntry::CorrectTrade { seq, price, quantity } => {
+                    self.correct_trade(*seq, *price, *quantity).map(drop).map_err(OrderBookError::from)
+                }
+            };
+            self.pending_times.clear();
+            if let Err(OrderBookError::Invariant(err)) = result {
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    // A clock reading for the call being carried out, recorded with its log entry: while
+    // replaying, the next one recorded with the entry replayed, then the clock's
+    pub(crate) fn now(&mut self) -> u64 {
+        let now = self.pending_times.pop_front().unwrap_or_else(|| self.clock.now());
+        self.last_time = self.last_time.max(now);
+        if let Some(log) = &mut self.log {
+            log.record_times([now]);
+        }
+        now
+    }
+
+    // The reading the next call to `now` takes, without using it up; for a check made ahead of
+    // the call, like an exchange's trading hours
+    pub(crate) fn peek_time(&mut self) -> u64 {
+        let now = self.pending_times.pop_front().unwrap_or_else(|| self.read_clock());
+        self.pending_times.push_front(now);
+        now
+    }
+
+    // A reading straight from the clock, for calls that don't log the times they take
+    pub(crate) fn read_clock(&mut self) -> u64 {
+        let now = self.clock.now();
+        self.last_time = self.last_time.max(now);
+        now
+    }
+
+    // Carry out `f` without logging the calls it makes, returning the clock readings they took
+    // for the caller to log with its own entry
+    pub(crate) fn unlogged<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> (T, Vec<u64>) {
+        let len = self.log.as_ref().map(EventLog::len);
+        let result = f(self);
+        let times = match (&mut self.log, len) {
+            (Some(log), Some(len)) => log.unwind(len),
+            _ => Vec::new(),
+        };
+        (result, times)
+    }
+
+    // Add clock readings to the entry logged last
+    pub(crate) fn log_times(&mut self, times: Vec<u64>) {
+        if let Some(log) = &mut self.log {
+            log.record_times(times);
+        }
+    }
+}
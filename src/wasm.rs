@@ -0,0 +1,261 @@
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+use crate::book::{DepthLevel, OrderBook};
+use crate::types::{ExecutionReport, OrderOutcome, Side, Trade};
+use crate::units::{widen, Units};
+
+// Number.MAX_SAFE_INTEGER, the largest whole number a JS number holds exactly
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+// Trades kept for `OrderBook.trades` unless the constructor says otherwise
+const TRADE_HISTORY: usize = 1000;
+
+/// `OrderBook` as a JavaScript class, for running the book in a browser or Node. Sides are
+/// given as "buy" or "sell"; prices, quantities and ids are numbers, which must be whole and
+/// at most `Number.MAX_SAFE_INTEGER`. Ids left out are taken from the book's own counter, and
+/// timestamps come from its deterministic clock, so a backtest replays identically.
+#[wasm_bindgen(js_name = OrderBook)]
+pub struct WasmOrderBook {
+    book: OrderBook,
+    on_trade: Option<Function>,
+}
+
+/// A trade as seen from JavaScript.
+#[wasm_bindgen(js_name = Trade)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WasmTrade {
+    #[wasm_bindgen(readonly)]
+    pub price: f64,
+    #[wasm_bindgen(readonly)]
+    pub quantity: f64,
+    #[wasm_bindgen(readonly, js_name = makerId)]
+    pub maker_id: f64,
+    #[wasm_bindgen(readonly, js_name = takerId)]
+    pub taker_id: f64,
+    #[wasm_bindgen(readonly)]
+    pub seq: f64,
+    #[wasm_bindgen(readonly)]
+    pub timestamp: f64,
+}
+
+/// An execution report as seen from JavaScript. `status` is "filled", "rested", "cancelled",
+/// "pending" or "rejected", with the reason in `rejectReason`.
+#[wasm_bindgen(js_name = ExecutionReport, getter_with_clone)]
+#[derive(Debug, Clone)]
+pub struct WasmExecutionReport {
+    #[wasm_bindgen(readonly, js_name = orderId)]
+    pub order_id: f64,
+    #[wasm_bindgen(readonly)]
+    pub status: String,
+    #[wasm_bindgen(readonly, js_name = rejectReason)]
+    pub reject_reason: Option<String>,
+    #[wasm_bindgen(readonly, js_name = filledQuantity)]
+    pub filled_quantity: f64,
+    #[wasm_bindgen(readonly, js_name = remainingQuantity)]
+    pub remaining_quantity: f64,
+    #[wasm_bindgen(readonly, js_name = restingId)]
+    pub resting_id: Option<f64>,
+    #[wasm_bindgen(readonly)]
+    pub trades: Vec<WasmTrade>,
+}
+
+/// A price level as seen from JavaScript: visible quantity and number of orders.
+#[wasm_bindgen(js_name = DepthLevel)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WasmDepthLevel {
+    #[wasm_bindgen(readonly)]
+    pub price: f64,
+    #[wasm_bindgen(readonly)]
+    pub quantity: f64,
+    #[wasm_bindgen(readonly)]
+    pub orders: usize,
+}
+
+/// The top of each side from `OrderBook.depth`, best price first.
+#[wasm_bindgen(js_name = Depth, getter_with_clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct WasmDepth {
+    #[wasm_bindgen(readonly)]
+    pub bids: Vec<WasmDepthLevel>,
+    #[wasm_bindgen(readonly)]
+    pub asks: Vec<WasmDepthLevel>,
+}
+
+impl From<&Trade> for WasmTrade {
+    fn from(trade: &Trade) -> Self {
+        Self {
+            price: number(trade.price),
+            quantity: number(trade.quantity),
+            maker_id: trade.maker_id as f64,
+            taker_id: trade.taker_id as f64,
+            seq: trade.seq as f64,
+            timestamp: trade.timestamp as f64,
+        }
+    }
+}
+
+impl From<&ExecutionReport> for WasmExecutionReport {
+    fn from(report: &ExecutionReport) -> Self {
+        let (status, reject_reason) = match report.status {
+            OrderOutcome::Filled => ("filled", None),
+            OrderOutcome::Rested => ("rested", None),
+            OrderOutcome::Cancelled => ("cancelled", None),
+            OrderOutcome::Pending => ("pending", None),
+            OrderOutcome::Rejected(reason) => ("rejected", Some(format!("{:?}", reason))),
+        };
+        Self {
+            order_id: report.order_id as f64,
+            status: status.to_string(),
+            reject_reason,
+            filled_quantity: number(report.filled_quantity),
+            remaining_quantity: number(report.remaining_quantity),
+            resting_id: report.resting_id.map(|id| id as f64),
+            trades: report.trades.iter().map(WasmTrade::from).collect(),
+        }
+    }
+}
+
+impl From<&DepthLevel> for WasmDepthLevel {
+    fn from(level: &DepthLevel) -> Self {
+        Self { price: number(level.price), quantity: number(level.quantity), orders: level.order_count }
+    }
+}
+
+#[wasm_bindgen(js_class = OrderBook)]
+impl WasmOrderBook {
+    /// An empty book keeping the last `tradeHistory` trades (1000 by default) for `trades`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(trade_history: Option<usize>) -> Self {
+        let mut book = OrderBook::new();
+        book.enable_trade_history(trade_history.unwrap_or(TRADE_HISTORY));
+        Self { book, on_trade: None }
+    }
+
+    /// Enter a limit order, returning its execution report.
+    pub fn place(&mut self, side: &str, price: f64, quantity: f64, id: Option<f64>) -> Result<WasmExecutionReport, JsValue> {
+        let (side, price, quantity) = (parse_side(side)?, units(price)?, units(quantity)?);
+        let id = self.order_id(id)?;
+        let report = self.book.place_order(side, price, quantity, id).map_err(js_error)?;
+        self.stream(&report)
+    }
+
+    /// Enter a market order, returning its execution report.
+    #[wasm_bindgen(js_name = placeMarket)]
+    pub fn place_market(&mut self, side: &str, quantity: f64, id: Option<f64>) -> Result<WasmExecutionReport, JsValue> {
+        let (side, quantity) = (parse_side(side)?, units(quantity)?);
+        let id = self.order_id(id)?;
+        let report = self.book.place_market_order(side, quantity, id).map_err(js_error)?;
+        self.stream(&report)
+    }
+
+    /// Cancel a resting order, returning its unfilled quantity; throws if it isn't resting.
+    pub fn cancel(&mut self, id: f64) -> Result<f64, JsValue> {
+        let order = self.book.cancel_order(whole(id)?).map_err(js_error)?;
+        Ok(number(order.remaining_quantity()))
+    }
+
+    /// Amend a resting order; see `OrderBook::modify_order`.
+    pub fn modify(&mut self, id: f64, price: f64, quantity: f64) -> Result<WasmExecutionReport, JsValue> {
+        let report = self.book.modify_order(whole(id)?, units(price)?, units(quantity)?).map_err(js_error)?;
+        self.stream(&report)
+    }
+
+    /// Call `callback` with each trade as it happens, before the call that caused it returns;
+    /// `undefined` stops it. An exception from the callback is rethrown by that call, after
+    /// the order has been processed.
+    #[wasm_bindgen(js_name = onTrade)]
+    pub fn on_trade(&mut self, callback: Option<Function>) {
+        self.on_trade = callback;
+    }
+
+    /// The best bid level, or `undefined`.
+    #[wasm_bindgen(js_name = bestBid)]
+    pub fn best_bid(&self) -> Option<WasmDepthLevel> {
+        self.book.depth(1).bids.first().map(WasmDepthLevel::from)
+    }
+
+    #[wasm_bindgen(js_name = bestAsk)]
+    pub fn best_ask(&self) -> Option<WasmDepthLevel> {
+        self.book.depth(1).asks.first().map(WasmDepthLevel::from)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        self.book.spread().map(number)
+    }
+
+    #[wasm_bindgen(js_name = midPrice)]
+    pub fn mid_price(&self) -> Option<f64> {
+        self.book.mid_price()
+    }
+
+    /// The top `levels` (10 by default) of each side.
+    pub fn depth(&self, levels: Option<usize>) -> WasmDepth {
+        let depth = self.book.depth(levels.unwrap_or(10));
+        WasmDepth {
+            bids: depth.bids.iter().map(WasmDepthLevel::from).collect(),
+            asks: depth.asks.iter().map(WasmDepthLevel::from).collect(),
+        }
+    }
+
+    /// Trades with a sequence number above `since` from the kept history, oldest first.
+    pub fn trades(&self, since: Option<f64>) -> Result<Vec<WasmTrade>, JsValue> {
+        let since = whole(since.unwrap_or(0.0))?;
+        Ok(self.book.trades_since(since).map(WasmTrade::from).collect())
+    }
+
+    /// Number of resting orders.
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.book.order_count()
+    }
+}
+
+impl WasmOrderBook {
+    fn order_id(&mut self, id: Option<f64>) -> Result<u64, JsValue> {
+        match id {
+            Some(id) => whole(id),
+            None => Ok(self.book.next_order_id()),
+        }
+    }
+
+    // Hands the report's trades to the `onTrade` callback
+    fn stream(&self, report: &ExecutionReport) -> Result<WasmExecutionReport, JsValue> {
+        let report = WasmExecutionReport::from(report);
+        if let Some(callback) = &self.on_trade {
+            for &trade in &report.trades {
+                callback.call1(&JsValue::NULL, &trade.into())?;
+            }
+        }
+        Ok(report)
+    }
+}
+
+fn parse_side(side: &str) -> Result<Side, JsValue> {
+    match side {
+        "buy" => Ok(Side::Buy),
+        "sell" => Ok(Side::Sell),
+        _ => Err(JsError::new(&format!("side must be 'buy' or 'sell', not {:?}", side)).into()),
+    }
+}
+
+fn js_error(err: impl std::error::Error) -> JsValue {
+    JsError::new(&err.to_string()).into()
+}
+
+fn whole(value: f64) -> Result<u64, JsValue> {
+    if value.fract() == 0.0 && (0.0..=MAX_SAFE_INTEGER).contains(&value) {
+        Ok(value as u64)
+    } else {
+        Err(JsError::new(&format!("{} isn't a whole number from 0 to Number.MAX_SAFE_INTEGER", value)).into())
+    }
+}
+
+fn units(value: f64) -> Result<Units, JsValue> {
+    whole(value).map(|value| value as Units)
+}
+
+// Rounds to the nearest number beyond Number.MAX_SAFE_INTEGER, which only `wide` books reach
+fn number(value: Units) -> f64 {
+    widen(value) as f64
+}
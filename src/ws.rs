@@ -0,0 +1,280 @@
+use std::collections::HashSet;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::{self, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::book::Depth;
+use crate::exchange::{Exchange, ExchangeError};
+use crate::order::{NewOrder, Order};
+use crate::types::{ExecutionReport, Side, Trade};
+use crate::units::Units;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WsConfig {
+    /// How often every connection is sent a heartbeat
+    pub heartbeat_interval: Duration,
+    /// Levels per side in depth messages
+    pub depth_levels: usize,
+}
+
+impl Default for WsConfig {
+    fn default() -> Self {
+        Self { heartbeat_interval: Duration::from_secs(5), depth_levels: 10 }
+    }
+}
+
+/// Streams a client can subscribe to, one symbol at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Trades,
+    Depth,
+}
+
+/// What a client sends, one JSON object per text frame, e.g.
+/// `{"op":"place","symbol":"ACME","side":"Buy","price":100,"quantity":5,"id":1}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// A limit order, or a market order when `price` is left out
+    Place {
+        symbol: String,
+        side: Side,
+        #[cfg_attr(feature = "wide", serde(default, deserialize_with = "crate::units::deserialize_optional_units"))]
+        price: Option<Units>,
+        #[cfg_attr(feature = "wide", serde(deserialize_with = "crate::units::deserialize_units"))]
+        quantity: Units,
+        id: u64,
+    },
+    Cancel { id: u64 },
+    Modify {
+        id: u64,
+        #[cfg_attr(feature = "wide", serde(deserialize_with = "crate::units::deserialize_units"))]
+        price: Units,
+        #[cfg_attr(feature = "wide", serde(deserialize_with = "crate::units::deserialize_units"))]
+        quantity: Units,
+    },
+    Subscribe { channel: Channel, symbol: String },
+    Unsubscribe { channel: Channel, symbol: String },
+    Ping,
+}
+
+/// What the server sends, tagged by `type`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Answer to `place` and `modify`
+    Report { symbol: String, report: ExecutionReport },
+    /// Answer to `cancel`
+    Cancelled { symbol: String, order: Order },
+    /// A request that failed or couldn't be parsed
+    Error { message: String },
+    Subscribed { channel: Channel, symbol: String },
+    Unsubscribed { channel: Channel, symbol: String },
+    Trade { symbol: String, trade: Trade },
+    /// Top of the book; sent on subscribing and after every change to it
+    Depth { symbol: String, depth: Depth },
+    /// The connection fell behind and `missed` market updates were dropped for it
+    Lagged { missed: u64 },
+    Heartbeat { timestamp_ms: u64 },
+    Pong,
+}
+
+// Published by the engine after every order operation
+#[derive(Debug, Clone)]
+struct MarketUpdate {
+    symbol: String,
+    trades: Vec<Trade>,
+    depth: Depth,
+}
+
+enum Request {
+    Place(String, Box<NewOrder>, oneshot::Sender<Result<ExecutionReport, ExchangeError>>),
+    Cancel(u64, oneshot::Sender<Result<(String, Order), ExchangeError>>),
+    Modify(u64, Units, Units, oneshot::Sender<Result<(String, ExecutionReport), ExchangeError>>),
+    Depth(String, oneshot::Sender<Result<Depth, ExchangeError>>),
+}
+
+// Market updates buffered per connection before it's reported as lagging
+const UPDATE_BUFFER: usize = 1024;
+
+/// Serve `exchange` over WebSocket to every connection accepted on `listener`.
+///
+/// One task owns the exchange and runs requests in arrival order; each connection gets its
+/// own task that forwards requests to it and filters the market updates it publishes down to
+/// the client's subscriptions. Runs until accepting fails.
+pub async fn serve(listener: TcpListener, exchange: Exchange, config: WsConfig) -> io::Result<()> {
+    let (requests, queue) = mpsc::channel(UPDATE_BUFFER);
+    let (updates, _) = broadcast::channel(UPDATE_BUFFER);
+    tokio::spawn(run_engine(exchange, config.depth_levels, queue, updates.clone()));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let connection = Connection { requests: requests.clone(), updates: updates.subscribe(), subscriptions: HashSet::new() };
+        tokio::spawn(connection.run(stream, config.heartbeat_interval));
+    }
+}
+
+async fn run_engine(mut exchange: Exchange, depth_levels: usize, mut queue: mpsc::Receiver<Request>, updates: broadcast::Sender<MarketUpdate>) {
+    // Nobody subscribed is fine, so send errors on `updates` are ignored throughout
+    let publish = |exchange: &Exchange, symbol: &str, trades: Vec<Trade>| {
+        if let Some(book) = exchange.book(symbol) {
+            let _ = updates.send(MarketUpdate { symbol: symbol.to_string(), trades, depth: book.depth(depth_levels) });
+        }
+    };
+    while let Some(request) = queue.recv().await {
+        // A connection that went away meanwhile no longer waits for the reply
+        match request {
+            Request::Place(symbol, order, reply) => {
+                let result = exchange.submit(&symbol, *order);
+                if let Ok(report) = &result {
+                    publish(&exchange, &symbol, report.trades.clone());
+                }
+                let _ = reply.send(result);
+            }
+            Request::Cancel(id, reply) => {
+                let symbol = exchange.symbol_of(id).map(str::to_string).ok_or(ExchangeError::UnknownOrder(id));
+                let result = symbol.and_then(|symbol| {
+                    let order = exchange.cancel_order(id)?;
+                    publish(&exchange, &symbol, Vec::new());
+                    Ok((symbol, order))
+                });
+                let _ = reply.send(result);
+            }
+            Request::Modify(id, price, quantity, reply) => {
+                let symbol = exchange.symbol_of(id).map(str::to_string).ok_or(ExchangeError::UnknownOrder(id));
+                let result = symbol.and_then(|symbol| {
+                    let report = exchange.modify_order(id, price, quantity)?;
+                    publish(&exchange, &symbol, report.trades.clone());
+                    Ok((symbol, report))
+                });
+                let _ = reply.send(result);
+            }
+            Request::Depth(symbol, reply) => {
+                let depth = exchange.book(&symbol).map(|book| book.depth(depth_levels));
+                let _ = reply.send(depth.ok_or(ExchangeError::UnknownSymbol(symbol)));
+            }
+        }
+    }
+}
+
+struct Connection {
+    requests: mpsc::Sender<Request>,
+    updates: broadcast::Receiver<MarketUpdate>,
+    subscriptions: HashSet<(Channel, String)>,
+}
+
+impl Connection {
+    async fn run(mut self, stream: TcpStream, heartbeat_interval: Duration) {
+        let Ok(socket) = tokio_tungstenite::accept_async(stream).await else {
+            return;
+        };
+        let (mut sink, mut frames) = socket.split();
+        let mut heartbeat = time::interval_at(Instant::now() + heartbeat_interval, heartbeat_interval);
+        loop {
+            let replies = tokio::select! {
+                frame = frames.next() => match frame {
+                    Some(Ok(Message::Text(text))) => self.handle(&text).await,
+                    // Control frames are answered by tungstenite itself
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                },
+                update = self.updates.recv() => match update {
+                    Ok(update) => self.filter(update),
+                    Err(RecvError::Lagged(missed)) => vec![ServerMessage::Lagged { missed }],
+                    Err(RecvError::Closed) => break,
+                },
+                _ = heartbeat.tick() => vec![ServerMessage::Heartbeat { timestamp_ms: unix_millis() }],
+            };
+            for reply in replies {
+                let Ok(text) = serde_json::to_string(&reply) else { continue };
+                if sink.send(Message::text(text)).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn handle(&mut self, text: &str) -> Vec<ServerMessage> {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(err) => return vec![ServerMessage::Error { message: format!("bad request: {}", err) }],
+        };
+        let reply = match message {
+            ClientMessage::Place { symbol, side, price, quantity, id } => {
+                let order = match price {
+                    Some(price) => NewOrder::limit(side, price, quantity, id),
+                    None => NewOrder::market(side, quantity, id),
+                };
+                self.call(|reply| Request::Place(symbol.clone(), Box::new(order), reply))
+                    .await
+                    .map(|report| ServerMessage::Report { symbol, report })
+            }
+            ClientMessage::Cancel { id } => self
+                .call(|reply| Request::Cancel(id, reply))
+                .await
+                .map(|(symbol, order)| ServerMessage::Cancelled { symbol, order }),
+            ClientMessage::Modify { id, price, quantity } => self
+                .call(|reply| Request::Modify(id, price, quantity, reply))
+                .await
+                .map(|(symbol, report)| ServerMessage::Report { symbol, report }),
+            ClientMessage::Subscribe { channel, symbol } => {
+                // Resolving the depth checks the symbol, and a depth subscriber starts from it
+                return match self.call(|reply| Request::Depth(symbol.clone(), reply)).await {
+                    Ok(depth) => {
+                        self.subscriptions.insert((channel, symbol.clone()));
+                        let mut replies = vec![ServerMessage::Subscribed { channel, symbol: symbol.clone() }];
+                        if channel == Channel::Depth {
+                            replies.push(ServerMessage::Depth { symbol, depth });
+                        }
+                        replies
+                    }
+                    Err(message) => vec![message],
+                };
+            }
+            ClientMessage::Unsubscribe { channel, symbol } => {
+                self.subscriptions.remove(&(channel, symbol.clone()));
+                Ok(ServerMessage::Unsubscribed { channel, symbol })
+            }
+            ClientMessage::Ping => Ok(ServerMessage::Pong),
+        };
+        vec![reply.unwrap_or_else(|message| message)]
+    }
+
+    // Send a request to the engine and wait for its answer, turning failures into an error message
+    async fn call<T>(
+        &self,
+        request: impl FnOnce(oneshot::Sender<Result<T, ExchangeError>>) -> Request,
+    ) -> Result<T, ServerMessage> {
+        let closed = || ServerMessage::Error { message: "the exchange has shut down".to_string() };
+        let (reply, answer) = oneshot::channel();
+        self.requests.send(request(reply)).await.map_err(|_| closed())?;
+        match answer.await {
+            Ok(result) => result.map_err(|err| ServerMessage::Error { message: err.to_string() }),
+            Err(_) => Err(closed()),
+        }
+    }
+
+    fn filter(&self, update: MarketUpdate) -> Vec<ServerMessage> {
+        let mut messages = Vec::new();
+        if self.subscriptions.contains(&(Channel::Trades, update.symbol.clone())) {
+            for trade in update.trades {
+                messages.push(ServerMessage::Trade { symbol: update.symbol.clone(), trade });
+            }
+        }
+        if self.subscriptions.contains(&(Channel::Depth, update.symbol.clone())) {
+            messages.push(ServerMessage::Depth { symbol: update.symbol, depth: update.depth });
+        }
+        messages
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
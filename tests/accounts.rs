@@ -0,0 +1,87 @@
+use orderbook::*;
+
+#[test]
+fn test_positions_follow_trades() {
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_owner(1)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 110, 10, 2).with_owner(1)).unwrap();
+    ob.submit(NewOrder::market(Side::Buy, 15, 3).with_owner(2)).unwrap();
+
+    let buyer = ob.position(2);
+    assert_eq!((buyer.net_quantity, buyer.open_notional, buyer.trades), (15, 1550, 2));
+    assert_eq!(buyer.average_price(), Some(1550.0 / 15.0));
+    let seller = ob.position(1);
+    assert_eq!((seller.net_quantity, seller.sold_quantity, seller.realized_pnl), (-15, 15, 0));
+    assert_eq!(ob.accounts().positions().count(), 2);
+
+    // Owner 3 clears the rest of the asks and bids 120 for 20 more, which the buyer sells 10
+    // of back: cost 1550 * 10 / 15 = 1033, proceeds 1200
+    ob.submit(NewOrder::limit(Side::Buy, 120, 25, 4).with_owner(3)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 120, 10, 5).with_owner(2)).unwrap();
+    let buyer = ob.position(2);
+    assert_eq!((buyer.net_quantity, buyer.open_notional, buyer.realized_pnl), (5, 517, 167));
+    assert_eq!(buyer.filled_volume(), 25);
+    assert_eq!(buyer.unrealized_pnl(120), 600 - 517);
+
+    // Selling 10 more flips it short 5 at 120
+    ob.submit(NewOrder::limit(Side::Sell, 120, 10, 6).with_owner(2)).unwrap();
+    let buyer = ob.position(2);
+    assert_eq!((buyer.net_quantity, buyer.open_notional, buyer.realized_pnl), (-5, 600, 167 + 83));
+    assert_eq!(buyer.unrealized_pnl(100), 100);
+
+    assert_eq!(ob.position(99), Position::default());
+    assert_eq!(ob.position(99).average_price(), None);
+}
+
+#[test]
+fn test_open_orders_by_owner() {
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Buy, 99, 10, 1).with_owner(1)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 100, 10, 2).with_owner(2)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 105, 10, 3).with_owner(1)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 101, 10, 4).with_owner(1)).unwrap();
+
+    let ids: Vec<u64> = ob.open_orders(1).map(|order| order.id).collect();
+    assert_eq!(ids, [4, 1, 3]);
+    ob.cancel_order(4).unwrap();
+    assert_eq!(ob.open_orders(1).count(), 2);
+    assert_eq!(ob.open_orders(7).count(), 0);
+}
+
+#[test]
+fn test_reduce_only_orders_close_the_position() {
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_owner(1)).unwrap();
+    ob.submit(NewOrder::market(Side::Buy, 6, 2).with_owner(2)).unwrap();
+
+    // Long 6: a reduce-only buy, or one without an owner, can't trade
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 2, 3).with_owner(2).reduce_only()).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ReduceOnly));
+    let report = ob.submit(NewOrder::limit(Side::Sell, 90, 2, 4).reduce_only()).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ReduceOnly));
+
+    // A sell for 10 is trimmed to the 6 held
+    ob.place_order(Side::Buy, 95, 20, 5).unwrap();
+    let report = ob.submit(NewOrder::limit(Side::Sell, 95, 10, 6).with_owner(2).reduce_only()).unwrap();
+    assert_eq!((report.status, report.filled_quantity, report.remaining_quantity), (OrderOutcome::Filled, 6, 0));
+    assert_eq!(ob.position(2).net_quantity, 0);
+
+    // Short 4 after selling into the bid; under the reject policy only up to 4 goes through
+    ob.submit(NewOrder::market(Side::Sell, 4, 7).with_owner(2)).unwrap();
+    ob.set_reduce_only_policy(ReduceOnlyPolicy::Reject);
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 5, 8).with_owner(2).reduce_only()).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ReduceOnly));
+    let report = ob.submit(NewOrder::limit(Side::Buy, 99, 4, 9).with_owner(2).reduce_only()).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rested);
+    // Its replacement on a modify is reduce-only too
+    let report = ob.modify_order(9, 99, 5).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ReduceOnly));
+    assert_eq!(ob.order_status(9).unwrap().remaining_quantity, 4);
+
+    // A reduce-only stop is checked when it triggers, by which time the short is covered
+    ob.submit(NewOrder::stop(Side::Buy, 100, 4, 10).with_owner(2).reduce_only()).unwrap();
+    ob.place_order(Side::Sell, 99, 4, 11).unwrap();
+    assert_eq!(ob.position(2).net_quantity, 0);
+    ob.submit(NewOrder::market(Side::Buy, 4, 12)).unwrap(); // trades at 100, triggering order 10
+    assert_eq!(ob.position(2).net_quantity, 0);
+}
@@ -0,0 +1,55 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use orderbook::*;
+
+// Counts every allocation in this test binary, which holds a single test so nothing else
+// runs alongside it
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// Quotes at prices that come and go around a resting book, each cancelled straight away
+fn churn(ob: &mut OrderBook, rounds: Units) {
+    for round in 0..rounds {
+        let offset = round % 20;
+        let bid = ob.place_order(Side::Buy, 980 + offset, 5, 1_000).unwrap();
+        let ask = ob.place_order(Side::Sell, 1_001 + offset, 5, 1_001).unwrap();
+        assert_eq!((bid.status, ask.status), (OrderOutcome::Rested, OrderOutcome::Rested));
+        ob.cancel_order(1_000).unwrap();
+        ob.cancel_order(1_001).unwrap();
+    }
+}
+
+#[test]
+fn test_quote_churn_does_not_allocate() {
+    let mut ob = OrderBook::new();
+    for id in 0..200 {
+        ob.place_order(Side::Buy, 900 + Units::from(id % 50), 10, id).unwrap();
+        ob.place_order(Side::Sell, 1_100 + Units::from(id % 50), 10, 500 + id).unwrap();
+    }
+    churn(&mut ob, 100);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    churn(&mut ob, 10_000);
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed) - before, 0);
+}
@@ -0,0 +1,75 @@
+use orderbook::*;
+
+fn feed(publisher: &mut L2Publisher, ob: &OrderBook, analytics: &mut BookAnalytics) {
+    for message in publisher.publish(ob) {
+        analytics.apply(&message).unwrap();
+    }
+}
+
+#[test]
+fn test_depth_metrics_follow_the_feed() {
+    let mut ob = OrderBook::new();
+    let mut publisher = L2Publisher::new(0);
+    let mut analytics = BookAnalytics::new(0);
+    ob.place_order(Side::Buy, 100, 30, 1).unwrap();
+    ob.place_order(Side::Buy, 100, 10, 2).unwrap();
+    ob.place_order(Side::Buy, 99, 20, 3).unwrap();
+    ob.place_order(Side::Sell, 101, 10, 4).unwrap();
+    ob.place_order(Side::Sell, 103, 30, 5).unwrap();
+    feed(&mut publisher, &ob, &mut analytics);
+
+    assert_eq!(analytics.depth_curve(Side::Buy, 5), [(100, 40), (99, 60)]);
+    assert_eq!(analytics.depth_curve(Side::Sell, 1), [(101, 10)]);
+    assert_eq!(analytics.imbalance(1), Some(0.6));
+    assert_eq!(analytics.imbalance(2), Some(0.2));
+    assert_eq!(analytics.average_order_size(Side::Buy), Some(20.0));
+    assert_eq!(analytics.total_quantity(Side::Sell), 40);
+
+    ob.cancel_order(1).unwrap();
+    ob.place_market_order(Side::Buy, 10, 6).unwrap();
+    feed(&mut publisher, &ob, &mut analytics);
+    assert_eq!(analytics.average_order_size(Side::Buy), Some(15.0));
+    assert_eq!(analytics.average_order_size(Side::Sell), Some(30.0));
+    assert_eq!(analytics.imbalance(1), Some(-0.5));
+
+    // A snapshot rebuilds the totals
+    let mut late = BookAnalytics::new(0);
+    late.apply(&MarketData::Snapshot(publisher.snapshot())).unwrap();
+    assert_eq!(late.total_quantity(Side::Buy), analytics.total_quantity(Side::Buy));
+    assert_eq!(late.average_order_size(Side::Sell), Some(30.0));
+
+    let empty = BookAnalytics::new(0);
+    assert_eq!(empty.imbalance(5), None);
+    assert_eq!(empty.average_order_size(Side::Buy), None);
+}
+
+#[test]
+fn test_realized_spread() {
+    let mut ob = OrderBook::with_clock(Box::new(TestClock::new(0, 0)));
+    let mut publisher = L2Publisher::new(0);
+    let mut analytics = BookAnalytics::new(10);
+    ob.place_order(Side::Buy, 98, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 102, 10, 2).unwrap();
+    feed(&mut publisher, &ob, &mut analytics);
+
+    // A buy at 102 with the mid at 100 still shows at settlement: the seller earned 4
+    let report = ob.place_market_order(Side::Buy, 5, 3).unwrap();
+    for trade in &report.trades {
+        analytics.on_trade(trade);
+    }
+    feed(&mut publisher, &ob, &mut analytics);
+    analytics.settle(5);
+    assert_eq!(analytics.realized_spread(), None);
+    analytics.settle(10);
+    assert_eq!(analytics.realized_spread(), Some(4.0));
+
+    // A sell at 98 after which the mid drops to 97: the buyer lost 2
+    let report = ob.place_market_order(Side::Sell, 10, 4).unwrap();
+    for trade in &report.trades {
+        analytics.on_trade(trade);
+    }
+    ob.place_order(Side::Buy, 92, 10, 5).unwrap();
+    feed(&mut publisher, &ob, &mut analytics);
+    analytics.settle(10);
+    assert_eq!(analytics.realized_spread(), Some((4.0 - 2.0) / 2.0));
+}
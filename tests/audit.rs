@@ -0,0 +1,106 @@
+use std::io::{self, Write};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+
+use orderbook::*;
+
+// Buffer the test keeps a handle to while the book owns the writer
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_audit_records_calls() {
+    let mut ob = OrderBook::new();
+    let (tx, rx) = mpsc::channel();
+    ob.enable_audit(tx, Box::new(TestClock::new(1000, 10)));
+
+    ob.place_order(Side::Sell, 101, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 102, 10, 2).unwrap();
+    ob.place_order(Side::Buy, 102, 15, 3).unwrap();
+    assert!(ob.cancel_order(9).is_err()); // logs nothing, so no record
+    ob.modify_order(2, 103, 5).unwrap();
+    ob.halt();
+    let records: Vec<AuditRecord> = rx.try_iter().collect();
+    assert_eq!(records.len(), 5);
+    assert_eq!(records.iter().map(|record| record.seq).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    assert_eq!(records.iter().map(|record| record.timestamp).collect::<Vec<_>>(), [1000, 1010, 1020, 1040, 1050]);
+
+    // Why order 3 traded at 101 and 102: it swept both asks
+    let sweep = &records[2];
+    assert_eq!(sweep.commands, [LogEntry::Submit(NewOrder::limit(Side::Buy, 102, 15, 3))]);
+    assert_eq!((sweep.best_before, sweep.best_after), ((None, Some(101)), (None, Some(102))));
+    assert_eq!(
+        sweep.trades.iter().map(|trade| (trade.maker_id, trade.price, trade.quantity)).collect::<Vec<_>>(),
+        [(1, 101, 10), (2, 102, 5)]
+    );
+    assert!(sweep.events.contains(&Event::Order(OrderEvent::Filled { id: 1, price: 101, quantity: 10 })));
+    assert!(sweep.events.contains(&Event::Book(BookEvent::BestPriceChanged { side: Side::Sell, price: Some(102) })));
+
+    // A cancel/replace is one record, under the modify
+    let modify = &records[3];
+    assert_eq!(modify.commands, [LogEntry::Modify { id: 2, price: 103, quantity: 5 }]);
+    assert_eq!(modify.best_after, (None, Some(103)));
+    assert!(modify.events.contains(&Event::Order(OrderEvent::Rested { id: 2, price: 103, quantity: 5 })));
+
+    assert_eq!(records[4].commands, [LogEntry::Halt]);
+    assert!(records[4].events.is_empty());
+}
+
+#[test]
+fn test_audit_groups_nested_calls() {
+    let mut ob = OrderBook::new();
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let sink = records.clone();
+    ob.enable_audit(move |record: &AuditRecord| sink.lock().unwrap().push(record.clone()), Box::new(TestClock::default()));
+
+    ob.set_halt_policy(HaltPolicy::Queue);
+    ob.halt();
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 99, 10, 2).unwrap();
+    ob.resume().unwrap();
+    ob.cancel_all().unwrap();
+
+    let records = records.lock().unwrap();
+    let commands: Vec<_> = records.iter().map(|record| record.commands.clone()).collect();
+    assert_eq!(
+        commands,
+        [
+            vec![LogEntry::Halt],
+            vec![LogEntry::Submit(NewOrder::limit(Side::Buy, 100, 10, 1))],
+            vec![LogEntry::Submit(NewOrder::limit(Side::Buy, 99, 10, 2))],
+            // The queued orders were logged when they arrived; they rest under the resume
+            vec![LogEntry::Resume],
+            vec![LogEntry::Cancel { id: 1 }, LogEntry::Cancel { id: 2 }],
+        ]
+    );
+    assert_eq!(records[3].best_after, (Some(100), None));
+    assert!(records[3].events.contains(&Event::Order(OrderEvent::Rested { id: 2, price: 99, quantity: 10 })));
+    assert_eq!(records[4].best_after, (None, None));
+}
+
+#[test]
+fn test_audit_writer() {
+    let mut ob = OrderBook::new();
+    let out = Shared::default();
+    ob.enable_audit(AuditWriter::new(out.clone()), Box::new(TestClock::default()));
+    ob.place_order(Side::Sell, 101, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 101, 4, 2).unwrap();
+    ob.disable_audit();
+    assert!(ob.audit_error().is_none());
+
+    let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("1 1 -/- -> -/101 | submit 1 sell 10 gtc 0 - - limit 101 | "));
+    assert!(lines[1].starts_with("2 2 -/101 -> -/101 | submit 2 buy 4 gtc 0 - - limit 101 | "));
+    assert!(lines[1].ends_with("| 4@101 maker 1 taker 2"));
+}
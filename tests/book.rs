@@ -0,0 +1,660 @@
+use orderbook::*;
+
+#[test]
+fn test_buy_at_and_sell_at() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 200, 2).unwrap();
+    ob.place_order(Side::Buy, 9, 300, 3).unwrap();
+
+    ob.place_order(Side::Sell, 11, 150, 4).unwrap();
+    ob.place_order(Side::Sell, 11, 50, 5).unwrap();
+    ob.place_order(Side::Sell, 12, 100, 6).unwrap();
+
+    assert_eq!(ob.buy_at(10), Some((10, 300))); // 100 + 200
+    assert_eq!(ob.buy_at(9), Some((9, 300)));
+    assert_eq!(ob.buy_at(8), None);
+
+    assert_eq!(ob.sell_at(11), Some((11, 200))); // 150 + 50
+    assert_eq!(ob.sell_at(12), Some((12, 100)));
+    assert_eq!(ob.sell_at(13), None);
+}
+
+#[test]
+fn test_level_total_matches_orders() {
+    let mut ob = OrderBook::new();
+
+    for id in 0..500 {
+        ob.place_order(Side::Buy, 10, Units::from(id % 7 + 1), id).unwrap();
+    }
+    ob.place_order(Side::Sell, 10, 321, 1000).unwrap();
+
+    let iterated: Units = ob.orders_at(Side::Buy, 10).unwrap().map(|o| o.quantity).sum();
+    assert_eq!(ob.buy_at(10), Some((10, iterated)));
+    assert_eq!(ob.best_buy(), Some((10, iterated)));
+}
+
+#[test]
+fn test_trade_history_window() {
+    let mut ob = OrderBook::new();
+    ob.enable_trade_history(4);
+
+    for id in 0..9 {
+        ob.place_order(Side::Buy, 10, 1, id).unwrap();
+        assert_eq!(ob.place_order(Side::Sell, 10, 1, 100 + id).unwrap().trades.len(), 1);
+    }
+
+    let makers: Vec<u64> = ob.recent_trades().iter().map(|t| t.maker_id).collect();
+    assert_eq!(makers, vec![5, 6, 7, 8]);
+}
+
+#[test]
+fn test_orders_at_fifo() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 12, 100, 1).unwrap();
+    ob.place_order(Side::Sell, 12, 200, 2).unwrap();
+    ob.place_order(Side::Sell, 12, 300, 3).unwrap();
+
+    let orders: Vec<(u64, Units)> = ob.orders_at(Side::Sell, 12).unwrap().map(|o| (o.id, o.quantity)).collect();
+    assert_eq!(orders, vec![(1, 100), (2, 200), (3, 300)]);
+
+    ob.place_order(Side::Buy, 12, 40, 4).unwrap();
+    let orders: Vec<(u64, Units)> = ob.orders_at(Side::Sell, 12).unwrap().map(|o| (o.id, o.quantity)).collect();
+    assert_eq!(orders, vec![(1, 60), (2, 200), (3, 300)]);
+
+    assert!(ob.orders_at(Side::Buy, 12).is_none());
+    assert!(ob.orders_at(Side::Sell, 13).is_none());
+}
+
+#[test]
+fn test_snapshot_restore_round_trip() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 9, 50, 2).unwrap();
+    ob.place_order(Side::Buy, 10, 200, 3).unwrap();
+    ob.place_order(Side::Buy, 10, 300, 4).unwrap();
+    ob.place_order(Side::Sell, 12, 70, 5).unwrap();
+    ob.place_order(Side::Sell, 11, 80, 6).unwrap();
+    ob.place_order(Side::Sell, 10, 150, 7).unwrap(); // fills id 1, partially fills id 3
+
+    let mut restored = OrderBook::restore(ob.snapshot()).unwrap();
+
+    assert_eq!(restored.best_buy(), ob.best_buy());
+    assert_eq!(restored.best_sell(), ob.best_sell());
+    for price in 8..13 {
+        assert_eq!(restored.buy_at(price), ob.buy_at(price));
+        assert_eq!(restored.sell_at(price), ob.sell_at(price));
+    }
+
+    let expected: Vec<(Units, Units, u64)> = ob
+        .place_order(Side::Sell, 9, 600, 8).unwrap()
+        .trades
+        .iter()
+        .map(|t| (t.price, t.quantity, t.maker_id))
+        .collect();
+    let actual: Vec<(Units, Units, u64)> = restored
+        .place_order(Side::Sell, 9, 600, 8).unwrap()
+        .trades
+        .iter()
+        .map(|t| (t.price, t.quantity, t.maker_id))
+        .collect();
+    assert_eq!(expected, vec![(10, 150, 3), (10, 300, 4), (9, 50, 2)]);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_aggregated_depth() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 1, 1).unwrap();
+    ob.place_order(Side::Buy, 11, 2, 2).unwrap();
+    ob.place_order(Side::Buy, 12, 3, 3).unwrap();
+    ob.place_order(Side::Buy, 14, 4, 4).unwrap();
+    ob.place_order(Side::Buy, 4, 5, 5).unwrap();
+
+    assert_eq!(ob.aggregated_depth(Side::Buy, 5, 10), vec![(10, 10), (0, 5)]);
+    assert_eq!(ob.aggregated_depth(Side::Buy, 5, 1), vec![(10, 10)]);
+    assert_eq!(
+        ob.aggregated_depth(Side::Buy, 1, 3),
+        vec![(14, 4), (12, 3), (11, 2)]
+    );
+
+    ob.place_order(Side::Sell, 20, 1, 6).unwrap();
+    ob.place_order(Side::Sell, 21, 2, 7).unwrap();
+    ob.place_order(Side::Sell, 25, 3, 8).unwrap();
+    ob.place_order(Side::Sell, 26, 4, 9).unwrap();
+
+    assert_eq!(ob.aggregated_depth(Side::Sell, 5, 10), vec![(20, 1), (25, 5), (30, 4)]);
+}
+
+#[test]
+fn test_cancel_order() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 200, 2).unwrap();
+    ob.place_order(Side::Buy, 9, 300, 3).unwrap();
+
+    let cancelled = ob.cancel_order(1).unwrap();
+    assert_eq!((cancelled.id, cancelled.quantity), (1, 100));
+    assert_eq!(ob.buy_at(10), Some((10, 200)));
+    assert_eq!(ob.cancel_order(1).unwrap_err(), CancelError::UnknownOrder(1));
+
+    // Emptying the best level must also drop it from the heap
+    ob.cancel_order(2).unwrap();
+    assert_eq!(ob.buy_at(10), None);
+    assert_eq!(ob.best_buy(), Some((9, 300)));
+
+    // Fully filled orders can no longer be cancelled, partially filled ones can
+    ob.place_order(Side::Buy, 9, 50, 4).unwrap();
+    ob.place_order(Side::Sell, 9, 320, 5).unwrap();
+    assert_eq!(ob.cancel_order(3).unwrap_err(), CancelError::UnknownOrder(3));
+    assert_eq!(ob.cancel_order(4).unwrap().quantity, 30);
+    assert_eq!(ob.best_buy(), None);
+
+    ob.place_order(Side::Sell, 9, 10, 6).unwrap();
+    assert_eq!(ob.best_sell(), Some((9, 10)));
+}
+
+#[test]
+fn test_modify_order() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 100, 2).unwrap();
+    ob.place_order(Side::Sell, 12, 100, 3).unwrap();
+
+    // Reducing keeps priority
+    assert_eq!(ob.modify_order(1, 10, 40).unwrap().trades.len(), 0);
+    assert_eq!(ob.buy_at(10), Some((10, 140)));
+    let ids: Vec<u64> = ob.orders_at(Side::Buy, 10).unwrap().map(|o| o.id).collect();
+    assert_eq!(ids, vec![1, 2]);
+
+    // Increasing loses priority
+    ob.modify_order(1, 10, 50).unwrap();
+    let ids: Vec<u64> = ob.orders_at(Side::Buy, 10).unwrap().map(|o| o.id).collect();
+    assert_eq!(ids, vec![2, 1]);
+
+    // Repricing through the spread trades
+    let trades = ob.modify_order(2, 12, 150).unwrap().trades;
+    assert_eq!(trades.len(), 1);
+    assert_eq!((trades[0].maker_id, trades[0].taker_id, trades[0].quantity), (3, 2, 100));
+    assert_eq!(ob.best_buy(), Some((12, 50)));
+    assert_eq!(ob.buy_at(10), Some((10, 50)));
+
+    assert_eq!(ob.modify_order(3, 12, 10).unwrap_err(), CancelError::UnknownOrder(3));
+    ob.modify_order(1, 10, 0).unwrap();
+    assert_eq!(ob.buy_at(10), None);
+}
+
+#[test]
+fn test_rejected_modify_leaves_order_resting() {
+    let mut ob = OrderBook::new();
+    ob.set_price_config(PriceConfig { tick_size: 5, ..PriceConfig::default() }).unwrap();
+
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 100, 10, 2).unwrap();
+    ob.place_order(Side::Buy, 100, 10, 3).unwrap();
+    ob.place_order(Side::Sell, 110, 10, 4).unwrap();
+    ob.place_order(Side::Sell, 100, 5, 5).unwrap();
+    ob.set_price_band(5);
+
+    let before: Vec<_> = (1..=4).map(|id| ob.order_status(id)).collect();
+    let report = ob.modify_order(2, 80, 10).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::PriceBand));
+    let report = ob.modify_order(2, 110, 20).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::PriceBand));
+    let report = ob.modify_order(1, 103, 10).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::OffTick));
+    // Alone at its level
+    let report = ob.modify_order(4, 200, 10).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::PriceBand));
+
+    assert_eq!((1..=4).map(|id| ob.order_status(id)).collect::<Vec<_>>(), before);
+    let ids: Vec<u64> = ob.orders_at(Side::Buy, 100).unwrap().map(|o| o.id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert_eq!(ob.best_sell(), Some((110, 10)));
+    ob.validate().unwrap();
+}
+
+#[test]
+fn test_depth() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 50, 2).unwrap();
+    ob.place_order(Side::Buy, 8, 70, 3).unwrap();
+    ob.place_order(Side::Buy, 9, 30, 4).unwrap();
+    ob.place_order(Side::Sell, 13, 40, 5).unwrap();
+    ob.place_order(Side::Sell, 12, 60, 6).unwrap();
+
+    let depth = ob.depth(2);
+    assert_eq!(
+        depth.bids,
+        vec![
+            DepthLevel { price: 10, quantity: 150, order_count: 2 },
+            DepthLevel { price: 9, quantity: 30, order_count: 1 },
+        ]
+    );
+    assert_eq!(
+        depth.asks,
+        vec![
+            DepthLevel { price: 12, quantity: 60, order_count: 1 },
+            DepthLevel { price: 13, quantity: 40, order_count: 1 },
+        ]
+    );
+
+    assert_eq!(ob.depth(10).bids.len(), 3);
+    assert_eq!(ob.depth(0), Depth::default());
+}
+
+#[test]
+fn test_best_n_prices() {
+    let mut ob = OrderBook::new();
+    assert_eq!(ob.best_n_buys(3).count(), 0);
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 50, 2).unwrap();
+    ob.place_order(Side::Buy, 8, 70, 3).unwrap();
+    ob.place_order(Side::Buy, 9, 30, 4).unwrap();
+    ob.place_order(Side::Sell, 13, 40, 5).unwrap();
+    ob.place_order(Side::Sell, 12, 60, 6).unwrap();
+
+    assert_eq!(ob.best_n_buys(2).collect::<Vec<_>>(), [(10, 150), (9, 30)]);
+    assert_eq!(ob.best_n_buys(10).collect::<Vec<_>>(), [(10, 150), (9, 30), (8, 70)]);
+    assert_eq!(ob.best_n_sells(5).collect::<Vec<_>>(), [(12, 60), (13, 40)]);
+    assert_eq!(ob.best_n_sells(0).next(), None);
+    assert_eq!(ob.best_n_buys(1).next(), ob.best_buy());
+
+    // A level traded away drops out
+    ob.place_order(Side::Sell, 10, 150, 7).unwrap();
+    assert_eq!(ob.best_n_buys(2).collect::<Vec<_>>(), [(9, 30), (8, 70)]);
+}
+
+#[test]
+fn test_iter_bids_and_asks() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 9, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 20, 2).unwrap();
+    ob.place_order(Side::Buy, 9, 30, 3).unwrap();
+    ob.place_order(Side::Buy, 10, 40, 4).unwrap();
+    ob.place_order(Side::Sell, 12, 50, 5).unwrap();
+    ob.place_order(Side::Sell, 11, 60, 6).unwrap();
+    ob.place_order(Side::Sell, 12, 70, 7).unwrap();
+
+    let bids: Vec<(Units, u64)> = ob.iter_bids().map(|o| (o.price, o.id)).collect();
+    assert_eq!(bids, vec![(10, 2), (10, 4), (9, 1), (9, 3)]);
+    let asks: Vec<(Units, u64)> = ob.iter_asks().map(|o| (o.price, o.id)).collect();
+    assert_eq!(asks, vec![(11, 6), (12, 5), (12, 7)]);
+
+    let snapshot = ob.snapshot();
+    let ids: Vec<u64> = snapshot.buys.iter().chain(snapshot.sells.iter()).map(|o| o.id).collect();
+    assert_eq!(ids, vec![2, 4, 1, 3, 6, 5, 7]);
+}
+
+#[test]
+fn test_order_status() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 10, 30, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 100, 2).unwrap(); // fills 30, rests 70
+    ob.place_order(Side::Buy, 10, 50, 3).unwrap();
+
+    assert_eq!(
+        ob.order_status(2),
+        Some(OrderStatus {
+            side: Side::Buy,
+            price: 10,
+            original_quantity: 100,
+            remaining_quantity: 70,
+            filled_quantity: 30,
+            queue_position: 0,
+        })
+    );
+
+    ob.place_order(Side::Sell, 10, 80, 4).unwrap();
+    assert_eq!(ob.order_status(2), None);
+    let status = ob.order_status(3).unwrap();
+    assert_eq!((status.remaining_quantity, status.filled_quantity, status.queue_position), (40, 10, 0));
+    assert_eq!(ob.order_status(1), None);
+}
+
+#[test]
+fn test_trade_sequence_queries() {
+    let mut ob = OrderBook::new();
+    ob.enable_trade_history(5);
+
+    ob.place_order(Side::Sell, 10, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 11, 10, 2).unwrap();
+    ob.place_order(Side::Sell, 12, 10, 3).unwrap();
+    let seqs: Vec<u64> = ob.place_order(Side::Buy, 12, 30, 4).unwrap().trades.iter().map(|t| t.seq).collect();
+    assert_eq!(seqs, vec![1, 2, 3]);
+
+    for id in 5..9 {
+        ob.place_order(Side::Sell, 10, 1, id).unwrap();
+        ob.place_order(Side::Buy, 10, 1, 100 + id).unwrap();
+    }
+    assert_eq!(ob.last_trade_seq(), 7);
+
+    let since: Vec<u64> = ob.trades_since(4).map(|t| t.seq).collect();
+    assert_eq!(since, vec![5, 6, 7]);
+    // Trades older than the retained window are gone
+    let since: Vec<u64> = ob.trades_since(0).map(|t| t.seq).collect();
+    assert_eq!(since, vec![3, 4, 5, 6, 7]);
+    let last: Vec<u64> = ob.last_n_trades(2).map(|t| t.seq).collect();
+    assert_eq!(last, vec![6, 7]);
+    assert_eq!(ob.last_n_trades(50).count(), 5);
+    assert_eq!(ob.trades_since(7).count(), 0);
+}
+
+#[test]
+fn test_sweep_cost() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    ob.place_order(Side::Sell, 11, 50, 2).unwrap();
+    ob.place_order(Side::Sell, 13, 50, 3).unwrap();
+    ob.place_order(Side::Buy, 9, 80, 4).unwrap();
+
+    let cost = ob.cost_to_buy(160);
+    assert_eq!(cost.filled_quantity, 160);
+    assert_eq!(cost.notional, 10 * 100 + 11 * 50 + 13 * 10);
+    assert_eq!(cost.worst_price, Some(13));
+    assert!(cost.fully_filled);
+    assert_eq!(cost.vwap(), Some(1680.0 / 160.0));
+
+    let cost = ob.cost_to_sell(100);
+    assert_eq!((cost.filled_quantity, cost.worst_price, cost.fully_filled), (80, Some(9), false));
+
+    // Estimating doesn't touch the book
+    assert_eq!(ob.best_sell(), Some((10, 100)));
+    assert_eq!(OrderBook::new().cost_to_buy(10).vwap(), None);
+}
+
+#[test]
+fn test_mid_spread_microprice() {
+    let mut ob = OrderBook::new();
+    assert_eq!(ob.mid_price(), None);
+    assert_eq!(ob.spread(), None);
+
+    ob.place_order(Side::Buy, 10, 300, 1).unwrap();
+    assert_eq!(ob.microprice(), None);
+
+    ob.place_order(Side::Sell, 13, 100, 2).unwrap();
+    assert_eq!(ob.mid_price(), Some(11.5));
+    assert_eq!(ob.spread(), Some(3));
+    // Three times as much bid as ask pulls the price towards the ask
+    assert_eq!(ob.microprice(), Some((10.0 * 100.0 + 13.0 * 300.0) / 400.0));
+
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, Units::MAX - 1, 1, 1).unwrap();
+    ob.place_order(Side::Sell, Units::MAX, 1, 2).unwrap();
+    assert_eq!(ob.spread(), Some(1));
+    assert!(ob.mid_price().unwrap() > 1e19);
+
+    // Crossed during an auction
+    let mut ob = OrderBook::new();
+    ob.start_auction();
+    ob.place_order(Side::Buy, 105, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 100, 10, 2).unwrap();
+    assert_eq!(ob.spread(), None);
+    assert_eq!(ob.mid_price(), Some(102.5));
+}
+
+#[test]
+fn test_mass_cancel() {
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Buy, 10, 100, 1).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 9, 100, 2).with_owner(8)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 10, 50, 3).with_owner(8)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 12, 100, 4).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 13, 100, 5).with_owner(8)).unwrap();
+    ob.submit(NewOrder::stop(Side::Buy, 14, 10, 6).with_owner(7)).unwrap();
+
+    let ids = |orders: Vec<Order>| orders.iter().map(|o| o.id).collect::<Vec<u64>>();
+    assert_eq!(ids(ob.cancel_by_owner(7).unwrap()), [1, 4]);
+    assert_eq!(ob.best_buy(), Some((10, 50)));
+    assert_eq!(ob.best_sell(), Some((13, 100)));
+
+    assert_eq!(ids(ob.cancel_where(|o| o.price == 9).unwrap()), [2]);
+    assert_eq!(ob.buy_at(9), None);
+
+    ob.place_order(Side::Sell, 14, 10, 7).unwrap();
+    assert_eq!(ids(ob.cancel_side(Side::Sell).unwrap()), [5, 7]);
+    assert_eq!(ids(ob.cancel_all().unwrap()), [3]);
+    assert_eq!(ob.order_count(), 0);
+    assert!(ob.cancel_order(3).is_err());
+    // Held stops aren't affected
+    assert!(ob.cancel_stop(6).is_ok());
+}
+
+#[test]
+fn test_good_till_expiry() {
+    let mut ob = OrderBook::new();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    ob.subscribe(sender);
+
+    ob.submit(NewOrder::limit(Side::Buy, 10, 100, 1).good_till(50)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 10, 100, 2).good_till(30)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 9, 100, 3)).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 5, 10, 4).good_till(40)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 12, 100, 5).good_till(20)).unwrap();
+    // Filled before it comes due
+    ob.submit(NewOrder::limit(Side::Sell, 12, 100, 6).good_till(25)).unwrap();
+    ob.place_order(Side::Buy, 12, 200, 7).unwrap();
+
+    assert!(ob.expire(10).unwrap().is_empty());
+    assert_eq!(ob.expire(40).unwrap(), [2, 4]);
+    assert_eq!(ob.buy_at(10), Some((10, 100)));
+    assert!(ob.cancel_stop(4).is_err());
+    assert_eq!(ob.expire(100).unwrap(), [1]);
+    assert_eq!(ob.best_buy(), Some((9, 100)));
+
+    let expired: Vec<u64> = receiver
+        .try_iter()
+        .filter_map(|event| match event {
+            Event::Order(OrderEvent::Expired { id, .. }) => Some(id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(expired, [2, 4, 1]);
+}
+
+#[test]
+fn test_injected_clock() {
+    let mut ob = OrderBook::with_clock(Box::new(TestClock::new(1_000, 10)));
+
+    ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    ob.place_order(Side::Sell, 10, 100, 2).unwrap();
+    let trades = ob.place_order(Side::Buy, 10, 150, 3).unwrap().trades;
+
+    let resting: Vec<(u64, u64, u64)> = ob.iter_asks().map(|o| (o.id, o.seq, o.timestamp)).collect();
+    assert_eq!(resting, [(2, 2, 1_010)]);
+    let stamps: Vec<(u64, u64)> = trades.iter().map(|t| (t.seq, t.timestamp)).collect();
+    assert_eq!(stamps, [(1, 1_020), (2, 1_020)]);
+
+    // Two books don't share a sequence
+    let mut other = OrderBook::new();
+    other.place_order(Side::Buy, 5, 10, 1).unwrap();
+    assert_eq!(other.iter_bids().next().map(|o| (o.seq, o.timestamp)), Some((1, 1)));
+
+    let mut clock = MonotonicClock::new();
+    let (first, second) = (clock.now(), clock.now());
+    assert!(first > 1_600_000_000_000_000_000 && second >= first);
+}
+
+#[test]
+fn test_stats() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 99, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 98, 5, 2).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 101, 30, 3).iceberg(10)).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 90, 5, 4)).unwrap();
+    ob.place_market_order(Side::Buy, 12, 5).unwrap();
+    ob.place_market_order(Side::Sell, 4, 6).unwrap();
+    ob.place_order(Side::Buy, 97, 0, 7).unwrap();
+    ob.cancel_order(2).unwrap();
+    ob.cancel_stop(4).unwrap();
+
+    let stats = ob.stats();
+    assert_eq!(
+        stats,
+        BookStats {
+            order_count: 2,
+            buy_levels: 1,
+            sell_levels: 1,
+            buy_quantity: 6,
+            sell_quantity: 18,
+            stop_count: 0,
+            trade_count: 3,
+            traded_volume: 16,
+            matches: 2,
+            cancels: 2,
+            rejects: 1,
+        }
+    );
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn test_stats_prometheus() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 99, 10, 1).unwrap();
+    let text = ob.stats().to_prometheus("BTC-USD");
+    assert!(text.contains("# TYPE orderbook_orders gauge\norderbook_orders{book=\"BTC-USD\"} 1\n"));
+    assert!(text.contains("orderbook_resting_quantity{book=\"BTC-USD\",side=\"buy\"} 10\n"));
+    assert!(text.contains("# TYPE orderbook_trades_total counter\norderbook_trades_total{book=\"BTC-USD\"} 0\n"));
+}
+
+#[test]
+fn test_level_totals_follow_every_change() {
+    let mut ob = OrderBook::new();
+    ob.set_self_trade_policy(SelfTradePolicy::Decrement);
+    ob.submit(NewOrder::limit(Side::Sell, 101, 30, 1).iceberg(10)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 101, 20, 2).with_owner(7)).unwrap();
+    ob.place_order(Side::Sell, 101, 5, 3).unwrap();
+    assert_eq!(ob.sell_at(101), Some((101, 35)));
+
+    // Iceberg slice filled and replenished
+    ob.place_market_order(Side::Buy, 12, 4).unwrap();
+    assert_eq!(ob.sell_at(101), Some((101, 33)));
+    assert_eq!(ob.stats().sell_quantity, 43);
+
+    // Amend down in place, then a self-trade decrement against owner 7
+    ob.modify_order(3, 101, 2).unwrap();
+    assert_eq!(ob.sell_at(101), Some((101, 30)));
+    ob.submit(NewOrder::limit(Side::Buy, 101, 25, 5).with_owner(7)).unwrap();
+    ob.cancel_order(1).unwrap();
+    assert_eq!(ob.best_sell(), None);
+    assert_eq!(ob.best_buy(), None);
+    assert_eq!(ob.depth(5), Depth::default());
+}
+
+#[test]
+fn test_overflow_safe_quantities() {
+    // Hidden iceberg quantity counts towards the level limit
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Sell, 100, Units::MAX - 5, 1).iceberg(10)).unwrap();
+    let report = ob.place_order(Side::Sell, 100, 6, 2).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::LevelOverflow));
+    assert_eq!(ob.place_order(Side::Sell, 100, 5, 3).unwrap().status, OrderOutcome::Rested);
+    assert_eq!(ob.cost_to_buy(Units::MAX).filled_quantity, 15);
+
+    // A snapshot that doesn't come from a book can't wrap a level total
+    let mut snapshot = ob.snapshot();
+    let mut extra = snapshot.sells[0].clone();
+    extra.id = 4;
+    extra.seq = 99;
+    snapshot.sells.push(extra);
+    assert_eq!(OrderBook::from_snapshot(snapshot).err(), Some(LoadError::LevelOverflow { side: Side::Sell, price: 100 }));
+    assert_eq!(OrderBook::from_snapshot(ob.snapshot()).unwrap().sell_at(100), Some((100, 15)));
+}
+
+#[test]
+fn test_validate_busy_book() {
+    let mut ob = OrderBook::new();
+    assert_eq!(ob.validate(), Ok(()));
+    ob.submit(NewOrder::limit(Side::Buy, 10, 100, 1).iceberg(20)).unwrap();
+    ob.place_order(Side::Buy, 10, 30, 2).unwrap();
+    ob.place_order(Side::Sell, 12, 50, 3).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 8, 10, 4)).unwrap();
+    ob.submit(NewOrder::trailing_stop(Side::Buy, TrailingOffset::Amount(3), 10, 5)).unwrap();
+    ob.submit(NewOrder::pegged(Side::Sell, PegReference::Primary, 0, 10, 6)).unwrap();
+    ob.place_market_order(Side::Sell, 50, 7).unwrap(); // replenishes the iceberg behind order 2
+    assert_eq!(ob.validate(), Ok(()));
+
+    // Crossed prices are expected while an auction collects orders
+    ob.start_auction();
+    ob.place_order(Side::Buy, 13, 40, 8).unwrap();
+    assert_eq!(ob.validate(), Ok(()));
+    ob.run_auction().unwrap();
+    ob.cancel_all().unwrap();
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+#[test]
+fn test_queue_position() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 50, 2).iceberg(5)).unwrap();
+    ob.place_order(Side::Sell, 100, 7, 3).unwrap();
+    ob.place_order(Side::Sell, 101, 4, 4).unwrap();
+
+    let position = ob.queue_position(3).unwrap();
+    assert_eq!(position, QueuePosition { side: Side::Sell, price: 100, orders_ahead: 2, quantity_ahead: 15 });
+    assert_eq!(ob.queue_position(4).map(|p| (p.orders_ahead, p.quantity_ahead)), Some((0, 0)));
+
+    ob.place_market_order(Side::Buy, 12, 5).unwrap(); // fills order 1, takes 2 off the iceberg slice
+    assert_eq!(ob.queue_position(3).map(|p| (p.orders_ahead, p.quantity_ahead)), Some((1, 3)));
+    ob.place_market_order(Side::Buy, 3, 6).unwrap(); // the iceberg refills behind order 3
+    assert_eq!(ob.queue_position(3).map(|p| (p.orders_ahead, p.quantity_ahead)), Some((0, 0)));
+    assert_eq!(ob.queue_position(2).map(|p| (p.orders_ahead, p.quantity_ahead)), Some((1, 7)));
+    assert_eq!(ob.order_status(2).unwrap().queue_position, 1);
+
+    ob.cancel_order(3).unwrap();
+    assert_eq!(ob.queue_position(2).map(|p| p.orders_ahead), Some(0));
+    assert_eq!(ob.queue_position(3), None);
+}
+
+#[test]
+fn test_diff() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 99, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 98, 10, 2).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 101, 30, 3).iceberg(10)).unwrap();
+    let mut mirror = OrderBook::restore(ob.snapshot()).unwrap();
+    assert!(ob.diff(&mirror).is_empty());
+
+    ob.place_order(Side::Sell, 102, 5, 4).unwrap();
+    mirror.cancel_order(2).unwrap();
+    mirror.place_order(Side::Buy, 99, 3, 5).unwrap();
+    mirror.modify_order(3, 101, 25).unwrap(); // gives up part of the iceberg reserve
+
+    let diff = ob.diff(&mirror);
+    assert_eq!(diff.levels_only_in_self, [(Side::Buy, 98), (Side::Sell, 102)]);
+    assert!(diff.levels_only_in_other.is_empty());
+    assert_eq!(
+        diff.level_mismatches,
+        [
+            LevelMismatch { side: Side::Buy, price: 99, quantity: (10, 13), hidden_quantity: (0, 0), orders: (1, 2) },
+            LevelMismatch { side: Side::Sell, price: 101, quantity: (10, 10), hidden_quantity: (20, 15), orders: (1, 1) },
+        ]
+    );
+    assert_eq!(diff.orders_only_in_self, [2, 4]);
+    assert_eq!(diff.orders_only_in_other, [5]);
+    assert_eq!(
+        diff.order_mismatches,
+        [OrderMismatch {
+            id: 3,
+            side: (Side::Sell, Side::Sell),
+            price: (101, 101),
+            quantity: (10, 10),
+            hidden_quantity: (20, 15)
+        }]
+    );
+    assert_eq!(mirror.diff(&ob).orders_only_in_self, [5]);
+}
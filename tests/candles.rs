@@ -0,0 +1,100 @@
+use orderbook::*;
+
+fn trade(timestamp: u64, price: Units, quantity: Units) -> Trade {
+    Trade {
+        price,
+        quantity,
+        maker_id: 1,
+        taker_id: 2,
+        taker_side: Side::Buy,
+        seq: 0,
+        timestamp,
+        maker_fee: 0,
+        taker_fee: 0,
+        maker_client_order_id: None,
+        taker_client_order_id: None,
+        maker_user_data: None,
+        taker_user_data: None,
+        maker_owner: None,
+        taker_owner: None,
+    }
+}
+
+#[test]
+fn test_candles_per_interval() {
+    let mut candles = CandleAggregator::new(&[10, 30, 10, 0]);
+    assert_eq!(candles.intervals().collect::<Vec<_>>(), [10, 30]);
+    candles.on_trades(&[trade(3, 100, 2), trade(7, 104, 1), trade(9, 98, 5), trade(12, 101, 4), trade(35, 99, 1)]);
+
+    let ten: Vec<_> = candles.completed(10).copied().collect();
+    assert_eq!(ten.len(), 2);
+    assert_eq!(
+        ten[0],
+        Candle { open_time: 0, open: 100, high: 104, low: 98, close: 98, volume: 8, notional: 794, trade_count: 3 }
+    );
+    assert_eq!(ten[0].vwap(), 99.25);
+    assert_eq!((ten[1].open_time, ten[1].open, ten[1].close, ten[1].volume), (10, 101, 101, 4));
+    assert_eq!(candles.live(10).map(|c| (c.open_time, c.close)), Some((30, 99)));
+
+    // The 30-unit candle still holds the first four trades
+    assert_eq!(candles.completed(30).count(), 1);
+    assert_eq!(candles.live(30).map(|c| (c.open_time, c.trade_count)), Some((30, 1)));
+    assert!(candles.completed(60).next().is_none());
+    assert!(candles.live(60).is_none());
+
+    candles.close_until(40);
+    assert!(candles.live(10).is_none());
+    assert!(candles.live(30).is_some());
+    assert_eq!(candles.completed(10).last().map(|c| c.open_time), Some(30));
+}
+
+#[test]
+fn test_candles_from_a_book() {
+    let mut ob = OrderBook::with_clock(Box::new(TestClock::new(0, 4)));
+    let mut candles = CandleAggregator::new(&[10]).with_history(2);
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 101, 10, 2).unwrap();
+    for id in 3..9 {
+        let report = ob.place_market_order(Side::Buy, 3, id).unwrap();
+        candles.on_trades(&report.trades);
+    }
+
+    // Only the last two completed candles are kept
+    let completed: Vec<_> = candles.completed(10).collect();
+    assert_eq!(completed.len(), 2);
+    assert!(completed[0].open_time < completed[1].open_time);
+    assert_eq!(candles.live(10).map(|c| c.close), Some(101));
+}
+
+#[test]
+fn test_candles_follow_busts_and_corrections() {
+    let mut candles = CandleAggregator::new(&[10]);
+    let prints = [(3, 100, 2), (5, 97, 1), (7, 104, 1), (12, 101, 4)];
+    let trades: Vec<_> = (1..).zip(prints).map(|(seq, (timestamp, price, quantity))| Trade { seq, ..trade(timestamp, price, quantity) }).collect();
+    candles.on_trades(&trades);
+    let busted = |seq, timestamp, price, quantity| TradeEvent::Busted { seq, timestamp, price, quantity };
+
+    // Busting the high print takes it out of the high, not just the volume
+    candles.on_trade_event(&busted(3, 7, 104, 1));
+    let first = candles.completed(10).next().copied().unwrap();
+    assert_eq!((first.open, first.high, first.low, first.close), (100, 100, 97, 97));
+    assert_eq!((first.volume, first.notional, first.trade_count), (3, 297, 2));
+    candles.on_trade_event(&busted(2, 5, 97, 1));
+    let first = candles.completed(10).next().copied().unwrap();
+    assert_eq!((first.high, first.low, first.close, first.volume), (100, 100, 100, 2));
+
+    let corrected = TradeEvent::Corrected { seq: 4, timestamp: 12, old_price: 101, old_quantity: 4, price: 95, quantity: 3 };
+    candles.on_trade_event(&corrected);
+    let live = candles.live(10).copied().unwrap();
+    assert_eq!((live.open, live.low, live.high, live.close), (95, 95, 95, 95));
+    assert_eq!((live.volume, live.notional, live.trade_count), (3, 285, 1));
+
+    // Trades no kept candle holds are ignored; intervals left without trades lose their candle
+    candles.on_trade_event(&busted(9, 12, 95, 3));
+    assert_eq!(candles.live(10).map(|c| c.trade_count), Some(1));
+    candles.on_trade_event(&busted(1, 3, 100, 2));
+    candles.on_trade_event(&busted(5, 55, 100, 2));
+    assert_eq!(candles.completed(10).count(), 0);
+    candles.on_trade_event(&busted(4, 12, 95, 3));
+    assert!(candles.live(10).is_none());
+}
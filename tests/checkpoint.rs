@@ -0,0 +1,125 @@
+use orderbook::*;
+
+#[test]
+fn test_checkpoint_restores_state_and_replays_the_tail() {
+    let mut ob = OrderBook::new();
+    ob.enable_log();
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 103, 40, 2).iceberg(10).good_till(500)).unwrap();
+    ob.place_order(Side::Sell, 102, 5, 3).unwrap();
+    ob.place_market_order(Side::Buy, 2, 4).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 98, 4, 5)).unwrap();
+    ob.submit(NewOrder::trailing_stop(Side::Sell, TrailingOffset::Amount(3), 2, 6)).unwrap();
+    ob.submit(NewOrder::pegged(Side::Buy, PegReference::Primary, -1, 6, 7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 105, 8, 8).with_client_order_id("ask 8").with_user_data(3)).unwrap();
+    ob.link_oco(3, 8, OcoMode::Reduce).unwrap();
+    ob.set_halt_policy(HaltPolicy::Queue);
+    ob.halt();
+    ob.place_order(Side::Buy, 102, 1, 9).unwrap();
+
+    let checkpoint = Checkpoint::from_bytes(&ob.checkpoint().to_bytes()).unwrap();
+    assert_eq!(checkpoint.log_position(), ob.log().unwrap().len());
+    let mut restored = OrderBook::restore_checkpoint(&checkpoint, &[], &[]).unwrap();
+    assert_eq!(restored.snapshot(), ob.snapshot());
+    assert_eq!(restored.queued_orders(), ob.queued_orders());
+    assert_eq!(restored.trading_state(), TradingState::Halted);
+    assert_eq!(restored.oco_partner(3), Some(8));
+    assert_eq!(restored.pegged_orders().collect::<Vec<_>>(), [7]);
+    let order = restored.cancel_order(8).unwrap();
+    assert_eq!((order.client_order_id, order.user_data), (Some(ClientOrderId::from("ask 8")), Some(3)));
+
+    ob.resume().unwrap();
+    ob.place_market_order(Side::Buy, 2, 10).unwrap();
+    ob.place_market_order(Side::Sell, 30, 11).unwrap();
+    ob.cancel_order(2).unwrap();
+    ob.place_order(Side::Buy, 99, 3, 12).unwrap();
+
+    let log = ob.log().unwrap();
+    let (tail, times) = (&log.entries()[checkpoint.log_position()..], &log.times()[checkpoint.log_position()..]);
+    let mut restored = OrderBook::restore_checkpoint(&checkpoint, tail, times).unwrap();
+    assert_eq!(restored.snapshot(), ob.snapshot());
+    assert_eq!(restored.last_trade_seq(), ob.last_trade_seq());
+    assert_eq!(restored.oco_partner(8), ob.oco_partner(8));
+    assert_eq!(restored.next_order_id(), ob.next_order_id());
+}
+
+#[test]
+fn test_checkpoint_keeps_positions_for_reduce_only_orders() {
+    let mut ob = OrderBook::new();
+    ob.enable_log();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 100, 10, 2).with_owner(8)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 90, 3, 3).with_owner(9)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 90, 3, 4).with_owner(7)).unwrap();
+    let checkpoint = ob.checkpoint();
+
+    ob.place_order(Side::Buy, 101, 10, 5).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 101, 10, 6).with_owner(8).reduce_only()).unwrap();
+    assert_eq!(ob.position(8).net_quantity, 0);
+
+    let log = ob.log().unwrap();
+    let (tail, times) = (&log.entries()[checkpoint.log_position()..], &log.times()[checkpoint.log_position()..]);
+    let checkpoint = Checkpoint::from_bytes(&checkpoint.to_bytes()).unwrap();
+    let restored = OrderBook::restore_checkpoint(&checkpoint, tail, times).unwrap();
+    for owner in [7, 8, 9] {
+        assert_eq!(restored.position(owner), ob.position(owner));
+    }
+    assert_eq!(restored.last_trade_seq(), ob.last_trade_seq());
+    assert_eq!(restored.snapshot(), ob.snapshot());
+}
+
+#[test]
+fn test_checkpoint_and_tail_keep_the_timestamps_a_full_replay_gives() {
+    let mut ob = OrderBook::new();
+    ob.set_clock(Box::new(TestClock::new(1_000, 7)));
+    ob.enable_log();
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 100, 5, 2).unwrap();
+    let checkpoint = Checkpoint::from_bytes(&ob.checkpoint().to_bytes()).unwrap();
+    ob.place_order(Side::Buy, 100, 4, 3).unwrap();
+    ob.place_market_order(Side::Sell, 3, 4).unwrap();
+    ob.place_order(Side::Buy, 100, 2, 5).unwrap();
+
+    let log = ob.log().unwrap();
+    let (tail, times) = (&log.entries()[checkpoint.log_position()..], &log.times()[checkpoint.log_position()..]);
+    let mut restored = OrderBook::restore_checkpoint(&checkpoint, tail, times).unwrap();
+    let replayed = OrderBook::replay(log).unwrap();
+    let level = |book: &OrderBook| book.snapshot().buys.iter().map(|o| (o.id, o.timestamp)).collect::<Vec<_>>();
+    assert_eq!(level(&restored), level(&replayed));
+    assert_eq!(level(&restored), level(&ob));
+
+    // The clock carries on after the tail's readings rather than starting over
+    restored.place_order(Side::Buy, 100, 1, 6).unwrap();
+    let last = level(&restored).last().copied().unwrap();
+    assert_eq!(last.0, 6);
+    assert!(last.1 > level(&ob).iter().map(|&(_, timestamp)| timestamp).max().unwrap());
+}
+
+#[test]
+fn test_checkpoint_is_compact() {
+    let mut ob = OrderBook::new();
+    for id in 1..=100 {
+        ob.place_order(Side::Buy, 1_000 + Units::from(id % 10), 5, id).unwrap();
+    }
+    // Well under the 88 bytes each order takes as fixed-width fields
+    assert!(ob.checkpoint().to_bytes().len() < 100 * 20);
+}
+
+#[test]
+fn test_bad_checkpoints_are_rejected() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    let bytes = ob.checkpoint().to_bytes();
+
+    assert_eq!(Checkpoint::from_bytes(b"nope"), Err(CheckpointError::BadMagic));
+    let mut newer = bytes.clone();
+    newer[4] = 10;
+    assert_eq!(Checkpoint::from_bytes(&newer), Err(CheckpointError::UnsupportedVersion(10)));
+
+    let truncated = Checkpoint::from_bytes(&bytes[..bytes.len() - 3]).unwrap();
+    assert_eq!(OrderBook::restore_checkpoint(&truncated, &[], &[]).err(), Some(CheckpointError::Truncated));
+    let mut padded = bytes.clone();
+    padded.push(0);
+    let padded = Checkpoint::from_bytes(&padded).unwrap();
+    assert_eq!(OrderBook::restore_checkpoint(&padded, &[], &[]).err(), Some(CheckpointError::Invalid("trailing bytes")));
+}
@@ -0,0 +1,51 @@
+use std::thread;
+
+use orderbook::*;
+
+#[test]
+fn test_concurrent_producers_and_readers() {
+    let book = ConcurrentOrderBook::new(OrderBook::new(), 5);
+
+    let producers: Vec<_> = (0..4u64)
+        .map(|t| {
+            let handle = book.handle();
+            thread::spawn(move || {
+                for i in 0..250 {
+                    let id = t * 1_000 + i;
+                    let (side, price) = if t % 2 == 0 { (Side::Buy, 100 - Units::from(i % 5)) } else { (Side::Sell, 101 + Units::from(i % 5)) };
+                    let report = handle.execute(Command::Place(NewOrder::limit(side, price, 10, id))).unwrap();
+                    assert_eq!(report.status, OrderOutcome::Rested);
+                }
+            })
+        })
+        .collect();
+    let reader = {
+        let handle = book.handle();
+        thread::spawn(move || {
+            let mut last_count = 0;
+            while last_count < 1_000 {
+                let view = handle.view();
+                assert!(view.order_count >= last_count);
+                last_count = view.order_count;
+            }
+        })
+    };
+    for producer in producers {
+        producer.join().unwrap();
+    }
+    reader.join().unwrap();
+
+    let handle = book.handle();
+    let reports = handle
+        .execute_batch(vec![Command::Place(NewOrder::market(Side::Buy, 30, 9_999)), Command::Cancel { id: 0 }])
+        .unwrap();
+    assert_eq!(reports[0].trades.len(), 3);
+    assert_eq!(reports[1].status, OrderOutcome::Cancelled);
+    let view = handle.view();
+    assert_eq!(view.best_buy, Some((100, 10 * 99)));
+    assert_eq!(view.last_trade_price, Some(101));
+
+    let ob = book.shutdown().unwrap();
+    assert_eq!(ob.order_count(), 996);
+    assert_eq!(handle.execute(Command::Cancel { id: 1 }), Err(BookClosed));
+}
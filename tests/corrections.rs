@@ -0,0 +1,84 @@
+use std::sync::mpsc;
+
+use orderbook::*;
+
+#[test]
+fn test_bust_and_correct_adjust_positions_fees_and_stats() {
+    let mut ob = OrderBook::new();
+    ob.enable_trade_history(10);
+    ob.enable_log();
+    ob.set_fee_schedule(FeeSchedule { taker_bps: 10, ..FeeSchedule::default() });
+    let (tx, rx) = mpsc::channel();
+    ob.subscribe(tx);
+
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 102, 5, 2).with_owner(7)).unwrap();
+    let report = ob.submit(NewOrder::market(Side::Buy, 15, 3).with_owner(8)).unwrap();
+    assert_eq!(report.trades[1].taker_side, Side::Buy);
+    assert_eq!((report.trades[1].maker_owner, report.trades[1].taker_owner), (Some(7), Some(8)));
+    assert_eq!(ob.fee_totals(8).taker_fees, 2);
+    while rx.try_recv().is_ok() {}
+
+    let busted = ob.bust_trade(2).unwrap();
+    assert_eq!((busted.price, busted.quantity), (102, 5));
+    let (timestamp, price, quantity) = (busted.timestamp, 102, 5);
+    assert_eq!(rx.try_recv().unwrap(), Event::Trade(TradeEvent::Busted { seq: 2, timestamp, price, quantity }));
+    assert_eq!(ob.recent_trades().iter().map(|trade| trade.seq).collect::<Vec<_>>(), [1]);
+    assert_eq!((ob.stats().trade_count, ob.stats().traded_volume), (1, 10));
+    assert_eq!(ob.last_trade_price(), Some(100));
+    let long = ob.position(8);
+    assert_eq!((long.net_quantity, long.open_notional, long.bought_quantity, long.trades), (10, 1000, 10, 1));
+    assert_eq!(ob.position(7).net_quantity, -10);
+    assert_eq!(ob.fee_totals(8), FeeTotals { taker_fees: 1, taker_trades: 1, ..FeeTotals::default() });
+    // The resting book doesn't get the quantity back
+    assert_eq!(ob.order_count(), 0);
+
+    let corrected = ob.correct_trade(1, 99, 8).unwrap();
+    assert_eq!((corrected.seq, corrected.price, corrected.quantity, corrected.taker_fee), (1, 99, 8, 1));
+    assert_eq!(ob.recent_trades()[0], corrected);
+    assert!(matches!(
+        rx.try_recv().unwrap(),
+        Event::Trade(TradeEvent::Corrected { seq: 1, old_price: 100, old_quantity: 10, price: 99, quantity: 8, .. })
+    ));
+    assert_eq!((ob.stats().trade_count, ob.stats().traded_volume), (1, 8));
+    assert_eq!(ob.last_trade_price(), Some(99));
+    assert_eq!((ob.position(8).net_quantity, ob.position(8).open_notional), (8, 792));
+    assert_eq!((ob.position(7).net_quantity, ob.position(7).open_notional), (-8, 792));
+
+    assert_eq!(ob.bust_trade(2), Err(CorrectionError::UnknownTrade(2)));
+    assert_eq!(ob.correct_trade(1, 0, 5), Err(CorrectionError::InvalidCorrection));
+    assert_eq!(ob.correct_trade(1, 99, 0), Err(CorrectionError::InvalidCorrection));
+    assert_eq!(OrderBookError::from(CorrectionError::UnknownTrade(4)).to_string(), "no retained trade with sequence number 4");
+
+    // Replaying the log into a book keeping history repeats the bust and correction
+    let mut text = Vec::new();
+    ob.log().unwrap().write_to(&mut text).unwrap();
+    let log = EventLog::read_from(text.as_slice()).unwrap();
+    assert!(log.entries().contains(&LogEntry::CorrectTrade { seq: 1, price: 99, quantity: 8 }));
+    let mut replayed = OrderBook::new();
+    replayed.enable_trade_history(10);
+    replayed.set_fee_schedule(FeeSchedule { taker_bps: 10, ..FeeSchedule::default() });
+    replayed.apply_log(&log).unwrap();
+    assert_eq!(replayed.recent_trades(), ob.recent_trades());
+    assert_eq!(replayed.position(8), ob.position(8));
+    assert_eq!(replayed.fee_totals(8), ob.fee_totals(8));
+}
+
+#[test]
+fn test_bust_after_the_position_closed_keeps_total_pnl() {
+    let mut ob = OrderBook::new();
+    ob.enable_trade_history(10);
+    ob.submit(NewOrder::limit(Side::Sell, 100, 8, 1)).unwrap();
+    ob.submit(NewOrder::market(Side::Buy, 8, 2).with_owner(8)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 110, 8, 3)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 110, 8, 4).with_owner(8)).unwrap();
+    assert_eq!((ob.position(8).net_quantity, ob.position(8).realized_pnl), (0, 80));
+
+    // Without the buy, the sale at 110 leaves a short worth the same at any price
+    ob.bust_trade(1).unwrap();
+    let position = ob.position(8);
+    assert_eq!((position.net_quantity, position.sold_quantity, position.bought_quantity, position.trades), (-8, 8, 0, 1));
+    for mark in [90, 110, 130] {
+        assert_eq!(position.realized_pnl + position.unrealized_pnl(mark), (110 - mark as i128) * 8);
+    }
+}
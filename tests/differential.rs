@@ -0,0 +1,155 @@
+use orderbook::*;
+use proptest::prelude::*;
+
+// Slow but obviously correct engine: a flat list of resting orders, searched in full for the
+// best counterparty on every fill.
+#[derive(Default)]
+struct ReferenceBook {
+    orders: Vec<RestingOrder>,
+    next_seq: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RestingOrder {
+    id: u64,
+    side: Side,
+    price: Units,
+    quantity: Units,
+    seq: u64,
+}
+
+impl ReferenceBook {
+    fn crosses(side: Side, limit: Option<Units>, resting: &RestingOrder) -> bool {
+        resting.side != side
+            && match (side, limit) {
+                (_, None) => true,
+                (Side::Buy, Some(limit)) => resting.price <= limit,
+                (Side::Sell, Some(limit)) => resting.price >= limit,
+            }
+    }
+
+    // Index of the counterparty with the best price, oldest first among equals
+    fn best_match(&self, side: Side, limit: Option<Units>) -> Option<usize> {
+        let candidates = self.orders.iter().enumerate().filter(|(_, o)| Self::crosses(side, limit, o));
+        match side {
+            Side::Buy => candidates.min_by_key(|(_, o)| (o.price, o.seq)),
+            Side::Sell => candidates.min_by_key(|(_, o)| (std::cmp::Reverse(o.price), o.seq)),
+        }
+        .map(|(i, _)| i)
+    }
+
+    // Trades as (price, quantity, maker, taker)
+    fn submit(
+        &mut self,
+        id: u64,
+        side: Side,
+        limit: Option<Units>,
+        quantity: Units,
+        time_in_force: TimeInForce,
+    ) -> Vec<(Units, Units, u64, u64)> {
+        let available: Units =
+            self.orders.iter().filter(|o| Self::crosses(side, limit, o)).map(|o| o.quantity).sum();
+        if time_in_force == TimeInForce::Fok && available < quantity {
+            return Vec::new();
+        }
+        self.next_seq += 1;
+        let seq = self.next_seq;
+
+        let mut trades = Vec::new();
+        let mut left = quantity;
+        while left > 0 {
+            let Some(i) = self.best_match(side, limit) else { break };
+            let maker = &mut self.orders[i];
+            let traded = left.min(maker.quantity);
+            trades.push((maker.price, traded, maker.id, id));
+            maker.quantity -= traded;
+            left -= traded;
+            if maker.quantity == 0 {
+                self.orders.remove(i);
+            }
+        }
+        if let (Some(price), TimeInForce::Gtc, true) = (limit, time_in_force, left > 0) {
+            self.orders.push(RestingOrder { id, side, price, quantity: left, seq });
+        }
+        trades
+    }
+
+    fn cancel(&mut self, id: u64) -> bool {
+        let before = self.orders.len();
+        self.orders.retain(|o| o.id != id);
+        self.orders.len() != before
+    }
+
+    // Resting (id, price, quantity) per side, in priority order
+    fn book(&self, side: Side) -> Vec<(u64, Units, Units)> {
+        let mut orders: Vec<&RestingOrder> = self.orders.iter().filter(|o| o.side == side).collect();
+        match side {
+            Side::Buy => orders.sort_by_key(|o| (std::cmp::Reverse(o.price), o.seq)),
+            Side::Sell => orders.sort_by_key(|o| (o.price, o.seq)),
+        }
+        orders.into_iter().map(|o| (o.id, o.price, o.quantity)).collect()
+    }
+}
+
+fn book_of<'a>(orders: impl Iterator<Item = &'a Order>) -> Vec<(u64, Units, Units)> {
+    orders.map(|o| (o.id, o.price, o.quantity)).collect()
+}
+
+#[derive(Debug, Clone)]
+enum Op {
+    Place { side: Side, price: Option<Units>, quantity: Units, time_in_force: TimeInForce },
+    Cancel(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let side = prop_oneof![Just(Side::Buy), Just(Side::Sell)];
+    let time_in_force = prop_oneof![
+        6 => Just(TimeInForce::Gtc),
+        1 => Just(TimeInForce::Ioc),
+        1 => Just(TimeInForce::Fok),
+    ];
+    let price = proptest::option::weighted(0.9, 95..105 as Units);
+    prop_oneof![
+        5 => (side, price, 1..40 as Units, time_in_force).prop_map(|(side, price, quantity, time_in_force)| Op::Place {
+            side,
+            price,
+            quantity,
+            time_in_force
+        }),
+        2 => any::<usize>().prop_map(Op::Cancel),
+    ]
+}
+
+fn run(ops: &[Op]) {
+    let mut ob = OrderBook::new();
+    let mut reference = ReferenceBook::default();
+    for (id, op) in (1..).zip(ops) {
+        match *op {
+            Op::Place { side, price, quantity, time_in_force } => {
+                let order = match price {
+                    Some(price) => NewOrder::limit(side, price, quantity, id),
+                    None => NewOrder::market(side, quantity, id),
+                };
+                let report = ob.submit(order.with_time_in_force(time_in_force)).unwrap();
+                let trades: Vec<_> =
+                    report.trades.iter().map(|t| (t.price, t.quantity, t.maker_id, t.taker_id)).collect();
+                assert_eq!(trades, reference.submit(id, side, price, quantity, time_in_force), "trades of order {}", id);
+            }
+            Op::Cancel(index) => {
+                let target = index as u64 % id;
+                assert_eq!(ob.cancel_order(target).is_ok(), reference.cancel(target), "cancel of {}", target);
+            }
+        }
+        assert_eq!(book_of(ob.iter_bids()), reference.book(Side::Buy), "bids after op {}", id);
+        assert_eq!(book_of(ob.iter_asks()), reference.book(Side::Sell), "asks after op {}", id);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn prop_matches_reference_model(ops in proptest::collection::vec(op(), 1..150)) {
+        run(&ops);
+    }
+}
@@ -0,0 +1,31 @@
+use std::error::Error;
+
+use orderbook::{CancelError, InvariantViolation, LoadError, OrderBook, OrderBookError, PlaceError, RestingOrder, Side};
+
+fn place_twice_and_cancel(ob: &mut OrderBook) -> Result<(), OrderBookError> {
+    ob.place_order(Side::Buy, 10, 5, 1)?;
+    ob.cancel_order(1)?;
+    ob.cancel_order(1)?;
+    Ok(())
+}
+
+#[test]
+fn errors_convert_into_order_book_error() {
+    let mut ob = OrderBook::new();
+    assert_eq!(place_twice_and_cancel(&mut ob), Err(OrderBookError::Cancel(CancelError::UnknownOrder(1))));
+
+    let err = OrderBookError::from(ob.load([RestingOrder::new(Side::Sell, 11, 0, 2)]).unwrap_err());
+    assert_eq!(err, OrderBookError::Load(LoadError::ZeroQuantity(2)));
+    assert_eq!(err.to_string(), "order 2 has zero quantity");
+    assert!(err.source().is_some());
+}
+
+#[test]
+fn invariant_violations_surface_under_one_variant() {
+    let violation = InvariantViolation("indexed order has no price level");
+    let expected = OrderBookError::Invariant(violation);
+    assert_eq!(OrderBookError::from(PlaceError::Invariant(violation)), expected);
+    assert_eq!(OrderBookError::from(CancelError::Invariant(violation)), expected);
+    assert_eq!(OrderBookError::from(LoadError::Invariant(violation)), expected);
+    assert_eq!(expected.to_string(), "order book invariant violated: indexed order has no price level");
+}
@@ -0,0 +1,84 @@
+use std::sync::mpsc;
+
+use orderbook::*;
+
+fn drain(rx: &mpsc::Receiver<Event>) -> Vec<Event> {
+    rx.try_iter().collect()
+}
+
+#[test]
+fn test_order_and_book_events() {
+    let mut ob = OrderBook::new();
+    let (tx, rx) = mpsc::channel();
+    ob.subscribe(tx);
+
+    ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    assert_eq!(
+        drain(&rx),
+        vec![
+            Event::Order(OrderEvent::Accepted { id: 1 }),
+            Event::Book(BookEvent::LevelAdded { side: Side::Sell, price: 10 }),
+            Event::Order(OrderEvent::Rested { id: 1, price: 10, quantity: 100 }),
+            Event::Book(BookEvent::BestPriceChanged { side: Side::Sell, price: Some(10) }),
+        ]
+    );
+
+    ob.place_order(Side::Buy, 10, 40, 2).unwrap();
+    assert_eq!(
+        drain(&rx),
+        vec![
+            Event::Order(OrderEvent::Accepted { id: 2 }),
+            Event::Order(OrderEvent::PartiallyFilled { id: 1, price: 10, quantity: 40, remaining: 60 }),
+            Event::Order(OrderEvent::Filled { id: 2, price: 10, quantity: 40 }),
+        ]
+    );
+
+    ob.place_market_order(Side::Buy, 100, 3).unwrap();
+    assert_eq!(
+        drain(&rx),
+        vec![
+            Event::Order(OrderEvent::Accepted { id: 3 }),
+            Event::Order(OrderEvent::Filled { id: 1, price: 10, quantity: 60 }),
+            Event::Order(OrderEvent::PartiallyFilled { id: 3, price: 10, quantity: 60, remaining: 40 }),
+            Event::Book(BookEvent::LevelRemoved { side: Side::Sell, price: 10 }),
+            Event::Order(OrderEvent::Cancelled { id: 3, remaining: 40 }),
+            Event::Book(BookEvent::BestPriceChanged { side: Side::Sell, price: None }),
+        ]
+    );
+}
+
+#[test]
+fn test_cancel_reject_and_trigger_events() {
+    let mut ob = OrderBook::new();
+    let (tx, rx) = mpsc::channel();
+    ob.subscribe(tx);
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 9, 100, 2).unwrap();
+    drain(&rx);
+
+    ob.cancel_order(1).unwrap();
+    assert_eq!(
+        drain(&rx),
+        vec![
+            Event::Order(OrderEvent::Cancelled { id: 1, remaining: 100 }),
+            Event::Book(BookEvent::LevelRemoved { side: Side::Buy, price: 10 }),
+            Event::Book(BookEvent::BestPriceChanged { side: Side::Buy, price: Some(9) }),
+        ]
+    );
+
+    ob.place_order(Side::Buy, 9, 0, 3).unwrap();
+    assert_eq!(drain(&rx), vec![Event::Order(OrderEvent::Rejected { id: 3, reason: RejectReason::ZeroQuantity })]);
+
+    ob.submit(NewOrder::stop(Side::Sell, 9, 10, 4)).unwrap();
+    ob.place_order(Side::Sell, 9, 5, 5).unwrap();
+    let events = drain(&rx);
+    assert!(events.contains(&Event::Order(OrderEvent::Triggered { id: 4 })));
+    assert!(events.contains(&Event::Order(OrderEvent::Filled { id: 4, price: 9, quantity: 10 })));
+    assert_eq!(ob.best_buy(), Some((9, 85)));
+
+    ob.submit(NewOrder::stop(Side::Sell, 5, 20, 6)).unwrap();
+    drain(&rx);
+    ob.cancel_stop(6).unwrap();
+    assert_eq!(drain(&rx), vec![Event::Order(OrderEvent::Cancelled { id: 6, remaining: 20 })]);
+}
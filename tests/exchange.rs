@@ -0,0 +1,138 @@
+use orderbook::*;
+
+#[test]
+fn test_exchange_routing() {
+    let mut ex = Exchange::new();
+    ex.add_symbol("AAPL").unwrap();
+    ex.add_symbol("MSFT").unwrap();
+    assert_eq!(ex.add_symbol("AAPL").unwrap_err(), ExchangeError::DuplicateSymbol("AAPL".to_string()));
+
+    ex.place_order("AAPL", Side::Buy, 10, 100, 1).unwrap();
+    ex.place_order("MSFT", Side::Buy, 20, 100, 2).unwrap();
+    assert_eq!(ex.book("AAPL").unwrap().best_buy(), Some((10, 100)));
+    assert_eq!(ex.book("MSFT").unwrap().best_buy(), Some((20, 100)));
+
+    // Ids are unique across books
+    assert_eq!(ex.place_order("MSFT", Side::Buy, 20, 100, 1).unwrap_err(), ExchangeError::DuplicateOrderId(1));
+    assert_eq!(ex.place_order("TSLA", Side::Buy, 20, 100, 3).unwrap_err(), ExchangeError::UnknownSymbol("TSLA".to_string()));
+
+    assert_eq!(ex.place_order("AAPL", Side::Sell, 10, 40, 4).unwrap().trades.len(), 1);
+    assert_eq!(ex.cancel_order(2).unwrap().quantity, 100);
+    assert_eq!(ex.cancel_order(2).unwrap_err(), ExchangeError::UnknownOrder(2));
+    assert_eq!(ex.modify_order(1, 10, 30).unwrap().trades.len(), 0);
+    assert_eq!(ex.book("AAPL").unwrap().best_buy(), Some((10, 30)));
+    assert_eq!(ex.symbol_of(4), Some("AAPL"));
+}
+
+#[test]
+fn test_exchange_stats() {
+    let mut ex = Exchange::new();
+    ex.add_symbol("AAPL").unwrap();
+    ex.add_symbol("MSFT").unwrap();
+
+    ex.place_order("AAPL", Side::Sell, 10, 100, 1).unwrap();
+    ex.place_order("AAPL", Side::Buy, 10, 30, 2).unwrap();
+    ex.place_order("AAPL", Side::Buy, 10, 20, 3).unwrap();
+    ex.place_order("MSFT", Side::Sell, 5, 10, 4).unwrap();
+    ex.place_order("MSFT", Side::Buy, 5, 15, 5).unwrap();
+    // A rejected order isn't counted as accepted
+    let report = ex.place_order("MSFT", Side::Buy, 5, 0, 6).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ZeroQuantity));
+
+    let aapl = ex.stats("AAPL").unwrap();
+    assert_eq!(aapl, SymbolStats { orders_accepted: 3, trade_count: 2, traded_volume: 50, resting_orders: 1 });
+    let total = ex.aggregate_stats();
+    assert_eq!(total, SymbolStats { orders_accepted: 5, trade_count: 3, traded_volume: 60, resting_orders: 2 });
+    assert_eq!(ex.stats("TSLA"), None);
+}
+
+#[test]
+fn test_instrument_rules() {
+    let mut ex = Exchange::new();
+    let grid = PriceConfig { tick_size: 5, price_scale: 2, lot_size: 10 };
+    ex.add_instrument(Instrument::new("ES").with_price_config(grid).with_max_order_quantity(100)).unwrap();
+    ex.add_instrument(Instrument::new("NQ").with_price_config(grid).rounding_orders()).unwrap();
+    assert_eq!(ex.book("ES").unwrap().price_config(), grid);
+    assert_eq!(ex.add_symbol("ES").unwrap_err(), ExchangeError::DuplicateSymbol("ES".to_string()));
+    let zero_tick = Instrument::new("CL").with_price_config(PriceConfig { tick_size: 0, ..grid });
+    assert_eq!(
+        ex.add_instrument(zero_tick).unwrap_err(),
+        ExchangeError::InvalidPriceConfig("CL".to_string(), PriceConfigError::ZeroTickSize)
+    );
+    let zero_lot = Instrument::new("CL").with_price_config(PriceConfig { lot_size: 0, ..grid });
+    assert_eq!(
+        ex.add_instrument(zero_lot).unwrap_err(),
+        ExchangeError::InvalidPriceConfig("CL".to_string(), PriceConfigError::ZeroLotSize)
+    );
+
+    // The book rejects what's off the grid, the exchange what's too big
+    assert_eq!(ex.place_order("ES", Side::Buy, 102, 10, 1).unwrap().status, OrderOutcome::Rejected(RejectReason::OffTick));
+    let report = ex.submit("ES", NewOrder::limit(Side::Buy, 100, 110, 2).with_client_order_id("big")).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::MaxOrderSize));
+    assert_eq!(report.client_order_id, Some(ClientOrderId::from("big")));
+    // Neither rejection counts as accepted
+    assert_eq!(ex.stats("ES").unwrap().orders_accepted, 0);
+
+    // NQ snaps orders onto its grid: buys down, sells up, quantities down to whole lots
+    ex.place_order("NQ", Side::Buy, 102, 25, 3).unwrap();
+    ex.place_order("NQ", Side::Sell, 111, 19, 4).unwrap();
+    assert_eq!(ex.book("NQ").unwrap().best_buy(), Some((100, 20)));
+    assert_eq!(ex.book("NQ").unwrap().best_sell(), Some((115, 10)));
+
+    ex.disable_symbol("NQ").unwrap();
+    assert!(!ex.instruments().is_enabled("NQ"));
+    assert_eq!(ex.place_order("NQ", Side::Buy, 100, 10, 5).unwrap_err(), ExchangeError::SymbolDisabled("NQ".to_string()));
+    assert_eq!(ex.cancel_order(3).unwrap().quantity, 20);
+    ex.enable_symbol("NQ").unwrap();
+    assert!(ex.place_order("NQ", Side::Buy, 100, 10, 5).is_ok());
+    assert_eq!(ex.disable_symbol("CL").unwrap_err(), ExchangeError::UnknownSymbol("CL".to_string()));
+    assert_eq!(ex.instruments().iter().count(), 2);
+}
+
+#[test]
+fn test_trading_hours() {
+    const HOUR: u64 = 60 * 60 * 1_000_000_000;
+    let day = TradingHours { open: 9 * HOUR, close: 17 * HOUR };
+    assert!(day.contains(24 * HOUR + 9 * HOUR) && !day.contains(24 * HOUR + 17 * HOUR));
+    let overnight = TradingHours { open: 22 * HOUR, close: 2 * HOUR };
+    assert!(overnight.contains(23 * HOUR) && overnight.contains(HOUR) && !overnight.contains(12 * HOUR));
+
+    let mut ex = Exchange::new();
+    ex.add_instrument(Instrument::new("ES").with_trading_hours(day)).unwrap();
+    // The book's default test clock reads from 1ns, just after midnight
+    let report = ex.place_order("ES", Side::Buy, 100, 10, 1).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::OutsideTradingHours));
+    ex.book_mut("ES").unwrap().set_clock(Box::new(TestClock::new(10 * HOUR, 1)));
+    assert_eq!(ex.place_order("ES", Side::Buy, 100, 10, 2).unwrap().status, OrderOutcome::Rested);
+
+    // Checking the hours doesn't use up a reading: trades carry the times a book sent the
+    // same orders directly would give them
+    let mut ob = OrderBook::new();
+    ob.set_clock(Box::new(TestClock::new(10 * HOUR, 1)));
+    ob.place_order(Side::Buy, 100, 10, 2).unwrap();
+    for id in 3..6 {
+        let direct = ob.place_order(Side::Sell, 100, 2, id).unwrap();
+        let routed = ex.place_order("ES", Side::Sell, 100, 2, id).unwrap();
+        assert_eq!(routed.trades[0].timestamp, direct.trades[0].timestamp);
+    }
+    assert_eq!(ex.book("ES").unwrap().best_buy(), ob.best_buy());
+
+    // A reading the book doesn't take, for a held stop or a rejected order, isn't left for the
+    // next order: each is checked at its own time
+    let mut ex = Exchange::new();
+    ex.add_instrument(Instrument::new("NQ").with_trading_hours(TradingHours { open: 0, close: 6 * HOUR })).unwrap();
+    ex.book_mut("NQ").unwrap().set_clock(Box::new(TestClock::new(HOUR, 12 * HOUR)));
+    let report = ex.submit("NQ", NewOrder::stop(Side::Buy, 110, 10, 1)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Pending);
+    let report = ex.place_order("NQ", Side::Buy, 100, 10, 2).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::OutsideTradingHours));
+    let report = ex.place_order("NQ", Side::Buy, 100, 0, 3).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ZeroQuantity));
+    let report = ex.place_order("NQ", Side::Buy, 100, 10, 4).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::OutsideTradingHours));
+    assert_eq!(ex.book("NQ").unwrap().order_count(), 0);
+
+    // Rejected orders aren't routed, so their ids are free again
+    assert_eq!(ex.symbol_of(2), None);
+    assert_eq!(ex.symbol_of(1), Some("NQ"));
+}
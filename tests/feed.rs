@@ -0,0 +1,148 @@
+use orderbook::*;
+
+#[test]
+fn test_l2_updates_keep_mirror_in_step() {
+    let mut ob = OrderBook::new();
+    let mut publisher = L2Publisher::new(0);
+    let mut mirror = BookMirror::new();
+
+    ob.place_order(Side::Buy, 99, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 101, 5, 2).unwrap();
+    ob.place_order(Side::Sell, 101, 7, 3).unwrap();
+    let messages = publisher.publish(&ob);
+    let update = |seq, side, price, action, quantity, order_count| {
+        MarketData::Update(L2Update { seq, side, price, action, quantity, order_count })
+    };
+    assert_eq!(
+        messages,
+        [update(1, Side::Buy, 99, LevelAction::Add, 10, 1), update(2, Side::Sell, 101, LevelAction::Add, 12, 2)]
+    );
+
+    ob.place_market_order(Side::Buy, 5, 4).unwrap();
+    ob.cancel_order(1).unwrap();
+    ob.place_order(Side::Buy, 100, 3, 5).unwrap();
+    let later = publisher.publish(&ob);
+    assert_eq!(
+        later,
+        [
+            update(3, Side::Buy, 99, LevelAction::Remove, 0, 0),
+            update(4, Side::Buy, 100, LevelAction::Add, 3, 1),
+            update(5, Side::Sell, 101, LevelAction::Change, 7, 1),
+        ]
+    );
+    assert!(publisher.publish(&ob).is_empty());
+
+    for message in messages.iter().chain(&later) {
+        mirror.apply(message).unwrap();
+    }
+    assert_eq!(mirror.depth(10), ob.depth(10));
+    assert_eq!((mirror.best_buy(), mirror.best_sell()), (ob.best_buy(), ob.best_sell()));
+    assert_eq!(mirror.seq(), 5);
+
+    // Replayed updates are skipped
+    mirror.apply(&later[0]).unwrap();
+    assert_eq!(mirror.depth(10), ob.depth(10));
+}
+
+#[test]
+fn test_change_tracking_feeds_the_same_updates() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 98, 4, 1).unwrap();
+    let (mut full, mut incremental) = (L2Publisher::new(3), L2Publisher::new(3));
+    ob.enable_change_tracking();
+    assert_eq!(incremental.publish(&ob), full.publish(&ob));
+
+    ob.place_order(Side::Buy, 99, 10, 2).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 101, 20, 3).iceberg(5)).unwrap();
+    ob.place_order(Side::Sell, 102, 7, 4).unwrap();
+    assert_eq!(ob.drain_changes(), [(Side::Buy, 99), (Side::Sell, 101), (Side::Sell, 102)]);
+    assert!(ob.drain_changes().is_empty());
+    assert_eq!(incremental.publish(&ob), full.publish(&ob));
+
+    // Trading, an iceberg refill, an in-place amend, a cross and a mass cancel
+    let mut check = |ob: &mut OrderBook| assert_eq!(incremental.publish_changes(ob), full.publish(ob));
+    ob.place_market_order(Side::Buy, 8, 5).unwrap();
+    check(&mut ob);
+    ob.modify_order(2, 99, 6).unwrap();
+    check(&mut ob);
+    ob.place_order(Side::Sell, 99, 6, 6).unwrap();
+    check(&mut ob);
+    ob.cancel_side(Side::Sell).unwrap();
+    check(&mut ob);
+    ob.place_order(Side::Buy, 98, 1, 7).unwrap();
+    check(&mut ob);
+    assert_eq!(incremental.snapshot(), full.snapshot());
+}
+
+#[test]
+fn test_mirror_detects_gaps_and_resyncs_from_snapshots() {
+    let mut ob = OrderBook::new();
+    let mut publisher = L2Publisher::new(4);
+    let mut mirror = BookMirror::new();
+
+    for id in 0..3 {
+        ob.place_order(Side::Sell, 101 + Units::from(id), 10, id).unwrap();
+    }
+    let messages = publisher.publish(&ob);
+    assert_eq!(messages.len(), 3);
+    mirror.apply(&messages[0]).unwrap();
+    // Update 2 is lost
+    assert_eq!(mirror.apply(&messages[2]), Err(SequenceGap { expected: 2, received: 3 }));
+    assert!(!mirror.is_synced());
+
+    ob.place_order(Side::Buy, 100, 4, 10).unwrap();
+    let messages = publisher.publish(&ob);
+    // The fourth update makes a snapshot due
+    assert!(matches!(messages[..], [MarketData::Update(_), MarketData::Snapshot(_)]));
+    assert!(mirror.apply(&messages[0]).is_err());
+    mirror.apply(&messages[1]).unwrap();
+    assert!(mirror.is_synced());
+    assert_eq!(mirror.depth(10), ob.depth(10));
+
+    // A late joiner starts from a requested snapshot
+    let mut late = BookMirror::new();
+    late.apply(&MarketData::Snapshot(publisher.snapshot())).unwrap();
+    assert_eq!(late.depth(10), ob.depth(10));
+    assert_eq!(late.seq(), 4);
+}
+
+#[test]
+fn test_bbo_ticker_publishes_touch_changes() {
+    let mut ob = OrderBook::new();
+    let mut ticker = BboTicker::new(BboMode::EveryChange);
+    assert_eq!(ticker.publish(&ob, 0), None);
+
+    ob.place_order(Side::Buy, 99, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 101, 5, 2).unwrap();
+    assert_eq!(ticker.publish(&ob, 0), Some(BboUpdate { seq: 1, bid: Some((99, 10)), ask: Some((101, 5)) }));
+
+    // Deeper levels don't matter, touch quantity does
+    ob.place_order(Side::Buy, 98, 10, 3).unwrap();
+    assert_eq!(ticker.publish(&ob, 0), None);
+    ob.place_order(Side::Sell, 101, 2, 4).unwrap();
+    assert_eq!(ticker.publish(&ob, 0), Some(BboUpdate { seq: 2, bid: Some((99, 10)), ask: Some((101, 7)) }));
+    ob.place_market_order(Side::Buy, 7, 5).unwrap();
+    assert_eq!(ticker.publish(&ob, 0), Some(BboUpdate { seq: 3, bid: Some((99, 10)), ask: None }));
+    assert_eq!(ticker.last().seq, 3);
+}
+
+#[test]
+fn test_conflated_bbo_ticker() {
+    let mut ob = OrderBook::new();
+    let mut ticker = BboTicker::new(BboMode::Conflated { interval: 10 });
+    ob.place_order(Side::Buy, 99, 10, 1).unwrap();
+    assert_eq!(ticker.publish(&ob, 100).map(|update| update.bid), Some(Some((99, 10))));
+
+    // Held until the interval passes, then published as the latest touch
+    ob.place_order(Side::Buy, 100, 1, 2).unwrap();
+    assert_eq!(ticker.publish(&ob, 105), None);
+    ob.place_order(Side::Buy, 100, 2, 3).unwrap();
+    assert_eq!(ticker.publish(&ob, 110), Some(BboUpdate { seq: 2, bid: Some((100, 3)), ask: None }));
+
+    // A change undone within the interval is never published
+    ob.place_order(Side::Buy, 101, 1, 4).unwrap();
+    assert_eq!(ticker.publish(&ob, 112), None);
+    ob.cancel_order(4).unwrap();
+    assert_eq!(ticker.publish(&ob, 130), None);
+    assert_eq!(ticker.seq(), 2);
+}
@@ -0,0 +1,57 @@
+use orderbook::*;
+
+#[test]
+fn test_fee_schedule_rates_and_rounding() {
+    let schedule = FeeSchedule { maker_bps: -2, taker_bps: 5, buy_flat: 1, sell_flat: 0, rounding: FeeRounding::Up };
+    // Notional 10_001: maker rebate -2.0002 -> -2, taker 5.0005 -> 6, buyer pays the flat fee
+    assert_eq!(schedule.fees(10_001, 1, Side::Buy), (-2, 7));
+    assert_eq!(schedule.fees(10_001, 1, Side::Sell), (-1, 6));
+
+    let down = FeeSchedule { rounding: FeeRounding::Down, ..schedule };
+    assert_eq!(down.fees(10_001, 1, Side::Sell), (-2, 5));
+
+    let nearest = FeeSchedule { maker_bps: -3, taker_bps: 3, buy_flat: 0, sell_flat: 0, rounding: FeeRounding::Nearest };
+    // Notional 5_000: +-1.5 rounds away from zero, notional 4_000: +-1.2 towards it
+    assert_eq!(nearest.fees(5_000, 1, Side::Buy), (-2, 2));
+    assert_eq!(nearest.fees(1_000, 4, Side::Buy), (-1, 1));
+
+    let huge = FeeSchedule { taker_bps: i64::MAX, ..FeeSchedule::default() };
+    assert_eq!(huge.fees(Units::MAX, Units::MAX, Side::Buy).1, i64::MAX);
+}
+
+#[test]
+fn test_fees_charged_on_trades_and_totalled_per_owner() {
+    let mut ob = OrderBook::new();
+    ob.set_fee_schedule(FeeSchedule { maker_bps: -1, taker_bps: 3, buy_flat: 0, sell_flat: 2, rounding: FeeRounding::Up });
+
+    ob.submit(NewOrder::limit(Side::Sell, 100, 100, 1).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 101, 100, 2)).unwrap();
+    let report = ob.submit(NewOrder::market(Side::Buy, 150, 3).with_owner(8)).unwrap();
+
+    // Notional 10_000 and 5_050; the makers are sellers and pay the flat fee
+    let fees: Vec<_> = report.trades.iter().map(|t| (t.maker_fee, t.taker_fee)).collect();
+    assert_eq!(fees, [(1, 3), (2, 2)]);
+    assert_eq!(ob.fee_totals(7), FeeTotals { maker_fees: 1, taker_fees: 0, maker_trades: 1, taker_trades: 0 });
+    assert_eq!(ob.fee_totals(8), FeeTotals { maker_fees: 0, taker_fees: 5, maker_trades: 0, taker_trades: 2 });
+    assert_eq!(ob.fee_totals(8).net(), 5);
+    assert_eq!(ob.all_fee_totals().count(), 2);
+
+    ob.clear_fee_schedule();
+    let report = ob.submit(NewOrder::market(Side::Buy, 10, 4).with_owner(8)).unwrap();
+    assert_eq!((report.trades[0].maker_fee, report.trades[0].taker_fee), (0, 0));
+    assert_eq!(ob.fee_totals(8).taker_trades, 2);
+}
+
+#[test]
+fn test_auction_trades_charge_fees() {
+    let mut ob = OrderBook::new();
+    ob.set_fee_schedule(FeeSchedule { maker_bps: 10, taker_bps: 20, ..FeeSchedule::default() });
+    ob.start_auction();
+    ob.submit(NewOrder::limit(Side::Buy, 100, 10, 1).with_owner(1)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 2).with_owner(2)).unwrap();
+    let result = ob.run_auction().unwrap();
+
+    assert_eq!((result.trades[0].maker_fee, result.trades[0].taker_fee), (1, 2));
+    assert_eq!(ob.fee_totals(1).maker_fees, 1);
+    assert_eq!(ob.fee_totals(2).taker_fees, 2);
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "ffi")]
+
+use std::ffi::c_void;
+use std::ptr;
+
+use orderbook::{
+    ObEvent, ObReport, ObTrade, OB_BUY, OB_ERR_DUPLICATE_ID, OB_ERR_EMPTY, OB_ERR_NULL, OB_ERR_SIDE,
+    OB_ERR_UNKNOWN_ORDER, OB_EVENT_ACCEPTED, OB_EVENT_BEST_PRICE_CHANGED, OB_EVENT_CANCELLED, OB_EVENT_FILLED,
+    OB_EVENT_LEVEL_REMOVED, OB_EVENT_PARTIALLY_FILLED, OB_OK, OB_SELL, OB_STATUS_CANCELLED, OB_STATUS_FILLED,
+    OB_STATUS_RESTED,
+};
+
+// Declared as in include/orderbook.h, so the calls go through the C ABI like a C caller's
+// and the book is as opaque as it is to one
+type ObBook = c_void;
+
+extern "C" {
+    fn ob_book_new() -> *mut ObBook;
+    fn ob_book_free(book: *mut ObBook);
+    fn ob_place_order(book: *mut ObBook, side: u8, price: u64, quantity: u64, id: u64, report: *mut ObReport) -> i32;
+    fn ob_place_market_order(book: *mut ObBook, side: u8, quantity: u64, id: u64, report: *mut ObReport) -> i32;
+    fn ob_cancel(book: *mut ObBook, id: u64, remaining: *mut u64) -> i32;
+    fn ob_best_price(book: *const ObBook, side: u8, price: *mut u64, quantity: *mut u64) -> i32;
+    fn ob_poll_events(book: *mut ObBook, events: *mut ObEvent, capacity: usize) -> usize;
+    fn ob_poll_trades(book: *mut ObBook, trades: *mut ObTrade, capacity: usize) -> usize;
+}
+
+fn poll_events(book: *mut ObBook) -> Vec<ObEvent> {
+    let mut events = Vec::new();
+    let mut buffer = [ObEvent::default(); 3];
+    loop {
+        let count = unsafe { ob_poll_events(book, buffer.as_mut_ptr(), buffer.len()) };
+        if count == 0 {
+            return events;
+        }
+        events.extend_from_slice(&buffer[..count]);
+    }
+}
+
+#[test]
+fn test_ffi_round_trip() {
+    unsafe {
+        let book = ob_book_new();
+        let mut report = ObReport::default();
+        assert_eq!(ob_place_order(book, OB_SELL, 101, 10, 1, &mut report), OB_OK);
+        assert_eq!(report.status, OB_STATUS_RESTED);
+        assert_eq!(ob_place_order(book, OB_SELL, 102, 10, 2, ptr::null_mut()), OB_OK);
+        assert_eq!(poll_events(book).len(), 7); // accepted, level added and rested twice, one best ask
+
+        assert_eq!(ob_place_order(book, OB_BUY, 102, 15, 3, &mut report), OB_OK);
+        assert_eq!(
+            report,
+            ObReport {
+                order_id: 3,
+                status: OB_STATUS_FILLED,
+                reject_reason: 0,
+                filled_quantity: 15,
+                remaining_quantity: 0,
+                trade_count: 2
+            }
+        );
+        let mut trades = [ObTrade::default(); 4];
+        assert_eq!(ob_poll_trades(book, trades.as_mut_ptr(), 1), 1);
+        assert_eq!((trades[0].maker_id, trades[0].taker_id, trades[0].price, trades[0].quantity), (1, 3, 101, 10));
+        assert_eq!(ob_poll_trades(book, trades.as_mut_ptr(), 4), 1);
+        assert_eq!((trades[0].maker_id, trades[0].price, trades[0].quantity, trades[0].seq), (2, 102, 5, 2));
+
+        let events = poll_events(book);
+        let kinds: Vec<u32> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                OB_EVENT_ACCEPTED,
+                OB_EVENT_FILLED,
+                OB_EVENT_PARTIALLY_FILLED,
+                OB_EVENT_LEVEL_REMOVED,
+                OB_EVENT_PARTIALLY_FILLED,
+                OB_EVENT_FILLED,
+                OB_EVENT_BEST_PRICE_CHANGED
+            ]
+        );
+        assert_eq!((events[2].id, events[2].price, events[2].quantity, events[2].remaining), (3, 101, 10, 5));
+        assert_eq!((events[6].side, events[6].has_price, events[6].price), (OB_SELL, true, 102));
+
+        let (mut price, mut quantity) = (0, 0);
+        assert_eq!(ob_best_price(book, OB_SELL, &mut price, &mut quantity), OB_OK);
+        assert_eq!((price, quantity), (102, 5));
+        assert_eq!(ob_best_price(book, OB_BUY, &mut price, &mut quantity), OB_ERR_EMPTY);
+
+        let mut remaining = 0;
+        assert_eq!(ob_cancel(book, 2, &mut remaining), OB_OK);
+        assert_eq!(remaining, 5);
+        let events = poll_events(book);
+        assert_eq!(events[0], ObEvent { kind: OB_EVENT_CANCELLED, id: 2, remaining: 5, ..ObEvent::default() });
+        assert_eq!((events[2].kind, events[2].has_price), (OB_EVENT_BEST_PRICE_CHANGED, false));
+
+        assert_eq!(ob_cancel(book, 2, &mut remaining), OB_ERR_UNKNOWN_ORDER);
+        assert_eq!(ob_place_market_order(book, OB_BUY, 5, 4, &mut report), OB_OK);
+        assert_eq!((report.status, report.filled_quantity), (OB_STATUS_CANCELLED, 0)); // nothing to buy
+        assert_eq!(ob_place_order(book, 2, 100, 1, 5, &mut report), OB_ERR_SIDE);
+        ob_place_order(book, OB_BUY, 100, 1, 6, &mut report);
+        assert_eq!(ob_place_order(book, OB_BUY, 100, 1, 6, &mut report), OB_ERR_DUPLICATE_ID);
+        ob_book_free(book);
+
+        assert_eq!(ob_place_order(ptr::null_mut(), OB_BUY, 100, 1, 1, &mut report), OB_ERR_NULL);
+        assert_eq!(ob_poll_events(ptr::null_mut(), ptr::null_mut(), 0), 0);
+        ob_book_free(ptr::null_mut());
+    }
+}
+
+#[test]
+fn test_ffi_header_is_current() {
+    let header = include_str!("../include/orderbook.h");
+    let source = include_str!("../src/ffi.rs");
+    let mut functions = 0;
+    for line in source.lines() {
+        let Some(signature) = line.strip_prefix("pub extern \"C\" fn ").or_else(|| line.strip_prefix("pub unsafe extern \"C\" fn "))
+        else {
+            continue;
+        };
+        let name = &signature[..signature.find('(').unwrap()];
+        assert!(header.contains(&format!("{}(", name)), "{} missing from include/orderbook.h; rerun cbindgen", name);
+        functions += 1;
+    }
+    assert_eq!(functions, 8);
+    for constant in source.lines().filter_map(|line| line.strip_prefix("pub const ")) {
+        let name = &constant[..constant.find(':').unwrap()];
+        assert!(header.contains(&format!("#define {} ", name)), "{} missing from include/orderbook.h; rerun cbindgen", name);
+    }
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "fix")]
+
+use orderbook::*;
+
+struct Client {
+    gateway: FixGateway,
+    seq: u64,
+}
+
+impl Client {
+    fn logged_on() -> Self {
+        let gateway = FixGateway::with_clock(OrderBook::new(), "ENGINE", "CLIENT", Box::new(TestClock::new(0, 1_000_000)));
+        let mut client = Self { gateway, seq: 0 };
+        let replies = client.send(FixMessage::new("A").with(98, 0).with(108, 30));
+        assert_eq!(replies[0].msg_type(), Some("A"));
+        client
+    }
+
+    // Send through the wire format and parse the replies back
+    fn send(&mut self, message: FixMessage) -> Vec<FixMessage> {
+        self.seq += 1;
+        let message = message.with(49, "CLIENT").with(56, "ENGINE").with(34, self.seq);
+        let replies = self.gateway.handle(&message.encode()).unwrap();
+        replies.iter().map(|raw| FixMessage::parse(raw).unwrap()).collect()
+    }
+}
+
+fn new_order(cl_ord_id: &str, side: char, quantity: u64, price: &str) -> FixMessage {
+    FixMessage::new("D")
+        .with(11, cl_ord_id)
+        .with(55, "XYZ")
+        .with(54, side)
+        .with(38, quantity)
+        .with(40, 2)
+        .with(44, price)
+}
+
+fn summary(reports: &[FixMessage]) -> Vec<(&str, &str, &str, &str, &str)> {
+    reports
+        .iter()
+        .map(|r| (r.get(11).unwrap(), r.get(150).unwrap(), r.get(39).unwrap(), r.get(14).unwrap(), r.get(151).unwrap()))
+        .collect()
+}
+
+#[test]
+fn test_fix_order_entry() {
+    let mut client = Client::logged_on();
+
+    let replies = client.send(new_order("s1", '2', 10, "101"));
+    assert_eq!(summary(&replies), [("s1", "0", "0", "0", "10")]);
+    assert_eq!(replies[0].get(49), Some("ENGINE"));
+    assert_eq!(replies[0].get(34), Some("2"));
+    assert_eq!(replies[0].get(52), Some("19700101-00:00:00.001"));
+    assert_eq!(replies[0].get(55), Some("XYZ"));
+
+    // Crossing buy: its ack, then a fill on each side of the trade
+    let replies = client.send(new_order("b1", '1', 4, "102"));
+    assert_eq!(
+        summary(&replies),
+        [("b1", "0", "0", "0", "4"), ("s1", "F", "1", "4", "6"), ("b1", "F", "2", "4", "0")]
+    );
+    assert_eq!((replies[1].get(31), replies[1].get(32)), (Some("101"), Some("4")));
+
+    let replies = client.send(FixMessage::new("G").with(41, "s1").with(11, "s2").with(54, '2').with(38, 8).with(44, "101"));
+    assert_eq!(summary(&replies), [("s2", "5", "1", "4", "4")]);
+    let replies = client.send(FixMessage::new("F").with(41, "s2").with(11, "s3").with(54, '2'));
+    assert_eq!(summary(&replies), [("s3", "4", "4", "4", "0")]);
+    assert_eq!(client.gateway.book().order_count(), 0);
+
+    let replies = client.send(FixMessage::new("F").with(41, "s2").with(11, "s4").with(54, '2'));
+    assert_eq!(replies[0].msg_type(), Some("9"));
+    assert_eq!(replies[0].get(102), Some("1"));
+
+    let replies = client.send(new_order("z", '1', 0, "100"));
+    assert_eq!((replies[0].get(39), replies[0].get(58)), (Some("8"), Some("ZeroQuantity")));
+    let replies = client.send(FixMessage::new("1").with(112, "ping"));
+    assert_eq!((replies[0].msg_type(), replies[0].get(112)), (Some("0"), Some("ping")));
+}
+
+#[test]
+fn test_fix_session_errors() {
+    let mut gateway = FixGateway::new(OrderBook::new(), "ENGINE", "CLIENT");
+    let order = new_order("1", '1', 5, "100").with(34, 1);
+    assert_eq!(gateway.handle(&order.encode()), Err(FixError::NotLoggedOn));
+
+    let mut raw = FixMessage::new("A").with(34, 2).encode();
+    let n = raw.len();
+    raw[n - 2] ^= 1;
+    assert_eq!(gateway.handle(&raw), Err(FixError::BadChecksum));
+    assert!(matches!(FixMessage::parse(b"8=FIX.4.2\x019=5\x0135=0\x0110=000\x01"), Err(FixError::Malformed(_))));
+
+    gateway.handle(&FixMessage::new("A").with(34, 3).encode()).unwrap();
+    assert!(gateway.is_logged_on());
+    let stale = FixMessage::new("0").with(34, 3).encode();
+    assert_eq!(gateway.handle(&stale), Err(FixError::SequenceTooLow { expected: 4, received: 3 }));
+    assert_eq!(gateway.handle(&new_order("1", '3', 5, "100").with(34, 4).encode()), Err(FixError::InvalidField(54)));
+}
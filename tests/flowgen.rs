@@ -0,0 +1,72 @@
+use orderbook::*;
+
+#[test]
+fn test_flow_is_reproducible_and_shaped_by_config() {
+    let config = FlowConfig { cancel_ratio: 0.25, market_ratio: 0.1, burst_probability: 0.0, ..FlowConfig::default() };
+    let flow: Vec<(u64, Command)> = FlowGenerator::new(7, config).take(10_000).collect();
+    assert_eq!(flow, FlowGenerator::new(7, config).take(10_000).collect::<Vec<_>>());
+    assert_ne!(flow, FlowGenerator::new(8, config).take(10_000).collect::<Vec<_>>());
+
+    assert!(flow.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    // 1000 commands a second on average, so 10_000 take about ten seconds
+    let seconds = flow.last().unwrap().0 as f64 / 1e9;
+    assert!((9.0..11.0).contains(&seconds), "{} seconds", seconds);
+
+    let mut placed = Vec::new();
+    let (mut cancels, mut markets) = (0, 0);
+    for (_, command) in &flow {
+        match command {
+            Command::Place(order) => {
+                assert!((1..=100).contains(&order.quantity));
+                placed.push(order.id);
+                if order.order_type == OrderType::Market {
+                    markets += 1;
+                }
+            }
+            Command::Cancel { id } => {
+                assert!(placed.contains(id));
+                cancels += 1;
+            }
+            Command::Modify { .. } => panic!("modify generated"),
+        }
+    }
+    assert!((2_250..2_750).contains(&cancels), "{} cancels", cancels);
+    assert!((550..950).contains(&markets), "{} market orders", markets);
+    assert_eq!(placed, (1..=placed.len() as u64).collect::<Vec<_>>());
+
+    // Driving a book with it keeps the book sane
+    let mut book = OrderBook::new();
+    let commands: Vec<Command> = flow.into_iter().map(|(_, command)| command).collect();
+    let reports = book.apply_batch(&commands);
+    assert!(reports.iter().any(|report| !report.trades.is_empty()));
+    assert!(book.order_count() > 0);
+    if let (Some((bid, _)), Some((ask, _))) = (book.best_buy(), book.best_sell()) {
+        assert!(bid < ask);
+    }
+}
+
+#[test]
+fn test_bursts_and_csv_output() {
+    let steady = FlowConfig { burst_probability: 0.0, ..FlowConfig::default() };
+    let bursty = FlowConfig { burst_probability: 0.05, burst_multiplier: 50.0, ..steady };
+    let end = |config| FlowGenerator::new(3, config).take(5_000).last().unwrap().0;
+    assert!(end(bursty) < end(steady) / 2);
+
+    let mut generator = FlowGenerator::new(1, FlowConfig { cancel_ratio: 0.5, ..FlowConfig::default() });
+    let mut csv = Vec::new();
+    generator.write_csv(200, &mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 201);
+    assert!(csv.lines().any(|line| line.ends_with(",cancel")));
+
+    let mut book = OrderBook::new();
+    let summary = replay(&mut book, csv.as_bytes(), FlowFormat::Csv, Vec::new(), ReplaySpeed::AsFastAsPossible).unwrap();
+    assert_eq!(summary.records, 200);
+
+    // Forgotten orders aren't cancelled
+    let mut generator = FlowGenerator::new(2, FlowConfig { cancel_ratio: 1.0, ..FlowConfig::default() });
+    let (_, first) = generator.next().unwrap();
+    assert!(matches!(first, Command::Place(ref order) if order.id == 1));
+    generator.forget(1);
+    assert!(matches!(generator.next(), Some((_, Command::Place(ref order))) if order.id == 2));
+}
@@ -0,0 +1,100 @@
+use orderbook::*;
+
+#[test]
+fn test_halt_rejects_orders_and_keeps_the_book() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    ob.submit(NewOrder::stop(Side::Buy, 100, 5, 2)).unwrap();
+    assert_eq!(ob.trading_state(), TradingState::Open);
+
+    ob.halt();
+    assert_eq!(ob.trading_state(), TradingState::Halted);
+    let report = ob.place_order(Side::Buy, 100, 5, 3).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::Halted));
+    // Reducing in place is allowed, a cancel/replace isn't
+    assert_eq!(ob.modify_order(1, 100, 8).unwrap().status, OrderOutcome::Rested);
+    assert_eq!(ob.modify_order(1, 101, 8).unwrap().status, OrderOutcome::Rejected(RejectReason::Halted));
+    assert_eq!(ob.best_sell(), Some((100, 8)));
+    ob.cancel_order(1).unwrap();
+
+    assert!(ob.resume().unwrap().is_empty());
+    assert_eq!(ob.trading_state(), TradingState::Open);
+    ob.start_auction();
+    assert_eq!(ob.trading_state(), TradingState::AuctionOnly);
+}
+
+#[test]
+fn test_halt_queues_orders_until_resume() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    ob.set_halt_policy(HaltPolicy::Queue);
+    ob.halt();
+
+    let report = ob.place_order(Side::Buy, 100, 4, 2).unwrap();
+    assert_eq!((report.status, report.remaining_quantity), (OrderOutcome::Pending, 4));
+    ob.place_order(Side::Buy, 100, 3, 3).unwrap();
+    ob.place_order(Side::Buy, 99, 3, 4).unwrap();
+    assert_eq!(ob.place_order(Side::Buy, 99, 3, 4), Err(PlaceError::DuplicateId(4)));
+    assert_eq!(ob.cancel_queued(3).unwrap().quantity, 3);
+    assert_eq!(ob.cancel_queued(3), Err(CancelError::UnknownOrder(3)));
+    assert_eq!(ob.queued_orders().len(), 2);
+    assert_eq!(ob.best_sell(), Some((100, 10)));
+
+    let reports = ob.resume().unwrap();
+    assert_eq!(reports.iter().map(|r| (r.order_id, r.status)).collect::<Vec<_>>(), [
+        (2, OrderOutcome::Filled),
+        (4, OrderOutcome::Rested)
+    ]);
+    assert!(ob.queued_orders().is_empty());
+    assert_eq!(ob.best_sell(), Some((100, 6)));
+}
+
+#[test]
+fn test_circuit_breaker_halts_on_large_moves() {
+    let mut ob = OrderBook::new();
+    // 5% within 100 clock units; the default clock ticks once per reading
+    ob.set_circuit_breaker(Some(CircuitBreaker { max_move_bps: 500, window: 100 }));
+    ob.place_order(Side::Sell, 100, 1, 1).unwrap();
+    ob.place_order(Side::Sell, 105, 1, 2).unwrap();
+    ob.place_order(Side::Sell, 106, 1, 3).unwrap();
+    ob.submit(NewOrder::stop(Side::Buy, 106, 1, 9)).unwrap();
+
+    ob.place_market_order(Side::Buy, 1, 4).unwrap();
+    ob.place_market_order(Side::Buy, 1, 5).unwrap();
+    assert_eq!(ob.trading_state(), TradingState::Open);
+    // 106 is 6% above the window's first trade at 100; the stop at 106 stays held
+    let report = ob.place_market_order(Side::Buy, 1, 6).unwrap();
+    assert_eq!(report.trades.len(), 1);
+    assert_eq!(ob.trading_state(), TradingState::Halted);
+    assert!(ob.triggered_stops().is_empty());
+
+    ob.resume().unwrap();
+    // The stop is checked again with the next order, and a new window starts at its trade
+    let report = ob.place_order(Side::Sell, 107, 1, 7).unwrap();
+    assert_eq!(ob.triggered_stops(), [9]);
+    assert_eq!(report.trades[0].price, 107);
+    assert_eq!(ob.trading_state(), TradingState::Open);
+}
+
+#[test]
+fn test_halt_and_resume_replay() {
+    let mut ob = OrderBook::new();
+    ob.enable_log();
+    ob.set_halt_policy(HaltPolicy::Queue);
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    ob.halt();
+    ob.place_order(Side::Buy, 100, 4, 2).unwrap();
+    ob.place_order(Side::Buy, 100, 4, 3).unwrap();
+    ob.cancel_queued(3).unwrap();
+    ob.resume().unwrap();
+
+    let mut file = Vec::new();
+    ob.log().unwrap().write_to(&mut file).unwrap();
+    let log = EventLog::read_from(file.as_slice()).unwrap();
+    assert_eq!(&log, ob.log().unwrap());
+
+    let mut replayed = OrderBook::new();
+    replayed.set_halt_policy(HaltPolicy::Queue);
+    replayed.apply_log(&log).unwrap();
+    assert_eq!(replayed.snapshot(), ob.snapshot());
+}
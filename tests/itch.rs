@@ -0,0 +1,68 @@
+use orderbook::*;
+
+#[test]
+fn test_wire_round_trip() {
+    let messages = [
+        WireMessage::Add { timestamp: 1, id: 7, side: Side::Sell, price: 10_100, quantity: 50 },
+        WireMessage::Add { timestamp: 2, id: 8, side: Side::Buy, price: u64::MAX, quantity: 1 },
+        WireMessage::Execute { timestamp: 3, id: 7, quantity: 20, match_number: 1 },
+        WireMessage::Cancel { timestamp: 4, id: 7 },
+        WireMessage::Replace { timestamp: 5, id: 8, price: 99, quantity: 2 },
+        WireMessage::Trade { timestamp: 6, match_number: 1, price: 10_100, quantity: 20, maker_id: 7, taker_id: 9 },
+    ];
+    let mut encoded = Vec::new();
+    for message in &messages {
+        let before = encoded.len();
+        encode_event(message, &mut encoded);
+        assert_eq!(encoded.len() - before, message.encoded_len());
+    }
+    assert_eq!(&encoded[..3], [0, 34, b'A']);
+
+    let (first, rest) = decode_event(&encoded).unwrap();
+    assert_eq!(first, messages[0]);
+    assert_eq!(rest.len(), encoded.len() - 36);
+    let decoded: Vec<WireMessage> = decode_all(&encoded).collect::<Result<_, _>>().unwrap();
+    assert_eq!(decoded, messages);
+
+    assert_eq!(decode_event(&encoded[..20]).err(), Some(WireError::Truncated));
+    let mut bad = encoded[..36].to_vec();
+    bad[19] = b'X';
+    assert_eq!(decode_event(&bad).err(), Some(WireError::BadSide(b'X')));
+    bad[2] = b'Z';
+    assert_eq!(decode_event(&bad).err(), Some(WireError::UnknownType(b'Z')));
+    assert_eq!(decode_event(&[0, 2, b'D', 0]).err(), Some(WireError::BadLength { message_type: b'D', length: 2 }));
+}
+
+#[test]
+fn test_recorded_session_replays() {
+    let commands = [
+        Command::Place(NewOrder::limit(Side::Sell, 101, 10, 1)),
+        Command::Place(NewOrder::limit(Side::Sell, 102, 10, 2)),
+        Command::Place(NewOrder::limit(Side::Buy, 101, 4, 3)),
+        Command::Modify { id: 2, price: 103, quantity: 6 },
+        Command::Place(NewOrder::limit(Side::Buy, 99, 8, 4)),
+        Command::Cancel { id: 4 },
+    ];
+    let mut ob = OrderBook::new();
+    let mut session = Vec::new();
+    for (timestamp, command) in (1..).zip(&commands) {
+        encode_event(&WireMessage::from_command(command, timestamp).unwrap(), &mut session);
+        for trade in ob.apply_batch(std::slice::from_ref(command)).remove(0).trades {
+            for message in WireMessage::from_trade(&trade).unwrap() {
+                encode_event(&message, &mut session);
+            }
+        }
+    }
+
+    let mut replayed = OrderBook::new();
+    let mut trades = Vec::new();
+    for message in decode_all(&session) {
+        let message = message.unwrap();
+        match message.to_command() {
+            Some(command) => trades.extend(replayed.apply_batch(&[command]).remove(0).trades),
+            None => assert!(matches!(message, WireMessage::Execute { .. } | WireMessage::Trade { .. })),
+        }
+    }
+    assert_eq!(replayed.snapshot(), ob.snapshot());
+    assert_eq!(trades.len(), 1);
+}
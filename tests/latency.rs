@@ -0,0 +1,53 @@
+#![cfg(feature = "latency")]
+
+use orderbook::*;
+
+#[test]
+fn test_histogram_percentiles() {
+    let mut histogram = LatencyHistogram::default();
+    assert_eq!(histogram.percentile(50.0), None);
+    assert_eq!(histogram.mean(), None);
+
+    for nanos in 1..=1000 {
+        histogram.record(nanos);
+    }
+    assert_eq!(histogram.count(), 1000);
+    assert_eq!(histogram.min(), Some(1));
+    assert_eq!(histogram.max(), Some(1000));
+    assert_eq!(histogram.mean(), Some(500.5));
+    // Small values are exact, larger ones within a bucket of about 3%
+    assert_eq!(histogram.percentile(1.0), Some(10));
+    for (percentile, exact) in [(50.0, 500), (90.0, 900), (99.0, 990)] {
+        let value = histogram.percentile(percentile).unwrap();
+        assert!(value >= exact && value <= exact + exact / 32, "p{percentile} = {value}");
+    }
+    assert_eq!(histogram.percentile(100.0), Some(1000));
+
+    histogram.record(u64::MAX);
+    assert_eq!(histogram.percentile(100.0), Some(u64::MAX));
+}
+
+#[test]
+fn test_latency_report() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 101, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 102, 10, 2).unwrap();
+    ob.place_order(Side::Buy, 101, 5, 3).unwrap();
+    ob.cancel_order(2).unwrap();
+    assert!(ob.cancel_order(2).is_err());
+
+    let report = ob.latency_report();
+    assert_eq!(report.place.count(), 3);
+    assert_eq!(report.matching.count(), 3);
+    assert_eq!(report.cancel.count(), 2);
+    assert!(report.place.max() >= report.matching.min());
+
+    // Orders collected for an auction don't match
+    ob.start_auction();
+    ob.place_order(Side::Buy, 100, 5, 4).unwrap();
+    assert_eq!(ob.latency_report().place.count(), 4);
+    assert_eq!(ob.latency_report().matching.count(), 3);
+
+    ob.reset_latency();
+    assert_eq!(ob.latency_report(), &LatencyReport::default());
+}
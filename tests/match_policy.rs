@@ -0,0 +1,148 @@
+use orderbook::*;
+
+fn fills(policy: &dyn MatchPolicy, quantity: Units, resting: &[Units]) -> Vec<Units> {
+    let mut fills = vec![0; resting.len()];
+    policy.allocate(quantity, resting, &mut fills);
+    fills
+}
+
+fn maker_fills(report: &ExecutionReport) -> Vec<(u64, Units)> {
+    report.trades.iter().map(|trade| (trade.maker_id, trade.quantity)).collect()
+}
+
+#[test]
+fn test_policies_allocate() {
+    assert_eq!(fills(&Fifo, 25, &[10, 30, 60]), [10, 15, 0]);
+    // 25 * 10/100 = 2.5, 25 * 30/100 = 7.5, 25 * 60/100 = 15: the 1 left over goes to the oldest
+    assert_eq!(fills(&ProRata, 25, &[10, 30, 60]), [3, 7, 15]);
+    assert_eq!(fills(&ProRata, 500, &[10, 30, 60]), [10, 30, 60]);
+    assert_eq!(fills(&ProRata, 1, &[1, 1, 1]), [1, 0, 0]);
+    // The top order takes 5, then 20 is shared over 5, 30 and 60 as 1, 6 and 12, and the 1 left
+    // over goes to the top order again
+    assert_eq!(fills(&TopOrderProRata { cap: Some(5) }, 25, &[10, 30, 60]), [5 + 2, 6, 12]);
+    assert_eq!(fills(&TopOrderProRata { cap: None }, 25, &[10, 30, 60]), [10, 5, 10]);
+}
+
+#[test]
+fn test_pro_rata_book() {
+    let mut ob = OrderBook::new();
+    ob.set_match_policy(ProRata);
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 100, 30, 2).unwrap();
+    ob.place_order(Side::Sell, 100, 60, 3).unwrap();
+    ob.place_order(Side::Sell, 101, 50, 4).unwrap();
+
+    let report = ob.place_order(Side::Buy, 100, 25, 5).unwrap();
+    assert_eq!(report.status, OrderOutcome::Filled);
+    assert_eq!(maker_fills(&report), [(1, 3), (2, 7), (3, 15)]);
+    assert_eq!(ob.sell_at(100), Some((100, 75)));
+
+    // Sweeping past the level fills it whole, then shares the next one
+    let report = ob.place_market_order(Side::Buy, 80, 6).unwrap();
+    assert_eq!(maker_fills(&report), [(1, 7), (2, 23), (3, 45), (4, 5)]);
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+#[test]
+fn test_pro_rata_refills_icebergs_between_rounds() {
+    let mut ob = OrderBook::new();
+    ob.set_match_policy(TopOrderProRata { cap: Some(2) });
+    ob.submit(NewOrder::limit(Side::Buy, 100, 30, 1).iceberg(10)).unwrap();
+    ob.place_order(Side::Buy, 100, 10, 2).unwrap();
+
+    // Round one fills both shown 10s; the iceberg then shows another 10 and takes the last 5
+    let report = ob.place_order(Side::Sell, 100, 25, 3).unwrap();
+    assert_eq!(maker_fills(&report), [(1, 10), (2, 10), (1, 5)]);
+    assert_eq!(ob.buy_at(100), Some((100, 5)));
+    assert_eq!(ob.order_status(1).unwrap().remaining_quantity, 15);
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+#[test]
+fn test_pro_rata_self_trade_prevention() {
+    let setup = |policy| {
+        let mut ob = OrderBook::new();
+        ob.set_match_policy(ProRata);
+        ob.set_self_trade_policy(policy);
+        ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_owner(1)).unwrap();
+        ob.submit(NewOrder::limit(Side::Sell, 100, 10, 2).with_owner(7)).unwrap();
+        ob.submit(NewOrder::limit(Side::Sell, 100, 10, 3).with_owner(2)).unwrap();
+        ob
+    };
+
+    // The taker's own order sits behind order 1, but the whole level trades at once
+    let mut ob = setup(SelfTradePolicy::CancelTaker);
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 10, 4).with_owner(7)).unwrap();
+    assert_eq!((report.status, report.trades.len()), (OrderOutcome::Cancelled, 0));
+
+    let mut ob = setup(SelfTradePolicy::CancelMaker);
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 10, 4).with_owner(7)).unwrap();
+    assert_eq!(maker_fills(&report), [(1, 5), (3, 5)]);
+    assert!(ob.order_status(2).is_none());
+    assert_eq!(ob.stats().cancels, 1);
+
+    let mut ob = setup(SelfTradePolicy::Decrement);
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 16, 4).with_owner(7)).unwrap();
+    assert_eq!(maker_fills(&report), [(1, 3), (3, 3)]);
+    assert_eq!(ob.sell_at(100), Some((100, 14)));
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+fn queue(ob: &OrderBook, side: Side, price: Units) -> Vec<u64> {
+    ob.orders_at(side, price).unwrap().map(|order| order.id).collect()
+}
+
+#[test]
+fn test_market_maker_tier_queues_first() {
+    let mut ob = OrderBook::new();
+    ob.set_priority_policy(PriorityPolicy::default().with_tier(7, 1)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_owner(1)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 20, 2).with_owner(7).iceberg(5)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 3).with_owner(2)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 4).with_owner(7)).unwrap();
+    assert_eq!(queue(&ob, Side::Sell, 100), [2, 4, 1, 3]);
+    assert_eq!(ob.order_status(1).unwrap().queue_position, 2);
+
+    // The iceberg's refill goes behind the rest of its tier, not behind everyone
+    let report = ob.place_order(Side::Buy, 100, 8, 5).unwrap();
+    assert_eq!(maker_fills(&report), [(2, 5), (4, 3)]);
+    assert_eq!(queue(&ob, Side::Sell, 100), [4, 2, 1, 3]);
+    assert_eq!(ob.validate(), Ok(()));
+
+    // Tiers survive a snapshot; a policy set later requeues what is resting
+    let mut restored = OrderBook::from_snapshot(ob.snapshot()).unwrap();
+    assert_eq!(queue(&restored, Side::Sell, 100), [4, 2, 1, 3]);
+    restored.set_priority_policy(PriorityPolicy::default()).unwrap();
+    assert_eq!(queue(&restored, Side::Sell, 100), [4, 2, 1, 3]);
+    ob.set_priority_policy(PriorityPolicy::default().with_tier(1, 2)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 6).with_owner(1)).unwrap();
+    assert_eq!(queue(&ob, Side::Sell, 100), [6, 4, 2, 1, 3]);
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+#[test]
+fn test_tie_break_for_same_timestamp_orders() {
+    let orders = || {
+        [(3, 5), (1, 10), (2, 20)].map(|(id, quantity)| RestingOrder::new(Side::Buy, 100, quantity, id))
+    };
+    let mut ob = OrderBook::new();
+    ob.load(orders()).unwrap();
+    assert_eq!(queue(&ob, Side::Buy, 100), [3, 1, 2]);
+
+    // Loaded orders share a timestamp; later ones queue behind them whatever the tie break
+    for (tie_break, expected) in [(TieBreak::LowerId, [1, 2, 3, 4]), (TieBreak::LargerQuantity, [2, 1, 3, 4])] {
+        let mut ob = OrderBook::new();
+        ob.set_priority_policy(PriorityPolicy::default().with_tie_break(tie_break)).unwrap();
+        ob.load(orders()).unwrap();
+        ob.place_order(Side::Buy, 100, 50, 4).unwrap();
+        assert_eq!(queue(&ob, Side::Buy, 100), expected);
+        assert_eq!(ob.validate(), Ok(()));
+    }
+
+    // Setting the policy on a loaded book breaks the ties it already holds
+    ob.set_priority_policy(PriorityPolicy::default().with_tie_break(TieBreak::LargerQuantity)).unwrap();
+    assert_eq!(queue(&ob, Side::Buy, 100), [2, 1, 3]);
+    let report = ob.place_order(Side::Sell, 100, 25, 5).unwrap();
+    assert_eq!(maker_fills(&report), [(2, 20), (1, 5)]);
+    assert_eq!(ob.validate(), Ok(()));
+}
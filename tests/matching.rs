@@ -0,0 +1,616 @@
+use orderbook::*;
+
+#[test]
+fn test_basic_match() {
+    let mut ob = OrderBook::new();
+
+    assert_eq!(ob.place_order(Side::Buy, 10, 100, 1).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 9, 200, 2).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 8, 300, 3).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 7, 400, 4).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 8, 500, 5).unwrap().trades.len(), 0);
+
+    assert_eq!(ob.place_order(Side::Sell, 11, 100, 6).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Sell, 12, 100, 7).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Sell, 13, 100, 8).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Sell, 14, 100, 9).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Sell, 15, 100, 10).unwrap().trades.len(), 0);
+
+    assert_eq!(ob.place_order(Side::Sell, 10, 100, 11).unwrap().trades.len(), 1);
+    assert_eq!(ob.place_order(Side::Sell, 10, 100, 12).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Sell, 8,  300, 13).unwrap().trades.len(), 2);
+    assert_eq!(ob.place_order(Side::Sell, 8,  100, 14).unwrap().trades.len(), 1);
+
+}
+
+#[test]
+fn test_fifo_priority() {
+    let mut ob = OrderBook::new();
+
+    assert_eq!(ob.place_order(Side::Buy, 10, 100, 1).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 10, 200, 2).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 10, 300, 3).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 9, 400, 4).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 9, 500, 5).unwrap().trades.len(), 0);
+
+    let trades = ob.place_order(Side::Sell, 10, 600, 10).unwrap().trades;
+
+    assert_eq!(trades.len(), 3);
+    assert_eq!(trades[0].maker_id, 1);
+    assert_eq!(trades[1].maker_id, 2);
+    assert_eq!(trades[2].maker_id, 3);
+}
+
+#[test]
+fn test_partial_fill() {
+    let mut ob = OrderBook::new();
+
+    assert_eq!(ob.place_order(Side::Buy, 10, 100, 1).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 10, 200, 2).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 10, 300, 3).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 9, 400, 4).unwrap().trades.len(), 0);
+    assert_eq!(ob.place_order(Side::Buy, 9, 500, 5).unwrap().trades.len(), 0);
+
+    println!("First partial fill");
+    let trades = ob.place_order(Side::Sell, 10, 199, 10).unwrap().trades;
+
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].maker_id, 1);
+    assert_eq!(trades[0].quantity, 100);
+    assert_eq!(trades[1].maker_id, 2);
+    assert_eq!(trades[1].quantity, 99);
+
+    println!("Second partial fill");
+    let trades = ob.place_order(Side::Sell, 10, 199, 11).unwrap().trades;
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].maker_id, 2);
+    assert_eq!(trades[0].quantity, 101);
+    assert_eq!(trades[1].maker_id, 3);
+    assert_eq!(trades[1].quantity, 98);
+}
+
+#[test]
+fn test_level_total_overflow_rejected() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 20, Units::MAX, 1).unwrap();
+    assert_eq!(ob.place_order(Side::Sell, 20, 1, 2).unwrap().trades.len(), 0);
+    assert_eq!(ob.sell_at(20), Some((20, Units::MAX)));
+    assert_eq!(ob.orders_at(Side::Sell, 20).unwrap().count(), 1);
+
+    // A different level is unaffected
+    ob.place_order(Side::Sell, 21, 5, 3).unwrap();
+    assert_eq!(ob.sell_at(21), Some((21, 5)));
+}
+
+#[test]
+fn test_price_band() {
+    let mut ob = OrderBook::new();
+    ob.set_price_band(5);
+
+    // No last trade yet: the band doesn't apply
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    assert_eq!(ob.place_order(Side::Sell, 100, 5, 2).unwrap().trades.len(), 1);
+    assert_eq!(ob.last_trade_price(), Some(100));
+
+    // Far-away orders are rejected and leave the book untouched
+    assert_eq!(ob.place_order(Side::Buy, 106, 10, 3).unwrap().trades.len(), 0);
+    assert_eq!(ob.buy_at(106), None);
+    assert_eq!(ob.place_order(Side::Sell, 94, 10, 4).unwrap().trades.len(), 0);
+    assert_eq!(ob.best_buy(), Some((100, 5)));
+    assert_eq!(ob.best_sell(), None);
+
+    // Within the band orders rest and match as usual
+    ob.place_order(Side::Sell, 105, 10, 5).unwrap();
+    assert_eq!(ob.best_sell(), Some((105, 10)));
+    assert_eq!(ob.place_order(Side::Sell, 95, 5, 6).unwrap().trades.len(), 1);
+    assert_eq!(ob.best_buy(), None);
+}
+
+#[test]
+fn test_market_order() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 11, 100, 1).unwrap();
+    ob.place_order(Side::Sell, 15, 100, 2).unwrap();
+    ob.place_order(Side::Buy, 9, 100, 3).unwrap();
+
+    // Sweeps through levels at any price
+    let trades = ob.place_market_order(Side::Buy, 150, 4).unwrap().trades;
+    assert_eq!(trades.len(), 2);
+    assert_eq!((trades[1].price, trades[1].quantity), (15, 50));
+    assert_eq!(ob.best_sell(), Some((15, 50)));
+
+    // The unfilled remainder is cancelled rather than resting
+    assert_eq!(ob.place_market_order(Side::Buy, 80, 5).unwrap().trades.len(), 1);
+    assert_eq!(ob.best_sell(), None);
+    assert_eq!(ob.best_buy(), Some((9, 100)));
+}
+
+#[test]
+fn test_market_order_reject_remainder() {
+    let mut ob = OrderBook::new();
+    ob.set_market_remainder(MarketRemainder::Reject);
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 9, 100, 2).unwrap();
+
+    assert_eq!(ob.place_market_order(Side::Sell, 201, 3).unwrap().trades.len(), 0);
+    assert_eq!(ob.best_buy(), Some((10, 100)));
+
+    assert_eq!(ob.place_market_order(Side::Sell, 200, 4).unwrap().trades.len(), 2);
+    assert_eq!(ob.best_buy(), None);
+}
+
+#[test]
+fn test_ioc_and_fok() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    ob.place_order(Side::Sell, 11, 100, 2).unwrap();
+    ob.place_order(Side::Sell, 13, 100, 3).unwrap();
+
+    // IOC fills what it can at its limit and never rests
+    let trades = ob.submit(NewOrder::limit(Side::Buy, 11, 250, 4).with_time_in_force(TimeInForce::Ioc)).unwrap().trades;
+    assert_eq!(trades.len(), 2);
+    assert_eq!(ob.best_buy(), None);
+    assert_eq!(ob.best_sell(), Some((13, 100)));
+
+    // FOK that can't be filled in full leaves the book untouched
+    ob.place_order(Side::Sell, 12, 100, 5).unwrap();
+    let trades = ob.submit(NewOrder::limit(Side::Buy, 12, 150, 6).with_time_in_force(TimeInForce::Fok)).unwrap().trades;
+    assert_eq!(trades.len(), 0);
+    assert_eq!(ob.best_sell(), Some((12, 100)));
+    assert_eq!(ob.best_buy(), None);
+
+    let trades = ob.submit(NewOrder::limit(Side::Buy, 13, 150, 7).with_time_in_force(TimeInForce::Fok)).unwrap().trades;
+    assert_eq!(trades.len(), 2);
+    assert_eq!(ob.best_sell(), Some((13, 50)));
+    assert_eq!(ob.best_buy(), None);
+}
+
+#[test]
+fn test_min_quantity_and_all_or_none() {
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).all_or_none()).unwrap();
+    ob.place_order(Side::Sell, 101, 5, 2).unwrap();
+
+    // Too small for the all-or-none order, the buy trades behind it and can't rest through it
+    let report = ob.place_order(Side::Buy, 101, 6, 3).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Cancelled, 5));
+    assert_eq!(report.trades[0].maker_id, 2);
+    assert_eq!(ob.best_sell(), Some((100, 10)));
+    assert_eq!(ob.best_buy(), None);
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 12, 4).with_time_in_force(TimeInForce::Ioc)).unwrap();
+    assert_eq!((report.filled_quantity, report.trades[0].maker_id), (10, 1));
+
+    // A taker short of its own minimum doesn't trade, unless it can rest clear of the book
+    ob.place_order(Side::Sell, 100, 5, 5).unwrap();
+    ob.place_order(Side::Sell, 101, 5, 6).unwrap();
+    let report = ob.submit(NewOrder::limit(Side::Buy, 101, 20, 7).with_min_quantity(15)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::InsufficientLiquidity));
+    assert_eq!(ob.order_count(), 2);
+    let report = ob.submit(NewOrder::limit(Side::Buy, 101, 20, 8).with_min_quantity(8)).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Rested, 10));
+    assert_eq!(ob.best_buy(), Some((101, 10)));
+
+    // Resting, it only trades in fills of its minimum, or all of what is left below that
+    let report = ob.place_order(Side::Sell, 101, 7, 9).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Cancelled, 0));
+    assert_eq!(ob.place_market_order(Side::Sell, 8, 10).unwrap().filled_quantity, 8);
+    assert_eq!(ob.place_market_order(Side::Sell, 1, 11).unwrap().filled_quantity, 0);
+    assert_eq!(ob.place_market_order(Side::Sell, 2, 12).unwrap().filled_quantity, 2);
+    assert_eq!(ob.best_buy(), None);
+
+    let report = ob.submit(NewOrder::limit(Side::Buy, 90, 20, 13).iceberg(5).with_min_quantity(10)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::MinQuantity));
+    // FOK and market orders under the reject policy count only what they'd really fill
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 14).all_or_none()).unwrap();
+    let fok = ob.submit(NewOrder::limit(Side::Buy, 100, 8, 15).with_time_in_force(TimeInForce::Fok)).unwrap();
+    assert_eq!(fok.status, OrderOutcome::Rejected(RejectReason::InsufficientLiquidity));
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+#[test]
+fn test_notional_orders() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 100, 5, 1).unwrap();
+    ob.place_order(Side::Sell, 102, 5, 2).unwrap();
+    ob.place_order(Side::Sell, 105, 10, 3).unwrap();
+
+    // 800 buys 5 at 100 and the 2 at 102 that the remaining 300 pays for
+    let report = ob.submit(NewOrder::market(Side::Buy, 1000, 4).with_notional(800)).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Filled, 7));
+    let fills: Vec<_> = report.trades.iter().map(|trade| (trade.maker_id, trade.quantity, trade.notional())).collect();
+    assert_eq!(fills, [(1, 5, 500), (2, 2, 204)]);
+
+    // A limit order rests with what is left of its notional at its limit price
+    let report = ob.submit(NewOrder::limit(Side::Buy, 103, 1000, 5).with_notional(1000)).unwrap();
+    assert_eq!((report.status, report.filled_quantity, report.remaining_quantity), (OrderOutcome::Rested, 3, 6));
+    assert_eq!(ob.best_buy(), Some((103, 6)));
+
+    let fok = NewOrder::limit(Side::Buy, 105, 1000, 6).with_notional(2000).with_time_in_force(TimeInForce::Fok);
+    assert_eq!(ob.submit(fok).unwrap().status, OrderOutcome::Rejected(RejectReason::InsufficientLiquidity));
+    let too_small = NewOrder::market(Side::Buy, 1000, 7).with_notional(50);
+    assert_eq!(ob.submit(too_small).unwrap().status, OrderOutcome::Rejected(RejectReason::InsufficientLiquidity));
+    let sell = NewOrder::market(Side::Sell, 1000, 8).with_notional(500);
+    assert_eq!(ob.submit(sell).unwrap().status, OrderOutcome::Rejected(RejectReason::NotionalOrder));
+    assert_eq!(ob.sell_at(105), Some((105, 10)));
+
+    // Passing over an all-or-none order, it still buys no more than the notional pays for
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).all_or_none()).unwrap();
+    ob.place_order(Side::Sell, 105, 10, 2).unwrap();
+    let report = ob.submit(NewOrder::market(Side::Buy, 1000, 3).with_notional(800)).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Filled, 7));
+    assert_eq!(report.trades.iter().map(Trade::notional).sum::<u128>(), 735);
+    assert_eq!(ob.validate(), Ok(()));
+}
+
+#[test]
+fn test_post_only() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 12, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 100, 2).unwrap();
+
+    assert_eq!(ob.submit(NewOrder::limit(Side::Buy, 11, 50, 3).post_only()).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rested));
+    assert_eq!(ob.best_buy(), Some((11, 50)));
+
+    assert_eq!(ob.submit(NewOrder::limit(Side::Buy, 12, 50, 4).post_only()).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::WouldCross)));
+    assert_eq!(ob.best_sell(), Some((12, 100)));
+
+    // Sliding reprices one tick behind the best bid of 11
+    ob.set_post_only_policy(PostOnlyPolicy::Slide);
+    assert_eq!(ob.submit(NewOrder::limit(Side::Sell, 9, 30, 5).post_only()).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rested));
+    assert_eq!(ob.best_sell(), Some((12, 130)));
+    assert_eq!(ob.best_buy(), Some((11, 50)));
+
+    // A modify's replacement is post-only too, so it can't be moved through the book
+    ob.set_post_only_policy(PostOnlyPolicy::Reject);
+    let report = ob.modify_order(3, 12, 50).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::WouldCross));
+    assert_eq!(ob.best_buy(), Some((11, 50)));
+    assert_eq!(ob.best_sell(), Some((12, 130)));
+}
+
+#[test]
+fn test_order_outcomes() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 40, 2).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Filled));
+    ob.place_order(Side::Buy, 10, 100, 3).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rested));
+    ob.place_market_order(Side::Sell, 500, 4).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Cancelled));
+    ob.place_order(Side::Buy, 10, 0, 5).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::ZeroQuantity)));
+}
+
+#[test]
+fn test_stop_orders() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    ob.place_order(Side::Sell, 12, 100, 2).unwrap();
+    ob.place_order(Side::Sell, 14, 100, 3).unwrap();
+
+    assert_eq!(ob.submit(NewOrder::stop(Side::Buy, 12, 150, 10)).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Pending));
+    assert_eq!(ob.submit(NewOrder::stop_limit(Side::Buy, 14, 14, 50, 11)).unwrap().trades.len(), 0);
+
+    // Trading at 10 doesn't reach either stop
+    assert_eq!(ob.place_order(Side::Buy, 10, 50, 20).unwrap().trades.len(), 1);
+    assert!(ob.triggered_stops().is_empty());
+
+    // A trade at 12 triggers the stop market order, whose sweep to 14 triggers the stop limit
+    let trades: Vec<(Units, Units, u64)> = ob
+        .place_order(Side::Buy, 12, 60, 21).unwrap()
+        .trades
+        .iter()
+        .map(|t| (t.price, t.quantity, t.taker_id))
+        .collect();
+    assert_eq!(trades, vec![(10, 50, 21), (12, 10, 21), (12, 90, 10), (14, 60, 10), (14, 40, 11)]);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Filled));
+    assert_eq!(ob.triggered_stops(), &[10, 11]);
+    assert_eq!(ob.best_sell(), None);
+    assert_eq!(ob.best_buy(), Some((14, 10)));
+}
+
+#[test]
+fn test_stop_best_price_trigger_and_cancel() {
+    let mut ob = OrderBook::new();
+    ob.set_stop_trigger(StopTrigger::BestPrice);
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 8, 100, 2).unwrap();
+    ob.submit(NewOrder::stop_limit(Side::Sell, 9, 7, 30, 10)).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 9, 30, 11)).unwrap();
+
+    assert_eq!(ob.cancel_stop(11).unwrap().quantity, 30);
+    assert_eq!(ob.cancel_stop(11).unwrap_err(), CancelError::UnknownOrder(11));
+
+    // Pulling the best bid drops it to 8; the stop limit fires on the next submitted order
+    ob.cancel_order(1).unwrap();
+    let trades = ob.place_order(Side::Buy, 5, 1, 3).unwrap().trades;
+    assert_eq!(trades.len(), 1);
+    assert_eq!((trades[0].price, trades[0].quantity, trades[0].taker_id), (8, 30, 10));
+    assert_eq!(ob.triggered_stops(), &[10]);
+    assert_eq!(ob.buy_at(8), Some((8, 70)));
+}
+
+#[test]
+fn test_iceberg_replenish() {
+    let mut ob = OrderBook::new();
+
+    ob.submit(NewOrder::limit(Side::Sell, 10, 250, 1).iceberg(100)).unwrap();
+    ob.place_order(Side::Sell, 10, 50, 2).unwrap();
+
+    // Only the displayed slice shows
+    assert_eq!(ob.best_sell(), Some((10, 150)));
+    assert_eq!(ob.aggregated_depth(Side::Sell, 1, 1), vec![(10, 150)]);
+
+    // Filling the slice replenishes it behind order 2
+    let trades: Vec<(u64, Units)> = ob.place_order(Side::Buy, 10, 120, 3).unwrap().trades.iter().map(|t| (t.maker_id, t.quantity)).collect();
+    assert_eq!(trades, vec![(1, 100), (2, 20)]);
+    let queue: Vec<(u64, Units)> = ob.orders_at(Side::Sell, 10).unwrap().map(|o| (o.id, o.quantity)).collect();
+    assert_eq!(queue, vec![(2, 30), (1, 100)]);
+    assert_eq!(ob.best_sell(), Some((10, 130)));
+
+    // A large taker goes through the reserve slice by slice
+    let trades: Vec<(u64, Units)> = ob.place_order(Side::Buy, 10, 500, 4).unwrap().trades.iter().map(|t| (t.maker_id, t.quantity)).collect();
+    assert_eq!(trades, vec![(2, 30), (1, 100), (1, 50)]);
+    assert_eq!(ob.best_sell(), None);
+    assert_eq!(ob.best_buy(), Some((10, 320)));
+}
+
+#[test]
+fn test_iceberg_fok_sees_reserve() {
+    let mut ob = OrderBook::new();
+
+    ob.submit(NewOrder::limit(Side::Buy, 10, 300, 1).iceberg(50)).unwrap();
+    let trades = ob.submit(NewOrder::limit(Side::Sell, 10, 300, 2).with_time_in_force(TimeInForce::Fok)).unwrap().trades;
+    assert_eq!(trades.len(), 6);
+    assert_eq!(ob.cancel_order(1).unwrap_err(), CancelError::UnknownOrder(1));
+}
+
+#[test]
+fn test_self_trade_prevention() {
+    let setup = |policy: SelfTradePolicy| {
+        let mut ob = OrderBook::new();
+        ob.set_self_trade_policy(policy);
+        ob.submit(NewOrder::limit(Side::Sell, 10, 50, 1).with_owner(7)).unwrap();
+        ob.submit(NewOrder::limit(Side::Sell, 10, 50, 2).with_owner(8)).unwrap();
+        ob
+    };
+    let taker = || NewOrder::limit(Side::Buy, 10, 80, 3).with_owner(7);
+
+    let mut ob = setup(SelfTradePolicy::CancelTaker);
+    assert_eq!(ob.submit(taker()).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Cancelled));
+    assert_eq!(ob.sell_at(10), Some((10, 100)));
+
+    let mut ob = setup(SelfTradePolicy::CancelMaker);
+    let trades = ob.submit(taker()).unwrap().trades;
+    assert_eq!((trades.len(), trades[0].maker_id, trades[0].quantity), (1, 2, 50));
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rested));
+    assert_eq!(ob.best_buy(), Some((10, 30)));
+    assert_eq!(ob.best_sell(), None);
+    assert_eq!(ob.stats().cancels, 1);
+
+    let mut ob = setup(SelfTradePolicy::CancelBoth);
+    assert_eq!(ob.submit(taker()).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Cancelled));
+    assert_eq!(ob.orders_at(Side::Sell, 10).unwrap().map(|o| o.id).collect::<Vec<_>>(), vec![2]);
+    // The taker never rested, so only the maker counts as cancelled
+    assert_eq!(ob.stats().cancels, 1);
+
+    let mut ob = setup(SelfTradePolicy::Decrement);
+    let trades = ob.submit(taker()).unwrap().trades;
+    assert_eq!((trades.len(), trades[0].maker_id, trades[0].quantity), (1, 2, 30));
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Filled));
+    assert_eq!(ob.orders_at(Side::Sell, 10).unwrap().map(|o| (o.id, o.quantity)).collect::<Vec<_>>(), vec![(2, 20)]);
+    // The maker was decremented to nothing, so it counts as cancelled
+    assert_eq!(ob.stats().cancels, 1);
+
+    let mut ob = setup(SelfTradePolicy::Decrement);
+    ob.submit(NewOrder::limit(Side::Buy, 10, 20, 3).with_owner(7)).unwrap();
+    assert_eq!(ob.orders_at(Side::Sell, 10).unwrap().map(|o| (o.id, o.quantity)).collect::<Vec<_>>(), vec![(1, 30), (2, 50)]);
+    assert_eq!(ob.stats().cancels, 0);
+
+    // Orders without an owner never trigger prevention
+    let mut ob = setup(SelfTradePolicy::CancelTaker);
+    assert_eq!(ob.place_order(Side::Buy, 10, 80, 4).unwrap().trades.len(), 2);
+}
+
+#[test]
+fn test_self_trade_prevention_fok() {
+    let mut ob = OrderBook::new();
+    ob.submit(NewOrder::limit(Side::Sell, 10, 50, 1).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Sell, 11, 50, 2).with_owner(8)).unwrap();
+
+    // The own order blocks the walk, so 60 can't be filled atomically
+    let fok = NewOrder::limit(Side::Buy, 11, 60, 3).with_owner(7).with_time_in_force(TimeInForce::Fok);
+    assert_eq!(ob.submit(fok).unwrap().trades.len(), 0);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::InsufficientLiquidity)));
+    assert_eq!(ob.best_sell(), Some((10, 50)));
+}
+
+#[test]
+fn test_tick_and_lot_sizes() {
+    let mut ob = OrderBook::new();
+    let config = PriceConfig { tick_size: 5, price_scale: 2, lot_size: 10 };
+    ob.set_price_config(config).unwrap();
+    assert_eq!(ob.set_price_config(PriceConfig { lot_size: 0, ..config }), Err(PriceConfigError::ZeroLotSize));
+    assert_eq!(ob.set_price_config(PriceConfig { price_scale: 20, ..config }), Err(PriceConfigError::ScaleTooLarge));
+    assert_eq!(ob.price_config(), config);
+
+    ob.place_order(Side::Buy, 10_005, 25, 1).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::OffLot)));
+    ob.place_order(Side::Buy, 10_003, 20, 2).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::OffTick)));
+    ob.submit(NewOrder::stop_limit(Side::Sell, 9_995, 9_991, 10, 3)).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::OffTick)));
+    ob.submit(NewOrder::limit(Side::Sell, 10_020, 30, 4).iceberg(15)).unwrap();
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::OffLot)));
+    assert_eq!(ob.order_count(), 0);
+
+    ob.place_order(Side::Sell, 10_010, 20, 5).unwrap();
+    // A post-only buy that would cross slides one tick, not one raw unit, below the ask
+    ob.set_post_only_policy(PostOnlyPolicy::Slide);
+    ob.submit(NewOrder::limit(Side::Buy, 10_010, 10, 6).post_only()).unwrap();
+    assert_eq!(ob.best_buy(), Some((10_005, 10)));
+
+    assert_eq!(config.format_price(10_005), "100.05");
+    assert_eq!(config.parse_price("100.05"), Some(10_005));
+    assert_eq!(config.parse_price("100.5"), Some(10_050));
+    assert_eq!(config.parse_price("100"), Some(10_000));
+    assert_eq!(config.parse_price("1.234"), None);
+    assert_eq!(config.parse_price("-1"), None);
+    assert_eq!(config.price_to_f64(10_005), 100.05);
+}
+
+#[test]
+fn test_execution_report() {
+    let mut ob = OrderBook::new();
+
+    let report = ob.place_order(Side::Sell, 10, 100, 1).unwrap();
+    assert_eq!((report.status, report.remaining_quantity, report.resting_id), (OrderOutcome::Rested, 100, Some(1)));
+    assert!(report.trades.is_empty());
+
+    // The report owns its trades, so the book can be used while holding it
+    let report = ob.place_order(Side::Buy, 10, 150, 2).unwrap();
+    ob.place_order(Side::Sell, 11, 10, 3).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rested);
+    assert!(report.is_partially_filled());
+    assert_eq!((report.filled_quantity, report.remaining_quantity, report.resting_id), (100, 50, Some(2)));
+    assert_eq!(report.trades.len(), 1);
+
+    let report = ob.submit(NewOrder::limit(Side::Sell, 10, 80, 4).with_time_in_force(TimeInForce::Ioc)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Cancelled);
+    assert_eq!((report.filled_quantity, report.remaining_quantity, report.resting_id), (50, 30, None));
+
+    let report = ob.place_market_order(Side::Buy, 10, 5).unwrap();
+    assert_eq!((report.status, report.filled_quantity, report.remaining_quantity), (OrderOutcome::Filled, 10, 0));
+
+    let report = ob.place_order(Side::Buy, 10, 0, 6).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::ZeroQuantity));
+
+    ob.place_order(Side::Buy, 8, 40, 7).unwrap();
+    let report = ob.modify_order(7, 8, 25).unwrap();
+    assert_eq!((report.status, report.remaining_quantity, report.resting_id), (OrderOutcome::Rested, 25, Some(7)));
+    let report = ob.modify_order(7, 8, 0).unwrap();
+    assert_eq!((report.status, report.remaining_quantity, report.resting_id), (OrderOutcome::Cancelled, 25, None));
+}
+
+#[test]
+fn test_duplicate_ids_and_generated_ids() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 5, 10, 2)).unwrap();
+    assert_eq!(ob.place_order(Side::Sell, 12, 50, 1), Err(PlaceError::DuplicateId(1)));
+    assert_eq!(ob.place_market_order(Side::Sell, 50, 2), Err(PlaceError::DuplicateId(2)));
+    assert_eq!(ob.best_buy(), Some((10, 100)));
+    assert_eq!(ob.best_sell(), None);
+
+    // Once an order has left the book its id is free again
+    ob.place_order(Side::Sell, 10, 100, 3).unwrap();
+    assert!(ob.place_order(Side::Buy, 9, 10, 1).is_ok());
+
+    let first = ob.submit_with_new_id(NewOrder::limit(Side::Buy, 8, 10, 0)).unwrap();
+    let second = ob.submit_with_new_id(NewOrder::limit(Side::Buy, 8, 10, 0)).unwrap();
+    // 1 and 2 are taken by the resting buy and the held stop
+    assert_eq!((first.order_id, second.order_id), (3, 4));
+    assert_eq!(ob.order_status(4).map(|status| status.remaining_quantity), Some(10));
+}
+
+#[test]
+fn test_apply_batch() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 11, 100, 1).unwrap();
+
+    let reports = ob.apply_batch(&[
+        Command::Place(NewOrder::limit(Side::Buy, 10, 50, 2)),
+        Command::Place(NewOrder::limit(Side::Buy, 11, 30, 3)),
+        Command::Modify { id: 2, price: 10, quantity: 20 },
+        Command::Cancel { id: 1 },
+        Command::Cancel { id: 1 },
+        Command::Place(NewOrder::limit(Side::Buy, 9, 10, 2)),
+    ]);
+
+    let statuses: Vec<OrderOutcome> = reports.iter().map(|r| r.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            OrderOutcome::Rested,
+            OrderOutcome::Filled,
+            OrderOutcome::Rested,
+            OrderOutcome::Cancelled,
+            OrderOutcome::Rejected(RejectReason::UnknownOrder),
+            OrderOutcome::Rejected(RejectReason::DuplicateId),
+        ]
+    );
+    // Each report carries only its own trades
+    assert_eq!(reports[1].trades.len(), 1);
+    assert!(reports[2].trades.is_empty());
+    assert_eq!(reports[3].remaining_quantity, 70);
+    assert_eq!(ob.best_buy(), Some((10, 20)));
+    assert_eq!(ob.best_sell(), None);
+}
+
+#[test]
+fn test_call_auction() {
+    let mut ob = OrderBook::new();
+    ob.start_auction();
+
+    ob.place_order(Side::Buy, 102, 10, 1).unwrap();
+    ob.place_order(Side::Buy, 101, 20, 2).unwrap();
+    ob.place_order(Side::Buy, 100, 30, 3).unwrap();
+    ob.place_order(Side::Sell, 99, 15, 4).unwrap();
+    ob.place_order(Side::Sell, 100, 25, 5).unwrap();
+    let report = ob.place_order(Side::Sell, 103, 20, 6).unwrap();
+    assert!(report.trades.is_empty());
+    assert_eq!((ob.best_buy(), ob.best_sell()), (Some((102, 10)), Some((99, 15)))); // crossed
+    let report = ob.place_market_order(Side::Buy, 10, 7).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::AuctionInProgress));
+
+    // 100 executes 40: all buy interest above it against all sell interest up to it
+    assert_eq!(ob.indicative_auction_price(), Some((100, 40)));
+    let result = ob.run_auction().unwrap();
+    assert_eq!((result.price, result.volume), (Some(100), 40));
+    let trades: Vec<(u64, u64, Units)> = result.trades.iter().map(|t| (t.maker_id, t.taker_id, t.quantity)).collect();
+    assert_eq!(trades, [(1, 4, 10), (2, 4, 5), (2, 5, 15), (3, 5, 10)]);
+    assert!(result.trades.iter().all(|t| t.price == 100));
+    assert_eq!((ob.best_buy(), ob.best_sell()), (Some((100, 20)), Some((103, 20))));
+    assert_eq!(ob.last_trade_price(), Some(100));
+
+    // Back to continuous matching
+    assert!(!ob.in_auction());
+    assert_eq!(ob.place_order(Side::Buy, 103, 5, 8).unwrap().trades.len(), 1);
+}
+
+#[test]
+fn test_client_order_ids_carried_through() {
+    let mut ob = OrderBook::new();
+    let report = ob.submit(NewOrder::limit(Side::Sell, 100, 10, 1).with_client_order_id("ask-1").with_user_data(7)).unwrap();
+    assert_eq!(report.client_order_id, Some(ClientOrderId::from("ask-1")));
+    assert_eq!(report.user_data, Some(7));
+
+    let report = ob.submit(NewOrder::limit(Side::Buy, 100, 4, 2).with_client_order_id(42u64)).unwrap();
+    let trade = &report.trades[0];
+    assert_eq!(trade.maker_client_order_id, Some(ClientOrderId::Text("ask-1".to_string())));
+    assert_eq!(trade.taker_client_order_id, Some(ClientOrderId::Number(42)));
+    assert_eq!((trade.maker_user_data, trade.taker_user_data), (Some(7), None));
+
+    // the amended order keeps its tags, and so does what a cancel hands back
+    let report = ob.modify_order(1, 101, 5).unwrap();
+    assert_eq!((report.client_order_id.clone(), report.user_data), (Some(ClientOrderId::from("ask-1")), Some(7)));
+    let cancelled = ob.cancel_order(1).unwrap();
+    assert_eq!((cancelled.client_order_id, cancelled.user_data), (Some(ClientOrderId::from("ask-1")), Some(7)));
+}
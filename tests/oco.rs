@@ -0,0 +1,97 @@
+use orderbook::*;
+
+// Take-profit at 110 (id 1) and stop-loss at 90 (id 2) on a long position, with a bid at 90
+fn bracket(mode: OcoMode) -> OrderBook {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 110, 10, 1).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 90, 10, 2)).unwrap();
+    ob.place_order(Side::Buy, 90, 20, 3).unwrap();
+    ob.link_oco(1, 2, mode).unwrap();
+    ob
+}
+
+#[test]
+fn test_fill_on_one_leg_cancels_the_other() {
+    let mut ob = bracket(OcoMode::Cancel);
+    assert_eq!(ob.oco_partner(2), Some(1));
+
+    let report = ob.place_order(Side::Buy, 110, 4, 10).unwrap();
+    assert_eq!(report.status, OrderOutcome::Filled);
+    assert_eq!(ob.cancel_stop(2), Err(CancelError::UnknownOrder(2)));
+    assert_eq!(ob.oco_partner(1), None);
+    assert_eq!(ob.best_sell(), Some((110, 6)));
+}
+
+#[test]
+fn test_triggered_stop_cancels_the_limit() {
+    let mut ob = bracket(OcoMode::Cancel);
+    let report = ob.place_order(Side::Sell, 90, 1, 10).unwrap();
+    assert_eq!(ob.triggered_stops(), [2]);
+    assert_eq!(report.trades.len(), 2);
+    assert_eq!(ob.best_sell(), None);
+    assert_eq!(ob.best_buy(), Some((90, 9)));
+}
+
+#[test]
+fn test_reduce_mode_and_cancel_path() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 110, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 120, 10, 2).unwrap();
+    ob.link_oco(1, 2, OcoMode::Reduce).unwrap();
+
+    ob.place_order(Side::Buy, 110, 4, 10).unwrap();
+    assert_eq!(ob.order_status(2).unwrap().remaining_quantity, 6);
+    assert_eq!(ob.oco_partner(2), Some(1));
+
+    // Cancelling either leg takes the other with it
+    ob.cancel_order(1).unwrap();
+    assert!(ob.order_status(2).is_none());
+    assert_eq!(ob.order_count(), 0);
+
+    // A fill as large as the other leg uses it up
+    let mut ob = bracket(OcoMode::Reduce);
+    ob.place_order(Side::Buy, 110, 10, 10).unwrap();
+    assert_eq!(ob.cancel_stop(2), Err(CancelError::UnknownOrder(2)));
+}
+
+#[test]
+fn test_link_errors_modify_and_unlink() {
+    let mut ob = bracket(OcoMode::Cancel);
+    assert_eq!(ob.link_oco(3, 3, OcoMode::Cancel), Err(LinkError::SameOrder(3)));
+    assert_eq!(ob.link_oco(3, 4, OcoMode::Cancel), Err(LinkError::UnknownOrder(4)));
+    assert_eq!(ob.link_oco(3, 1, OcoMode::Cancel), Err(LinkError::AlreadyLinked(1)));
+
+    // A cancel/replace keeps the pair together
+    ob.modify_order(1, 111, 10).unwrap();
+    assert_eq!(ob.oco_partner(1), Some(2));
+    assert_eq!(ob.best_sell(), Some((111, 10)));
+
+    assert_eq!(ob.unlink_oco(2), Some(1));
+    assert_eq!(ob.unlink_oco(2), None);
+    ob.cancel_order(1).unwrap();
+    assert_eq!(ob.cancel_stop(2).unwrap().quantity, 10);
+}
+
+#[test]
+fn test_links_replay() {
+    let mut ob = OrderBook::new();
+    ob.enable_log();
+    ob.place_order(Side::Sell, 110, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 120, 10, 2).unwrap();
+    ob.place_order(Side::Sell, 130, 10, 3).unwrap();
+    ob.place_order(Side::Sell, 140, 10, 4).unwrap();
+    ob.link_oco(1, 2, OcoMode::Reduce).unwrap();
+    ob.link_oco(3, 4, OcoMode::Cancel).unwrap();
+    ob.unlink_oco(4);
+    ob.place_order(Side::Buy, 130, 13, 10).unwrap();
+
+    let mut file = Vec::new();
+    ob.log().unwrap().write_to(&mut file).unwrap();
+    let log = EventLog::read_from(file.as_slice()).unwrap();
+    assert_eq!(&log, ob.log().unwrap());
+    assert_eq!(OrderBook::replay(&log).unwrap().snapshot(), ob.snapshot());
+    // Order 1's fill used up order 2; 4 was unlinked before 3 traded
+    assert!(ob.order_status(2).is_none());
+    assert_eq!(ob.order_status(3).unwrap().remaining_quantity, 7);
+    assert!(ob.order_status(4).is_some());
+}
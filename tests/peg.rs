@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use orderbook::*;
+
+fn book() -> OrderBook {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 105, 10, 2).unwrap();
+    ob
+}
+
+fn price(ob: &OrderBook, id: u64) -> Option<Units> {
+    ob.order_status(id).map(|status| status.price)
+}
+
+#[test]
+fn test_primary_peg_follows_its_side() {
+    let mut ob = book();
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = Arc::clone(&events);
+    ob.subscribe(move |event: &Event| sink.lock().unwrap().push(*event));
+
+    let report = ob.submit(NewOrder::pegged(Side::Buy, PegReference::Primary, 0, 5, 10)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rested);
+    assert_eq!(price(&ob, 10), Some(100));
+
+    ob.place_order(Side::Buy, 101, 3, 3).unwrap();
+    assert_eq!(price(&ob, 10), Some(101));
+    assert_eq!(ob.order_status(10).unwrap().queue_position, 1);
+    assert!(events.lock().unwrap().contains(&Event::Order(OrderEvent::Repriced { id: 10, price: 101 })));
+
+    // Back down once the order it followed leaves; the peg doesn't hold itself up
+    ob.cancel_order(3).unwrap();
+    assert_eq!(price(&ob, 10), Some(100));
+    assert_eq!(ob.pegged_orders().collect::<Vec<_>>(), [10]);
+
+    // Trading against it works as for any resting order
+    ob.place_market_order(Side::Sell, 15, 4).unwrap();
+    assert_eq!(ob.pegged_orders().count(), 0);
+}
+
+#[test]
+fn test_market_and_mid_pegs_stay_passive() {
+    let mut ob = book();
+    ob.submit(NewOrder::pegged(Side::Buy, PegReference::Mid, 0, 1, 10)).unwrap();
+    ob.submit(NewOrder::pegged(Side::Sell, PegReference::Mid, 0, 1, 11)).unwrap();
+    ob.submit(NewOrder::pegged(Side::Sell, PegReference::Market, 3, 1, 12)).unwrap();
+    ob.submit(NewOrder::pegged(Side::Sell, PegReference::Market, -5, 1, 13)).unwrap();
+    // Mid of 100 and 105 rounded away from the other side; 95 would cross the mid peg bid
+    let prices = |ob: &OrderBook| [10, 11, 12, 13].map(|id| price(ob, id).unwrap());
+    assert_eq!(prices(&ob), [102, 103, 103, 103]);
+    assert_eq!(ob.last_trade_price(), None);
+
+    // A mid of 102 would cross the pegged bid at 102
+    ob.place_order(Side::Sell, 104, 1, 3).unwrap();
+    assert_eq!(prices(&ob), [102, 103, 103, 103]);
+    ob.place_order(Side::Buy, 102, 1, 4).unwrap();
+    assert_eq!(prices(&ob), [102, 103, 105, 103]);
+}
+
+#[test]
+fn test_peg_rejections_and_modify() {
+    let mut ob = OrderBook::new();
+    let report = ob.submit(NewOrder::pegged(Side::Buy, PegReference::Mid, 0, 1, 1)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::NoReferencePrice));
+
+    let mut ob = book();
+    ob.submit(NewOrder::pegged(Side::Buy, PegReference::Primary, -1, 5, 10)).unwrap();
+    assert_eq!(price(&ob, 10), Some(99));
+    // It can be reduced in place, but moving or growing it would drop the peg
+    assert_eq!(ob.modify_order(10, 99, 3).unwrap().status, OrderOutcome::Rested);
+    for (price, quantity) in [(98, 3), (99, 4)] {
+        let report = ob.modify_order(10, price, quantity).unwrap();
+        assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::Pegged));
+    }
+    ob.place_order(Side::Buy, 101, 1, 3).unwrap();
+    assert_eq!(price(&ob, 10), Some(100));
+    assert_eq!(ob.order_status(10).unwrap().remaining_quantity, 3);
+    assert_eq!(ob.pegged_orders().collect::<Vec<_>>(), [10]);
+}
+
+struct SharedClock(Arc<AtomicU64>);
+
+impl Clock for SharedClock {
+    fn now(&mut self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[test]
+fn test_reprice_throttle() {
+    let time = Arc::new(AtomicU64::new(0));
+    let mut ob = OrderBook::with_clock(Box::new(SharedClock(Arc::clone(&time))));
+    ob.set_peg_reprice_interval(10);
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.submit(NewOrder::pegged(Side::Buy, PegReference::Primary, 0, 5, 10)).unwrap();
+
+    time.store(5, Ordering::SeqCst);
+    ob.place_order(Side::Buy, 101, 1, 2).unwrap();
+    assert_eq!(price(&ob, 10), Some(100));
+    time.store(10, Ordering::SeqCst);
+    ob.place_order(Side::Buy, 102, 1, 3).unwrap();
+    assert_eq!(price(&ob, 10), Some(102));
+}
+
+#[test]
+fn test_pegs_replay() {
+    let mut ob = book();
+    ob.enable_log();
+    ob.submit(NewOrder::pegged(Side::Buy, PegReference::Mid, -1, 5, 10)).unwrap();
+    ob.submit(NewOrder::pegged(Side::Sell, PegReference::Primary, 2, 5, 11)).unwrap();
+    ob.place_order(Side::Sell, 103, 1, 3).unwrap();
+
+    let mut file = Vec::new();
+    ob.log().unwrap().write_to(&mut file).unwrap();
+    let log = EventLog::read_from(file.as_slice()).unwrap();
+    assert_eq!(&log, ob.log().unwrap());
+    let mut replayed = book();
+    replayed.apply_log(&log).unwrap();
+    assert_eq!(replayed.snapshot(), ob.snapshot());
+}
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use std::thread;
+
+use orderbook::*;
+
+#[test]
+fn test_spsc_ring_order_and_drop() {
+    for wait in [WaitStrategy::BusySpin, WaitStrategy::Park] {
+        let (mut producer, mut consumer) = spsc_channel::<u64>(8);
+        let sender = thread::spawn(move || {
+            for i in 0..10_000 {
+                producer.push(i).unwrap();
+            }
+        });
+        for i in 0..10_000 {
+            assert_eq!(consumer.pop(wait), Some(i));
+        }
+        sender.join().unwrap();
+        assert_eq!(consumer.pop(wait), None);
+    }
+
+    // Values still queued when both halves are gone are dropped
+    let value = Arc::new(());
+    let (mut producer, consumer) = spsc_channel(4);
+    producer.try_push(Arc::clone(&value)).unwrap();
+    producer.try_push(Arc::clone(&value)).unwrap();
+    assert_eq!(Arc::strong_count(&value), 3);
+    drop(consumer);
+    assert!(producer.try_push(Arc::clone(&value)).is_err());
+    drop(producer);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn test_pipeline_executes_in_order() {
+    for wait in [WaitStrategy::BusySpin, WaitStrategy::Park] {
+        let mut pipeline = Pipeline::spawn(OrderBook::new(), 64, wait);
+        for id in 0..100 {
+            pipeline.send(Command::Place(NewOrder::limit(Side::Sell, 101 + Units::from(id % 5), 10, id))).unwrap();
+        }
+        for _ in 0..100 {
+            assert_eq!(pipeline.recv().unwrap().status, OrderOutcome::Rested);
+        }
+
+        let report = pipeline.execute(Command::Place(NewOrder::market(Side::Buy, 25, 500))).unwrap();
+        assert_eq!(report.filled_quantity, 25);
+        assert_eq!(report.trades.len(), 3);
+        let report = pipeline.execute(Command::Cancel { id: 0 }).unwrap();
+        assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::UnknownOrder));
+        assert_eq!(pipeline.try_recv(), None);
+
+        pipeline.send(Command::Cancel { id: 1 }).unwrap();
+        let ob = pipeline.shutdown().unwrap();
+        assert_eq!(ob.order_count(), 97);
+        assert_eq!(ob.last_trade_price(), Some(101));
+    }
+}
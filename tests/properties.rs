@@ -0,0 +1,130 @@
+use orderbook::*;
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Limit { side: Side, price: Units, quantity: Units, time_in_force: TimeInForce, display: Option<Units> },
+    Market { side: Side, quantity: Units },
+    // Index into the ids submitted so far
+    Cancel(usize),
+}
+
+fn side() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Buy), Just(Side::Sell)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let time_in_force = prop_oneof![
+        6 => Just(TimeInForce::Gtc),
+        1 => Just(TimeInForce::Ioc),
+        1 => Just(TimeInForce::Fok),
+    ];
+    prop_oneof![
+        6 => (side(), 90..110 as Units, 1..50 as Units, time_in_force, proptest::option::weighted(0.2, 1..10 as Units)).prop_map(
+            |(side, price, quantity, time_in_force, display)| Op::Limit { side, price, quantity, time_in_force, display }
+        ),
+        1 => (side(), 1..100 as Units).prop_map(|(side, quantity)| Op::Market { side, quantity }),
+        2 => any::<usize>().prop_map(Op::Cancel),
+    ]
+}
+
+#[derive(Default)]
+struct Totals {
+    submitted: Units,
+    traded: Units,
+    cancelled: Units,
+    dropped: Units,
+}
+
+fn check_invariants(ob: &OrderBook, totals: &Totals) {
+    assert_eq!(ob.validate(), Ok(()));
+
+    // Never crossed after an operation in continuous trading
+    if let (Some((bid, _)), Some((ask, _))) = (ob.best_buy(), ob.best_sell()) {
+        assert!(bid < ask, "crossed book: {} >= {}", bid, ask);
+    }
+
+    let mut resting = 0;
+    let mut count = 0;
+    for (side, orders) in [(Side::Buy, ob.iter_bids().collect::<Vec<_>>()), (Side::Sell, ob.iter_asks().collect())] {
+        for pair in orders.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            match side {
+                Side::Buy => assert!(a.price >= b.price, "bids out of price order"),
+                Side::Sell => assert!(a.price <= b.price, "asks out of price order"),
+            }
+            if a.price == b.price {
+                assert!(a.seq < b.seq, "level {} not FIFO", a.price);
+            }
+        }
+        for order in &orders {
+            assert!(order.quantity > 0, "empty order {} left on the book", order.id);
+            // Index and levels agree
+            let status = ob.order_status(order.id).expect("resting order missing from the index");
+            assert_eq!((status.side, status.price), (side, order.price));
+            assert_eq!(status.remaining_quantity, order.remaining_quantity());
+            resting += order.remaining_quantity();
+            count += 1;
+        }
+        let levels: Vec<Units> = orders.iter().map(|order| order.price).collect();
+        for &price in &levels {
+            let level: Units = ob.orders_at(side, price).unwrap().map(|order| order.quantity).sum();
+            let total = match side {
+                Side::Buy => ob.buy_at(price),
+                Side::Sell => ob.sell_at(price),
+            };
+            assert_eq!(total, Some((price, level)), "level total out of date");
+        }
+    }
+    assert_eq!(count, ob.order_count());
+
+    // Each traded unit fills both a maker and a taker
+    assert_eq!(totals.submitted, 2 * totals.traded + resting + totals.cancelled + totals.dropped);
+}
+
+fn run(ops: Vec<Op>) {
+    let mut ob = OrderBook::new();
+    let mut totals = Totals::default();
+    let mut ids = Vec::new();
+    for (id, op) in (1..).zip(ops) {
+        let report = match op {
+            Op::Limit { side, price, quantity, time_in_force, display } => {
+                let mut order = NewOrder::limit(side, price, quantity, id).with_time_in_force(time_in_force);
+                if let Some(display) = display {
+                    order = order.iceberg(display);
+                }
+                totals.submitted += quantity;
+                ids.push(id);
+                ob.submit(order).unwrap()
+            }
+            Op::Market { side, quantity } => {
+                totals.submitted += quantity;
+                ob.submit(NewOrder::market(side, quantity, id)).unwrap()
+            }
+            Op::Cancel(index) => {
+                if !ids.is_empty() {
+                    if let Ok(order) = ob.cancel_order(ids[index % ids.len()]) {
+                        totals.cancelled += order.remaining_quantity();
+                    }
+                }
+                check_invariants(&ob, &totals);
+                continue;
+            }
+        };
+        totals.traded += report.trades.iter().map(|trade| trade.quantity).sum::<Units>();
+        assert_eq!(report.filled_quantity, report.trades.iter().map(|trade| trade.quantity).sum::<Units>());
+        if report.resting_id.is_none() {
+            totals.dropped += report.remaining_quantity;
+        }
+        check_invariants(&ob, &totals);
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn prop_matching_invariants(ops in proptest::collection::vec(op(), 1..200)) {
+        run(ops);
+    }
+}
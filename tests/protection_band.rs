@@ -0,0 +1,77 @@
+use orderbook::*;
+
+fn ladder() -> OrderBook {
+    let mut ob = OrderBook::new();
+    for (i, price) in [100, 101, 102, 105, 110].into_iter().enumerate() {
+        ob.place_order(Side::Sell, price, 10, i as u64 + 1).unwrap();
+    }
+    ob.place_order(Side::Buy, 95, 10, 10).unwrap();
+    ob
+}
+
+#[test]
+fn test_band_rests_the_remainder_at_its_edge() {
+    let mut ob = ladder();
+    let band = ProtectionBand { width: BandWidth::Ticks(2), remainder: BandRemainder::Rest };
+    ob.set_protection_band(Some(band));
+    assert_eq!(ob.protection_band(), Some(band));
+
+    let report = ob.place_order(Side::Buy, 200, 50, 20).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rested);
+    assert_eq!((report.filled_quantity, report.remaining_quantity), (30, 20));
+    assert_eq!(report.trades.iter().map(|t| t.price).collect::<Vec<_>>(), [100, 101, 102]);
+    assert_eq!(ob.best_buy(), Some((102, 20)));
+    assert_eq!(ob.best_sell(), Some((105, 10)));
+}
+
+#[test]
+fn test_band_can_cancel_the_remainder() {
+    let mut ob = ladder();
+    ob.set_protection_band(Some(ProtectionBand { width: BandWidth::Ticks(1), remainder: BandRemainder::Cancel }));
+
+    let report = ob.place_order(Side::Buy, 200, 50, 20).unwrap();
+    assert_eq!(report.status, OrderOutcome::Cancelled);
+    assert_eq!((report.filled_quantity, report.remaining_quantity), (20, 30));
+    assert_eq!(ob.best_buy(), Some((95, 10)));
+
+    // An order within the band is untouched
+    let report = ob.place_order(Side::Buy, 102, 15, 21).unwrap();
+    assert_eq!((report.status, report.remaining_quantity), (OrderOutcome::Rested, 5));
+}
+
+#[test]
+fn test_band_caps_market_orders_and_fok() {
+    let mut ob = ladder();
+    ob.set_protection_band(Some(ProtectionBand { width: BandWidth::Bps(300), remainder: BandRemainder::Rest }));
+
+    // 3% of 100 is 3 ticks: 100 to 103
+    let report = ob.place_market_order(Side::Buy, 40, 20).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Cancelled, 30));
+    assert_eq!(ob.best_sell(), Some((105, 10)));
+
+    let fok = NewOrder::limit(Side::Buy, 200, 20, 21).with_time_in_force(TimeInForce::Fok);
+    assert_eq!(
+        ob.submit(fok).unwrap().status,
+        OrderOutcome::Rejected(RejectReason::InsufficientLiquidity)
+    );
+
+    let report = ob.place_market_order(Side::Sell, 20, 22).unwrap();
+    assert_eq!((report.status, report.filled_quantity), (OrderOutcome::Cancelled, 10));
+}
+
+#[test]
+fn test_band_rounds_to_the_tick_and_skips_auctions() {
+    let mut ob = OrderBook::new();
+    ob.set_price_config(PriceConfig { tick_size: 5, ..PriceConfig::default() }).unwrap();
+    for (i, price) in [1000, 1005, 1010, 1015].into_iter().enumerate() {
+        ob.place_order(Side::Sell, price, 10, i as u64 + 1).unwrap();
+    }
+    // 1.2% of 1000 is 12, rounded in to 10
+    ob.set_protection_band(Some(ProtectionBand { width: BandWidth::Bps(120), remainder: BandRemainder::Rest }));
+    let report = ob.place_order(Side::Buy, 1100, 35, 10).unwrap();
+    assert_eq!((report.filled_quantity, ob.best_buy()), (30, Some((1010, 5))));
+
+    ob.start_auction();
+    ob.place_order(Side::Buy, 1100, 20, 11).unwrap();
+    assert_eq!(ob.run_auction().unwrap().volume, 10);
+}
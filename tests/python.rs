@@ -0,0 +1,69 @@
+#![cfg(feature = "python")]
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+// Runs `script` with the module imported as `orderbook`
+fn run(script: &str) -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = PyModule::new_bound(py, "orderbook")?;
+        orderbook::python_module(&module)?;
+        let globals = PyDict::new_bound(py);
+        globals.set_item("orderbook", module)?;
+        py.run_bound(script, Some(&globals), None)
+    })
+}
+
+#[test]
+fn test_python_order_book() {
+    run(r#"
+ob = orderbook.OrderBook()
+assert ob.place("sell", 101, 10, 1).status == "rested"
+ob.place("sell", 102, 5)
+assert ob.best_ask() == (101, 10) and ob.best_bid() is None and len(ob) == 2
+
+report = ob.place("buy", 102, 12)
+assert report.status == "filled" and report.filled_quantity == 12
+assert [(t.price, t.quantity, t.maker_id) for t in report.trades] == [(101, 10, 1), (102, 2, 2)]
+assert [t.seq for t in ob.trades()] == [1, 2] and [t.seq for t in ob.trades(since=1)] == [2]
+
+assert ob.place_market("buy", 10).status == "cancelled"
+resting = ob.place("sell", 105, 4).resting_id
+assert ob.cancel(resting) == 4
+try:
+    ob.cancel(1)
+    raise AssertionError("cancelled a filled order")
+except KeyError:
+    pass
+try:
+    ob.place("hold", 100, 1)
+    raise AssertionError("took a bad side")
+except ValueError:
+    pass
+"#)
+    .unwrap();
+}
+
+#[test]
+fn test_python_depth_arrays() {
+    pyo3::prepare_freethreaded_python();
+    if Python::with_gil(|py| py.import_bound("numpy").is_err()) {
+        eprintln!("numpy isn't installed; skipping");
+        return;
+    }
+    run(r#"
+ob = orderbook.OrderBook()
+ob.place("buy", 99, 10)
+ob.place("buy", 99, 5)
+ob.place("buy", 98, 7)
+ob.place("sell", 101, 3)
+bids, asks = ob.depth(5)
+assert bids.shape == (2, 3) and str(bids.dtype) == "uint64"
+assert bids.tolist() == [[99, 15, 2], [98, 7, 1]]
+assert asks.tolist() == [[101, 3, 1]]
+assert ob.depth(1)[0].tolist() == [[99, 15, 2]]
+assert orderbook.OrderBook().depth()[1].shape == (0, 3)
+"#)
+    .unwrap();
+}
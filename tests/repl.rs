@@ -0,0 +1,45 @@
+use orderbook::*;
+
+#[test]
+fn test_parse_repl_commands() {
+    assert_eq!("buy 100@10".parse(), Ok(ReplCommand::Order { side: Side::Buy, quantity: 100, price: Some(10) }));
+    assert_eq!("  sell 5  ".parse(), Ok(ReplCommand::Order { side: Side::Sell, quantity: 5, price: None }));
+    assert_eq!("cancel 3".parse(), Ok(ReplCommand::Cancel(3)));
+    assert_eq!("modify 3 20@11".parse(), Ok(ReplCommand::Modify { id: 3, quantity: 20, price: 11 }));
+    assert_eq!("depth".parse(), Ok(ReplCommand::Depth(10)));
+    assert_eq!("depth 5".parse(), Ok(ReplCommand::Depth(5)));
+    assert_eq!("trades 2".parse(), Ok(ReplCommand::Trades(2)));
+    assert_eq!("exit".parse(), Ok(ReplCommand::Quit));
+
+    let err = "buy ten@10".parse::<ReplCommand>().unwrap_err();
+    assert_eq!(err.to_string(), "not a number: ten");
+    assert_eq!("modify 3 20".parse::<ReplCommand>().unwrap_err().to_string(), "expected QTY@PRICE, got 20");
+    assert_eq!("launch".parse::<ReplCommand>().unwrap_err().to_string(), "unknown command: launch");
+}
+
+#[test]
+fn test_repl_session() {
+    let mut repl = Repl::new();
+    let mut run = |line: &str| repl.execute(line.parse().unwrap());
+
+    assert_eq!(run("depth"), "empty book");
+    assert_eq!(run("sell 50@11"), "#1 Rested: filled 0, remaining 50");
+    run("sell 100@10");
+    run("buy 100@9");
+    assert_eq!(run("buy 20"), "#4 Filled: filled 20, remaining 0\n  trade 20 @ 10 (maker #2, taker #4)");
+    assert_eq!(
+        run("depth"),
+        [
+            "         BID     PRICE  ASK",
+            "                    11  50 (1)",
+            "                    10  80 (1)",
+            "     100 (1)         9",
+        ]
+        .join("\n")
+    );
+    assert_eq!(run("trades"), "  trade 20 @ 10 (maker #2, taker #4)");
+    assert_eq!(run("cancel 3"), "cancelled #3: 100 @ 9");
+    assert_eq!(run("cancel 3"), "no resting order with id 3");
+
+    assert_eq!(repl.book().best_sell(), Some((10, 80)));
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+use orderbook::*;
+
+const CSV_FLOW: &str = "\
+timestamp,side,price,qty,id,action
+1000,sell,100,10,1,new
+1500,sell,101,5,2,new
+2000,buy,,12,3,new
+3000,,,,2,cancel
+3500,,,,2,cancel
+4000,buy,99,5,4,new
+5000,,98,6,4,modify
+";
+
+#[test]
+fn test_replay_csv() {
+    let mut ob = OrderBook::new();
+    let mut trades = Vec::new();
+    let summary = replay(&mut ob, CSV_FLOW.as_bytes(), FlowFormat::Csv, &mut trades, ReplaySpeed::AsFastAsPossible).unwrap();
+
+    assert_eq!(summary, ReplaySummary { records: 7, trades: 2, failed: 1 });
+    assert_eq!(
+        String::from_utf8(trades).unwrap(),
+        "timestamp,price,qty,maker_id,taker_id\n2000,100,10,1,3\n2000,101,2,2,3\n"
+    );
+    assert_eq!(ob.best_buy(), Some((98, 6)));
+    assert_eq!(ob.best_sell(), None);
+}
+
+#[test]
+fn test_replay_errors() {
+    let mut ob = OrderBook::new();
+    let flow = "1,buy,10,5,1,new\n2,buy,10,5,2,launch\n3,buy,10,5,3,new\n";
+    match replay(&mut ob, flow.as_bytes(), FlowFormat::Csv, Vec::new(), ReplaySpeed::AsFastAsPossible) {
+        Err(ReplayError::Parse { line, message }) => assert_eq!((line, message.as_str()), (2, "invalid action \"launch\"")),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+    // Everything before the bad line was applied
+    assert_eq!(ob.best_buy(), Some((10, 5)));
+
+    let missing_side = replay(&mut ob, "1,,10,5,9,new\n".as_bytes(), FlowFormat::Csv, Vec::new(), ReplaySpeed::AsFastAsPossible);
+    assert!(matches!(missing_side, Err(ReplayError::Parse { message, .. }) if message == "missing side"));
+}
+
+#[test]
+fn test_replay_paced() {
+    let mut ob = OrderBook::new();
+    // 40ms apart at the original pace, 20ms at double speed
+    let flow = "0,buy,10,1,1,new\n40000000,buy,10,1,2,new\n";
+    let start = Instant::now();
+    replay(&mut ob, flow.as_bytes(), FlowFormat::Csv, Vec::new(), ReplaySpeed::Paced(2.0)).unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert_eq!(ob.order_count(), 2);
+}
+
+#[test]
+fn test_flow_format_from_path() {
+    assert_eq!(FlowFormat::from_path("flow.jsonl".as_ref()), FlowFormat::JsonLines);
+    assert_eq!(FlowFormat::from_path("flow.csv".as_ref()), FlowFormat::Csv);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_replay_json_lines() {
+    let flow = r#"{"timestamp":1,"side":"sell","price":100,"qty":10,"id":1,"action":"new"}
+{"timestamp":2,"side":"buy","qty":4,"id":2,"action":"new"}
+{"timestamp":3,"id":1,"action":"cancel"}
+"#;
+    let mut ob = OrderBook::new();
+    let mut trades = Vec::new();
+    let summary = replay(&mut ob, flow.as_bytes(), FlowFormat::JsonLines, &mut trades, ReplaySpeed::AsFastAsPossible).unwrap();
+
+    assert_eq!(summary, ReplaySummary { records: 3, trades: 1, failed: 0 });
+    assert_eq!(
+        String::from_utf8(trades).unwrap(),
+        "{\"timestamp\":2,\"price\":100,\"qty\":4,\"maker_id\":1,\"taker_id\":2}\n"
+    );
+    assert_eq!(ob.order_count(), 0);
+}
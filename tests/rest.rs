@@ -0,0 +1,91 @@
+#![cfg(feature = "rest")]
+
+use orderbook::*;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+async fn start() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let mut exchange = Exchange::new();
+    exchange.add_symbol("ACME").unwrap();
+    tokio::spawn(serve_http(listener, exchange, RestConfig::default()));
+    address
+}
+
+// One request per connection, returning the status code and body
+async fn request(address: &str, method: &str, path: &str, body: Option<String>) -> (u16, String) {
+    let mut stream = TcpStream::connect(address).await.unwrap();
+    let body = body.unwrap_or_default();
+    let head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+        method,
+        path,
+        address,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(body.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    (status, body.to_string())
+}
+
+async fn call<T: DeserializeOwned>(address: &str, method: &str, path: &str, body: Option<String>) -> (u16, T) {
+    let (status, body) = request(address, method, path, body).await;
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+fn order(side: Side, price: Option<Units>, quantity: Units, id: u64) -> Option<String> {
+    let request = OrderRequest { symbol: "ACME".to_string(), side, price, quantity, id };
+    Some(serde_json::to_string(&request).unwrap())
+}
+
+#[tokio::test]
+async fn test_rest_order_entry_and_queries() {
+    let address = start().await;
+
+    let (status, placed): (_, PlacedOrder) = call(&address, "POST", "/orders", order(Side::Sell, Some(100), 10, 1)).await;
+    assert_eq!(status, 200);
+    assert_eq!((placed.symbol.as_str(), placed.report.resting_id), ("ACME", Some(1)));
+    call::<PlacedOrder>(&address, "POST", "/orders", order(Side::Sell, Some(101), 5, 2)).await;
+
+    let (_, placed): (_, PlacedOrder) = call(&address, "POST", "/orders", order(Side::Buy, None, 4, 3)).await;
+    assert_eq!(placed.report.filled_quantity, 4);
+
+    let (status, depth): (_, Depth) = call(&address, "GET", "/book/depth?symbol=ACME&levels=1", None).await;
+    assert_eq!(status, 200);
+    assert_eq!(depth.asks, vec![DepthLevel { price: 100, quantity: 6, order_count: 1 }]);
+
+    let (_, trades): (_, Vec<Trade>) = call(&address, "GET", "/trades?symbol=ACME", None).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!((trades[0].price, trades[0].quantity, trades[0].maker_id, trades[0].taker_id), (100, 4, 1, 3));
+    let (_, trades): (_, Vec<Trade>) = call(&address, "GET", "/trades?symbol=ACME&since=1", None).await;
+    assert!(trades.is_empty());
+
+    let (status, cancelled): (_, CancelledOrder) = call(&address, "DELETE", "/orders/2", None).await;
+    assert_eq!(status, 200);
+    assert_eq!(cancelled.order.price, 101);
+    let (_, depth): (_, Depth) = call(&address, "GET", "/book/depth?symbol=ACME", None).await;
+    assert_eq!(depth.asks.len(), 1);
+}
+
+#[tokio::test]
+async fn test_rest_errors() {
+    let address = start().await;
+
+    let (status, error): (_, ErrorBody) = call(&address, "GET", "/book/depth?symbol=NOPE", None).await;
+    assert_eq!((status, error.error.as_str()), (404, "unknown symbol NOPE"));
+    let (status, error): (_, ErrorBody) = call(&address, "DELETE", "/orders/99", None).await;
+    assert_eq!((status, error.error.as_str()), (404, "no resting order with id 99"));
+
+    call::<PlacedOrder>(&address, "POST", "/orders", order(Side::Buy, Some(90), 1, 1)).await;
+    let (status, error): (_, ErrorBody) = call(&address, "POST", "/orders", order(Side::Buy, Some(90), 1, 1)).await;
+    assert_eq!((status, error.error.as_str()), (409, "order id 1 is already in use"));
+
+    let (status, _) = request(&address, "POST", "/orders", Some("{\"symbol\":\"ACME\"}".to_string())).await;
+    assert_eq!(status, 422);
+}
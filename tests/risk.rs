@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use orderbook::*;
+
+fn rejected(ob: &mut OrderBook, order: NewOrder) -> RejectReason {
+    match ob.submit(order).unwrap().status {
+        OrderOutcome::Rejected(reason) => reason,
+        other => panic!("expected a rejection, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_risk_limits() {
+    let mut ob = OrderBook::new();
+    ob.add_pre_trade_check(RiskLimits {
+        max_order_quantity: Some(100),
+        max_notional: Some(4_000),
+        max_deviation_from_last_bps: Some(1_000),
+        max_deviation_from_mid_bps: None,
+        max_open_orders_per_owner: Some(2),
+    });
+
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 10, 101, 1)), RejectReason::MaxOrderSize);
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 60, 100, 1)), RejectReason::MaxNotional);
+    ob.submit(NewOrder::limit(Side::Sell, 50, 10, 2)).unwrap();
+    // Market orders are priced at the opposite touch
+    assert_eq!(rejected(&mut ob, NewOrder::market(Side::Buy, 101, 3)), RejectReason::MaxOrderSize);
+    assert_eq!(rejected(&mut ob, NewOrder::market(Side::Buy, 100, 3)), RejectReason::MaxNotional);
+
+    // No last trade yet, so any price passes the deviation check until one prints
+    ob.submit(NewOrder::limit(Side::Buy, 50, 1, 4)).unwrap();
+    assert_eq!(ob.last_trade_price(), Some(50));
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 44, 1, 5)), RejectReason::PriceDeviation);
+    assert_eq!(ob.submit(NewOrder::limit(Side::Buy, 45, 1, 5)).unwrap().status, OrderOutcome::Rested);
+
+    ob.submit(NewOrder::limit(Side::Buy, 46, 1, 6).with_owner(7)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 47, 1, 7).with_owner(7)).unwrap();
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 48, 1, 8).with_owner(7)), RejectReason::OpenOrderLimit);
+    assert_eq!(ob.stats().rejects, 6);
+
+    // A rejected order leaves the book as it was
+    assert_eq!(ob.order_count(), 4);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Rejected(RejectReason::OpenOrderLimit)));
+}
+
+#[test]
+fn test_deviation_from_mid() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 90, 1, 1).unwrap();
+    ob.place_order(Side::Sell, 110, 1, 2).unwrap();
+    ob.add_pre_trade_check(RiskLimits { max_deviation_from_mid_bps: Some(500), ..RiskLimits::default() });
+
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 94, 1, 3)), RejectReason::PriceDeviation);
+    assert_eq!(ob.submit(NewOrder::limit(Side::Buy, 95, 1, 3)).unwrap().status, OrderOutcome::Rested);
+    // Market orders have no price to check
+    assert_eq!(ob.submit(NewOrder::market(Side::Buy, 1, 4)).unwrap().status, OrderOutcome::Filled);
+}
+
+struct Cash(HashMap<u64, u128>);
+
+impl BuyingPower for Cash {
+    fn available(&self, owner: u64, _side: Side) -> u128 {
+        self.0.get(&owner).copied().unwrap_or(0)
+    }
+}
+
+#[test]
+fn test_buying_power_and_custom_checks_run_in_order() {
+    let mut ob = OrderBook::new();
+    ob.add_pre_trade_check(BuyingPowerCheck(Cash(HashMap::from([(1, 1_000)]))));
+    ob.add_pre_trade_check(|order: &NewOrder, _: &OrderBook| {
+        if order.id.is_multiple_of(2) {
+            Err(RejectReason::MaxOrderSize)
+        } else {
+            Ok(())
+        }
+    });
+
+    assert_eq!(ob.submit(NewOrder::limit(Side::Buy, 100, 10, 1).with_owner(1)).unwrap().status, OrderOutcome::Rested);
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 100, 11, 3).with_owner(1)), RejectReason::InsufficientBuyingPower);
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 100, 10, 5).with_owner(2)), RejectReason::InsufficientBuyingPower);
+    // The buying power check comes first, then the custom one
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 100, 11, 4).with_owner(1)), RejectReason::InsufficientBuyingPower);
+    assert_eq!(rejected(&mut ob, NewOrder::limit(Side::Buy, 100, 1, 4)), RejectReason::MaxOrderSize);
+
+    ob.clear_pre_trade_checks();
+    assert_eq!(ob.submit(NewOrder::limit(Side::Buy, 100, 1, 4)).unwrap().status, OrderOutcome::Rested);
+}
+
+#[test]
+fn test_order_notional() {
+    let mut ob = OrderBook::new();
+    assert_eq!(order_notional(&NewOrder::market(Side::Buy, 5, 1), &ob), None);
+    ob.place_order(Side::Sell, 20, 5, 1).unwrap();
+    assert_eq!(order_notional(&NewOrder::market(Side::Buy, 5, 2), &ob), Some(100));
+    assert_eq!(order_notional(&NewOrder::stop(Side::Buy, 30, 5, 2), &ob), Some(150));
+    assert_eq!(order_notional(&NewOrder::stop_limit(Side::Buy, 30, 31, 5, 2), &ob), Some(155));
+}
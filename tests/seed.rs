@@ -0,0 +1,59 @@
+use orderbook::*;
+
+#[test]
+fn test_load_builds_the_book_without_matching() {
+    let mut ob = OrderBook::new();
+    let mut iceberg = RestingOrder::new(Side::Sell, 101, 30, 4);
+    iceberg.display_quantity = Some(10);
+    ob.load([
+        RestingOrder::new(Side::Buy, 99, 5, 1),
+        RestingOrder::new(Side::Buy, 100, 7, 2),
+        RestingOrder::new(Side::Buy, 99, 3, 3),
+        iceberg,
+    ])
+    .unwrap();
+
+    assert_eq!(ob.order_count(), 4);
+    assert_eq!(ob.best_buy(), Some((100, 7)));
+    assert_eq!(ob.best_sell(), Some((101, 10)));
+    assert_eq!(ob.order_status(3).unwrap().queue_position, 1);
+    assert_eq!(ob.order_status(4).unwrap().remaining_quantity, 30);
+
+    // Later loads queue behind, and the book trades normally afterwards
+    ob.load([RestingOrder::new(Side::Buy, 100, 1, 5)]).unwrap();
+    assert_eq!(ob.order_status(5).unwrap().queue_position, 1);
+    let report = ob.place_market_order(Side::Sell, 8, 6).unwrap();
+    assert_eq!(report.trades.iter().map(|t| t.maker_id).collect::<Vec<_>>(), [2, 5]);
+}
+
+#[test]
+fn test_load_validates_before_touching_the_book() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 105, 1, 1).unwrap();
+    ob.set_price_config(PriceConfig { tick_size: 5, lot_size: 2, price_scale: 0 }).unwrap();
+    let buy = |price, quantity, id| RestingOrder::new(Side::Buy, price, quantity, id);
+
+    assert_eq!(ob.load([buy(100, 2, 2), buy(105, 2, 3)]), Err(LoadError::Crossed { bid: 105, ask: 105 }));
+    assert_eq!(ob.load([buy(100, 2, 2), buy(100, 2, 2)]), Err(LoadError::DuplicateId(2)));
+    assert_eq!(ob.load([buy(100, 2, 1)]), Err(LoadError::DuplicateId(1)));
+    assert_eq!(ob.load([buy(101, 2, 2)]), Err(LoadError::OffTick(2)));
+    assert_eq!(ob.load([buy(100, 3, 2)]), Err(LoadError::OffLot(2)));
+    assert_eq!(ob.load([buy(100, 0, 2)]), Err(LoadError::ZeroQuantity(2)));
+    let overflow = ob.load([buy(100, Units::MAX - 1, 2), buy(100, 2, 3)]);
+    assert_eq!(overflow, Err(LoadError::LevelOverflow { side: Side::Buy, price: 100 }));
+    assert_eq!(ob.order_count(), 1);
+    assert_eq!(ob.best_buy(), None);
+}
+
+#[test]
+fn test_load_at_scale() {
+    let orders = (0..100_000u64).map(|i| {
+        let side = if i % 2 == 0 { Side::Buy } else { Side::Sell };
+        let price = if side == Side::Buy { 10_000 - Units::from(i % 1000) } else { 10_001 + Units::from(i % 1000) };
+        RestingOrder::new(side, price, Units::from(1 + i % 7), i)
+    });
+    let mut ob = OrderBook::new();
+    ob.load(orders).unwrap();
+    assert_eq!(ob.order_count(), 100_000);
+    assert_eq!(ob.spread(), Some(2));
+}
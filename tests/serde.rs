@@ -0,0 +1,41 @@
+#![cfg(feature = "serde")]
+
+use orderbook::*;
+
+#[test]
+fn test_snapshot_serde_round_trip() {
+    let mut ob = OrderBook::new();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.place_order(Side::Buy, 10, 50, 2).unwrap();
+    ob.place_order(Side::Sell, 12, 70, 3).unwrap();
+    ob.place_order(Side::Sell, 11, 30, 4).unwrap();
+    ob.place_order(Side::Buy, 11, 10, 5).unwrap();
+    ob.submit(NewOrder::stop(Side::Buy, 13, 40, 6)).unwrap();
+
+    let json = serde_json::to_string(&ob.snapshot()).unwrap();
+    let mut restored = OrderBook::from_snapshot(serde_json::from_str(&json).unwrap()).unwrap();
+
+    assert_eq!(restored.depth(5), ob.depth(5));
+    assert_eq!(restored.last_trade_price(), Some(11));
+    assert_eq!(restored.last_trade_seq(), 1);
+
+    // The held stop survives and triggers once the market trades at 13
+    restored.place_order(Side::Sell, 13, 100, 7).unwrap();
+    restored.place_order(Side::Buy, 13, 100, 8).unwrap();
+    assert_eq!(restored.triggered_stops(), &[6]);
+}
+
+#[test]
+fn test_trade_and_depth_serialize() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    let trade = ob.place_order(Side::Sell, 10, 40, 2).unwrap().trades[0].clone();
+
+    let json = serde_json::to_string(&trade).unwrap();
+    let back: Trade = serde_json::from_str(&json).unwrap();
+    assert_eq!((back.price, back.quantity, back.maker_id, back.taker_id, back.seq), (10, 40, 1, 2, 1));
+
+    let depth: Depth = serde_json::from_str(&serde_json::to_string(&ob.depth(1)).unwrap()).unwrap();
+    assert_eq!(depth, ob.depth(1));
+}
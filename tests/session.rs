@@ -0,0 +1,86 @@
+use std::sync::mpsc;
+
+use orderbook::*;
+
+const HOUR: u64 = 60 * 60 * 1_000_000_000;
+
+fn schedule() -> SessionSchedule {
+    SessionSchedule { pre_open: 8 * HOUR, open: 9 * HOUR, closing_auction: Some(16 * HOUR), close: 17 * HOUR }
+}
+
+#[test]
+fn test_schedule_phases() {
+    let schedule = schedule();
+    assert_eq!(schedule.phase_at(7 * HOUR), SessionPhase::Closed);
+    assert_eq!(schedule.phase_at(8 * HOUR), SessionPhase::PreOpen);
+    assert_eq!(schedule.phase_at(24 * HOUR + 12 * HOUR), SessionPhase::Continuous);
+    assert_eq!(schedule.phase_at(16 * HOUR + 1), SessionPhase::ClosingAuction);
+    assert_eq!(schedule.phase_at(17 * HOUR), SessionPhase::Closed);
+    let no_closing_auction = SessionSchedule { closing_auction: None, ..schedule };
+    assert_eq!(no_closing_auction.phase_at(16 * HOUR + 1), SessionPhase::Continuous);
+}
+
+#[test]
+fn test_trading_day() {
+    let clock = SimClock::default();
+    let mut ob = OrderBook::with_clock(Box::new(clock.clone()));
+    let (tx, rx) = mpsc::channel();
+    ob.subscribe(tx);
+    let phases = |rx: &mpsc::Receiver<Event>| -> Vec<SessionPhase> {
+        rx.try_iter()
+            .filter_map(|event| match event {
+                Event::Session(SessionEvent::PhaseChanged { phase, .. }) => Some(phase),
+                _ => None,
+            })
+            .collect()
+    };
+    ob.set_session_schedule(Some(schedule()));
+
+    clock.set(7 * HOUR);
+    assert!(ob.update_session().unwrap().is_empty());
+    assert_eq!(ob.session_phase(), Some(SessionPhase::Closed));
+    assert_eq!(ob.trading_state(), TradingState::Halted);
+    assert_eq!(ob.place_order(Side::Buy, 100, 10, 1).unwrap().status, OrderOutcome::Rejected(RejectReason::Halted));
+
+    // Crossing orders collect during pre-open and uncross at the open
+    clock.set(8 * HOUR);
+    ob.update_session().unwrap();
+    assert_eq!(ob.trading_state(), TradingState::AuctionOnly);
+    ob.place_order(Side::Buy, 101, 10, 2).unwrap();
+    ob.place_order(Side::Sell, 99, 6, 3).unwrap();
+    clock.set(9 * HOUR);
+    let auctions = ob.update_session().unwrap();
+    assert_eq!(auctions.len(), 1);
+    assert_eq!((auctions[0].price, auctions[0].volume), (Some(99), 6));
+    assert_eq!(ob.trading_state(), TradingState::Open);
+    assert_eq!(phases(&rx), [SessionPhase::Closed, SessionPhase::PreOpen, SessionPhase::Continuous]);
+
+    // A clock that jumps past several transitions makes each of them in turn
+    ob.place_order(Side::Sell, 102, 5, 4).unwrap();
+    clock.set(24 * HOUR + 30 * 60 * 1_000_000_000);
+    let auctions = ob.update_session().unwrap();
+    assert_eq!(auctions.len(), 1); // the closing auction, with nothing to cross
+    assert_eq!(auctions[0].price, None);
+    assert_eq!(phases(&rx), [SessionPhase::ClosingAuction, SessionPhase::Closed]);
+    assert_eq!(ob.trading_state(), TradingState::Halted);
+    assert_eq!(ob.best_buy(), Some((101, 4)));
+}
+
+#[test]
+fn test_session_transitions_replay_from_the_log() {
+    let clock = SimClock::default();
+    let mut ob = OrderBook::with_clock(Box::new(clock.clone()));
+    ob.enable_log();
+    ob.set_session_schedule(Some(schedule()));
+    clock.set(8 * HOUR);
+    ob.update_session().unwrap();
+    ob.place_order(Side::Buy, 100, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 100, 4, 2).unwrap();
+    clock.set(9 * HOUR);
+    ob.update_session().unwrap();
+
+    let replayed = OrderBook::replay(ob.log().unwrap()).unwrap();
+    assert_eq!(replayed.best_buy(), Some((100, 6)));
+    assert_eq!(replayed.last_trade_seq(), ob.last_trade_seq());
+    assert_eq!(replayed.trading_state(), TradingState::Open);
+}
@@ -0,0 +1,74 @@
+use orderbook::*;
+
+fn place(side: Side, price: Units, quantity: Units, id: u64) -> Command {
+    Command::Place(NewOrder::limit(side, price, quantity, id))
+}
+
+#[test]
+fn test_fixed_latencies_and_delivery_order() {
+    let latency = LatencyModel { inbound: Latency::Fixed(10), outbound: Latency::Fixed(5) };
+    let mut sim = Simulator::new(1, latency);
+    sim.send_at(0, place(Side::Sell, 100, 5, 1));
+    sim.send_at(3, place(Side::Buy, 100, 2, 2));
+
+    let first = sim.next_delivery().unwrap();
+    assert_eq!(first.time, 15);
+    assert!(matches!(first.message, SimMessage::Report(ref report) if report.order_id == 1));
+
+    let rest = sim.run_until(100);
+    assert!(rest.iter().all(|delivery| delivery.time == 15 || delivery.time == 18));
+    let report = rest.iter().find_map(|delivery| match &delivery.message {
+        SimMessage::Report(report) if report.order_id == 2 => Some(report.clone()),
+        _ => None,
+    });
+    let report = report.unwrap();
+    assert_eq!(report.status, OrderOutcome::Filled);
+    // Book timestamps are simulated time: the buy reached the book at 13
+    assert_eq!(report.trades[0].timestamp, 13);
+    let filled = OrderEvent::Filled { id: 2, price: 100, quantity: 2 };
+    assert!(rest.contains(&Delivery { time: 18, message: SimMessage::Event(Event::Order(filled)) }));
+    assert_eq!(sim.now(), 100);
+    assert_eq!(sim.next_delivery(), None);
+}
+
+fn run(seed: u64) -> Vec<(u64, u64)> {
+    let latency =
+        LatencyModel { inbound: Latency::Uniform { min: 1, max: 50 }, outbound: Latency::Uniform { min: 1, max: 20 } };
+    let mut sim = Simulator::new(seed, latency);
+    sim.book_mut().place_order(Side::Sell, 101, 1_000, 1).unwrap();
+    for id in 10..20 {
+        sim.send_at(0, Command::Place(NewOrder::market(Side::Buy, 10, id)));
+    }
+    // A participant that re-quotes the ask each time its order is hit
+    let mut next_id = 100;
+    let mut seen = Vec::new();
+    while let Some(delivery) = sim.next_delivery() {
+        if let SimMessage::Report(report) = &delivery.message {
+            seen.push((delivery.time, report.order_id));
+            if report.order_id < 100 && sim.rng().below(2) == 0 {
+                next_id += 1;
+                sim.send(place(Side::Sell, 101, 10, next_id));
+            }
+        }
+    }
+    seen
+}
+
+#[test]
+fn test_same_seed_replays_exactly() {
+    let first = run(7);
+    assert_eq!(first.len(), run(7).len());
+    assert_eq!(first, run(7));
+    assert_ne!(first, run(8));
+}
+
+#[test]
+fn test_rng_ranges() {
+    let mut rng = SimRng::new(42);
+    assert!((0..1000).all(|_| rng.below(6) < 6));
+    assert!((0..1000).map(|_| rng.next_f64()).all(|x| (0.0..1.0).contains(&x)));
+    let latency = Latency::Uniform { min: 3, max: 5 };
+    assert!((0..100).map(|_| latency.sample(&mut rng)).all(|delay| (3..=5).contains(&delay)));
+    assert_eq!(Latency::Fixed(4).sample(&mut rng), 4);
+    assert_eq!(SimRng::new(1).next_u64(), SimRng::new(1).next_u64());
+}
@@ -0,0 +1,74 @@
+use std::sync::{Arc, Mutex};
+
+use orderbook::*;
+
+#[test]
+fn test_strategy_places_exit_when_entry_fills() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 100, 10, 1).unwrap();
+    // Once entry order 10 fills, offer the position out at 105
+    ob.add_strategy(|trade: &Trade, book: &OrderBook, actions: &mut Vec<Command>| {
+        if trade.taker_id == 10 && book.order_status(10).is_none() {
+            actions.push(Command::Place(NewOrder::limit(Side::Sell, 105, 10, 11)));
+        }
+    });
+
+    let report = ob.place_order(Side::Buy, 100, 10, 10).unwrap();
+    assert_eq!(report.status, OrderOutcome::Filled);
+    assert_eq!(report.trades.len(), 1);
+    assert_eq!(ob.best_sell(), Some((105, 10)));
+}
+
+#[test]
+fn test_follow_up_trades_reach_the_strategies_and_the_report() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 100, 5, 1).unwrap();
+    ob.place_order(Side::Sell, 101, 5, 2).unwrap();
+    ob.place_order(Side::Buy, 90, 5, 3).unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    ob.add_strategy(move |trade: &Trade, _: &OrderBook, actions: &mut Vec<Command>| {
+        log.lock().unwrap().push(trade.maker_id);
+        // Each fill of the first two asks chases the next one
+        if trade.maker_id < 3 {
+            actions.push(Command::Place(NewOrder::market(Side::Buy, 5, trade.maker_id + 10)));
+        }
+    });
+
+    let report = ob.place_order(Side::Buy, 100, 5, 20).unwrap();
+    assert_eq!(report.status, OrderOutcome::Filled);
+    assert_eq!(report.trades.iter().map(|t| (t.maker_id, t.taker_id)).collect::<Vec<_>>(), [(1, 20), (2, 11)]);
+    // The order for the fill of ask 2 found no asks left
+    assert_eq!(*seen.lock().unwrap(), [1, 2]);
+    assert_eq!(ob.last_outcome(), Some(OrderOutcome::Cancelled));
+
+    ob.clear_strategies();
+    ob.place_market_order(Side::Sell, 5, 30).unwrap();
+    assert_eq!(seen.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_strategy_cancels_the_other_leg() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Sell, 110, 10, 1).unwrap();
+    ob.place_order(Side::Sell, 120, 10, 2).unwrap();
+    ob.add_strategy(|trade: &Trade, _: &OrderBook, actions: &mut Vec<Command>| match trade.maker_id {
+        1 => actions.push(Command::Cancel { id: 2 }),
+        2 => actions.push(Command::Cancel { id: 1 }),
+        _ => {}
+    });
+
+    ob.place_order(Side::Buy, 110, 4, 10).unwrap();
+    assert_eq!(ob.best_sell(), Some((110, 6)));
+    assert!(ob.order_status(2).is_none());
+
+    let mut auction = OrderBook::new();
+    auction.add_strategy(|trade: &Trade, _: &OrderBook, actions: &mut Vec<Command>| {
+        actions.push(Command::Place(NewOrder::limit(Side::Sell, 200, trade.quantity, 99)));
+    });
+    auction.start_auction();
+    auction.place_order(Side::Buy, 100, 3, 1).unwrap();
+    auction.place_order(Side::Sell, 100, 3, 2).unwrap();
+    assert_eq!(auction.run_auction().unwrap().trades.len(), 1);
+    assert_eq!(auction.best_sell(), Some((200, 3)));
+}
@@ -0,0 +1,63 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use orderbook::*;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+// Records the level and message of every event
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<(Level, String)>>>);
+
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message(String::new());
+        event.record(&mut message);
+        self.0.lock().unwrap().push((*event.metadata().level(), message.0));
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn test_tracing_events() {
+    let capture = Capture::default();
+    tracing::subscriber::with_default(capture.clone(), || {
+        let mut ob = OrderBook::new();
+        ob.place_order(Side::Sell, 101, 10, 1).unwrap();
+        ob.place_market_order(Side::Buy, 10, 2).unwrap();
+    });
+
+    let events = capture.0.lock().unwrap();
+    let expected = [
+        (Level::DEBUG, "order accepted"),
+        (Level::TRACE, "level added"),
+        (Level::DEBUG, "order rested"),
+        (Level::DEBUG, "order accepted"),
+        (Level::TRACE, "level removed"),
+        (Level::INFO, "trade"),
+    ];
+    let events: Vec<(Level, &str)> = events.iter().map(|(level, message)| (*level, message.as_str())).collect();
+    assert_eq!(events, expected);
+}
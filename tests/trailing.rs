@@ -0,0 +1,85 @@
+use orderbook::*;
+
+fn trade_at(ob: &mut OrderBook, price: Units, id: u64) {
+    ob.place_order(Side::Sell, price, 1, id).unwrap();
+    ob.place_order(Side::Buy, price, 1, id + 1).unwrap();
+}
+
+#[test]
+fn test_sell_trailing_stop_follows_last_trade() {
+    let mut ob = OrderBook::new();
+    ob.place_order(Side::Buy, 90, 5, 1).unwrap();
+    trade_at(&mut ob, 100, 2);
+    let report = ob.submit(NewOrder::trailing_stop(Side::Sell, TrailingOffset::Amount(5), 3, 10)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Pending);
+    assert_eq!(ob.stop_price(10), Some(95));
+
+    trade_at(&mut ob, 104, 20);
+    assert_eq!(ob.stop_price(10), Some(99));
+    // Only ever moves up
+    trade_at(&mut ob, 102, 30);
+    assert_eq!(ob.stop_price(10), Some(99));
+
+    ob.place_order(Side::Buy, 99, 1, 40).unwrap();
+    let report = ob.place_order(Side::Sell, 99, 1, 41).unwrap();
+    assert_eq!(ob.triggered_stops(), [10]);
+    assert_eq!(report.trades.last().map(|t| (t.price, t.quantity, t.taker_id)), Some((90, 3, 10)));
+    assert_eq!(ob.stop_price(10), None);
+}
+
+#[test]
+fn test_buy_trailing_stop_limit_on_best_price() {
+    let mut ob = OrderBook::new();
+    ob.set_stop_trigger(StopTrigger::BestPrice);
+    ob.place_order(Side::Sell, 1000, 10, 1).unwrap();
+    // 1% of the best ask
+    let order = NewOrder::trailing_stop_limit(Side::Buy, TrailingOffset::Bps(100), 5, 2, 10);
+    ob.submit(order).unwrap();
+    assert_eq!(ob.stop_price(10), Some(1010));
+
+    // 1% of 990 is 9.9, rounded in to 9
+    ob.place_order(Side::Sell, 990, 1, 2).unwrap();
+    assert_eq!(ob.stop_price(10), Some(999));
+
+    let report = ob.place_order(Side::Buy, 990, 1, 3).unwrap();
+    assert_eq!(ob.triggered_stops(), [10]);
+    assert_eq!(report.trades.last().map(|t| (t.price, t.quantity)), Some((1000, 2)));
+    assert_eq!(ob.best_sell(), Some((1000, 8)));
+}
+
+#[test]
+fn test_trailing_stop_rejections() {
+    let mut ob = OrderBook::new();
+    let report = ob.submit(NewOrder::trailing_stop(Side::Sell, TrailingOffset::Bps(50), 1, 1)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::NoReferencePrice));
+
+    ob.set_price_config(PriceConfig { tick_size: 5, ..PriceConfig::default() }).unwrap();
+    trade_at(&mut ob, 100, 2);
+    let report = ob.submit(NewOrder::trailing_stop(Side::Sell, TrailingOffset::Amount(3), 1, 4)).unwrap();
+    assert_eq!(report.status, OrderOutcome::Rejected(RejectReason::OffTick));
+    let order = NewOrder::trailing_stop_limit(Side::Sell, TrailingOffset::Amount(5), 2, 1, 4);
+    assert_eq!(ob.submit(order).unwrap().status, OrderOutcome::Rejected(RejectReason::OffTick));
+}
+
+#[test]
+fn test_trailing_stops_replay_and_restore() {
+    let mut ob = OrderBook::new();
+    ob.enable_log();
+    trade_at(&mut ob, 100, 1);
+    ob.submit(NewOrder::trailing_stop(Side::Sell, TrailingOffset::Amount(5), 3, 10)).unwrap();
+    ob.submit(NewOrder::trailing_stop_limit(Side::Buy, TrailingOffset::Bps(200), 1, 3, 11)).unwrap();
+    trade_at(&mut ob, 97, 3);
+
+    let mut file = Vec::new();
+    ob.log().unwrap().write_to(&mut file).unwrap();
+    let log = EventLog::read_from(file.as_slice()).unwrap();
+    assert_eq!(&log, ob.log().unwrap());
+    let replayed = OrderBook::replay(&log).unwrap();
+    assert_eq!(replayed.snapshot(), ob.snapshot());
+    assert_eq!((replayed.stop_price(10), replayed.stop_price(11)), (Some(95), Some(98)));
+
+    // Restored stops keep trailing
+    let mut restored = OrderBook::from_snapshot(ob.snapshot()).unwrap();
+    trade_at(&mut restored, 103, 5);
+    assert_eq!(restored.stop_price(10), Some(98));
+}
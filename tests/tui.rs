@@ -0,0 +1,72 @@
+#![cfg(feature = "tui")]
+
+use orderbook::{Command, LadderView, NewOrder, Side};
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::Modifier;
+use ratatui::Terminal;
+
+fn type_line(view: &mut LadderView, line: &str) -> bool {
+    for c in line.chars() {
+        assert!(view.handle_key(KeyEvent::from(KeyCode::Char(c))));
+    }
+    view.handle_key(KeyEvent::from(KeyCode::Enter))
+}
+
+fn screen(view: &LadderView) -> (Terminal<TestBackend>, Vec<String>) {
+    let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+    terminal.draw(|frame| view.render(frame)).unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let lines = (0..buffer.area.height)
+        .map(|y| (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+        .collect();
+    (terminal, lines)
+}
+
+#[test]
+fn test_ladder_view_renders_book_and_trades() {
+    let mut view = LadderView::new();
+    assert!(type_line(&mut view, "sell 10@101"));
+    assert!(type_line(&mut view, "sell 5@103"));
+    assert!(type_line(&mut view, "buy 7@99"));
+    view.apply(&Command::Place(NewOrder::limit(Side::Buy, 98, 3, 1000)));
+    assert!(type_line(&mut view, "buy 4@101"));
+    assert!(type_line(&mut view, "bogus"));
+    assert_eq!(view.book().best_sell(), Some((101, 6)));
+
+    let (terminal, lines) = screen(&view);
+    // Ladder rows, in the left half
+    let ladder: Vec<String> = lines.iter().map(|line| line.chars().take(50).collect()).collect();
+    let row = |price: &str| ladder.iter().position(|line| line.contains(&format!(" {} ", price))).unwrap();
+    // Asks above bids, highest first
+    assert!(row("103") < row("101") && row("101") < row("99") && row("99") < row("98"));
+    assert!(ladder[row("101")].contains("6 (1)") && ladder[row("99")].contains("7 (1)"));
+    assert!(lines[0].contains("spread 2"));
+    assert!(lines.iter().any(|line| line.contains("4 @ 101  #1 <- #4")));
+    assert!(lines.iter().any(|line| line.contains("Filled { id: 4, price: 101, quantity: 4 }")));
+    assert!(lines.iter().any(|line| line.contains("unknown command: bogus")));
+
+    // The level the last order traded against is highlighted, the others aren't
+    let buffer = terminal.backend().buffer();
+    let price_x = |y: usize| ladder[y].chars().position(|c| c == '1').unwrap() as u16;
+    let highlighted = |y: usize| buffer[(price_x(y), y as u16)].modifier.contains(Modifier::REVERSED);
+    assert!(highlighted(row("101")));
+    assert!(!highlighted(row("103")));
+}
+
+#[test]
+fn test_ladder_view_keys() {
+    let mut view = LadderView::new();
+    for c in "buy 1@9x".chars() {
+        view.handle_key(KeyEvent::from(KeyCode::Char(c)));
+    }
+    view.handle_key(KeyEvent::from(KeyCode::Backspace));
+    assert!(view.handle_key(KeyEvent::from(KeyCode::Enter)));
+    assert_eq!(view.book().best_buy(), Some((9, 1)));
+    let (_, lines) = screen(&view);
+    assert!(lines.iter().any(|line| line.contains("buy 1@9: #1 Rested")));
+
+    assert!(!type_line(&mut view, "quit"));
+    assert!(!view.handle_key(KeyEvent::from(KeyCode::Esc)));
+    assert!(!view.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+}
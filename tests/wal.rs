@@ -0,0 +1,97 @@
+use orderbook::*;
+
+#[test]
+fn test_replay_reproduces_book() {
+    let mut ob = OrderBook::new();
+    ob.enable_log();
+
+    ob.place_order(Side::Buy, 10, 100, 1).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 10, 90, 2).iceberg(30)).unwrap();
+    ob.place_order(Side::Sell, 12, 70, 3).unwrap();
+    ob.submit(NewOrder::stop(Side::Sell, 9, 20, 4)).unwrap();
+    ob.submit(NewOrder::stop(Side::Buy, 15, 20, 5).good_till(90)).unwrap();
+    ob.submit(NewOrder::limit(Side::Buy, 7, 10, 9).good_till(80)).unwrap();
+    ob.place_market_order(Side::Sell, 120, 6).unwrap(); // refills the iceberg
+    ob.place_order(Side::Buy, 8, 40, 7).unwrap();
+    ob.modify_order(3, 11, 50).unwrap();
+    ob.modify_order(2, 13, 60).unwrap(); // crosses
+    ob.cancel_order(7).unwrap();
+    ob.cancel_stop(5).unwrap();
+    assert_eq!(ob.expire(85).unwrap(), [9]);
+    assert!(ob.cancel_order(99).is_err());
+
+    let mut file = Vec::new();
+    ob.log().unwrap().write_to(&mut file).unwrap();
+    let log = EventLog::read_from(file.as_slice()).unwrap();
+    assert_eq!(&log, ob.log().unwrap());
+    assert_eq!(log.len(), 13); // the failed cancel isn't recorded
+
+    let replayed = OrderBook::replay(&log).unwrap();
+    assert_eq!(replayed.validate(), Ok(()));
+    assert_eq!(replayed.snapshot(), ob.snapshot());
+    assert_eq!(replayed.last_trade_seq(), ob.last_trade_seq());
+}
+
+#[test]
+fn test_replay_takes_the_time_from_the_log() {
+    let mut ob = OrderBook::with_clock(Box::new(TestClock::new(1_000, 60)));
+    ob.enable_log();
+    ob.set_circuit_breaker(Some(CircuitBreaker { max_move_bps: 500, window: 100 }));
+    ob.place_order(Side::Sell, 100, 1, 1).unwrap();
+    ob.place_order(Side::Sell, 106, 1, 2).unwrap();
+    ob.place_order(Side::Buy, 90, 5, 3).unwrap();
+    ob.place_market_order(Side::Buy, 1, 4).unwrap();
+    ob.modify_order(3, 95, 5).unwrap();
+    // Past the window the first trade opened, so it doesn't halt the book
+    ob.place_market_order(Side::Buy, 1, 5).unwrap();
+    assert_eq!(ob.trading_state(), TradingState::Open);
+
+    let mut file = Vec::new();
+    ob.log().unwrap().write_to(&mut file).unwrap();
+    assert!(String::from_utf8(file.clone()).unwrap().contains("modify 3 95 5 @ 1240\n"));
+    let log = EventLog::read_from(file.as_slice()).unwrap();
+    assert_eq!(&log, ob.log().unwrap());
+
+    // The default clock would have both trades in one window
+    let mut replayed = OrderBook::new();
+    replayed.set_circuit_breaker(Some(CircuitBreaker { max_move_bps: 500, window: 100 }));
+    replayed.apply_log(&log).unwrap();
+    assert_eq!(replayed.trading_state(), TradingState::Open);
+    assert_eq!(replayed.snapshot(), ob.snapshot());
+    assert_eq!(replayed.order_status(3), ob.order_status(3));
+}
+
+#[test]
+fn test_log_parse_errors() {
+    let text = "submit 1 buy 10 gtc 0 - 7 limit 100\ncancel 1\nmodify 2 x 5\n";
+    match EventLog::read_from(text.as_bytes()) {
+        Err(LogError::Parse { line, .. }) => assert_eq!(line, 3),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+
+    let log = EventLog::read_from("submit 1 buy 10 ioc 1 5 7 stop_limit 90 95\n".as_bytes()).unwrap();
+    let expected = NewOrder::stop_limit(Side::Buy, 90, 95, 10, 1)
+        .with_time_in_force(TimeInForce::Ioc)
+        .post_only()
+        .iceberg(5)
+        .with_owner(7);
+    assert_eq!(log.entries(), &[LogEntry::Submit(expected)]);
+}
+
+#[test]
+fn test_log_round_trips_client_order_ids() {
+    let entries = [
+        LogEntry::Submit(NewOrder::limit(Side::Buy, 10, 5, 1).with_client_order_id("a b%c\té").with_user_data(9).all_or_none()),
+        LogEntry::Submit(NewOrder::market(Side::Sell, 5, 2).good_till(40).with_client_order_id(u128::MAX).reduce_only()),
+        LogEntry::Submit(NewOrder::market(Side::Buy, 100, 3).with_notional(2500)),
+    ];
+    let mut log = EventLog::new();
+    for entry in entries.clone() {
+        log.append(entry);
+    }
+    let mut file = Vec::new();
+    log.write_to(&mut file).unwrap();
+    let text = String::from_utf8(file.clone()).unwrap();
+    assert!(text.contains(" cl_ord_id s:a%20b%25c%09%C3%A9 user_data 9 min_qty 5\n"), "{}", text);
+    assert_eq!(EventLog::read_from(file.as_slice()).unwrap().entries(), entries);
+}
@@ -0,0 +1,49 @@
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use js_sys::{Function, Reflect};
+use orderbook::WasmOrderBook;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_wasm_order_book() {
+    let mut book = WasmOrderBook::new(None);
+    assert_eq!(book.place("sell", 101.0, 10.0, Some(1.0)).unwrap().status, "rested");
+    book.place("sell", 102.0, 5.0, None).unwrap();
+    book.place("buy", 99.0, 4.0, None).unwrap();
+    let best_ask = book.best_ask().unwrap();
+    assert_eq!((best_ask.price, best_ask.quantity, best_ask.orders), (101.0, 10.0, 1));
+    assert_eq!((book.spread(), book.mid_price(), book.length()), (Some(2.0), Some(100.0), 3));
+
+    // Prices of the trades handed to the callback, through JS
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let record = Closure::<dyn FnMut(JsValue)>::new({
+        let seen = Rc::clone(&seen);
+        move |trade: JsValue| seen.borrow_mut().push(Reflect::get(&trade, &"price".into()).unwrap().as_f64().unwrap())
+    });
+    book.on_trade(Some(record.as_ref().unchecked_ref::<Function>().clone()));
+
+    let report = book.place("buy", 102.0, 12.0, None).unwrap();
+    assert_eq!((report.status.as_str(), report.filled_quantity, report.trades.len()), ("filled", 12.0, 2));
+    assert_eq!((report.trades[1].maker_id, report.trades[1].quantity, report.trades[1].seq), (2.0, 2.0, 2.0));
+    assert_eq!(*seen.borrow(), [101.0, 102.0]);
+    let since_first: Vec<f64> = book.trades(Some(1.0)).unwrap().iter().map(|trade| trade.seq).collect();
+    assert_eq!(since_first, [2.0]);
+
+    let depth = book.depth(Some(5));
+    assert_eq!((depth.bids[0].price, depth.asks[0].price, depth.asks[0].quantity), (99.0, 102.0, 3.0));
+    book.on_trade(None);
+    assert_eq!(book.place_market("sell", 4.0, None).unwrap().trades.len(), 1);
+    assert_eq!(seen.borrow().len(), 2);
+    assert!(book.best_bid().is_none());
+
+    assert_eq!(book.cancel(2.0).unwrap(), 3.0);
+    assert!(book.cancel(2.0).is_err());
+    assert!(book.place("hold", 100.0, 1.0, None).is_err());
+    assert!(book.place("buy", 100.5, 1.0, None).is_err());
+    assert!(book.place("buy", 100.0, -1.0, None).is_err());
+    assert!(book.place("buy", 100.0, 1.0, Some(2f64.powi(60))).is_err());
+}
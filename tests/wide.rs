@@ -0,0 +1,40 @@
+#![cfg(feature = "wide")]
+
+use orderbook::*;
+
+// 18-decimal token amounts: 10^6 tokens is already past u64::MAX
+const TOKEN: Units = 1_000_000_000_000_000_000;
+
+#[test]
+fn test_prices_and_quantities_beyond_u64() {
+    let mut ob = OrderBook::new();
+    let quantity = 1_000_000 * TOKEN;
+    assert!(quantity > Units::from(u64::MAX));
+
+    ob.place_order(Side::Sell, 3 * TOKEN, quantity, 1).unwrap();
+    ob.place_order(Side::Sell, 4 * TOKEN, quantity, 2).unwrap();
+    assert_eq!(ob.best_sell(), Some((3 * TOKEN, quantity)));
+
+    let report = ob.place_order(Side::Buy, 4 * TOKEN, quantity + TOKEN, 3).unwrap();
+    assert_eq!(report.filled_quantity, quantity + TOKEN);
+    assert_eq!(report.trades.len(), 2);
+    assert_eq!(ob.sell_at(4 * TOKEN), Some((4 * TOKEN, quantity - TOKEN)));
+    // Notionals saturate rather than wrap
+    assert_eq!(ob.cost_to_buy(quantity - TOKEN).notional, u128::MAX);
+
+    let checkpoint = Checkpoint::from_bytes(&ob.checkpoint().to_bytes()).unwrap();
+    let restored = OrderBook::restore_checkpoint(&checkpoint, &[], &[]).unwrap();
+    assert_eq!(restored.sell_at(4 * TOKEN), ob.sell_at(4 * TOKEN));
+
+    // The binary session format keeps 64-bit fields
+    let command = Command::Place(NewOrder::limit(Side::Buy, TOKEN, quantity, 4));
+    assert_eq!(WireMessage::from_command(&command, 0), None);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_wide_values_round_trip_through_json() {
+    let order = NewOrder::limit(Side::Buy, 5 * TOKEN, 1_000_000 * TOKEN, 1);
+    let json = serde_json::to_string(&order).unwrap();
+    assert_eq!(serde_json::from_str::<NewOrder>(&json).unwrap(), order);
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "ws")]
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use orderbook::*;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn start(config: WsConfig) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = format!("ws://{}", listener.local_addr().unwrap());
+    let mut exchange = Exchange::new();
+    exchange.add_symbol("ACME").unwrap();
+    exchange.add_symbol("INITECH").unwrap();
+    tokio::spawn(serve(listener, exchange, config));
+    address
+}
+
+async fn send(client: &mut Client, message: &ClientMessage) {
+    client.send(Message::text(serde_json::to_string(message).unwrap())).await.unwrap();
+}
+
+async fn next(client: &mut Client) -> ServerMessage {
+    loop {
+        let frame = tokio::time::timeout(Duration::from_secs(5), client.next()).await.expect("no message").unwrap().unwrap();
+        if let Message::Text(text) = frame {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+fn place(symbol: &str, side: Side, price: Option<Units>, quantity: Units, id: u64) -> ClientMessage {
+    ClientMessage::Place { symbol: symbol.to_string(), side, price, quantity, id }
+}
+
+#[tokio::test]
+async fn test_ws_order_entry_and_streams() {
+    let address = start(WsConfig::default()).await;
+    let (mut trader, _) = connect_async(&address).await.unwrap();
+    let (mut watcher, _) = connect_async(&address).await.unwrap();
+
+    send(&mut watcher, &ClientMessage::Subscribe { channel: Channel::Trades, symbol: "ACME".to_string() }).await;
+    assert_eq!(next(&mut watcher).await, ServerMessage::Subscribed { channel: Channel::Trades, symbol: "ACME".to_string() });
+    send(&mut watcher, &ClientMessage::Subscribe { channel: Channel::Depth, symbol: "ACME".to_string() }).await;
+    assert_eq!(next(&mut watcher).await, ServerMessage::Subscribed { channel: Channel::Depth, symbol: "ACME".to_string() });
+    assert_eq!(next(&mut watcher).await, ServerMessage::Depth { symbol: "ACME".to_string(), depth: Depth::default() });
+
+    // Nothing on INITECH reaches the watcher
+    send(&mut trader, &place("INITECH", Side::Sell, Some(50), 1, 1)).await;
+    assert!(matches!(next(&mut trader).await, ServerMessage::Report { .. }));
+
+    send(&mut trader, &place("ACME", Side::Sell, Some(100), 10, 2)).await;
+    match next(&mut trader).await {
+        ServerMessage::Report { symbol, report } => {
+            assert_eq!(symbol, "ACME");
+            assert_eq!(report.resting_id, Some(2));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    match next(&mut watcher).await {
+        ServerMessage::Depth { symbol, depth } => {
+            assert_eq!(symbol, "ACME");
+            assert_eq!(depth.asks, vec![DepthLevel { price: 100, quantity: 10, order_count: 1 }]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+
+    send(&mut trader, &place("ACME", Side::Buy, None, 4, 3)).await;
+    match next(&mut trader).await {
+        ServerMessage::Report { report, .. } => assert_eq!(report.filled_quantity, 4),
+        other => panic!("unexpected {:?}", other),
+    }
+    match next(&mut watcher).await {
+        ServerMessage::Trade { trade, .. } => assert_eq!((trade.price, trade.quantity, trade.maker_id), (100, 4, 2)),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(matches!(next(&mut watcher).await, ServerMessage::Depth { depth, .. } if depth.asks[0].quantity == 6));
+
+    send(&mut trader, &ClientMessage::Modify { id: 2, price: 101, quantity: 6 }).await;
+    assert!(matches!(next(&mut trader).await, ServerMessage::Report { report, .. } if report.resting_id == Some(2)));
+    send(&mut trader, &ClientMessage::Cancel { id: 2 }).await;
+    assert!(matches!(next(&mut trader).await, ServerMessage::Cancelled { order, .. } if order.price == 101));
+    assert!(matches!(next(&mut watcher).await, ServerMessage::Depth { depth, .. } if depth.asks[0].price == 101));
+    assert_eq!(next(&mut watcher).await, ServerMessage::Depth { symbol: "ACME".to_string(), depth: Depth::default() });
+
+    send(&mut watcher, &ClientMessage::Unsubscribe { channel: Channel::Depth, symbol: "ACME".to_string() }).await;
+    assert!(matches!(next(&mut watcher).await, ServerMessage::Unsubscribed { .. }));
+}
+
+#[tokio::test]
+async fn test_ws_errors_ping_and_heartbeat() {
+    let address = start(WsConfig { heartbeat_interval: Duration::from_millis(50), ..WsConfig::default() }).await;
+    let (mut client, _) = connect_async(&address).await.unwrap();
+
+    client.send(Message::text("{\"op\":\"launch\"}")).await.unwrap();
+    assert!(matches!(next(&mut client).await, ServerMessage::Error { message } if message.starts_with("bad request")));
+    send(&mut client, &place("NOPE", Side::Buy, Some(1), 1, 1)).await;
+    assert_eq!(next(&mut client).await, ServerMessage::Error { message: "unknown symbol NOPE".to_string() });
+    send(&mut client, &ClientMessage::Cancel { id: 99 }).await;
+    assert_eq!(next(&mut client).await, ServerMessage::Error { message: "no resting order with id 99".to_string() });
+    send(&mut client, &ClientMessage::Ping).await;
+    assert_eq!(next(&mut client).await, ServerMessage::Pong);
+
+    assert!(matches!(next(&mut client).await, ServerMessage::Heartbeat { timestamp_ms } if timestamp_ms > 0));
+}